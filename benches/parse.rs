@@ -0,0 +1,132 @@
+//! Benchmarks for inputs that have been slow to parse in the past: structs
+//! with many fields, deeply nested values, long lists, long chains of
+//! operators, and many records of the same type.
+//!
+//! Each benchmark is run at several sizes and reports throughput, so the cost
+//! per byte should stay flat as the input grows. The inputs are parsed by the
+//! deserializer, into a `Value`, and by the syntax tree behind the text
+//! helpers such as `canonicalize`. To compare against an older
+//! revision, run `cargo bench -- --save-baseline old` on it and then
+//! `cargo bench -- --baseline old` on the newer one.
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use serde::de::IgnoredAny;
+use serde::Deserialize;
+use serde_dbgfmt::{Options, Value};
+
+const SIZES: [usize; 3] = [16, 128, 1024];
+
+/// A struct with `len` fields, as emitted by `#[derive(Debug)]`.
+fn long_struct(len: usize) -> String {
+    let fields: Vec<_> = (0..len).map(|i| format!("field_{i}: {i}")).collect();
+    format!("Record {{ {} }}", fields.join(", "))
+}
+
+/// Values nested `depth` levels deep, alternating between structs, lists and
+/// tuples.
+fn nested(depth: usize) -> String {
+    let mut text = String::from("0");
+    for i in 0..depth {
+        text = match i % 3 {
+            0 => format!("Wrapper {{ inner: {text} }}"),
+            1 => format!("[{text}]"),
+            _ => format!("({text}, 1)"),
+        };
+    }
+    text
+}
+
+/// Flags joined by `len` operators, as printed by bitflags.
+fn operators(len: usize) -> String {
+    let flags: Vec<_> = (0..=len).map(|i| format!("FLAG_{i}")).collect();
+    flags.join(" | ")
+}
+
+/// A list of `len` small structs.
+fn long_list(len: usize) -> String {
+    let elements: Vec<_> = (0..len)
+        .map(|i| format!("Point {{ x: {i}, y: -{i}, label: \"p{i}\" }}"))
+        .collect();
+    format!("[{}]", elements.join(", "))
+}
+
+#[derive(Deserialize)]
+#[allow(dead_code)]
+struct Request {
+    method: String,
+    path: String,
+    status: u16,
+    bytes: u64,
+    latency_ms: f64,
+    user_agent: Option<String>,
+    #[serde(default)]
+    café: bool,
+}
+
+/// A list of `len` records of the same type, with one field name that is not
+/// in NFC.
+fn records(len: usize) -> String {
+    let cafe = "cafe\u{301}";
+    let elements: Vec<_> = (0..len)
+        .map(|i| {
+            format!(
+                "Request {{ method: \"GET\", path: \"/item/{i}\", status: 200, bytes: {i}, \
+                 latency_ms: 1.5, user_agent: None, {cafe}: true }}"
+            )
+        })
+        .collect();
+    format!("[{}]", elements.join(", "))
+}
+
+/// Deserialize `records` into typed structs, which is where field names are
+/// matched against the fields of the struct.
+fn bench_records(c: &mut Criterion) {
+    let configs = [
+        ("default", Options::new()),
+        #[cfg(feature = "unicode_normalization")]
+        ("normalized", Options::new().normalize_identifiers(true)),
+    ];
+
+    let mut group = c.benchmark_group("records");
+    for &size in &SIZES {
+        let text = records(size);
+        group.throughput(Throughput::Bytes(text.len() as u64));
+        for (name, options) in &configs {
+            group.bench_with_input(BenchmarkId::new(*name, size), &text, |b, text| {
+                b.iter(|| serde_dbgfmt::from_str_with::<Vec<Request>>(text, options).unwrap())
+            });
+        }
+    }
+    group.finish();
+}
+
+fn bench(c: &mut Criterion, name: &str, input: fn(usize) -> String, sizes: &[usize]) {
+    let options = Options::new().max_depth(usize::MAX);
+    let mut group = c.benchmark_group(name);
+    for &size in sizes {
+        let text = input(size);
+        group.throughput(Throughput::Bytes(text.len() as u64));
+        group.bench_with_input(BenchmarkId::new("ignored", size), &text, |b, text| {
+            b.iter(|| serde_dbgfmt::from_str::<IgnoredAny>(text).unwrap())
+        });
+        group.bench_with_input(BenchmarkId::new("value", size), &text, |b, text| {
+            b.iter(|| text.parse::<Value>().unwrap())
+        });
+        group.bench_with_input(BenchmarkId::new("tree", size), &text, |b, text| {
+            b.iter(|| serde_dbgfmt::canonicalize_with(text, &options).unwrap())
+        });
+    }
+    group.finish();
+}
+
+fn parse(c: &mut Criterion) {
+    bench(c, "long_struct", long_struct, &SIZES);
+    // Deeper inputs recurse far enough to risk overflowing the stack.
+    bench(c, "nested", nested, &SIZES[..2]);
+    bench(c, "long_list", long_list, &SIZES);
+    bench(c, "operators", operators, &SIZES);
+    bench_records(c);
+}
+
+criterion_group!(benches, parse);
+criterion_main!(benches);
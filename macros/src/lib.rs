@@ -0,0 +1,215 @@
+//! Compile-time checked debug literals for [`serde_dbgfmt`].
+//!
+//! Fixture strings containing debug output are easy to break without
+//! noticing, since nothing checks them until the test that uses them runs.
+//! The [`dbg_lit!`] macro parses the literal while compiling so that any
+//! syntax errors are reported right where the literal is written.
+//!
+//! [`serde_dbgfmt`]: https://docs.rs/serde_dbgfmt
+
+use proc_macro::TokenStream;
+use proc_macro2::{Literal, TokenStream as TokenStream2};
+use quote::quote;
+use serde_dbgfmt::Value;
+use syn::parse::{Parse, ParseStream};
+use syn::{parse_macro_input, LitStr, Token, Type};
+
+struct DbgLit {
+    ty: Option<Type>,
+    text: LitStr,
+}
+
+impl Parse for DbgLit {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        if input.peek(LitStr) {
+            return Ok(Self {
+                ty: None,
+                text: input.parse()?,
+            });
+        }
+
+        let ty = input.parse()?;
+        input.parse::<Token![,]>()?;
+        let text = input.parse()?;
+
+        Ok(Self { ty: Some(ty), text })
+    }
+}
+
+/// Check a debug-formatted string literal at compile time.
+///
+/// With just a literal, this expands to an expression that constructs the
+/// [`Value`] parsed from the literal, without parsing it again at runtime.
+/// When given a type first, it expands to an expression that deserializes that
+/// type from the literal using [`serde_dbgfmt::from_str`].
+///
+/// In both cases the literal must pass [`serde_dbgfmt::validate`] or
+/// compilation fails. Whether the text matches the type can only be checked
+/// at runtime, so the typed form panics if deserialization fails.
+///
+/// # Example
+/// ```
+/// use serde::Deserialize;
+/// use serde_dbgfmt_macros::dbg_lit;
+///
+/// #[derive(Debug, Deserialize, PartialEq)]
+/// struct Point {
+///     x: i32,
+///     y: i32,
+/// }
+///
+/// let value = dbg_lit!("Point { x: 1, y: 2 }");
+/// let point = dbg_lit!(Point, "Point { x: 1, y: 2 }");
+///
+/// assert_eq!(point, Point { x: 1, y: 2 });
+/// assert_eq!(value.field("y"), Some(&serde_dbgfmt::Value::Int(2)));
+/// assert_eq!(Point::deserialize(&value).unwrap(), point);
+/// ```
+///
+/// Invalid literals are rejected:
+/// ```compile_fail
+/// use serde_dbgfmt_macros::dbg_lit;
+///
+/// let text = dbg_lit!("Point { x: 1, y: }");
+/// ```
+///
+/// [`Value`]: https://docs.rs/serde_dbgfmt/latest/serde_dbgfmt/enum.Value.html
+/// [`serde_dbgfmt::from_str`]: https://docs.rs/serde_dbgfmt/latest/serde_dbgfmt/fn.from_str.html
+/// [`serde_dbgfmt::validate`]: https://docs.rs/serde_dbgfmt/latest/serde_dbgfmt/fn.validate.html
+#[proc_macro]
+pub fn dbg_lit(input: TokenStream) -> TokenStream {
+    let DbgLit { ty, text } = parse_macro_input!(input as DbgLit);
+
+    let expanded = match expand(ty, &text) {
+        Ok(expanded) => expanded,
+        Err(message) => {
+            return syn::Error::new(text.span(), format!("invalid debug literal: {message}"))
+                .to_compile_error()
+                .into()
+        }
+    };
+
+    expanded.into()
+}
+
+fn expand(ty: Option<Type>, text: &LitStr) -> Result<TokenStream2, String> {
+    let value = text.value();
+    serde_dbgfmt::validate(&value).map_err(|e| e.to_string())?;
+
+    Ok(match ty {
+        None => {
+            let value = value.parse::<Value>().map_err(|e| e.to_string())?;
+            construct(&value)?
+        }
+        Some(ty) => quote! {
+            ::serde_dbgfmt::from_str::<#ty>(#text)
+                .unwrap_or_else(|e| ::core::panic!("failed to deserialize debug literal: {}", e))
+        },
+    })
+}
+
+/// Build an expression that constructs `value`.
+///
+/// Strings, boxes and vectors are all built through `From` so that the
+/// expansion does not depend on how the caller refers to `alloc` or `std`.
+fn construct(value: &Value) -> Result<TokenStream2, String> {
+    let all = |values: &[Value]| values.iter().map(construct).collect::<Result<Vec<_>, _>>();
+
+    Ok(match value {
+        Value::Bool(b) => quote! { ::serde_dbgfmt::Value::Bool(#b) },
+        Value::Int(i) => {
+            let i = Literal::i128_unsuffixed(*i);
+            quote! { ::serde_dbgfmt::Value::Int(#i) }
+        }
+        Value::UInt(u) => {
+            let u = Literal::u128_unsuffixed(*u);
+            quote! { ::serde_dbgfmt::Value::UInt(#u) }
+        }
+        Value::Float(f) => {
+            // Going through the bits keeps NaN payloads and infinities intact.
+            let bits = Literal::u64_suffixed(f.to_bits());
+            quote! { ::serde_dbgfmt::Value::Float(f64::from_bits(#bits)) }
+        }
+        Value::Str(s) => {
+            let s = &**s;
+            quote! { ::serde_dbgfmt::Value::Str(::core::convert::From::from(#s)) }
+        }
+        Value::Char(c) => quote! { ::serde_dbgfmt::Value::Char(#c) },
+        Value::Unit => quote! { ::serde_dbgfmt::Value::Unit },
+        Value::Option(None) => quote! { ::serde_dbgfmt::Value::Option(None) },
+        Value::Option(Some(inner)) => {
+            let inner = construct(inner)?;
+            quote! { ::serde_dbgfmt::Value::Option(Some(::core::convert::From::from(#inner))) }
+        }
+        Value::Ident(name) => {
+            quote! { ::serde_dbgfmt::Value::Ident(::core::convert::From::from(#name)) }
+        }
+        Value::Struct {
+            name,
+            fields,
+            non_exhaustive,
+        } => {
+            let names = fields.iter().map(|(name, _)| &**name);
+            let values = fields
+                .iter()
+                .map(|(_, value)| construct(value))
+                .collect::<Result<Vec<_>, _>>()?;
+            quote! {
+                ::serde_dbgfmt::Value::Struct {
+                    name: ::core::convert::From::from(#name),
+                    fields: ::core::convert::From::from([
+                        #((::core::convert::From::from(#names), #values)),*
+                    ]),
+                    non_exhaustive: #non_exhaustive,
+                }
+            }
+        }
+        Value::TupleStruct { name, elements } => {
+            let elements = all(elements)?;
+            quote! {
+                ::serde_dbgfmt::Value::TupleStruct {
+                    name: ::core::convert::From::from(#name),
+                    elements: ::core::convert::From::from([#(#elements),*]),
+                }
+            }
+        }
+        Value::Tuple(elements) => {
+            let elements = all(elements)?;
+            quote! { ::serde_dbgfmt::Value::Tuple(::core::convert::From::from([#(#elements),*])) }
+        }
+        Value::Seq(elements) => {
+            let elements = all(elements)?;
+            quote! { ::serde_dbgfmt::Value::Seq(::core::convert::From::from([#(#elements),*])) }
+        }
+        Value::Set(elements) => {
+            let elements = all(elements)?;
+            quote! { ::serde_dbgfmt::Value::Set(::core::convert::From::from([#(#elements),*])) }
+        }
+        Value::Map(entries) => {
+            let keys = entries
+                .iter()
+                .map(|(key, _)| construct(key))
+                .collect::<Result<Vec<_>, _>>()?;
+            let values = entries
+                .iter()
+                .map(|(_, value)| construct(value))
+                .collect::<Result<Vec<_>, _>>()?;
+            quote! {
+                ::serde_dbgfmt::Value::Map(::core::convert::From::from([#((#keys, #values)),*]))
+            }
+        }
+        Value::Other(text) => {
+            quote! { ::serde_dbgfmt::Value::Other(::core::convert::From::from(#text)) }
+        }
+        Value::Truncated { value, len } => {
+            let value = construct(value)?;
+            quote! {
+                ::serde_dbgfmt::Value::Truncated {
+                    value: ::core::convert::From::from(#value),
+                    len: #len,
+                }
+            }
+        }
+        _ => return Err(format!("`{value}` has no constructor")),
+    })
+}
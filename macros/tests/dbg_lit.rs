@@ -0,0 +1,55 @@
+use std::collections::BTreeMap;
+
+use serde::Deserialize;
+use serde_dbgfmt::Value;
+use serde_dbgfmt_macros::dbg_lit;
+
+#[derive(Debug, Deserialize, PartialEq)]
+struct Config<'a> {
+    name: &'a str,
+    limits: BTreeMap<String, u32>,
+}
+
+#[test]
+fn untyped() {
+    const TEXT: &str = r#"Config { name: "a", limits: {}, .. }"#;
+    let value: Value = dbg_lit!(r#"Config { name: "a", limits: {}, .. }"#);
+
+    assert_eq!(value, TEXT.parse::<Value>().unwrap());
+    assert_eq!(value.to_string(), TEXT);
+}
+
+#[test]
+fn untyped_every_kind() {
+    const TEXT: &str = r#"[true, -1, 1e40, NaN, "s\n", 'c', (), Some(None), Unit, T(1), (1,), {1}, {"k": []}, 1..=2, A | B]"#;
+    let value = dbg_lit!(
+        r#"[true, -1, 1e40, NaN, "s\n", 'c', (), Some(None), Unit, T(1), (1,), {1}, {"k": []}, 1..=2, A | B]"#
+    );
+
+    assert_eq!(
+        value.to_string(),
+        TEXT.parse::<Value>().unwrap().to_string()
+    );
+    assert_eq!(
+        dbg_lit!("340282366920938463463374607431768211455"),
+        Value::UInt(u128::MAX)
+    );
+}
+
+#[test]
+fn typed() {
+    let config = dbg_lit!(Config, r#"Config { name: "a", limits: {"cpu": 2} }"#);
+
+    assert_eq!(config.name, "a");
+    assert_eq!(config.limits["cpu"], 2);
+    assert_eq!(
+        dbg_lit!(Vec<Option<u8>>, "[Some(1), None]"),
+        [Some(1), None]
+    );
+}
+
+#[test]
+#[should_panic(expected = "failed to deserialize debug literal")]
+fn typed_mismatch() {
+    let _ = dbg_lit!(Config, "Other { x: 1 }");
+}
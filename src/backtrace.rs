@@ -0,0 +1,326 @@
+//! Parsing for the debug output of backtraces.
+//!
+//! Backtraces do not follow the usual debug format closely enough to be
+//! deserialized directly, so this module provides a dedicated parser which
+//! turns them into a list of [`Frame`]s. Both of the common formats are
+//! supported:
+//!
+//! - The output of [`std::backtrace::Backtrace`], which is a single line that
+//!   looks like `Backtrace [{ fn: "main", file: "./src/main.rs", line: 4 }]`.
+//! - The multi-line output of `backtrace::Backtrace` from the `backtrace`
+//!   crate, which is the same format used when printing a panic backtrace.
+
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use crate::error::LexerError;
+use crate::Error;
+
+/// A single frame within a backtrace.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Frame {
+    /// The demangled name of the function, if it could be resolved.
+    pub symbol: Option<String>,
+
+    /// The source file containing the function, if known.
+    pub file: Option<String>,
+
+    /// The line within `file`, if known.
+    pub line: Option<u32>,
+}
+
+/// Parse the debug output of a backtrace into its frames.
+///
+/// Backtraces that were not captured (printed as `<disabled>` or
+/// `<unsupported>`) are parsed as an empty list of frames.
+///
+/// # Example
+/// ```
+/// use serde_dbgfmt::backtrace::parse_backtrace;
+///
+/// let text = r#"Backtrace [{ fn: "app::main", file: "./src/main.rs", line: 4 }, { fn: "std::rt::lang_start" }]"#;
+/// let frames = parse_backtrace(text).unwrap();
+///
+/// assert_eq!(frames.len(), 2);
+/// assert_eq!(frames[0].symbol.as_deref(), Some("app::main"));
+/// assert_eq!(frames[0].file.as_deref(), Some("./src/main.rs"));
+/// assert_eq!(frames[0].line, Some(4));
+/// assert_eq!(frames[1].file, None);
+/// ```
+pub fn parse_backtrace(text: &str) -> Result<Vec<Frame>, Error> {
+    let text = text.trim();
+
+    match text {
+        "<disabled>" | "<unsupported>" | "" => return Ok(Vec::new()),
+        _ => (),
+    }
+
+    match text.strip_prefix("Backtrace") {
+        Some(rest) => parse_std(rest.trim_start()),
+        None => parse_multiline(text),
+    }
+}
+
+/// Parse the frame list emitted by [`std::backtrace::Backtrace`].
+fn parse_std(text: &str) -> Result<Vec<Frame>, Error> {
+    let mut rest = text
+        .strip_prefix('[')
+        .ok_or_else(|| LexerError::unexpected_token(first_char(text), '['))?
+        .trim_start();
+    let mut frames = Vec::new();
+
+    loop {
+        if let Some(tail) = rest.strip_prefix(']') {
+            if !tail.trim().is_empty() {
+                return Err(LexerError::unexpected_token(tail.trim(), "end-of-file").into());
+            }
+
+            return Ok(frames);
+        }
+
+        let (frame, tail) = parse_std_frame(rest)?;
+        frames.push(frame);
+
+        rest = tail.trim_start();
+        if let Some(tail) = rest.strip_prefix(',') {
+            rest = tail.trim_start();
+        }
+    }
+}
+
+/// Parse a single `{ fn: "...", file: "...", line: N }` entry.
+///
+/// The values here are not escaped like regular debug strings are, so a value
+/// only ends at a quote that is followed by the end of the field.
+fn parse_std_frame(text: &str) -> Result<(Frame, &str), Error> {
+    let mut rest = text
+        .strip_prefix('{')
+        .ok_or_else(|| LexerError::unexpected_token(first_char(text), '{'))?;
+    let mut frame = Frame::default();
+
+    loop {
+        rest = rest.trim_start();
+        if let Some(tail) = rest.strip_prefix('}') {
+            return Ok((frame, tail));
+        }
+
+        let (name, tail) = rest
+            .split_once(':')
+            .ok_or_else(|| LexerError::unexpected_eof("a backtrace field"))?;
+        let tail = tail.trim_start();
+
+        let (value, tail) = match tail.strip_prefix('"') {
+            Some(quoted) => {
+                let end = find_closing_quote(quoted)
+                    .ok_or_else(|| LexerError::unexpected_eof("a closing `\"`"))?;
+                (Some(&quoted[..end]), &quoted[end + 1..])
+            }
+            None => {
+                let end = tail.find([',', '}']).unwrap_or(tail.len());
+                let value = tail[..end].trim();
+                (Some(value).filter(|&v| v != "<unknown>"), &tail[end..])
+            }
+        };
+
+        match (name.trim(), value) {
+            ("fn", value) => frame.symbol = value.map(Into::into),
+            ("file", value) => frame.file = value.map(Into::into),
+            ("line", Some(value)) => {
+                frame.line = Some(value.parse().map_err(|e| Error::parse_int(value, e))?)
+            }
+            _ => (),
+        }
+
+        rest = tail.trim_start();
+        if let Some(tail) = rest.strip_prefix(',') {
+            rest = tail;
+        }
+    }
+}
+
+fn find_closing_quote(text: &str) -> Option<usize> {
+    text.match_indices('"').map(|(idx, _)| idx).find(|&idx| {
+        let after = text[idx + 1..].trim_start();
+        after.starts_with(',') || after.starts_with('}')
+    })
+}
+
+/// Parse the multi-line format used by the `backtrace` crate and by panics.
+///
+/// ```text
+/// stack backtrace:
+///    0: app::main
+///              at ./src/main.rs:4:5
+///    1: 0x55d0e1c5 - std::rt::lang_start
+/// ```
+fn parse_multiline(text: &str) -> Result<Vec<Frame>, Error> {
+    let mut frames: Vec<Frame> = Vec::new();
+
+    for line in text.lines() {
+        let line = line.trim();
+
+        if let Some(location) = line.strip_prefix("at ") {
+            let frame = frames
+                .last_mut()
+                .ok_or_else(|| LexerError::unexpected_token(line, "a backtrace frame"))?;
+            let (file, line) = parse_location(location);
+
+            frame.file = Some(file.into());
+            frame.line = line;
+            continue;
+        }
+
+        let symbol = match line.split_once(": ") {
+            Some((index, symbol)) if index.bytes().all(|b| b.is_ascii_digit()) => symbol,
+            // Inlined functions are printed on their own line without an index.
+            _ if !frames.is_empty() && is_symbol(line) => line,
+            _ => continue,
+        };
+
+        // The full format includes the instruction pointer before the symbol.
+        let symbol = match symbol.split_once(" - ") {
+            Some((ip, symbol)) if ip.starts_with("0x") => symbol,
+            _ => symbol,
+        };
+
+        frames.push(Frame {
+            symbol: Some(symbol.trim())
+                .filter(|s| !s.is_empty() && *s != "<unknown>")
+                .map(Into::into),
+            ..Frame::default()
+        });
+    }
+
+    if frames.is_empty() {
+        return Err(LexerError::unexpected_token(first_char(text), "a backtrace").into());
+    }
+
+    Ok(frames)
+}
+
+/// Split a `file:line:column` location into the file and line.
+fn parse_location(location: &str) -> (&str, Option<u32>) {
+    let mut parts = location.rsplitn(3, ':');
+    let last = parts.next().unwrap_or_default();
+    let middle = parts.next();
+    let first = parts.next();
+
+    match (
+        first,
+        middle.and_then(|m| m.parse().ok()),
+        last.parse::<u32>(),
+    ) {
+        (Some(file), Some(line), Ok(_)) => (file, Some(line)),
+        _ => match location.rsplit_once(':') {
+            Some((file, line)) => match line.parse() {
+                Ok(line) => (file, Some(line)),
+                Err(_) => (location, None),
+            },
+            None => (location, None),
+        },
+    }
+}
+
+fn is_symbol(line: &str) -> bool {
+    !line.is_empty() && !line.starts_with("note:") && !line.ends_with(':')
+}
+
+fn first_char(s: &str) -> &str {
+    match s.chars().next() {
+        Some(c) => &s[..c.len_utf8()],
+        None => s,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frame(symbol: &str, file: Option<&str>, line: Option<u32>) -> Frame {
+        Frame {
+            symbol: Some(symbol.into()),
+            file: file.map(Into::into),
+            line,
+        }
+    }
+
+    #[test]
+    fn std_backtrace() {
+        let text = concat!(
+            r#"Backtrace [{ fn: "app::main", file: "./src/main.rs", line: 4 }, "#,
+            r#"{ fn: "core::ops::function::FnOnce::call_once", file: "/rustc/library/core/src/ops/function.rs", line: 250 }, "#,
+            r#"{ fn: "std::rt::lang_start" }, { fn: <unknown> }]"#
+        );
+
+        assert_eq!(
+            parse_backtrace(text).unwrap(),
+            [
+                frame("app::main", Some("./src/main.rs"), Some(4)),
+                frame(
+                    "core::ops::function::FnOnce::call_once",
+                    Some("/rustc/library/core/src/ops/function.rs"),
+                    Some(250)
+                ),
+                frame("std::rt::lang_start", None, None),
+                Frame::default(),
+            ]
+        );
+    }
+
+    #[test]
+    fn std_backtrace_empty() {
+        assert_eq!(parse_backtrace("Backtrace []").unwrap(), []);
+        assert_eq!(parse_backtrace("<disabled>").unwrap(), []);
+        assert_eq!(parse_backtrace("<unsupported>").unwrap(), []);
+    }
+
+    #[test]
+    fn std_backtrace_live() {
+        let backtrace = std::backtrace::Backtrace::force_capture();
+        let frames = parse_backtrace(&format!("{backtrace:?}")).unwrap();
+
+        assert!(frames.iter().any(|frame| frame
+            .symbol
+            .as_deref()
+            .unwrap_or_default()
+            .contains("std_backtrace_live")));
+    }
+
+    #[test]
+    fn multiline_backtrace() {
+        let text = "\
+stack backtrace:
+   0: app::inner
+             at ./src/lib.rs:12:9
+      app::main
+             at ./src/main.rs:4:5
+   1: 0x55d0e1c5 - std::rt::lang_start
+   2: <unknown>
+             at C:\\src\\main.rs:7
+note: Some details are omitted, run with `RUST_BACKTRACE=full` for a verbose backtrace.
+";
+
+        assert_eq!(
+            parse_backtrace(text).unwrap(),
+            [
+                frame("app::inner", Some("./src/lib.rs"), Some(12)),
+                frame("app::main", Some("./src/main.rs"), Some(4)),
+                frame("std::rt::lang_start", None, None),
+                Frame {
+                    file: Some("C:\\src\\main.rs".into()),
+                    line: Some(7),
+                    ..Frame::default()
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn invalid_backtrace() {
+        assert!(parse_backtrace("Backtrace {").is_err());
+        assert!(parse_backtrace("Backtrace [{ fn: \"a").is_err());
+        assert!(parse_backtrace("Backtrace [] extra").is_err());
+        assert!(parse_backtrace("not a backtrace").is_err());
+    }
+}
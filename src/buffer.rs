@@ -0,0 +1,300 @@
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::ops::Range;
+
+use serde::Deserialize;
+
+use crate::de::Nested;
+use crate::lex::{Lexer, Token, TokenKind};
+use crate::{Deserializer, Error, Options};
+
+/// Input that has been split into tokens ahead of time.
+///
+/// Any number of [`Deserializer`]s can be created over the same buffer
+/// without splitting the input into tokens again. This makes it cheap to try
+/// deserializing a large input as several candidate types, or to extract
+/// different parts of it one after the other.
+///
+/// Parts of the input that are not made up of valid tokens are still accepted
+/// and are handled the same way they would be without the buffer.
+///
+/// # Example
+/// ```
+/// use serde::Deserialize;
+/// use serde_dbgfmt::TokenBuffer;
+///
+/// #[derive(Debug, Deserialize)]
+/// struct V1 {
+///     name: String,
+/// }
+///
+/// #[derive(Debug, Deserialize)]
+/// struct V2 {
+///     name: String,
+///     id: u32,
+/// }
+///
+/// let buffer = TokenBuffer::new(r#"V2 { name: "test", id: 5 }"#);
+///
+/// assert!(buffer.deserialize::<V1>().is_err());
+/// assert_eq!(buffer.deserialize::<V2>().unwrap().id, 5);
+/// ```
+#[derive(Clone, Debug)]
+pub struct TokenBuffer<'de> {
+    input: &'de str,
+    tokens: Vec<Token<'de>>,
+}
+
+impl<'de> TokenBuffer<'de> {
+    /// Split `input` into tokens.
+    ///
+    /// This stops at the first part of the input that is not a valid token.
+    pub fn new(input: &'de str) -> Self {
+        let mut lexer = Lexer::new(input);
+        let mut tokens = Vec::new();
+
+        while let Ok(token) = lexer.parse_token() {
+            tokens.push(token);
+
+            if token.kind == TokenKind::Eof {
+                break;
+            }
+        }
+
+        Self { input, tokens }
+    }
+
+    /// Create a buffer from a sequence of tokens, such as those produced by a
+    /// [`Lexer`] after they have been filtered or rewritten.
+    ///
+    /// The text of the tokens is written into `buf`, separated by spaces, and
+    /// becomes the input of the buffer. Any existing contents of `buf` are
+    /// cleared first. The tokens are used as they are instead of being split
+    /// out of the text again, so a rewritten token does not need to be a
+    /// single valid token on its own. Its text must still be valid for its
+    /// kind.
+    ///
+    /// # Example
+    /// ```
+    /// use serde_dbgfmt::{Lexer, TokenBuffer, TokenKind};
+    ///
+    /// let tokens = Lexer::new("[1, 2, 3]").map(Result::unwrap);
+    /// let tokens = tokens.filter(|token| token.value != "2");
+    ///
+    /// let mut buf = String::new();
+    /// let buffer = TokenBuffer::from_tokens(tokens, &mut buf);
+    ///
+    /// assert_eq!(buffer.input(), "[ 1 , , 3 ]");
+    /// ```
+    pub fn from_tokens<'t, I>(tokens: I, buf: &'de mut String) -> Self
+    where
+        I: IntoIterator<Item = Token<'t>>,
+    {
+        buf.clear();
+
+        // The text may be moved as it grows, so the tokens can only refer to
+        // it once it is complete.
+        let mut spans = Vec::new();
+        for token in tokens {
+            if token.kind == TokenKind::Eof {
+                continue;
+            }
+
+            if !buf.is_empty() {
+                buf.push(' ');
+            }
+            spans.push((token.kind, buf.len()..buf.len() + token.value.len()));
+            buf.push_str(token.value);
+        }
+
+        let input: &'de str = buf;
+        let mut tokens: Vec<_> = spans
+            .into_iter()
+            .map(|(kind, span)| Token::new(kind, &input[span]))
+            .collect();
+        tokens.push(Token::new(TokenKind::Eof, &input[input.len()..]));
+
+        Self { input, tokens }
+    }
+
+    /// Split `input` into tokens, reusing the tokens from this buffer for the
+    /// parts of the input that have not changed.
+    ///
+    /// `input` must be the input of this buffer with the bytes in `replaced`
+    /// swapped out for some other text. Only the tokens around the edit are
+    /// split out again, so small edits to a large input are cheap. The result
+    /// is the same as calling [`TokenBuffer::new`] on `input`.
+    ///
+    /// # Panics
+    /// Panics if `replaced` is out of bounds for the input of this buffer or
+    /// if it is longer than the difference between the two inputs allows.
+    ///
+    /// # Example
+    /// ```
+    /// use serde_dbgfmt::TokenBuffer;
+    ///
+    /// let old = "[1, 2, 3]";
+    /// let buffer = TokenBuffer::new(old);
+    ///
+    /// // Replace the `2` with `20, 21`.
+    /// let new = old.replacen('2', "20, 21", 1);
+    /// let buffer = buffer.relex(&new, 4..5);
+    ///
+    /// assert_eq!(buffer.deserialize::<Vec<u32>>().unwrap(), [1, 20, 21, 3]);
+    /// ```
+    pub fn relex<'n>(&self, input: &'n str, replaced: Range<usize>) -> TokenBuffer<'n> {
+        let old = self.input;
+        assert!(replaced.start <= replaced.end && replaced.end <= old.len());
+        let unchanged = old.len() - replaced.len();
+        assert!(unchanged <= input.len(), "edit is out of bounds");
+
+        // The replaced range, but within the new input.
+        let edited = replaced.start..input.len() - (old.len() - replaced.end);
+        debug_assert_eq!(old[..replaced.start], input[..edited.start]);
+        debug_assert_eq!(old[replaced.end..], input[edited.end..]);
+
+        let offset = |token: &Token| token.value.as_ptr() as usize - old.as_ptr() as usize;
+        let end = |token: &Token| offset(token) + token.value.len();
+        let moved = |token: &Token, start: usize| Token {
+            kind: token.kind,
+            value: &input[start..start + token.value.len()],
+        };
+
+        // The end of a token depends on the character that follows it, so
+        // tokens that end right at the start of the edit must be lexed again.
+        let kept = self
+            .tokens
+            .partition_point(|token| token.kind != TokenKind::Eof && end(token) < replaced.start);
+        let mut tokens: Vec<_> = self.tokens[..kept]
+            .iter()
+            .map(|token| moved(token, offset(token)))
+            .collect();
+
+        // Every token other than end-of-file, ordered by where they end.
+        let ended = match self.tokens.last() {
+            Some(token) if token.kind == TokenKind::Eof => &self.tokens[..self.tokens.len() - 1],
+            _ => &self.tokens[..],
+        };
+
+        let start = self.tokens[..kept].last().map(end).unwrap_or(0);
+        let mut lexer = Lexer::new(&input[start..]);
+        while let Ok(token) = lexer.parse_token() {
+            tokens.push(token);
+            if token.kind == TokenKind::Eof {
+                break;
+            }
+
+            // Once past the edit, the lexer will produce the same tokens as
+            // it did for the old input if it ends up at the end of one of the
+            // old tokens.
+            let position = input.len() - lexer.remaining().len();
+            if position < edited.end {
+                continue;
+            }
+
+            let old_position = position - edited.end + replaced.end;
+            if let Ok(index) = ended.binary_search_by_key(&old_position, end) {
+                let rest = &self.tokens[index + 1..];
+                tokens.extend(
+                    rest.iter()
+                        .map(|token| moved(token, offset(token) - replaced.end + edited.end)),
+                );
+                break;
+            }
+        }
+
+        TokenBuffer { input, tokens }
+    }
+
+    /// The input that this buffer was created from.
+    pub fn input(&self) -> &'de str {
+        self.input
+    }
+
+    /// Create a deserializer that reads from this buffer.
+    ///
+    /// This is the same as [`Deserializer::from_tokens`].
+    pub fn deserializer(&self) -> Deserializer<'_> {
+        Deserializer::from_tokens(self)
+    }
+
+    /// Create a deserializer that reads from this buffer using the provided
+    /// options.
+    ///
+    /// This is the same as [`Deserializer::from_tokens_with`].
+    pub fn deserializer_with(&self, options: Options) -> Deserializer<'_> {
+        Deserializer::from_tokens_with(self, options)
+    }
+
+    /// A lexer that takes its tokens from this buffer.
+    pub(crate) fn lexer(&self) -> Lexer<'_> {
+        Lexer::with_buffer(self.input, &self.tokens)
+    }
+
+    /// Parse a `T` from the buffered input.
+    ///
+    /// This is the same as [`from_str`](crate::from_str) except that the input
+    /// does not need to be split into tokens again.
+    pub fn deserialize<'a, T>(&'a self) -> Result<T, Error>
+    where
+        T: Deserialize<'a>,
+    {
+        let mut de = self.deserializer();
+        let value = T::deserialize(Nested(&mut de)).map_err(|e| de.locate(e))?;
+        de.end()?;
+        Ok(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TokenBuffer;
+    use crate::lex::TokenKind;
+
+    fn tokens<'a>(buffer: &TokenBuffer<'a>) -> Vec<(TokenKind, usize, &'a str)> {
+        let start = buffer.input.as_ptr() as usize;
+        buffer
+            .tokens
+            .iter()
+            .map(|token| {
+                (
+                    token.kind,
+                    token.value.as_ptr() as usize - start,
+                    token.value,
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn relex() {
+        let input = r#"Foo { a: [12, 3], b: "x y", c: 'q' }"#;
+        let buffer = TokenBuffer::new(input);
+
+        let edits = [
+            (0..0, " "),
+            (0..3, "Bar"),
+            (11..11, "4"),
+            (12..13, ""),
+            (10..16, "()"),
+            (21..21, "\""),
+            (22..23, ", "),
+            (27..27, ", d: 1.5e3"),
+            (31..34, "@"),
+            (35..36, ""),
+            (36..36, " [1, 2]"),
+            (0..36, "5"),
+        ];
+
+        for (range, replacement) in edits {
+            let mut edited = input.to_owned();
+            edited.replace_range(range.clone(), replacement);
+
+            assert_eq!(
+                tokens(&buffer.relex(&edited, range)),
+                tokens(&TokenBuffer::new(&edited)),
+                "{edited}"
+            );
+        }
+    }
+}
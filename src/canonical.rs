@@ -0,0 +1,130 @@
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt::Write;
+
+use crate::tree::{Node, NodeKind};
+use crate::{Error, Options};
+
+/// Re-emit debug-formatted text in a canonical form.
+///
+/// The output is always on a single line, using the same layout as `{:?}`.
+/// The entries of maps and the elements of sets are sorted by their canonical
+/// text. Since `HashMap` and `HashSet` print their contents in an arbitrary
+/// order, this makes textual diffs between two dumps meaningful. Pretty
+/// printed input (`{:#?}`) is accepted as well.
+///
+/// Other values, such as literals, are kept as they appear in the input.
+///
+/// # Example
+/// ```
+/// let text = r#"Config {
+///     ports: {
+///         443,
+///         80,
+///     },
+///     env: {
+///         "b": 2,
+///         "a": 1,
+///     },
+/// }"#;
+///
+/// assert_eq!(
+///     serde_dbgfmt::canonicalize(text).unwrap(),
+///     r#"Config { ports: {443, 80}, env: {"a": 1, "b": 2} }"#
+/// );
+/// ```
+pub fn canonicalize(input: &str) -> Result<String, Error> {
+    Ok(canonical(&Node::parse(input)?))
+}
+
+/// Re-emit debug-formatted text in a canonical form using the provided
+/// options.
+///
+/// Only the options that affect how the input is split into tokens apply:
+/// [`on_unknown_token`](Options::on_unknown_token), progress reporting,
+/// cancellation and the time budget. Text handled by the unknown token
+/// callback is kept as it appears in the input.
+///
+/// # Example
+/// ```
+/// use serde_dbgfmt::{Options, UnknownToken};
+///
+/// let options = Options::new().on_unknown_token(|text| match text.find('>') {
+///     Some(end) if text.starts_with('<') => UnknownToken::String(end + 1),
+///     _ => UnknownToken::Error,
+/// });
+///
+/// let text = "{b: <opaque>, a: 1}";
+/// assert!(serde_dbgfmt::canonicalize(text).is_err());
+/// assert_eq!(
+///     serde_dbgfmt::canonicalize_with(text, &options).unwrap(),
+///     "{a: 1, b: <opaque>}"
+/// );
+/// ```
+pub fn canonicalize_with(input: &str, options: &Options) -> Result<String, Error> {
+    Ok(canonical(&Node::parse_with(input, options)?))
+}
+
+pub(crate) fn canonical(node: &Node) -> String {
+    let mut out = String::new();
+
+    match &node.kind {
+        NodeKind::Struct {
+            name,
+            fields,
+            non_exhaustive,
+        } => {
+            let mut items: Vec<_> = fields
+                .iter()
+                .map(|(name, value)| format!("{name}: {}", canonical(value)))
+                .collect();
+            if *non_exhaustive {
+                items.push("..".into());
+            }
+
+            match items.is_empty() {
+                true => write!(out, "{name} {{}}"),
+                false => write!(out, "{name} {{ {} }}", items.join(", ")),
+            }
+            .unwrap();
+        }
+        NodeKind::TupleStruct { name, elements } => {
+            write!(out, "{name}({})", join(elements.iter().map(canonical))).unwrap();
+        }
+        NodeKind::Tuple(elements) => {
+            let trailing = match elements.len() {
+                1 => ",",
+                _ => "",
+            };
+            write!(out, "({}{trailing})", join(elements.iter().map(canonical))).unwrap();
+        }
+        NodeKind::List(elements) => {
+            write!(out, "[{}]", join(elements.iter().map(canonical))).unwrap();
+        }
+        NodeKind::Set(elements) => {
+            let mut elements: Vec<_> = elements.iter().map(canonical).collect();
+            elements.sort();
+            write!(out, "{{{}}}", elements.join(", ")).unwrap();
+        }
+        NodeKind::Map(entries) => {
+            let mut entries: Vec<_> = entries
+                .iter()
+                .map(|(key, value)| (canonical(key), canonical(value)))
+                .collect();
+            entries.sort();
+
+            let entries = entries
+                .into_iter()
+                .map(|(key, value)| format!("{key}: {value}"));
+            write!(out, "{{{}}}", join(entries)).unwrap();
+        }
+        _ => out.push_str(node.span),
+    }
+
+    out
+}
+
+fn join(items: impl Iterator<Item = String>) -> String {
+    items.collect::<Vec<_>>().join(", ")
+}
@@ -0,0 +1,331 @@
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt;
+
+use crate::walk::{Path, PathSegment};
+use crate::{Error, Options, Value};
+
+/// Check whether `a` and `b` contain the same value.
+///
+/// Formatting is ignored, as is the order of map entries. Floats must be
+/// exactly equal. Use [`Comparator`] to allow for a tolerance between them.
+///
+/// # Example
+/// ```
+/// let compact = r#"Point { x: 1.0, y: 2.5 }"#;
+/// let pretty = "Point {\n    x: 1.0,\n    y: 2.5,\n}";
+///
+/// assert!(serde_dbgfmt::structurally_equals(compact, pretty).unwrap());
+/// assert!(!serde_dbgfmt::structurally_equals(compact, "Point { x: 1.0, y: 2.6 }").unwrap());
+/// ```
+pub fn structurally_equals(a: &str, b: &str) -> Result<bool, Error> {
+    Comparator::new().equals(a, b)
+}
+
+/// List the differences between the values in `a` and `b`.
+///
+/// See [`Comparator::diff_values`] for how values are compared.
+///
+/// # Example
+/// ```
+/// let a = r#"Config { name: "prod", ports: [80, 443] }"#;
+/// let b = r#"Config { name: "prod", ports: [80, 8443], debug: true }"#;
+///
+/// let differences: Vec<_> = serde_dbgfmt::diff(a, b)
+///     .unwrap()
+///     .iter()
+///     .map(|difference| difference.to_string())
+///     .collect();
+///
+/// assert_eq!(differences, ["ports[1]: 443 != 8443", "debug: <missing> != true"]);
+/// ```
+pub fn diff(a: &str, b: &str) -> Result<Vec<Difference>, Error> {
+    Comparator::new().diff(a, b)
+}
+
+/// Configurable version of [`structurally_equals`] and [`diff`].
+///
+/// # Example
+/// ```
+/// use serde_dbgfmt::Comparator;
+///
+/// let comparator = Comparator::new().relative_epsilon(1e-9);
+///
+/// assert!(comparator.equals("[0.30000000000000004]", "[0.3]").unwrap());
+/// assert!(!comparator.equals("[0.3001]", "[0.3]").unwrap());
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct Comparator {
+    absolute_epsilon: f64,
+    relative_epsilon: f64,
+    options: Options,
+}
+
+/// A single difference found by [`diff`].
+///
+/// The [`Display`](fmt::Display) impl prints the path followed by both
+/// values, with `<missing>` in place of a value that only appears on one side.
+#[derive(Clone, Debug, PartialEq)]
+#[non_exhaustive]
+pub struct Difference {
+    /// The path to the value that differs, using the same syntax as
+    /// [`flatten`](crate::flatten).
+    pub path: String,
+
+    /// The value in the first input, or `None` if it is not present there.
+    pub left: Option<Value>,
+
+    /// The value in the second input, or `None` if it is not present there.
+    pub right: Option<Value>,
+}
+
+impl fmt::Display for Difference {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let value = |value: &Option<Value>| match value {
+            Some(value) => value.to_string(),
+            None => "<missing>".into(),
+        };
+
+        write!(
+            f,
+            "{}: {} != {}",
+            self.path,
+            value(&self.left),
+            value(&self.right)
+        )
+    }
+}
+
+enum Step<'v> {
+    Compare(Path<'v>, &'v Value, &'v Value),
+    Report(Path<'v>, Option<&'v Value>, Option<&'v Value>),
+}
+
+impl Comparator {
+    /// Create a comparator with the same behaviour as
+    /// [`structurally_equals`].
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Treat two floats as equal if they differ by at most `epsilon`.
+    #[must_use]
+    pub fn absolute_epsilon(mut self, epsilon: f64) -> Self {
+        self.absolute_epsilon = epsilon;
+        self
+    }
+
+    /// Treat two floats as equal if they differ by at most `epsilon` times
+    /// the larger of their magnitudes.
+    ///
+    /// This can be combined with [`absolute_epsilon`](Self::absolute_epsilon),
+    /// in which case floats are equal if they are within either tolerance.
+    #[must_use]
+    pub fn relative_epsilon(mut self, epsilon: f64) -> Self {
+        self.relative_epsilon = epsilon;
+        self
+    }
+
+    /// Use `options` when parsing the inputs.
+    #[must_use]
+    pub fn options(mut self, options: Options) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Parse `a` and `b` and check whether they contain the same value.
+    pub fn equals(&self, a: &str, b: &str) -> Result<bool, Error> {
+        let (a, b) = self.parse(a, b)?;
+        Ok(self.equals_values(&a, &b))
+    }
+
+    /// Parse `a` and `b` and list the differences between them.
+    pub fn diff(&self, a: &str, b: &str) -> Result<Vec<Difference>, Error> {
+        let (a, b) = self.parse(a, b)?;
+        Ok(self.diff_values(&a, &b))
+    }
+
+    /// Check whether `a` and `b` are the same value.
+    pub fn equals_values(&self, a: &Value, b: &Value) -> bool {
+        self.compare(a, b, true).is_empty()
+    }
+
+    /// List the differences between `a` and `b`, in the order they appear.
+    ///
+    /// Struct fields are matched up by name and map entries by key, so their
+    /// order does not matter. A field or entry that only appears on one side
+    /// is reported with the other side missing. Set elements that appear
+    /// exactly on both sides are matched up regardless of their position and
+    /// any that remain are compared in order. Elements of all other
+    /// collections are compared in order.
+    ///
+    /// Values of different kinds, or structs with different names, are
+    /// reported as a single difference without comparing their contents. A
+    /// [`Truncated`](Value::Truncated) collection is compared using only the
+    /// elements that were kept.
+    ///
+    /// This does not recurse, so it works for arbitrarily deeply nested
+    /// values.
+    pub fn diff_values(&self, a: &Value, b: &Value) -> Vec<Difference> {
+        self.compare(a, b, false)
+    }
+
+    fn parse(&self, a: &str, b: &str) -> Result<(Value, Value), Error> {
+        let a = crate::from_str_with(a, &self.options)?;
+        let b = crate::from_str_with(b, &self.options)?;
+        Ok((a, b))
+    }
+
+    fn floats_equal(&self, a: f64, b: f64) -> bool {
+        if a == b || (a.is_nan() && b.is_nan()) {
+            return true;
+        }
+
+        let difference = (a - b).abs();
+        difference <= self.absolute_epsilon
+            || difference <= self.relative_epsilon * a.abs().max(b.abs())
+    }
+
+    /// Compare `a` and `b`, stopping at the first difference if `first` is
+    /// set.
+    fn compare(&self, a: &Value, b: &Value, first: bool) -> Vec<Difference> {
+        let mut differences = Vec::new();
+        let mut stack = vec![Step::Compare(Path::default(), a, b)];
+
+        while let Some(step) = stack.pop() {
+            let (path, a, b) = match step {
+                Step::Compare(path, a, b) => (path, a.untruncated(), b.untruncated()),
+                Step::Report(path, left, right) => {
+                    differences.push(Difference {
+                        path: path.to_string(),
+                        left: left.cloned(),
+                        right: right.cloned(),
+                    });
+
+                    if first {
+                        break;
+                    }
+                    continue;
+                }
+            };
+
+            let start = stack.len();
+            match (a, b) {
+                (Value::Float(x), Value::Float(y)) if self.floats_equal(*x, *y) => (),
+                (
+                    Value::Struct {
+                        name: a_name,
+                        fields: a_fields,
+                        non_exhaustive: a_non_exhaustive,
+                    },
+                    Value::Struct {
+                        name: b_name,
+                        fields: b_fields,
+                        non_exhaustive: b_non_exhaustive,
+                    },
+                ) if a_name == b_name && a_non_exhaustive == b_non_exhaustive => {
+                    for (name, a) in a_fields {
+                        let child = path.child(PathSegment::Field(name));
+                        stack.push(match b_fields.iter().find(|(field, _)| field == name) {
+                            Some((_, b)) => Step::Compare(child, a, b),
+                            None => Step::Report(child, Some(a), None),
+                        });
+                    }
+                    for (name, b) in b_fields {
+                        if !a_fields.iter().any(|(field, _)| field == name) {
+                            let child = path.child(PathSegment::Field(name));
+                            stack.push(Step::Report(child, None, Some(b)));
+                        }
+                    }
+                }
+                (
+                    Value::TupleStruct {
+                        name: a_name,
+                        elements: a,
+                    },
+                    Value::TupleStruct {
+                        name: b_name,
+                        elements: b,
+                    },
+                ) if a_name == b_name => push_elements(&mut stack, &path, a, b),
+                (Value::Tuple(a), Value::Tuple(b)) | (Value::Seq(a), Value::Seq(b)) => {
+                    push_elements(&mut stack, &path, a, b)
+                }
+                (Value::Set(a), Value::Set(b)) => {
+                    let mut matched = vec![false; b.len()];
+                    let mut rest = Vec::new();
+                    for (index, a) in a.iter().enumerate() {
+                        match (0..b.len()).find(|&i| !matched[i] && b[i] == *a) {
+                            Some(i) => matched[i] = true,
+                            None => rest.push((index, a)),
+                        }
+                    }
+
+                    let mut unmatched = (0..b.len()).filter(|&i| !matched[i]);
+                    for (index, a) in rest {
+                        let child = path.child(PathSegment::Index(index));
+                        stack.push(match unmatched.next() {
+                            Some(i) => Step::Compare(child, a, &b[i]),
+                            None => Step::Report(child, Some(a), None),
+                        });
+                    }
+                    for i in unmatched {
+                        let child = path.child(PathSegment::Index(i));
+                        stack.push(Step::Report(child, None, Some(&b[i])));
+                    }
+                }
+                (Value::Map(a_entries), Value::Map(b_entries)) => {
+                    for (key, a) in a_entries {
+                        let child = path.child(PathSegment::Key(key));
+                        stack.push(match b_entries.iter().find(|(other, _)| other == key) {
+                            Some((_, b)) => Step::Compare(child, a, b),
+                            None => Step::Report(child, Some(a), None),
+                        });
+                    }
+                    for (key, b) in b_entries {
+                        if !a_entries.iter().any(|(other, _)| other == key) {
+                            let child = path.child(PathSegment::Key(key));
+                            stack.push(Step::Report(child, None, Some(b)));
+                        }
+                    }
+                }
+                (Value::Option(Some(a)), Value::Option(Some(b))) => {
+                    stack.push(Step::Compare(path.child(PathSegment::Index(0)), a, b));
+                }
+                _ if is_scalar(a) && a == b => (),
+                _ => stack.push(Step::Report(path, Some(a), Some(b))),
+            }
+            stack[start..].reverse();
+        }
+
+        differences
+    }
+}
+
+/// Compare the elements of two ordered collections by position.
+fn push_elements<'v>(stack: &mut Vec<Step<'v>>, path: &Path<'v>, a: &'v [Value], b: &'v [Value]) {
+    for index in 0..a.len().max(b.len()) {
+        let child = path.child(PathSegment::Index(index));
+        stack.push(match (a.get(index), b.get(index)) {
+            (Some(a), Some(b)) => Step::Compare(child, a, b),
+            (a, b) => Step::Report(child, a, b),
+        });
+    }
+}
+
+/// Whether `value` contains no other values, so that comparing it using `==`
+/// does not recurse.
+fn is_scalar(value: &Value) -> bool {
+    !matches!(
+        value,
+        Value::Struct { .. }
+            | Value::TupleStruct { .. }
+            | Value::Tuple(_)
+            | Value::Seq(_)
+            | Value::Set(_)
+            | Value::Map(_)
+            | Value::Option(Some(_))
+            | Value::Truncated { .. }
+    )
+}
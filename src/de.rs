@@ -1,27 +1,370 @@
-use std::borrow::Cow;
-
-use serde::de::value::BorrowedStrDeserializer;
+use alloc::borrow::{Cow, ToOwned};
+use alloc::boxed::Box;
+use alloc::collections::{BTreeMap, BTreeSet};
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::num::FpCategory;
+use core::time::Duration;
+#[cfg(feature = "std")]
+use std::time::UNIX_EPOCH;
+
+use serde::de::value::{
+    BorrowedStrDeserializer, SeqDeserializer, StrDeserializer, StringDeserializer,
+};
 use serde::de::{Deserializer as _, Error as _, *};
 
+use crate::dialect::{unescape_python, Dialect};
 use crate::error::Expected;
-use crate::lex::{Lexer, Token, TokenKind};
-use crate::Error;
+use crate::flatten::is_ident;
+use crate::lex::{is_operator, is_terminator, strip_generics, Lexer, Token, TokenKind};
+use crate::literal::{self, FloatFormat, FloatLiteral, IntLiteral};
+use crate::stats::StatsCollector;
+use crate::stream::StreamDeserializer;
+#[cfg(feature = "std")]
+use crate::time::RawTime;
+use crate::util::{unescape, unescape_bytes, unescape_into, unescape_with};
+use crate::{value, Addresses, DuplicateKeys, Error, Options, Stats, TokenBuffer, Value, Warning};
+
+/// Magic newtype struct name which causes the deserializer to pass the raw
+/// text of the next value to the visitor as a borrowed string.
+pub(crate) const RAW_VALUE_TOKEN: &str = "$serde_dbgfmt::private::RawValue";
+
+/// Magic newtype struct name used by the `Deserialize` impl for [`Value`].
+///
+/// The value is parsed directly from the input, using the options of the
+/// deserializer, and then passed to the visitor.
+pub(crate) const VALUE_TOKEN: &str = "$serde_dbgfmt::private::Value";
+
+/// Like [`RAW_VALUE_TOKEN`] but the value is scanned character by character
+/// instead of being split into tokens.
+///
+/// This allows capturing values that are not made up of valid tokens, such as
+/// IP addresses or `<opaque>` placeholders.
+pub(crate) const RAW_TEXT_TOKEN: &str = "$serde_dbgfmt::private::RawText";
 
 /// A serde deserializer for rust's debug format.
 pub struct Deserializer<'de> {
     total: &'de str,
     lexer: Lexer<'de>,
+    options: Options,
+
+    /// The name of the struct field whose value is about to be deserialized,
+    /// along with the input starting at that value.
+    ///
+    /// This is used to tell whether a value being skipped is the value of an
+    /// unknown field.
+    field: Option<(&'de str, &'de str)>,
+
+    /// Whether we are currently skipping over the value of an unknown field.
+    skipping: bool,
+
+    /// The number of collections that are currently open.
+    depth: usize,
+
+    stats: Option<Box<StatsCollector>>,
+
+    /// Field names that have already been matched against each struct.
+    fields: FieldCache<'de>,
+
+    /// Buffer that escaped strings are unescaped into, if one was provided.
+    scratch: Option<String>,
+
+    /// The values of unknown fields, if they are being collected.
+    unknown_fields: BTreeMap<String, Value>,
+
+    /// The path to the value currently being deserialized. This is only
+    /// tracked while collecting unknown fields.
+    path: Vec<Step<'de>>,
+}
+
+/// A single step within the path to a value.
+#[derive(Copy, Clone, Debug)]
+enum Step<'de> {
+    Field(&'de str),
+    Index(usize),
+    /// The value of a map entry, along with the input of its key.
+    Key(&'de str),
 }
 
+/// Caches the result of matching field names against the fields of a struct,
+/// keyed by the address of the field list passed to `deserialize_struct`.
+///
+/// Matching a field name that needs to be normalized allocates. Records of the
+/// same type tend to spell their fields the same way so this only needs to be
+/// done once for each distinct spelling.
+type FieldCache<'de> = BTreeMap<(usize, usize), BTreeMap<&'de str, Option<&'static str>>>;
+
 impl<'de> Deserializer<'de> {
     /// Create a deserializer to deserialize from a string.
     pub fn new(data: &'de str) -> Self {
+        Self::with_options(data, Options::default())
+    }
+
+    /// Create a deserializer to deserialize from a string using the provided
+    /// options.
+    pub fn with_options(data: &'de str, options: Options) -> Self {
+        Self::with_lexer(data, Lexer::new(data), options)
+    }
+
+    /// Create a deserializer that reads from a buffer of tokens.
+    ///
+    /// The buffer can be created from the output of a [`Lexer`] using
+    /// [`TokenBuffer::from_tokens`], which allows the tokens to be filtered
+    /// or rewritten before they are deserialized.
+    ///
+    /// # Example
+    /// ```
+    /// use serde::Deserialize;
+    /// use serde_dbgfmt::{Deserializer, Lexer, Token, TokenBuffer, TokenKind};
+    ///
+    /// #[derive(Deserialize)]
+    /// struct Node {
+    ///     parent: u64,
+    ///     name: String,
+    /// }
+    ///
+    /// // Replace pointer addresses with 0 so that the output is reproducible.
+    /// let tokens = Lexer::new(r#"Node { parent: 0x7ffd5e8c1a40, name: "root" }"#).map(|token| {
+    ///     token.map(|token| match token.value.starts_with("0x") {
+    ///         true => Token::new(TokenKind::Integer, "0"),
+    ///         false => token,
+    ///     })
+    /// });
+    /// let tokens = tokens.collect::<Result<Vec<_>, _>>().unwrap();
+    ///
+    /// let mut buf = String::new();
+    /// let buffer = TokenBuffer::from_tokens(tokens, &mut buf);
+    /// let mut de = Deserializer::from_tokens(&buffer);
+    /// let node = Node::deserialize(&mut de).unwrap();
+    /// de.end().unwrap();
+    ///
+    /// assert_eq!(node.parent, 0);
+    /// assert_eq!(node.name, "root");
+    /// ```
+    pub fn from_tokens(buffer: &'de TokenBuffer<'_>) -> Self {
+        Self::from_tokens_with(buffer, Options::default())
+    }
+
+    /// Create a deserializer that reads from a buffer of tokens using the
+    /// provided options.
+    pub fn from_tokens_with(buffer: &'de TokenBuffer<'_>, options: Options) -> Self {
+        Self::with_lexer(buffer.input(), buffer.lexer(), options)
+    }
+
+    pub(crate) fn with_lexer(data: &'de str, mut lexer: Lexer<'de>, options: Options) -> Self {
+        // A lexer over part of a larger input may already share its checks.
+        match lexer.checks() {
+            Some(_) => lexer.set_unknown_handler(options.config().on_unknown_token.clone()),
+            None => lexer.set_options(data, &options),
+        }
+        let stats = match options.config().collect_stats {
+            true => Some(Box::default()),
+            false => None,
+        };
+
         Self {
             total: data,
-            lexer: Lexer::new(data),
+            lexer,
+            options,
+            field: None,
+            skipping: false,
+            depth: 0,
+            stats,
+            fields: BTreeMap::new(),
+            scratch: None,
+            unknown_fields: BTreeMap::new(),
+            path: Vec::new(),
         }
     }
 
+    /// Turn this deserializer into an iterator over the values of type `T`
+    /// within the rest of the input.
+    ///
+    /// The values may be separated by any whitespace, such as one value per
+    /// line in a log file. See [`StreamDeserializer`] for details.
+    // Named to match `serde_json::Deserializer::into_iter`.
+    #[allow(clippy::should_implement_trait)]
+    pub fn into_iter<T>(self) -> StreamDeserializer<'de, T>
+    where
+        T: Deserialize<'de>,
+    {
+        StreamDeserializer::new(self)
+    }
+
+    /// Use `scratch` as the buffer that escaped strings are unescaped into.
+    ///
+    /// Normally a new `String` is allocated for each string containing escape
+    /// sequences. With a scratch buffer the unescaped text is written into the
+    /// buffer instead and passed to the visitor as a borrowed `&str`, which
+    /// avoids the allocation for types that do not need to own their strings.
+    /// The buffer can be recovered with [`into_scratch`](Self::into_scratch)
+    /// and reused when deserializing the next record.
+    ///
+    /// # Example
+    /// ```
+    /// use serde::Deserialize;
+    /// use serde_dbgfmt::Deserializer;
+    ///
+    /// let mut scratch = String::new();
+    /// for line in [r#""plain""#, r#""tab\t""#] {
+    ///     let mut de = Deserializer::new(line).with_scratch(scratch);
+    ///     let text = String::deserialize(&mut de).unwrap();
+    ///     assert!(!text.is_empty());
+    ///     de.end().unwrap();
+    ///     scratch = de.into_scratch();
+    /// }
+    /// ```
+    #[must_use]
+    pub fn with_scratch(mut self, scratch: String) -> Self {
+        self.scratch = Some(scratch);
+        self
+    }
+
+    /// Consume the deserializer, returning the buffer passed to
+    /// [`with_scratch`](Self::with_scratch). An empty `String` is returned if
+    /// there was none.
+    pub fn into_scratch(self) -> String {
+        self.scratch.unwrap_or_default()
+    }
+
+    /// Statistics about the input that has been deserialized so far.
+    ///
+    /// This returns `None` unless stats collection was enabled using
+    /// [`Options::collect_stats`].
+    pub fn stats(&self) -> Option<Stats> {
+        let collector = self.stats.as_ref()?;
+
+        Some(Stats {
+            tokens: self.lexer.token_count(),
+            ..collector.stats.clone()
+        })
+    }
+
+    /// The fields that were present in the input but which the struct being
+    /// deserialized did not know about, along with their values.
+    ///
+    /// This is always empty unless [`Options::collect_unknown_fields`] is
+    /// enabled. Fields are keyed by the path to them, using the same syntax as
+    /// [`flatten`](crate::flatten), e.g. `items[2].extra`. A field of the top
+    /// level struct is keyed by its name alone.
+    ///
+    /// # Example
+    /// ```
+    /// use serde::Deserialize;
+    /// use serde_dbgfmt::{Deserializer, Options};
+    ///
+    /// #[derive(Deserialize)]
+    /// struct User {
+    ///     name: String,
+    /// }
+    ///
+    /// let text = r#"User { name: "alice", id: 7, roles: [Admin] }"#;
+    /// let options = Options::new().collect_unknown_fields(true);
+    /// let mut de = Deserializer::with_options(text, options);
+    /// let user = User::deserialize(&mut de).unwrap();
+    /// de.end().unwrap();
+    ///
+    /// assert_eq!(user.name, "alice");
+    ///
+    /// let unknown = de.unknown_fields();
+    /// assert_eq!(unknown.keys().collect::<Vec<_>>(), ["id", "roles"]);
+    /// assert_eq!(unknown["id"].as_i128(), Some(7));
+    /// assert_eq!(unknown["roles"].to_string(), "[Admin]");
+    /// ```
+    pub fn unknown_fields(&self) -> &BTreeMap<String, Value> {
+        &self.unknown_fields
+    }
+
+    /// Take the unknown fields collected so far, leaving none behind.
+    ///
+    /// See [`unknown_fields`](Self::unknown_fields).
+    pub fn take_unknown_fields(&mut self) -> BTreeMap<String, Value> {
+        core::mem::take(&mut self.unknown_fields)
+    }
+
+    /// Deserialize a value nested within another one, with `step` appended
+    /// to the path while doing so.
+    fn nested<T>(&mut self, step: Step<'de>, seed: T) -> Result<T::Value, Error>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        if !self.options.config().collect_unknown_fields {
+            return seed.deserialize(Nested(self));
+        }
+
+        self.path.push(step);
+        let value = seed.deserialize(Nested(&mut *self));
+        self.path.pop();
+        value
+    }
+
+    /// The path to the current value, in the same form as
+    /// [`flatten`](crate::flatten).
+    fn path(&self) -> Result<String, Error> {
+        fn field(path: &mut String, name: &str) {
+            if !path.is_empty() {
+                path.push('.');
+            }
+            path.push_str(name);
+        }
+
+        let mut path = String::new();
+
+        for step in &self.path {
+            match *step {
+                Step::Field(name) => field(&mut path, name),
+                Step::Index(index) => path.push_str(&format!("[{index}]")),
+                Step::Key(key) => {
+                    let string = match key.strip_prefix('"').and_then(|k| k.strip_suffix('"')) {
+                        Some(inner) => Some(unescape(inner)?),
+                        None => None,
+                    };
+
+                    match string {
+                        Some(string) if is_ident(&string) => field(&mut path, &string),
+                        _ => path.push_str(&format!("[{key}]")),
+                    }
+                }
+            }
+        }
+
+        Ok(path)
+    }
+
+    /// The input that has not been consumed yet.
+    pub(crate) fn remaining(&self) -> &'de str {
+        self.lexer.remaining()
+    }
+
+    /// Attach the position of `error` within the input to it.
+    ///
+    /// The error is normally reported just after the offending token has been
+    /// consumed, so the position is moved back to the start of the token when
+    /// it can be found from the error's span.
+    pub(crate) fn locate(&self, error: Error) -> Error {
+        let mut offset = self.total.len() - self.remaining().len();
+
+        if let Some(span) = error.span() {
+            let rest = self.total[offset..].trim_start();
+
+            if self.total[..offset].ends_with(span) {
+                offset -= span.len();
+            } else if rest.starts_with(span) {
+                offset = self.total.len() - rest.len();
+            }
+        }
+
+        error.locate(self.total, offset)
+    }
+
+    /// Attach the position of `span`, which must be a slice of the input, to
+    /// `error`.
+    fn locate_span(&self, error: Error, span: &str) -> Error {
+        let offset = span.as_ptr() as usize - self.total.as_ptr() as usize;
+        error.locate(self.total, offset)
+    }
+
     /// The `end` method should be called after a value has been fully
     /// deserialized. This allows the deserializer to validate that the input
     /// stream is at the end or that it only has trailing whitespace.
@@ -29,12 +372,71 @@ impl<'de> Deserializer<'de> {
     /// If you would like to parse multiple objects in a stream then you can do
     /// that by calling `deserialize` multiple times and then calling `end` at
     /// the end.
+    ///
+    /// Trailing input is not checked if
+    /// [`Options::allow_trailing_data`] is enabled.
     pub fn end(&mut self) -> Result<(), Error> {
-        let token = self.lexer.parse_token()?;
-        if token.kind != TokenKind::Eof {
-            return Err(Error::unexpected_token(token, TokenKind::Eof));
+        if !self.options.config().allow_trailing_data {
+            let token = self
+                .lexer
+                .parse_token()
+                .map_err(|e| self.locate(e.into()))?;
+            if token.kind != TokenKind::Eof {
+                let error = Error::unexpected_token(token, TokenKind::Eof);
+                return Err(self.locate(error));
+            }
         }
 
+        self.lexer.finish_progress();
+
+        Ok(())
+    }
+
+    /// Deserialize the elements of a list, set, or map one at a time, passing
+    /// each to `func`.
+    ///
+    /// Map entries are deserialized as a `(key, value)` tuple.
+    pub(crate) fn for_each_element<T, E, F>(&mut self, mut func: F) -> Result<(), E>
+    where
+        T: Deserialize<'de>,
+        E: From<Error>,
+        F: FnMut(T) -> Result<(), E>,
+    {
+        let close = match self.parse_punct_ex("`[` or `{`", |v| matches!(v, "[" | "{"))? {
+            "[" => ']',
+            _ => '}',
+        };
+
+        self.enter()?;
+        loop {
+            let token = self.peek()?;
+            if token.kind == TokenKind::Punct && token.value.starts_with(close) {
+                break;
+            }
+
+            // Sets and maps both use braces so we need to look past the first
+            // value to tell whether this is an entry.
+            let entry = close == '}' && {
+                let mut lexer = self.lexer.clone();
+                lexer.skip_key().map_err(Error::from)?;
+                lexer.parse_token().map_err(Error::from)?.is_punct(":")
+            };
+
+            self.element()?;
+            let value = match entry {
+                true => T::deserialize(DebugEntryDeserializer(&mut *self))?,
+                false => T::deserialize(&mut *self)?,
+            };
+            func(value)?;
+
+            let token = self.peek()?;
+            if !(token.kind == TokenKind::Punct && token.value.starts_with(close)) {
+                self.parse_punct(',')?;
+            }
+        }
+        self.parse_punct(close)?;
+        self.leave();
+
         Ok(())
     }
 }
@@ -47,23 +449,273 @@ enum Sign {
 
 struct Integer<'de> {
     sign: Sign,
-    value: &'de str,
+    value: Cow<'de, str>,
     span: &'de str,
 }
 
 struct Float<'de> {
     sign: Sign,
-    value: &'de str,
+    value: Cow<'de, str>,
     span: &'de str,
     kind: TokenKind,
 }
 
+/// A collection that is open while skipping over a value.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+enum Skipped {
+    /// `Name { field: value, .. }`
+    Struct,
+    /// `(a, b)` or `Name(a, b)`
+    Tuple,
+    /// `[a, b]`
+    Seq,
+    /// A `{` that could still be either a set or a map.
+    Braced,
+    Set,
+    /// A map, along with whether the value of an entry follows.
+    Map {
+        value: bool,
+    },
+}
+
+impl Skipped {
+    fn close(self) -> &'static str {
+        match self {
+            Self::Tuple => ")",
+            Self::Seq => "]",
+            _ => "}",
+        }
+    }
+}
+
 struct Str<'de> {
     span: &'de str,
     value: Cow<'de, str>,
 }
 
 impl<'de> Deserializer<'de> {
+    /// Called when entering a collection (a struct, tuple, sequence, or map).
+    pub(crate) fn enter(&mut self) -> Result<(), Error> {
+        if let Some(stats) = &mut self.stats {
+            stats.enter();
+        }
+
+        self.depth += 1;
+        match self.options.config().max_depth {
+            Some(limit) if self.depth > limit => Err(Error::depth_limit_exceeded(limit)),
+            _ => Ok(()),
+        }
+    }
+
+    /// Skip over the next value.
+    ///
+    /// This uses an explicit stack instead of recursing so that arbitrarily
+    /// deeply nested input can be skipped without overflowing the stack. The
+    /// value must still be well-formed: elements are separated by commas,
+    /// struct fields and map entries are written as `key: value`, and so on.
+    fn skip_ignored(&mut self) -> Result<(), Error> {
+        let mut stack = Vec::new();
+
+        'value: loop {
+            // Ranges may have no start bound.
+            let operand = !is_operator(&self.peek()?) || self.skip_operator()?;
+            if operand && !self.skip_primary(&mut stack)? {
+                continue;
+            }
+
+            // The value is complete so add it to the collection that contains
+            // it. This may in turn complete that collection, and so on.
+            loop {
+                while is_operator(&self.peek()?) {
+                    if self.skip_operator()? && !self.skip_primary(&mut stack)? {
+                        continue 'value;
+                    }
+                }
+
+                let Some(frame) = stack.last_mut() else {
+                    return Ok(());
+                };
+
+                if self.skip_push(frame)? || !self.skip_next_element(&mut stack)? {
+                    continue 'value;
+                }
+            }
+        }
+    }
+
+    /// Skip over an operator. Returns whether it is followed by an operand.
+    fn skip_operator(&mut self) -> Result<bool, Error> {
+        self.lexer.parse_token()?;
+
+        // Ranges may also have no end bound.
+        Ok(!is_terminator(&self.peek()?))
+    }
+
+    /// Skip over a value that is not built from operators.
+    ///
+    /// Returns true if the value is complete. Otherwise the value opened a
+    /// collection, which has been pushed onto `stack`, and its first element
+    /// follows.
+    fn skip_primary(&mut self, stack: &mut Vec<Skipped>) -> Result<bool, Error> {
+        let token = self.lexer.parse_token()?;
+
+        let open = match (token.kind, token.value) {
+            (TokenKind::Ident, "b")
+                if matches!(self.peek()?.kind, TokenKind::String | TokenKind::Char) =>
+            {
+                self.lexer.parse_token()?;
+                return Ok(true);
+            }
+            (TokenKind::Ident, _) => {
+                let open = match self.peek()? {
+                    next if next.is_punct("{") => Skipped::Struct,
+                    next if next.is_punct("(") => Skipped::Tuple,
+                    _ => return Ok(true),
+                };
+
+                self.lexer.parse_token()?;
+                open
+            }
+            (TokenKind::Punct, "+" | "-") => {
+                let number = self.lexer.parse_token()?;
+                return match number.kind {
+                    TokenKind::Integer | TokenKind::Float => self.skip_suffix(),
+                    TokenKind::Ident if literal::is_debug_float_ident(number.value) => Ok(true),
+                    _ => Err(Error::unexpected_token(number, "a number")),
+                };
+            }
+            (TokenKind::Punct, "(") => Skipped::Tuple,
+            (TokenKind::Punct, "[") => Skipped::Seq,
+            (TokenKind::Punct, "{") => Skipped::Braced,
+            (TokenKind::Punct | TokenKind::Eof, _) => {
+                return Err(Error::unexpected_token(token, "a value"))
+            }
+            (TokenKind::Integer | TokenKind::Float, _) => return self.skip_suffix(),
+            _ => return Ok(true),
+        };
+
+        self.enter()?;
+        stack.push(open);
+        self.skip_next_element(stack)
+    }
+
+    /// Skip over a type suffix directly following a number, such as the `u8`
+    /// in `3u8`.
+    fn skip_suffix(&mut self) -> Result<bool, Error> {
+        let adjacent = self
+            .remaining()
+            .starts_with(|c: char| c.is_ascii_alphabetic());
+        if adjacent && self.peek()?.kind == TokenKind::Ident {
+            self.lexer.parse_token()?;
+        }
+
+        Ok(true)
+    }
+
+    /// Record a complete value in the innermost collection and skip the
+    /// separator that follows it.
+    ///
+    /// Returns true if the value was a map key, in which case the map value
+    /// follows.
+    fn skip_push(&mut self, frame: &mut Skipped) -> Result<bool, Error> {
+        *frame = match *frame {
+            Skipped::Braced if self.peek()?.is_punct(":") => Skipped::Map { value: true },
+            Skipped::Braced => Skipped::Set,
+            Skipped::Map { value } => Skipped::Map { value: !value },
+            frame => frame,
+        };
+
+        match *frame {
+            Skipped::Map { value: true } => {
+                self.lexer.expect_punct(":")?;
+                return Ok(true);
+            }
+            frame if self.peek()?.is_punct(frame.close()) => (),
+            _ => self.lexer.expect_punct(",")?,
+        }
+
+        Ok(false)
+    }
+
+    /// Prepare to skip the next element of the innermost collection.
+    ///
+    /// Returns true if the collection ended instead, in which case it is
+    /// removed from `stack`.
+    fn skip_next_element(&mut self, stack: &mut Vec<Skipped>) -> Result<bool, Error> {
+        let frame = *stack.last().expect("no collection is open");
+
+        if frame == Skipped::Struct {
+            let token = self.lexer.parse_token()?;
+            match token.kind {
+                _ if token.is_punct("}") => (),
+                _ if token.is_punct("..") => self.lexer.expect_punct("}")?,
+                TokenKind::Ident => {
+                    self.lexer.expect_punct(":")?;
+                    self.element()?;
+                    return Ok(false);
+                }
+                _ => return Err(Error::unexpected_token(token, TokenKind::Ident)),
+            }
+        } else if self.peek()?.is_punct(frame.close()) {
+            self.lexer.parse_token()?;
+        } else {
+            self.element()?;
+            return Ok(false);
+        }
+
+        stack.pop();
+        self.leave();
+        Ok(true)
+    }
+
+    /// Called before each element of a sequence, map, or struct.
+    fn element(&mut self) -> Result<(), Error> {
+        if let Some(stats) = &mut self.stats {
+            stats.element();
+        }
+
+        Ok(())
+    }
+
+    fn leave(&mut self) {
+        if let Some(stats) = &mut self.stats {
+            stats.leave();
+        }
+
+        self.depth -= 1;
+    }
+
+    fn unescaped(&mut self, value: &str) {
+        if let Some(stats) = &mut self.stats {
+            stats.stats.unescaped_bytes += value.len();
+        }
+    }
+
+    /// Record whether a string literal could be borrowed from the input.
+    ///
+    /// This is an error for strings that could not be borrowed if
+    /// [`Options::require_borrowed`] is enabled.
+    fn borrowed(&mut self, span: &'de str, borrowed: bool) -> Result<(), Error> {
+        if let Some(stats) = &mut self.stats {
+            match borrowed {
+                true => stats.stats.borrowed_strings += 1,
+                false => stats.stats.owned_strings += 1,
+            }
+        }
+
+        if !borrowed && self.options.config().require_borrowed {
+            return Err(self.locate_span(Error::not_borrowed(span), span));
+        }
+
+        Ok(())
+    }
+
+    fn warn(&self, warning: Warning<'_>) {
+        if let Some(callback) = &self.options.config().on_warning {
+            (callback.0)(&warning);
+        }
+    }
+
     fn join_spans(&self, a: &'de str, b: &'de str) -> &'de str {
         let range = self.total.as_bytes().as_ptr_range();
         let range = range.start..=range.end;
@@ -88,9 +740,183 @@ impl<'de> Deserializer<'de> {
     }
 
     fn peek(&self) -> Result<Token<'de>, Error> {
+        self.lexer.peek_token().map_err(From::from)
+    }
+
+    /// Whether the next value is a `SystemTime` printed in the form used by
+    /// its serde impl, rather than by its `Debug` impl.
+    #[cfg(feature = "std")]
+    fn is_serde_system_time(&self) -> Result<bool, Error> {
         let mut lexer = self.lexer.clone();
 
-        lexer.parse_token().map_err(From::from)
+        lexer.parse_token()?;
+        lexer.parse_token()?;
+        Ok(lexer.parse_token()?.value == "secs_since_epoch")
+    }
+
+    /// Consume the next token if it is a pointer address that should be
+    /// treated as `()`, returning whether it was.
+    fn skip_address(&mut self) -> Result<bool, Error> {
+        let token = self.peek()?;
+        if token.kind != TokenKind::Address {
+            return Ok(false);
+        }
+
+        match self.options.config().addresses {
+            Addresses::Integer => Ok(false),
+            Addresses::Skip => {
+                self.lexer.parse_token()?;
+                Ok(true)
+            }
+            Addresses::Error => {
+                self.lexer.parse_token()?;
+                Err(Error::address_not_allowed(token.value))
+            }
+        }
+    }
+
+    /// The name of the field holding the value if the next value is a
+    /// standard library wrapper such as `RefCell { value: 42 }`.
+    ///
+    /// `target` is the name of the struct being deserialized, if any. A struct
+    /// with the same name as a wrapper is deserialized as-is.
+    fn std_wrapper(&self, target: Option<&str>) -> Result<Option<&'static str>, Error> {
+        let mut lexer = self.lexer.clone();
+        let token = lexer.parse_token()?;
+        if token.kind != TokenKind::Ident {
+            return Ok(None);
+        }
+
+        let name = self.type_name(token.value);
+        if target == Some(name) {
+            return Ok(None);
+        }
+
+        let field = match name {
+            "Cell" | "RefCell" => "value",
+            "Mutex" | "RwLock" => "data",
+            _ => return Ok(None),
+        };
+
+        match (lexer.parse_token()?.is_punct("{"), lexer.parse_token()?) {
+            (true, token) if token.kind == TokenKind::Ident && token.value == field => {
+                Ok(Some(field))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    /// Whether the `{` at the current position opens a set rather than a map.
+    ///
+    /// This looks for a `:` within the first element. Empty braces are
+    /// treated as a map. If the input can't be split into tokens then this
+    /// also returns false and leaves the error to be reported by the map.
+    fn is_set(&self) -> bool {
+        let mut lexer = self.lexer.clone();
+        let mut depth = 0usize;
+        let mut empty = true;
+
+        let _ = lexer.parse_token();
+        while let Ok(token) = lexer.parse_token() {
+            match (token.kind, token.value) {
+                (TokenKind::Eof, _) => break,
+                (TokenKind::Punct, ":") if depth == 0 => return false,
+                (TokenKind::Punct, ",") if depth == 0 => return true,
+                (TokenKind::Punct, ")" | "]" | "}") if depth == 0 => return !empty,
+                (TokenKind::Punct, "(" | "[" | "{") => depth += 1,
+                (TokenKind::Punct, ")" | "]" | "}") => depth -= 1,
+                _ => (),
+            }
+
+            empty = false;
+        }
+
+        false
+    }
+
+    /// Whether the next value is a python `set(..)` or `frozenset(..)`.
+    fn is_python_set(&self) -> Result<bool, Error> {
+        let token = self.peek()?;
+        Ok(self.is_python()
+            && token.kind == TokenKind::Ident
+            && matches!(token.value, "set" | "frozenset")
+            && self.peek2()?.is_punct("("))
+    }
+
+    /// Whether the next value is `Some(..)`.
+    fn is_some(&self) -> Result<bool, Error> {
+        let token = self.peek()?;
+        Ok(token.kind == TokenKind::Ident
+            && self.type_name(token.value) == "Some"
+            && self.peek2()?.is_punct("("))
+    }
+
+    /// Deserialize a value using `func`, first unwrapping any standard library
+    /// wrappers around it if [`Options::unwrap_std_wrappers`] is enabled.
+    ///
+    /// If `some` is true then `Some(..)` is unwrapped as well when
+    /// [`Options::unwrap_some`] is enabled. `target` is the name of the struct
+    /// being deserialized, as for [`std_wrapper`](Self::std_wrapper).
+    fn unwrapped<T, F>(&mut self, some: bool, target: Option<&str>, func: F) -> Result<T, Error>
+    where
+        F: FnOnce(&mut Self) -> Result<T, Error>,
+    {
+        let config = self.options.config();
+        let std = config.unwrap_std_wrappers;
+        let some = some && config.unwrap_some;
+        if !std && !some {
+            return func(self);
+        }
+
+        // Whether each wrapper that has been opened is a `Some`, innermost
+        // last.
+        let mut wrappers = Vec::new();
+        loop {
+            if std && self.std_wrapper(target)?.is_some() {
+                self.lexer.parse_token()?;
+                self.parse_punct('{')?;
+                self.enter()?;
+                self.lexer.parse_token()?;
+                self.parse_punct(':')?;
+                wrappers.push(false);
+            } else if some && self.is_some()? {
+                self.lexer.parse_token()?;
+                self.parse_punct('(')?;
+                self.enter()?;
+                wrappers.push(true);
+            } else {
+                break;
+            }
+        }
+
+        let value = func(self)?;
+
+        while let Some(is_some) = wrappers.pop() {
+            if is_some {
+                self.parse_newtype_end()?;
+                self.leave();
+                continue;
+            }
+
+            // Skip the rest of the fields, such as `poisoned: false, ..`.
+            while !self.peek()?.is_punct("}") {
+                self.parse_punct(',')?;
+                match self.peek()? {
+                    token if token.is_punct("}") => break,
+                    token if token.is_punct("..") => drop(self.lexer.parse_token()?),
+                    _ => {
+                        self.lexer.skip_key()?;
+                        self.parse_punct(':')?;
+                        self.lexer.skip_value()?;
+                    }
+                }
+            }
+
+            self.parse_punct('}')?;
+            self.leave();
+        }
+
+        Ok(value)
     }
 
     fn peek2(&self) -> Result<Token<'de>, Error> {
@@ -122,15 +948,26 @@ impl<'de> Deserializer<'de> {
             token = self.lexer.parse_token()?;
         }
 
+        let start = sign_span.unwrap_or(token.value);
+        let (token, value) = self.parse_digit_groups(token);
+        let span = self.join_spans(start, token.value);
+
         match token.kind {
-            TokenKind::Integer => Ok(Integer {
-                sign,
-                value: token.value,
-                span: match sign_span {
-                    Some(span) => self.join_spans(span, token.value),
-                    None => token.value,
-                },
-            }),
+            TokenKind::Integer => Ok(Integer { sign, value, span }),
+            TokenKind::Address if self.options.config().addresses == Addresses::Error => {
+                Err(Error::address_not_allowed(token.value))
+            }
+            TokenKind::Address => Ok(Integer { sign, value, span }),
+            TokenKind::Float if self.options.config().coerce_numbers => {
+                let value = float_to_integer_literal(&value)?;
+                self.warn(Warning::NumberCoerced { literal: span });
+
+                Ok(Integer {
+                    sign,
+                    value: Cow::Owned(value),
+                    span,
+                })
+            }
             _ => Err(Error::unexpected_token(token, TokenKind::Integer)),
         }
     }
@@ -157,28 +994,163 @@ impl<'de> Deserializer<'de> {
             token = self.lexer.parse_token()?;
         }
 
-        let span = match sign_span {
-            Some(span) => self.join_spans(span, token.value),
-            None => token.value,
-        };
+        let start = sign_span.unwrap_or(token.value);
+        let (token, value) = self.parse_digit_groups(token);
+        let span = self.join_spans(start, token.value);
 
         match token.kind {
             TokenKind::Float => Ok(Float {
                 sign,
-                value: token.value,
+                value,
                 span,
                 kind: token.kind,
             }),
-            TokenKind::Ident if token.value.eq_ignore_ascii_case("NaN") => Ok(Float {
+            TokenKind::Ident if literal::is_float_ident(token.value) => Ok(Float {
                 sign,
-                value: token.value,
+                value,
                 span,
                 kind: token.kind,
             }),
+            TokenKind::Integer if self.options.config().coerce_numbers => {
+                self.warn(Warning::NumberCoerced { literal: span });
+
+                Ok(Float {
+                    sign,
+                    value,
+                    span,
+                    kind: token.kind,
+                })
+            }
             _ => Err(Error::unexpected_token(token, TokenKind::Float)),
         }
     }
 
+    /// Merge a number written with thousands separators (e.g. `1,000,000`)
+    /// into a single literal, if the option to do so is enabled.
+    ///
+    /// Since `,` is also the separator between elements the groups are only
+    /// merged when they follow the digit-grouping pattern exactly: the first
+    /// group has 1-3 digits, every following group has exactly 3 digits, and
+    /// there is no whitespace anywhere within the number. The last group may
+    /// have a fractional part or exponent.
+    ///
+    /// Returns the last token that is part of the number along with the
+    /// merged literal text.
+    fn parse_digit_groups(&mut self, first: Token<'de>) -> (Token<'de>, Cow<'de, str>) {
+        fn is_group(token: &Token, len: usize) -> bool {
+            let digits = token
+                .value
+                .find(|c: char| !c.is_ascii_digit())
+                .unwrap_or(token.value.len());
+
+            digits == len && (token.kind == TokenKind::Integer || digits > 0)
+        }
+
+        fn is_adjacent(a: &str, b: &str) -> bool {
+            a.as_ptr().wrapping_add(a.len()) == b.as_ptr()
+        }
+
+        let value = Cow::Borrowed(first.value);
+        if !self.options.config().digit_grouping
+            || first.kind != TokenKind::Integer
+            || first.value.starts_with('0')
+            || !(1..=3).any(|len| is_group(&first, len))
+        {
+            return (first, value);
+        }
+
+        let mut last = first;
+        let mut value = String::from(first.value);
+        while last.kind == TokenKind::Integer {
+            let mut lexer = self.lexer.clone();
+            let (comma, group) = match (lexer.parse_token(), lexer.parse_token()) {
+                (Ok(comma), Ok(group)) => (comma, group),
+                _ => break,
+            };
+
+            if !comma.is_punct(",")
+                || !matches!(group.kind, TokenKind::Integer | TokenKind::Float)
+                || !is_adjacent(last.value, comma.value)
+                || !is_adjacent(comma.value, group.value)
+                || !is_group(&group, 3)
+            {
+                break;
+            }
+
+            value.push_str(group.value);
+            last = group;
+            self.lexer = lexer;
+        }
+
+        (last, Cow::Owned(value))
+    }
+
+    pub(crate) fn parse_int_value<T: IntLiteral>(&mut self) -> Result<T, Error> {
+        let int = self.parse_integer()?;
+
+        literal::parse_int(int.sign == Sign::Negative, &int.value)
+            .map_err(|e| Error::parse_int(int.span, e))
+    }
+
+    pub(crate) fn parse_float_value<T: FloatLiteral>(&mut self) -> Result<T, Error> {
+        let float = self.parse_float()?;
+        let value = match float.kind {
+            TokenKind::Ident if float.value.eq_ignore_ascii_case("NaN") => T::NAN,
+            TokenKind::Ident => T::INFINITY,
+            TokenKind::Float if literal::is_hex_float(&float.value) => {
+                T::parse_hex(&float.value).ok_or_else(|| Error::invalid_hex_float(float.span))?
+            }
+            TokenKind::Float => {
+                T::parse_decimal(&float.value).map_err(|e| Error::parse_float(float.span, e))?
+            }
+            TokenKind::Integer => literal::parse_int::<u128>(false, &float.value)
+                .map(T::from_u128)
+                .map_err(|e| Error::parse_int(float.span, e))?,
+            _ => unreachable!(),
+        };
+
+        if self.options.config().exact_floats {
+            let exact = match float.kind {
+                TokenKind::Float if literal::is_hex_float(&float.value) => true,
+                TokenKind::Float => literal::is_exact_decimal(&float.value, &format!("{value:?}")),
+                TokenKind::Integer => {
+                    let magnitude = literal::parse_int::<u128>(false, &float.value)
+                        .map_err(|e| Error::parse_int(float.span, e))?;
+                    literal::is_exact_decimal(&magnitude.to_string(), &format!("{value:?}"))
+                }
+                _ => true,
+            };
+
+            if !exact {
+                let error = Error::custom(format_args!(
+                    "float literal `{}` cannot be represented exactly as {}",
+                    float.span,
+                    core::any::type_name::<T>()
+                ));
+                return Err(self.locate_span(error, float.span));
+            }
+        }
+
+        if float.kind == TokenKind::Float {
+            match value.category() {
+                FpCategory::Infinite => self.warn(Warning::FloatOverflow {
+                    literal: float.span,
+                }),
+                FpCategory::Zero if literal::has_nonzero_mantissa(&float.value) => {
+                    self.warn(Warning::FloatUnderflow {
+                        literal: float.span,
+                    })
+                }
+                _ => (),
+            }
+        }
+
+        Ok(match float.sign {
+            Sign::Positive => value,
+            Sign::Negative => value.negate(),
+        })
+    }
+
     fn parse_ident(&mut self) -> Result<&'de str, Error> {
         let token = self.lexer.parse_token()?;
 
@@ -190,38 +1162,201 @@ impl<'de> Deserializer<'de> {
 
     fn parse_ident_exact(&mut self, expected: &'de str) -> Result<(), Error> {
         let token = self.lexer.parse_token()?;
+        let name = self.type_name(token.value);
 
         match token.kind {
-            TokenKind::Ident if token.value == expected => Ok(()),
-            TokenKind::Ident => Err(Error::unexpected_token(token, expected)),
+            TokenKind::Ident if token.value == expected || name == expected => Ok(()),
+            TokenKind::Ident if self.normalize(name) == self.normalize(expected) => Ok(()),
+            TokenKind::Ident if self.options.config().ignore_struct_names => Ok(()),
+            TokenKind::Ident => Err(Error::unexpected_token(token, expected.to_owned())),
             _ => Err(Error::unexpected_token(token, TokenKind::Ident)),
         }
     }
 
-    fn parse_string(&mut self) -> Result<Str<'de>, Error> {
-        let token = self.lexer.parse_token()?;
-        if token.kind != TokenKind::String {
-            return Err(Error::unexpected_token(token, TokenKind::String));
+    /// The name of a type or variant as it should be compared to the names
+    /// that serde uses.
+    ///
+    /// Names printed by custom `Debug` impls may include generic arguments,
+    /// which serde's names never do. The path is removed as well if
+    /// [`Options::ignore_type_paths`] is enabled.
+    fn type_name<'a>(&self, ident: &'a str) -> &'a str {
+        let name = strip_generics(ident);
+        match self.options.config().ignore_type_paths {
+            true => name.rsplit("::").next().unwrap_or(name),
+            false => name,
+        }
+    }
+
+    /// Whether `ident` is changed by [`normalize`](Self::normalize).
+    fn needs_normalization(&self, ident: &str) -> bool {
+        #[cfg(feature = "unicode_normalization")]
+        if self.options.config().normalize_identifiers {
+            return !unicode_normalization::is_nfc(ident);
         }
 
-        let inner = &token.value[1..token.value.len() - 1];
-        Ok(Str {
-            span: token.value,
-            value: unescape(inner)?,
-        })
+        let _ = ident;
+        false
+    }
+
+    /// Normalize an identifier to NFC, if enabled in the options.
+    fn normalize<'a>(&self, ident: &'a str) -> Cow<'a, str> {
+        #[cfg(feature = "unicode_normalization")]
+        if self.needs_normalization(ident) {
+            use unicode_normalization::UnicodeNormalization;
+
+            return Cow::Owned(ident.nfc().collect());
+        }
+
+        Cow::Borrowed(ident)
+    }
+
+    /// Normalize the name of a field of a struct with the given `fields`.
+    ///
+    /// Names that need to be normalized are matched against `fields` once and
+    /// then served from the cache.
+    fn normalize_field(
+        &mut self,
+        ident: &'de str,
+        fields: Option<&'static [&'static str]>,
+    ) -> Cow<'de, str> {
+        let Some(fields) = fields else {
+            return self.normalize(ident);
+        };
+        if !self.needs_normalization(ident) {
+            return Cow::Borrowed(ident);
+        }
+
+        let key = (fields.as_ptr() as usize, fields.len());
+        if let Some(&field) = self.fields.get(&key).and_then(|names| names.get(ident)) {
+            return field
+                .map(Cow::Borrowed)
+                .unwrap_or_else(|| self.normalize(ident));
+        }
+
+        let normalized = self.normalize(ident);
+        let field = fields.iter().copied().find(|field| *field == normalized);
+        self.fields.entry(key).or_default().insert(ident, field);
+
+        match field {
+            Some(field) => Cow::Borrowed(field),
+            None => normalized,
+        }
+    }
+
+    fn is_python(&self) -> bool {
+        self.options.config().dialect == Dialect::PythonRepr
+    }
+
+    fn parse_string(&mut self) -> Result<Str<'de>, Error> {
+        self.parse_quoted(TokenKind::String)
     }
 
     fn parse_char(&mut self) -> Result<Str<'de>, Error> {
+        self.parse_quoted(TokenKind::Char)
+    }
+
+    /// Parse a string or character literal.
+    ///
+    /// Python doesn't have character literals and allows strings to use either
+    /// quote so both token kinds are accepted there.
+    fn parse_quoted(&mut self, kind: TokenKind) -> Result<Str<'de>, Error> {
+        let span = self.parse_quoted_token(kind)?;
+
+        // Strings produced by `Options::on_unknown_token` have no quotes and
+        // are used as-is.
+        if !span.starts_with(['"', '\'']) {
+            return Ok(Str {
+                span,
+                value: Cow::Borrowed(span),
+            });
+        }
+
+        let inner = &span[1..span.len() - 1];
+        let value = match self.is_python() {
+            true => unescape_python(inner)?,
+            false => unescape_with(inner, |escape| self.surrogate(escape))?,
+        };
+
+        if let Cow::Owned(value) = &value {
+            self.unescaped(value);
+        }
+
+        Ok(Str { span, value })
+    }
+
+    /// Parse a string or character token and return its text, including the
+    /// quotes.
+    fn parse_quoted_token(&mut self, kind: TokenKind) -> Result<&'de str, Error> {
         let token = self.lexer.parse_token()?;
-        if token.kind != TokenKind::Char {
-            return Err(Error::unexpected_token(token, TokenKind::Char));
+
+        match token.kind {
+            TokenKind::String | TokenKind::Char if self.is_python() => Ok(token.value),
+            actual if actual == kind => Ok(token.value),
+            _ => Err(Error::unexpected_token(token, kind)),
         }
+    }
 
-        let inner = &token.value[1..token.value.len() - 1];
-        Ok(Str {
-            span: token.value,
-            value: unescape(inner)?,
-        })
+    /// Parse a byte string literal such as `b"abc\xff"`, if the input is at
+    /// one.
+    ///
+    /// The lexer sees these as a `b` identifier that is immediately followed
+    /// by a string. Python writes them with either quote.
+    fn parse_byte_string(&mut self) -> Result<Option<Cow<'de, [u8]>>, Error> {
+        let prefix = self.peek()?;
+        if prefix.kind != TokenKind::Ident || prefix.value != "b" {
+            return Ok(None);
+        }
+
+        let literal = self.peek2()?;
+        let quoted = match literal.kind {
+            TokenKind::String => true,
+            TokenKind::Char => self.is_python(),
+            _ => false,
+        };
+        if !quoted || prefix.value.as_ptr().wrapping_add(1) != literal.value.as_ptr() {
+            return Ok(None);
+        }
+
+        self.lexer.parse_token()?;
+        self.lexer.parse_token()?;
+
+        let inner = &literal.value[1..literal.value.len() - 1];
+        unescape_bytes(inner)
+            .map(Some)
+            .map_err(|e| self.locate_span(e, literal.value))
+    }
+
+    /// Unescape the string literal `span` into `scratch`, replacing its
+    /// previous contents.
+    fn unescape_into_scratch(&mut self, span: &'de str, scratch: &mut String) -> Result<(), Error> {
+        scratch.clear();
+        unescape_into(&span[1..span.len() - 1], scratch, |escape| {
+            self.surrogate(escape)
+        })?;
+        self.unescaped(scratch);
+        Ok(())
+    }
+
+    /// Get the replacement for a `\u{..}` escape of a lone surrogate.
+    fn surrogate(&self, escape: &str) -> Option<char> {
+        if !self.options.config().replace_lone_surrogates {
+            return None;
+        }
+
+        self.warn(Warning::SurrogateReplaced { escape });
+        Some(char::REPLACEMENT_CHARACTER)
+    }
+
+    /// Parse the `)` that closes `Some` or a newtype struct or variant.
+    ///
+    /// Pretty-printed output (`{:#?}`) puts the value on its own line followed
+    /// by a trailing comma.
+    fn parse_newtype_end(&mut self) -> Result<(), Error> {
+        if self.peek()?.is_punct(",") {
+            self.lexer.parse_token()?;
+        }
+
+        self.parse_punct(')')
     }
 
     fn parse_punct(&mut self, punct: char) -> Result<(), Error> {
@@ -254,14 +1389,21 @@ impl<'de> Deserializer<'de> {
         Ok(token.value)
     }
 
-    fn deserialize_struct_dyn<V>(&mut self, name: &'de str, visitor: V) -> Result<V::Value, Error>
+    fn deserialize_struct_dyn<V>(
+        &mut self,
+        name: &'de str,
+        fields: Option<&'static [&'static str]>,
+        visitor: V,
+    ) -> Result<V::Value, Error>
     where
         V: Visitor<'de>,
     {
         self.parse_ident_exact(name)?;
         self.parse_punct('{')?;
-        let value = visitor.visit_map(DebugStructAccess(&mut *self))?;
+        self.enter()?;
+        let value = visitor.visit_map(DebugStructAccess::new(&mut *self, fields))?;
         self.parse_punct('}')?;
+        self.leave();
         Ok(value)
     }
 
@@ -292,65 +1434,14 @@ impl<'de> Deserializer<'de> {
     }
 }
 
-macro_rules! deserialize_unsigned {
-    ($deserialize:ident, $uint:ty, $visit:ident) => {
-        fn $deserialize<V>(self, visitor: V) -> Result<V::Value, Self::Error>
-        where
-            V: Visitor<'de>,
-        {
-            let int = self.parse_integer()?;
-            let result = match int.value.get(..2) {
-                _ if int.sign == Sign::Negative => "-1".parse(),
-                Some("0x" | "0X") => <$uint>::from_str_radix(&int.value[2..], 16),
-                Some("0o" | "0O") => <$uint>::from_str_radix(&int.value[2..], 8),
-                Some("0b" | "0B") => <$uint>::from_str_radix(&int.value[2..], 2),
-                _ => int.value.parse(),
-            };
-
-            match result {
-                Ok(value) => visitor.$visit(value),
-                Err(e) => Err(Error::parse_int(int.span, e)),
-            }
-        }
-    };
-}
-
-macro_rules! deserialize_signed {
+macro_rules! deserialize_integer {
     ($deserialize:ident, $int:ty, $visit:ident) => {
         fn $deserialize<V>(self, visitor: V) -> Result<V::Value, Self::Error>
         where
             V: Visitor<'de>,
         {
-            let int = self.parse_integer()?;
-            let (rest, radix) = match int.value.get(..2) {
-                Some("0x" | "0X") => (&int.value[2..], 16),
-                Some("0o" | "0O") => (&int.value[2..], 8),
-                Some("0b" | "0B") => (&int.value[2..], 2),
-                _ => (int.value, 10),
-            };
-
-            let trimmed = match rest.trim_matches('0') {
-                "" => "0",
-                trimmed => trimmed,
-            };
-
-            // Copy the string to a temporary buffer so that we get the proper error type
-            // when parsing overlarge signed integers.
-            let mut storage = [0xFF; <$int>::MAX.ilog10() as usize + 2];
-            storage[0] = match int.sign {
-                Sign::Positive => b'+',
-                Sign::Negative => b'-',
-            };
-
-            let len = trimmed.len().min(storage.len() - 1);
-            storage[1..][..len].copy_from_slice(&trimmed.as_bytes()[..len]);
-
-            let value = unsafe { std::str::from_utf8_unchecked(&storage[..len + 1]) };
-
-            match <$int>::from_str_radix(value, radix) {
-                Ok(value) => visitor.$visit(value),
-                Err(e) => Err(Error::parse_int(int.span, e)),
-            }
+            let value = self.parse_int_value::<$int>()?;
+            visitor.$visit(value)
         }
     };
 }
@@ -369,10 +1460,21 @@ impl<'de> serde::de::Deserializer<'de> for &'_ mut Deserializer<'de> {
         let token = self.peek()?;
         match (token.kind, token.value) {
             (TokenKind::String, _) => self.deserialize_str(visitor),
+            (TokenKind::Char, _) if self.is_python() => self.deserialize_str(visitor),
             (TokenKind::Char, _) => self.deserialize_char(visitor),
+            (TokenKind::Ident, "True" | "False") if self.is_python() => {
+                self.deserialize_bool(visitor)
+            }
+            (TokenKind::Ident, "None") if self.is_python() => self.deserialize_option(visitor),
 
             (TokenKind::Integer, _) => self.deserialize_u64(visitor),
+            (TokenKind::Address, _) if self.skip_address()? => visitor.visit_unit(),
+            (TokenKind::Address, _) => self.deserialize_u64(visitor),
             (TokenKind::Float, _) => self.deserialize_f64(visitor),
+            (TokenKind::Duration, value) => {
+                self.lexer.parse_token()?;
+                visitor.visit_borrowed_str(value)
+            }
             (TokenKind::Punct, sign @ ("+" | "-")) => {
                 let peek2 = self.peek2()?;
                 match peek2.kind {
@@ -380,26 +1482,36 @@ impl<'de> serde::de::Deserializer<'de> for &'_ mut Deserializer<'de> {
                     TokenKind::Integer if sign == "-" => self.deserialize_i64(visitor),
                     TokenKind::Integer => unreachable!(),
                     TokenKind::Float => self.deserialize_f64(visitor),
+                    TokenKind::Ident if literal::is_debug_float_ident(peek2.value) => {
+                        self.deserialize_f64(visitor)
+                    }
                     _ => Err(Error::unexpected_token(peek2, "an integer or a float")),
                 }
             }
 
+            (TokenKind::Ident, _) if self.is_python_set()? => self.deserialize_seq(visitor),
+            (TokenKind::Ident, "b")
+                if matches!(self.peek2()?.kind, TokenKind::String | TokenKind::Char) =>
+            {
+                self.deserialize_bytes(visitor)
+            }
             (TokenKind::Ident, value) => {
                 let peek2 = self.peek2()?;
                 match (peek2.kind, peek2.value) {
-                    (TokenKind::Punct, "{") => self.deserialize_struct_dyn(value, visitor),
+                    (TokenKind::Punct, "{") => self.deserialize_struct_dyn(value, None, visitor),
                     (TokenKind::Punct, "(") => self.deserialize_tuple_struct_dyn(value, 0, visitor),
                     _ if matches!(value, "true" | "false") => self.deserialize_bool(visitor),
+                    _ if literal::is_debug_float_ident(value) => self.deserialize_f64(visitor),
                     _ => self.deserialize_unit_struct_dyn(value, visitor),
                 }
             }
 
             (TokenKind::Punct, "(") => self.deserialize_tuple(0, visitor),
-            // TODO: This could also be a set.
+            (TokenKind::Punct, "{") if self.is_set() => self.deserialize_seq(visitor),
             (TokenKind::Punct, "{") => self.deserialize_map(visitor),
             (TokenKind::Punct, "[") => self.deserialize_seq(visitor),
 
-            _ => todo!(),
+            _ => Err(Error::unexpected_token(token, "a value")),
         }
     }
 
@@ -410,6 +1522,26 @@ impl<'de> serde::de::Deserializer<'de> for &'_ mut Deserializer<'de> {
         match self.parse_ident()? {
             "true" => visitor.visit_bool(true),
             "false" => visitor.visit_bool(false),
+            "True" if self.is_python() => visitor.visit_bool(true),
+            "False" if self.is_python() => visitor.visit_bool(false),
+            ident if self.options.config().coerce_bools => {
+                let value = match ident.to_ascii_lowercase().as_str() {
+                    "yes" | "on" => true,
+                    "no" | "off" => false,
+                    _ => {
+                        return Err(Error::unexpected_token(
+                            Token {
+                                kind: TokenKind::Ident,
+                                value: ident,
+                            },
+                            "a boolean",
+                        ))
+                    }
+                };
+
+                self.warn(Warning::BoolCoerced { word: ident });
+                visitor.visit_bool(value)
+            }
             ident => Err(Error::unexpected_token(
                 Token {
                     kind: TokenKind::Ident,
@@ -420,37 +1552,23 @@ impl<'de> serde::de::Deserializer<'de> for &'_ mut Deserializer<'de> {
         }
     }
 
-    deserialize_signed!(deserialize_i8, i8, visit_i8);
-    deserialize_signed!(deserialize_i16, i16, visit_i16);
-    deserialize_signed!(deserialize_i32, i32, visit_i32);
-    deserialize_signed!(deserialize_i64, i64, visit_i64);
-    deserialize_signed!(deserialize_i128, i128, visit_i128);
+    deserialize_integer!(deserialize_i8, i8, visit_i8);
+    deserialize_integer!(deserialize_i16, i16, visit_i16);
+    deserialize_integer!(deserialize_i32, i32, visit_i32);
+    deserialize_integer!(deserialize_i64, i64, visit_i64);
+    deserialize_integer!(deserialize_i128, i128, visit_i128);
 
-    deserialize_unsigned!(deserialize_u8, u8, visit_u8);
-    deserialize_unsigned!(deserialize_u16, u16, visit_u16);
-    deserialize_unsigned!(deserialize_u32, u32, visit_u32);
-    deserialize_unsigned!(deserialize_u64, u64, visit_u64);
-    deserialize_unsigned!(deserialize_u128, u128, visit_u128);
+    deserialize_integer!(deserialize_u8, u8, visit_u8);
+    deserialize_integer!(deserialize_u16, u16, visit_u16);
+    deserialize_integer!(deserialize_u32, u32, visit_u32);
+    deserialize_integer!(deserialize_u64, u64, visit_u64);
+    deserialize_integer!(deserialize_u128, u128, visit_u128);
 
     fn deserialize_f32<V>(self, visitor: V) -> Result<V::Value, Self::Error>
     where
         V: Visitor<'de>,
     {
-        let float = self.parse_float()?;
-        let value = match float.kind {
-            TokenKind::Ident => f32::NAN,
-            TokenKind::Float => float
-                .value
-                .parse()
-                .map_err(|e| Error::parse_float(float.span, e))?,
-            _ => unreachable!(),
-        };
-
-        let value = match float.sign {
-            Sign::Positive => value,
-            Sign::Negative => -value,
-        };
-
+        let value = self.parse_float_value()?;
         visitor.visit_f32(value)
     }
 
@@ -458,21 +1576,7 @@ impl<'de> serde::de::Deserializer<'de> for &'_ mut Deserializer<'de> {
     where
         V: Visitor<'de>,
     {
-        let float = self.parse_float()?;
-        let value = match float.kind {
-            TokenKind::Ident => f64::NAN,
-            TokenKind::Float => float
-                .value
-                .parse()
-                .map_err(|e| Error::parse_float(float.span, e))?,
-            _ => unreachable!(),
-        };
-
-        let value = match float.sign {
-            Sign::Positive => value,
-            Sign::Negative => -value,
-        };
-
+        let value = self.parse_float_value()?;
         visitor.visit_f64(value)
     }
 
@@ -507,7 +1611,38 @@ impl<'de> serde::de::Deserializer<'de> for &'_ mut Deserializer<'de> {
     where
         V: Visitor<'de>,
     {
+        if self.options.config().stringify_nested {
+            let token = self.peek()?;
+            let quoted = match token.kind {
+                TokenKind::String => true,
+                TokenKind::Char => self.is_python(),
+                _ => false,
+            };
+
+            if !quoted && token.kind != TokenKind::Eof {
+                return visitor.visit_borrowed_str(self.lexer.skip_value()?);
+            }
+        }
+
+        // Escaped strings are unescaped into the scratch buffer, if there is
+        // one, instead of allocating a new string for each.
+        if let Some(mut scratch) = self.scratch.take() {
+            let span = self.peek()?.value;
+            if !self.is_python() && span.starts_with('"') && span.contains('\\') {
+                let result = self
+                    .parse_quoted_token(TokenKind::String)
+                    .and_then(|span| self.borrowed(span, false).map(|()| span))
+                    .and_then(|span| self.unescape_into_scratch(span, &mut scratch))
+                    .and_then(|()| visitor.visit_str(&scratch));
+                self.scratch = Some(scratch);
+                return result;
+            }
+
+            self.scratch = Some(scratch);
+        }
+
         let str = self.parse_string()?;
+        self.borrowed(str.span, matches!(str.value, Cow::Borrowed(_)))?;
         match str.value {
             Cow::Owned(value) => visitor.visit_string(value),
             Cow::Borrowed(value) => visitor.visit_borrowed_str(value),
@@ -521,31 +1656,53 @@ impl<'de> serde::de::Deserializer<'de> for &'_ mut Deserializer<'de> {
         self.deserialize_str(visitor)
     }
 
+    /// Bytes are accepted either as a byte string literal (`b"abc"`) or as a
+    /// list of integers, which is how `[u8]` and `Vec<u8>` are printed. Both
+    /// are passed to the visitor as bytes instead of as a sequence.
     fn deserialize_bytes<V>(self, visitor: V) -> Result<V::Value, Self::Error>
     where
         V: Visitor<'de>,
     {
-        self.deserialize_seq(visitor)
+        match self.parse_byte_string()? {
+            Some(Cow::Borrowed(bytes)) => visitor.visit_borrowed_bytes(bytes),
+            Some(Cow::Owned(bytes)) => visitor.visit_byte_buf(bytes),
+            None => visitor.visit_byte_buf(Vec::<u8>::deserialize(self)?),
+        }
     }
 
     fn deserialize_byte_buf<V>(self, visitor: V) -> Result<V::Value, Self::Error>
     where
         V: Visitor<'de>,
     {
-        self.deserialize_seq(visitor)
+        self.deserialize_bytes(visitor)
     }
 
     fn deserialize_option<V>(self, visitor: V) -> Result<V::Value, Self::Error>
     where
         V: Visitor<'de>,
     {
+        if self.skip_address()? {
+            return visitor.visit_none();
+        }
+
+        // Python has no wrapper for present values.
+        if self.is_python() {
+            return match self.peek()? {
+                token if token.kind == TokenKind::Ident && token.value == "None" => {
+                    self.lexer.parse_token()?;
+                    visitor.visit_none()
+                }
+                _ => visitor.visit_some(self),
+            };
+        }
+
         let ident = self.parse_ident()?;
 
-        match ident {
+        match self.type_name(ident) {
             "Some" => {
-                self.parse_punct('{')?;
-                let value = visitor.visit_some(&mut *self)?;
-                self.parse_punct('}')?;
+                self.parse_punct('(')?;
+                let value = visitor.visit_some(Nested(&mut *self))?;
+                self.parse_newtype_end()?;
                 Ok(value)
             }
             "None" => visitor.visit_none(),
@@ -557,6 +1714,15 @@ impl<'de> serde::de::Deserializer<'de> for &'_ mut Deserializer<'de> {
     where
         V: Visitor<'de>,
     {
+        if is_phantom_data(&self.peek()?) {
+            self.lexer.parse_token()?;
+            return visitor.visit_unit();
+        }
+
+        if self.skip_address()? {
+            return visitor.visit_unit();
+        }
+
         self.parse_punct('(')?;
         self.parse_punct(')')?;
 
@@ -582,10 +1748,31 @@ impl<'de> serde::de::Deserializer<'de> for &'_ mut Deserializer<'de> {
     where
         V: Visitor<'de>,
     {
+        if name == RAW_VALUE_TOKEN {
+            return visitor.visit_borrowed_str(self.lexer.skip_value()?);
+        }
+        if name == RAW_TEXT_TOKEN {
+            return visitor.visit_borrowed_str(self.lexer.skip_raw()?);
+        }
+        if name == VALUE_TOKEN {
+            let value = value::parse_value(&mut self.lexer, &self.options, self.depth)?;
+            return value::visit_parsed(&value, visitor);
+        }
+
+        // The half float types deserialize as newtypes but print as plain
+        // floats. Both are subsets of f32 so the conversion is exact.
+        #[cfg(feature = "half")]
+        if matches!(name, "f16" | "bf16") && self.peek()?.value != name {
+            return match name {
+                "f16" => visitor.visit_f32(self.parse_float_value::<half::f16>()?.to_f32()),
+                _ => visitor.visit_f32(self.parse_float_value::<half::bf16>()?.to_f32()),
+            };
+        }
+
         self.parse_ident_exact(name)?;
         self.parse_punct('(')?;
-        let value = visitor.visit_newtype_struct(&mut *self)?;
-        self.parse_punct(')')?;
+        let value = visitor.visit_newtype_struct(Nested(&mut *self))?;
+        self.parse_newtype_end()?;
 
         Ok(value)
     }
@@ -596,19 +1783,40 @@ impl<'de> serde::de::Deserializer<'de> for &'_ mut Deserializer<'de> {
     {
         let value;
 
+        // Python tuples are commonly used where a list would be.
+        if self.is_python() && self.peek()?.is_punct("(") {
+            return self.deserialize_tuple(0, visitor);
+        }
+
+        // Python prints empty sets as `set()`, since `{}` is an empty dict, and
+        // frozensets as a set wrapped in `frozenset(..)`.
+        if self.is_python_set()? {
+            self.lexer.parse_token()?;
+            self.parse_punct('(')?;
+            let value = match self.peek()?.is_punct(")") {
+                true => visitor.visit_seq(DebugTupleAccess(&mut *self, 0))?,
+                false => self.deserialize_seq(visitor)?,
+            };
+            self.parse_punct(')')?;
+            return Ok(value);
+        }
+
         // Both DebugList and DebugSet correspond to a serde sequence.
         match self.parse_punct_ex("`[` or `{`", |v| matches!(v, "[" | "{"))? {
             "[" => {
-                value = visitor.visit_seq(DebugSeqAccess(self))?;
+                self.enter()?;
+                value = visitor.visit_seq(DebugSeqAccess(&mut *self, 0))?;
                 self.parse_punct(']')?;
             }
             "{" => {
-                value = visitor.visit_seq(DebugSeqAccess(self))?;
+                self.enter()?;
+                value = visitor.visit_seq(DebugSeqAccess(&mut *self, 0))?;
                 self.parse_punct('}')?;
             }
             _ => unreachable!(),
         }
 
+        self.leave();
         Ok(value)
     }
 
@@ -616,9 +1824,16 @@ impl<'de> serde::de::Deserializer<'de> for &'_ mut Deserializer<'de> {
     where
         V: Visitor<'de>,
     {
+        // Serde reads arrays as tuples, but their debug output is a list.
+        if self.peek()?.is_punct("[") {
+            return self.deserialize_seq(visitor);
+        }
+
         self.parse_punct('(')?;
-        let value = visitor.visit_seq(DebugTupleAccess(&mut *self))?;
+        self.enter()?;
+        let value = visitor.visit_seq(DebugTupleAccess(&mut *self, 0))?;
         self.parse_punct(')')?;
+        self.leave();
         Ok(value)
     }
 
@@ -638,22 +1853,67 @@ impl<'de> serde::de::Deserializer<'de> for &'_ mut Deserializer<'de> {
     where
         V: Visitor<'de>,
     {
+        // Structs can be read as maps from their field names to their values.
+        let token = self.peek()?;
+        if token.kind == TokenKind::Ident {
+            return self.deserialize_struct_dyn(token.value, None, visitor);
+        }
+
         self.parse_punct('{')?;
-        let value = visitor.visit_map(DebugMapAccess(&mut *self))?;
+        self.enter()?;
+        let value = visitor.visit_map(DebugMapAccess::new(&mut *self)?)?;
         self.parse_punct('}')?;
+        self.leave();
         Ok(value)
     }
 
     fn deserialize_struct<V>(
         self,
         name: &'static str,
-        _fields: &'static [&'static str],
+        fields: &'static [&'static str],
         visitor: V,
     ) -> Result<V::Value, Self::Error>
     where
         V: Visitor<'de>,
     {
-        self.deserialize_struct_dyn(name, visitor)
+        // Records are sometimes printed as a map from field names to values
+        // (e.g. `{"name": "x", "age": 3}`). Python objects are usually
+        // represented this way as well.
+        if self.peek()?.is_punct("{") {
+            return self.deserialize_map(visitor);
+        }
+
+        // `Duration` prints as a number with a unit (e.g. `1.5s`), which is
+        // passed to its visitor as a `(secs, nanos)` sequence.
+        if name == "Duration" && self.peek()?.kind == TokenKind::Duration {
+            let token = self.lexer.parse_token()?;
+            let duration = literal::parse_duration(token.value).ok_or_else(|| {
+                Error::invalid_value(Unexpected::Str(token.value), &"a valid duration")
+            })?;
+
+            return visit_duration(duration, visitor);
+        }
+
+        // `SystemTime` prints the platform-specific value that it wraps. Its
+        // serde impl expects the time since the Unix epoch instead.
+        #[cfg(feature = "std")]
+        if name == "SystemTime"
+            && fields == ["secs_since_epoch", "nanos_since_epoch"]
+            && self.peek()?.value == name
+            && !self.is_serde_system_time()?
+        {
+            let text = self.lexer.skip_value()?;
+            let since = RawTime::parse(text, name)?
+                .to_system_time()
+                .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+                .ok_or_else(|| {
+                    Error::invalid_value(Unexpected::Str(text), &"a time after the Unix epoch")
+                })?;
+
+            return visit_duration(since, visitor);
+        }
+
+        self.deserialize_struct_dyn(name, Some(fields), visitor)
     }
 
     fn deserialize_enum<V>(
@@ -665,13 +1925,20 @@ impl<'de> serde::de::Deserializer<'de> for &'_ mut Deserializer<'de> {
     where
         V: Visitor<'de>,
     {
-        visitor.visit_enum(DebugEnumAccess(&mut *self))
+        visitor.visit_enum(DebugEnumAccess::new(&mut *self))
     }
 
     fn deserialize_identifier<V>(self, visitor: V) -> Result<V::Value, Self::Error>
     where
         V: Visitor<'de>,
     {
+        // Map keys are strings, which are used as struct field names.
+        match self.peek()?.kind {
+            TokenKind::String => return self.deserialize_str(visitor),
+            TokenKind::Char if self.is_python() => return self.deserialize_str(visitor),
+            _ => (),
+        }
+
         let ident = self.parse_ident()?;
         visitor.visit_borrowed_str(ident)
     }
@@ -680,11 +1947,50 @@ impl<'de> serde::de::Deserializer<'de> for &'_ mut Deserializer<'de> {
     where
         V: Visitor<'de>,
     {
-        self.deserialize_any(visitor)
+        let mut unknown = false;
+        if let Some((name, value)) = self.field.take() {
+            // Fields nested within the unknown field are not reported since
+            // the whole value is being discarded anyway.
+            if value.as_ptr() == self.lexer.remaining().as_ptr() && !self.skipping {
+                self.warn(Warning::UnknownField { name });
+                unknown = true;
+            }
+        }
+
+        let start = self.remaining().trim_start();
+        self.skip_ignored()?;
+
+        if unknown && self.options.config().collect_unknown_fields {
+            let text = start[..start.len() - self.remaining().len()].trim_end();
+            let value = text
+                .parse()
+                .unwrap_or_else(|_| Value::Other(text.to_owned()));
+            let path = self.path()?;
+            self.unknown_fields.insert(path, value);
+        }
+
+        visitor.visit_unit()
     }
 }
 
-struct DebugSeqAccess<'a, 'de>(&'a mut Deserializer<'de>);
+/// Whether `token` is a `PhantomData`, which prints the name of the type that
+/// it wraps as a generic argument (e.g. `PhantomData<u32>`).
+fn is_phantom_data(token: &Token) -> bool {
+    token.kind == TokenKind::Ident && strip_generics(token.value) == "PhantomData"
+}
+
+/// Pass `duration` to the visitor of a `Duration` or `SystemTime` as the
+/// `(secs, nanos)` sequence that their serde impls accept.
+pub(crate) fn visit_duration<'de, V>(duration: Duration, visitor: V) -> Result<V::Value, Error>
+where
+    V: Visitor<'de>,
+{
+    let parts = [duration.as_secs(), duration.subsec_nanos().into()];
+    SeqDeserializer::new(parts.into_iter()).deserialize_any(visitor)
+}
+
+/// Accesses the elements of a sequence, along with the index of the next one.
+struct DebugSeqAccess<'a, 'de>(&'a mut Deserializer<'de>, usize);
 
 impl<'de> SeqAccess<'de> for DebugSeqAccess<'_, 'de> {
     type Error = Error;
@@ -701,7 +2007,9 @@ impl<'de> SeqAccess<'de> for DebugSeqAccess<'_, 'de> {
             return Ok(None);
         }
 
-        let value = seed.deserialize(&mut *self.0)?;
+        self.0.element()?;
+        let value = self.0.nested(Step::Index(self.1), seed)?;
+        self.1 += 1;
         match self.0.peek()? {
             // Trailing commas are permitted to be missing only if there is a closing brace there
             // instead.
@@ -716,7 +2024,8 @@ impl<'de> SeqAccess<'de> for DebugSeqAccess<'_, 'de> {
     }
 }
 
-struct DebugTupleAccess<'a, 'de>(&'a mut Deserializer<'de>);
+/// Accesses the elements of a tuple, along with the index of the next one.
+struct DebugTupleAccess<'a, 'de>(&'a mut Deserializer<'de>, usize);
 
 impl<'de> SeqAccess<'de> for DebugTupleAccess<'_, 'de> {
     type Error = Error;
@@ -733,7 +2042,9 @@ impl<'de> SeqAccess<'de> for DebugTupleAccess<'_, 'de> {
             return Ok(None);
         }
 
-        let value = seed.deserialize(&mut *self.0)?;
+        self.0.element()?;
+        let value = self.0.nested(Step::Index(self.1), seed)?;
+        self.1 += 1;
         match self.0.peek()? {
             // Trailing commas are permitted to be missing only if there is a closing brace there
             // instead.
@@ -748,7 +2059,99 @@ impl<'de> SeqAccess<'de> for DebugTupleAccess<'_, 'de> {
     }
 }
 
-struct DebugMapAccess<'a, 'de>(&'a mut Deserializer<'de>);
+struct DebugMapAccess<'a, 'de> {
+    de: &'a mut Deserializer<'de>,
+
+    /// The keys seen so far, if duplicate keys need to be detected.
+    keys: Option<BTreeSet<&'de str>>,
+
+    /// The keys of entries that are followed by another entry with the same
+    /// key, when using [`DuplicateKeys::KeepLast`].
+    superseded: BTreeSet<*const u8>,
+
+    /// The input of the current key, if unknown fields are being collected.
+    key: &'de str,
+}
+
+impl<'a, 'de> DebugMapAccess<'a, 'de> {
+    fn new(de: &'a mut Deserializer<'de>) -> Result<Self, Error> {
+        let mut keys = None;
+        let mut superseded = BTreeSet::new();
+
+        match de.options.config().duplicate_keys {
+            DuplicateKeys::Allow => (),
+            DuplicateKeys::Error | DuplicateKeys::KeepFirst => keys = Some(BTreeSet::new()),
+            DuplicateKeys::KeepLast => {
+                // Find the last entry for each key up front, since it is too
+                // late to go back once a later one has been found.
+                let mut lexer = de.lexer.clone();
+                let mut last = BTreeSet::new();
+                while !lexer.peek_token()?.is_punct("}") {
+                    let key = lexer.skip_key()?;
+                    if let Some(previous) = last.replace(key) {
+                        superseded.insert(previous.as_ptr());
+                    }
+
+                    let token = lexer.parse_token()?;
+                    if !token.is_punct(":") {
+                        return Err(Error::unexpected_token(token, ':'));
+                    }
+                    lexer.skip_value()?;
+                    if !lexer.peek_token()?.is_punct("}") {
+                        let token = lexer.parse_token()?;
+                        if !token.is_punct(",") {
+                            return Err(Error::unexpected_token(token, ','));
+                        }
+                    }
+                }
+            }
+        }
+
+        Ok(Self {
+            de,
+            keys,
+            superseded,
+            key: "",
+        })
+    }
+
+    /// Whether the entry with `key` should be skipped.
+    fn is_duplicate(&mut self, key: &'de str) -> Result<bool, Error> {
+        if self.superseded.contains(&key.as_ptr()) {
+            return Ok(true);
+        }
+
+        let Some(keys) = &mut self.keys else {
+            return Ok(false);
+        };
+        let Some(first) = keys.get(key) else {
+            keys.insert(key);
+            return Ok(false);
+        };
+
+        if self.de.options.config().duplicate_keys == DuplicateKeys::Error {
+            let offset = |span: &str| span.as_ptr() as usize - self.de.total.as_ptr() as usize;
+            let error = Error::custom(format_args!(
+                "duplicate map key `{key}` at offset {}, first seen at offset {}",
+                offset(key),
+                offset(first)
+            ));
+            return Err(self.de.locate_span(error, key));
+        }
+
+        Ok(true)
+    }
+
+    fn parse_separator(&mut self) -> Result<(), Error> {
+        match self.de.peek()? {
+            Token {
+                kind: TokenKind::Punct,
+                value: "}",
+            } => Ok(()),
+            _ => self.de.parse_punct(','),
+        }
+    }
+}
 
 impl<'de> MapAccess<'de> for DebugMapAccess<'_, 'de> {
     type Error = Error;
@@ -757,33 +2160,116 @@ impl<'de> MapAccess<'de> for DebugMapAccess<'_, 'de> {
     where
         K: DeserializeSeed<'de>,
     {
-        if self.0.peek()?.is_punct("}") {
-            return Ok(None);
+        loop {
+            if self.de.peek()?.is_punct("}") {
+                return Ok(None);
+            }
+
+            self.de.element()?;
+            if self.keys.is_none() && self.superseded.is_empty() {
+                break;
+            }
+
+            let key = self.de.lexer.clone().skip_key()?;
+            if !self.is_duplicate(key)? {
+                break;
+            }
+
+            self.de.lexer.skip_key()?;
+            self.de.parse_punct(':')?;
+            self.de.lexer.skip_value()?;
+            self.parse_separator()?;
+            self.de.warn(Warning::DuplicateKey { key });
         }
 
-        seed.deserialize(&mut *self.0).map(Some)
+        if self.de.options.config().collect_unknown_fields {
+            self.key = self.de.lexer.clone().skip_key()?.trim();
+        }
+        seed.deserialize(&mut *self.de).map(Some)
     }
 
     fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value, Self::Error>
     where
         V: DeserializeSeed<'de>,
     {
-        self.0.parse_punct(':')?;
-        let value = seed.deserialize(&mut *self.0)?;
-
-        match self.0.peek()? {
-            Token {
-                kind: TokenKind::Punct,
-                value: "}",
-            } => (),
-            _ => self.0.parse_punct(',')?,
-        }
+        self.de.parse_punct(':')?;
+        let value = self.de.nested(Step::Key(self.key), seed)?;
+        self.parse_separator()?;
 
         Ok(value)
     }
 }
 
-struct DebugStructAccess<'a, 'de>(&'a mut Deserializer<'de>);
+struct DebugStructAccess<'a, 'de> {
+    de: &'a mut Deserializer<'de>,
+    field: &'de str,
+
+    /// The fields of the struct, if known.
+    fields: Option<&'static [&'static str]>,
+
+    /// The index within `fields` of the last field that was read, used to
+    /// check the order of the fields when [`Options::strict_field_order`] is
+    /// enabled.
+    last: Option<usize>,
+}
+
+impl<'a, 'de> DebugStructAccess<'a, 'de> {
+    fn new(de: &'a mut Deserializer<'de>, fields: Option<&'static [&'static str]>) -> Self {
+        Self {
+            de,
+            field: "",
+            fields,
+            last: None,
+        }
+    }
+
+    /// Check that the current field does not come before the previous one in
+    /// the list of fields, if [`Options::strict_field_order`] is enabled.
+    fn check_field_order(&mut self, name: &str) -> Result<(), Error> {
+        let Some(fields) = self.fields else {
+            return Ok(());
+        };
+        if !self.de.options.config().strict_field_order {
+            return Ok(());
+        }
+        let Some(index) = fields.iter().position(|field| *field == name) else {
+            return Ok(());
+        };
+
+        match self.last {
+            Some(last) if index < last => {
+                let error = serde::de::Error::custom(format_args!(
+                    "field `{name}` is out of order, it should come before `{}`",
+                    fields[last]
+                ));
+                Err(self.de.locate_span(error, self.field))
+            }
+            _ => {
+                self.last = Some(index);
+                Ok(())
+            }
+        }
+    }
+
+    /// Whether the value of the current field is a `None` that should be
+    /// treated as a missing field, per [`Options::none_as_missing`].
+    fn is_none_field(&self) -> Result<bool, Error> {
+        if !self.de.options.config().none_as_missing {
+            return Ok(false);
+        }
+
+        let mut lexer = self.de.lexer.clone();
+        let (colon, value, next) = (
+            lexer.parse_token()?,
+            lexer.parse_token()?,
+            lexer.peek_token()?,
+        );
+        Ok(colon.is_punct(":")
+            && value.kind == TokenKind::Ident
+            && self.de.type_name(value.value) == "None"
+            && (next.is_punct(",") || next.is_punct("}")))
+    }
+}
 
 impl<'de> MapAccess<'de> for DebugStructAccess<'_, 'de> {
     type Error = Error;
@@ -792,43 +2278,79 @@ impl<'de> MapAccess<'de> for DebugStructAccess<'_, 'de> {
     where
         K: DeserializeSeed<'de>,
     {
-        let token = self.0.peek()?;
-        match (token.kind, token.value) {
-            (TokenKind::Punct, "}") => return Ok(None),
-            // This marks the end of a non-exhaustive struct. Example:
-            // Test { a: 4, .. }
-            (TokenKind::Punct, "..") => {
-                self.0.parse_punct_ex("..", |v| v == "..")?;
-                return Ok(None);
+        loop {
+            let token = self.de.peek()?;
+            match (token.kind, token.value) {
+                (TokenKind::Punct, "}") => return Ok(None),
+                // This marks the end of a non-exhaustive struct. Example:
+                // Test { a: 4, .. }
+                (TokenKind::Punct, "..") => {
+                    let span = self.de.parse_punct_ex("..", |v| v == "..")?;
+                    if !self.de.skipping {
+                        self.de.warn(Warning::NonExhaustive { span });
+                    }
+                    return Ok(None);
+                }
+                _ => (),
+            }
+
+            self.field = self.de.parse_ident()?;
+            if !self.is_none_field()? {
+                break;
+            }
+
+            // Treat the field as missing so that it gets its default value.
+            self.de.parse_punct(':')?;
+            self.de.lexer.parse_token()?;
+            if self.de.peek()?.is_punct(",") {
+                self.de.lexer.parse_token()?;
             }
-            _ => (),
         }
 
-        let ident = self.0.parse_ident()?;
-        seed.deserialize(BorrowedStrDeserializer::new(ident))
-            .map(Some)
+        self.de.element()?;
+        let field = self.de.normalize_field(self.field, self.fields);
+        self.check_field_order(&field)?;
+
+        match field {
+            Cow::Borrowed(field) => seed.deserialize(BorrowedStrDeserializer::new(field)),
+            Cow::Owned(field) => seed.deserialize(StringDeserializer::new(field)),
+        }
+        .map(Some)
     }
 
     fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value, Self::Error>
     where
         V: DeserializeSeed<'de>,
     {
-        self.0.parse_punct(':')?;
-        let value = seed.deserialize(&mut *self.0)?;
+        self.de.parse_punct(':')?;
+        self.de.field = Some((self.field, self.de.lexer.remaining()));
+        let value = self.de.nested(Step::Field(self.field), seed)?;
 
-        match self.0.peek()? {
+        match self.de.peek()? {
             Token {
                 kind: TokenKind::Punct,
                 value: "}",
             } => (),
-            _ => self.0.parse_punct(',')?,
+            _ => self.de.parse_punct(',')?,
         }
 
         Ok(value)
     }
 }
 
-struct DebugEnumAccess<'a, 'de>(&'a mut Deserializer<'de>);
+struct DebugEnumAccess<'a, 'de> {
+    de: &'a mut Deserializer<'de>,
+
+    /// The state of the lexer just before the variant name.
+    variant: Lexer<'de>,
+}
+
+impl<'a, 'de> DebugEnumAccess<'a, 'de> {
+    fn new(de: &'a mut Deserializer<'de>) -> Self {
+        let variant = de.lexer.clone();
+        Self { de, variant }
+    }
+}
 
 impl<'de> EnumAccess<'de> for DebugEnumAccess<'_, 'de> {
     type Error = Error;
@@ -838,8 +2360,18 @@ impl<'de> EnumAccess<'de> for DebugEnumAccess<'_, 'de> {
     where
         V: DeserializeSeed<'de>,
     {
-        let ident = self.0.parse_ident()?;
-        let value = seed.deserialize(BorrowedStrDeserializer::<Error>::new(ident))?;
+        let ident = self.de.parse_ident()?;
+        let ident = self.de.type_name(ident);
+        let ident = self.de.normalize(ident);
+        let value = match self.de.options.config().variant_alias(&ident) {
+            Some(variant) => seed.deserialize(StrDeserializer::<Error>::new(variant))?,
+            None => match ident {
+                Cow::Borrowed(ident) => {
+                    seed.deserialize(BorrowedStrDeserializer::<Error>::new(ident))?
+                }
+                Cow::Owned(ident) => seed.deserialize(StringDeserializer::<Error>::new(ident))?,
+            },
+        };
 
         Ok((value, self))
     }
@@ -856,9 +2388,18 @@ impl<'de> VariantAccess<'de> for DebugEnumAccess<'_, 'de> {
     where
         T: DeserializeSeed<'de>,
     {
-        self.0.parse_punct('(')?;
-        let value = seed.deserialize(&mut *self.0)?;
-        self.0.parse_punct(')')?;
+        // An enum whose variants each wrap a struct of the same name, such as
+        // `Connect(Connect)`, is often given a struct printed on its own (e.g.
+        // `Connect { .. }`). In that case the variant name is also the struct
+        // name so it is handed back to the inner value to parse again.
+        if !self.de.peek()?.is_punct("(") {
+            self.de.lexer = self.variant;
+            return seed.deserialize(&mut *self.de);
+        }
+
+        self.de.parse_punct('(')?;
+        let value = seed.deserialize(Nested(&mut *self.de))?;
+        self.de.parse_newtype_end()?;
         Ok(value)
     }
 
@@ -866,144 +2407,285 @@ impl<'de> VariantAccess<'de> for DebugEnumAccess<'_, 'de> {
     where
         V: Visitor<'de>,
     {
-        self.0.deserialize_tuple(len, visitor)
+        self.de.deserialize_tuple(len, visitor)
     }
 
     fn struct_variant<V>(
         self,
-        _fields: &'static [&'static str],
+        fields: &'static [&'static str],
         visitor: V,
     ) -> Result<V::Value, Self::Error>
     where
         V: Visitor<'de>,
     {
-        self.0.parse_punct('{')?;
-        let value = visitor.visit_map(DebugStructAccess(&mut *self.0))?;
-        self.0.parse_punct('}')?;
+        self.de.parse_punct('{')?;
+        self.de.enter()?;
+        let value = visitor.visit_map(DebugStructAccess::new(&mut *self.de, Some(fields)))?;
+        self.de.parse_punct('}')?;
+        self.de.leave();
         Ok(value)
     }
 }
 
-fn unescape(mut text: &str) -> Result<Cow<'_, str>, Error> {
-    let mut next = match text.find('\\') {
-        Some(pos) => pos,
-        None => return Ok(Cow::Borrowed(text)),
-    };
+/// Deserializes a value, unwrapping any wrappers around it that are enabled in
+/// the options.
+///
+/// This is used for values nested within another one as well as for the top
+/// level value.
+pub(crate) struct Nested<'a, 'de>(pub(crate) &'a mut Deserializer<'de>);
 
-    let mut escaped = String::new();
-
-    loop {
-        let (head, rest) = text.split_at(next);
-        escaped.push_str(head);
-        text = rest;
-
-        let (c, len) = match &text[..2] {
-            r"\0" => ('\0', 2),
-            r"\t" => ('\t', 2),
-            r"\r" => ('\r', 2),
-            r"\n" => ('\n', 2),
-            r"\\" => ('\\', 2),
-            r"\'" => ('\'', 2),
-            "\\\"" => ('"', 2),
-            r"\u" => {
-                let rest = &text[2..]
-                    .strip_prefix('{')
-                    .ok_or_else(|| Error::invalid_string_literal(text, "invalid unicode escape"))?;
-
-                let offset = rest
-                    .chars()
-                    .position(|x| !x.is_ascii_hexdigit())
-                    .unwrap_or(rest.len());
-                let (digits, rest) = rest.split_at(offset);
-
-                rest.strip_prefix('}')
-                    .ok_or_else(|| Error::invalid_string_literal(text, "invalid unicode escape"))?;
-
-                let escape = &text[..digits.len() + 4];
-                let code = u32::from_str_radix(digits, 16)
-                    .map_err(|_| Error::invalid_string_literal(escape, "invalid unicode escape"))?;
-                let c = match char::from_u32(code) {
-                    Some(c) => c,
-                    None => {
-                        return Err(Error::invalid_string_literal(
-                            text,
-                            "unicode escape was not a valid unicode codepoint",
-                        ))
-                    }
-                };
+macro_rules! deserialize_nested {
+    ($($deserialize:ident($($arg:ident: $ty:ty),*);)*) => {$(
+        fn $deserialize<V>(self, $($arg: $ty,)* visitor: V) -> Result<V::Value, Self::Error>
+        where
+            V: Visitor<'de>,
+        {
+            self.0.unwrapped(true, None, |de| de.$deserialize($($arg,)* visitor))
+        }
+    )*};
+}
 
-                (c, digits.len() + 4)
-            }
-            escape => {
-                return Err(Error::invalid_string_literal(
-                    text,
-                    format!("invalid escape sequence '{escape}'"),
-                ))
-            }
-        };
+impl<'de> serde::Deserializer<'de> for Nested<'_, 'de> {
+    type Error = Error;
 
-        escaped.push(c);
-        text = text.split_at(len).1;
+    fn is_human_readable(&self) -> bool {
+        true
+    }
 
-        next = match text.find('\\') {
-            Some(pos) => pos,
-            None => break,
-        };
+    // A `Some` here is the value itself rather than a wrapper around it.
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.0
+            .unwrapped(false, None, |de| de.deserialize_any(visitor))
+    }
+
+    fn deserialize_option<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.0
+            .unwrapped(false, None, |de| de.deserialize_option(visitor))
+    }
+
+    fn deserialize_enum<V>(
+        self,
+        name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        let some = !variants.contains(&"Some");
+        self.0.unwrapped(some, None, |de| {
+            de.deserialize_enum(name, variants, visitor)
+        })
+    }
+
+    fn deserialize_newtype_struct<V>(
+        self,
+        name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        // Raw values are captured exactly as they appear in the input.
+        if name == RAW_VALUE_TOKEN || name == RAW_TEXT_TOKEN || name == VALUE_TOKEN {
+            return self.0.deserialize_newtype_struct(name, visitor);
+        }
+
+        self.0.unwrapped(true, Some(name), |de| {
+            de.deserialize_newtype_struct(name, visitor)
+        })
+    }
+
+    fn deserialize_unit_struct<V>(self, name: &'static str, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.0.unwrapped(true, Some(name), |de| {
+            de.deserialize_unit_struct(name, visitor)
+        })
+    }
+
+    fn deserialize_tuple_struct<V>(
+        self,
+        name: &'static str,
+        len: usize,
+        visitor: V,
+    ) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.0.unwrapped(true, Some(name), |de| {
+            de.deserialize_tuple_struct(name, len, visitor)
+        })
+    }
+
+    fn deserialize_struct<V>(
+        self,
+        name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.0.unwrapped(true, Some(name), |de| {
+            de.deserialize_struct(name, fields, visitor)
+        })
+    }
+
+    fn deserialize_identifier<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.0.deserialize_identifier(visitor)
+    }
+
+    fn deserialize_ignored_any<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.0.deserialize_ignored_any(visitor)
     }
 
-    escaped.push_str(text);
-    Ok(escaped.into())
+    deserialize_nested! {
+        deserialize_bool();
+        deserialize_i8();
+        deserialize_i16();
+        deserialize_i32();
+        deserialize_i64();
+        deserialize_i128();
+        deserialize_u8();
+        deserialize_u16();
+        deserialize_u32();
+        deserialize_u64();
+        deserialize_u128();
+        deserialize_f32();
+        deserialize_f64();
+        deserialize_char();
+        deserialize_str();
+        deserialize_string();
+        deserialize_bytes();
+        deserialize_byte_buf();
+        deserialize_unit();
+        deserialize_seq();
+        deserialize_tuple(len: usize);
+        deserialize_map();
+    }
 }
 
-#[cfg(test)]
-mod unescape_tests {
-    use super::*;
+/// Presents a single map entry as a `(key, value)` tuple.
+struct DebugEntryDeserializer<'a, 'de>(&'a mut Deserializer<'de>);
 
-    macro_rules! unescape_test {
-        {
-            $(
-                $( #[$attr:meta] )*
-                $test:ident: $input:expr $( => $output:expr )? ;
-            )*
-        } => {$(
-            #[test]
-            $( #[$attr] )*
-            #[allow(unused_variables)]
-            fn $test() {
-                let escaped = unescape($input)
-                    .expect("escape sequence was invalid");
-
-                $(
-                    let expected: &str = $output;
-
-                    assert_eq!(&*escaped, expected);
-                )?
-            }
-        )*}
-    }
-
-    unescape_test! {
-        basic: "test" => "test";
-        null: r"\0" => "\0";
-        tab: r"\t" => "\t";
-        crlf: r"\r\n" => "\r\n";
-        backslash: r"\\" => "\\";
-
-        single_quote: r"\'" => "\'";
-        double_quote: r#"\""# => "\"";
-
-        unicode_null: r"\u{0}" => "\u{0}";
-        unicode_max: r"\u{109999}" => "\u{109999}";
-        unicode_hex_uppercase: r"\u{ABCDE}" => "\u{ABCDE}";
-        unicode_hex_lowercase: r"\u{abcde}" => "\u{abcde}";
-
-        #[should_panic]
-        unicode_incomplete: r"\u{123";
-        #[should_panic]
-        invalid_escape: r"\a";
-
-        mixed: r"One, two, three, four!\nI declare a \tab war!\n\\\u{9123}"
-            => "One, two, three, four!\nI declare a \tab war!\n\\\u{9123}";
-        empty: "" => "";
+impl<'de> serde::Deserializer<'de> for DebugEntryDeserializer<'_, 'de> {
+    type Error = Error;
+
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_seq(DebugEntryAccess {
+            de: self.0,
+            index: 0,
+        })
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf option unit unit_struct newtype_struct seq tuple
+        tuple_struct map struct enum identifier ignored_any
+    }
+}
+
+struct DebugEntryAccess<'a, 'de> {
+    de: &'a mut Deserializer<'de>,
+    index: usize,
+}
+
+impl<'de> SeqAccess<'de> for DebugEntryAccess<'_, 'de> {
+    type Error = Error;
+
+    fn next_element_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>, Self::Error>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        match self.index {
+            0 => (),
+            1 => self.de.parse_punct(':')?,
+            _ => return Ok(None),
+        }
+
+        self.index += 1;
+        seed.deserialize(&mut *self.de).map(Some)
     }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(2usize.saturating_sub(self.index))
+    }
+}
+
+/// Convert a float literal with an integral value (e.g. `1e6` or `2.50e1`) into
+/// the equivalent decimal integer literal.
+///
+/// This is done on the digits directly, instead of going through `f64`, so
+/// that values too large to be exactly represented as a float still end up as
+/// the correct integer.
+fn float_to_integer_literal(float: &str) -> Result<String, Error> {
+    // Any integer with more digits than this is going to overflow even a u128 so
+    // there is no point in producing more zeros than this.
+    const MAX_DIGITS: usize = 64;
+
+    if literal::is_hex_float(float) {
+        let value = literal::parse_hex_float(float, FloatFormat::F64)
+            .map(f64::from_bits)
+            .ok_or_else(|| Error::invalid_hex_float(float))?;
+
+        if !value.is_finite() || value % 1.0 != 0.0 {
+            return Err(Error::invalid_value(
+                Unexpected::Float(value),
+                &"an integer",
+            ));
+        }
+
+        return Ok(format!("{value:.0}"));
+    }
+
+    let (mantissa, exponent) = match float.find(['e', 'E']) {
+        Some(index) => (&float[..index], &float[index + 1..]),
+        None => (float, "0"),
+    };
+    let exponent: i64 = exponent.parse().unwrap_or(match exponent.starts_with('-') {
+        true => i64::MIN / 2,
+        false => i64::MAX / 2,
+    });
+
+    let (int, frac) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+    let digits = format!("{int}{frac}");
+    let digits = digits.trim_start_matches('0');
+    if digits.is_empty() {
+        return Ok("0".into());
+    }
+
+    let shift = exponent - frac.len() as i64;
+    if shift >= 0 {
+        let zeros = (shift as usize).min(MAX_DIGITS);
+        return Ok(format!("{digits}{}", "0".repeat(zeros)));
+    }
+
+    let cut = shift.unsigned_abs().min(usize::MAX as u64) as usize;
+    let (head, tail) = digits.split_at(digits.len().saturating_sub(cut));
+    if head.is_empty() || tail.bytes().any(|b| b != b'0') {
+        let value = float.parse().unwrap_or(f64::NAN);
+        return Err(Error::invalid_value(
+            Unexpected::Float(value),
+            &"an integer",
+        ));
+    }
+
+    Ok(head.into())
 }
@@ -0,0 +1,118 @@
+use alloc::borrow::Cow;
+use alloc::format;
+use alloc::string::String;
+
+use crate::Error;
+
+/// The syntax that the input is expected to follow.
+///
+/// See [`Options::dialect`](crate::Options::dialect).
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum Dialect {
+    /// The output of rust's [`Debug`](std::fmt::Debug) impls.
+    #[default]
+    Rust,
+
+    /// The output of python's `repr()`.
+    ///
+    /// In this dialect:
+    /// - strings may use either single or double quotes and use python's escape
+    ///   sequences (e.g. `\x1b` and `\u00e9`),
+    /// - booleans are written as `True` and `False`,
+    /// - optional values are either `None` or the value itself,
+    /// - dicts can be deserialized into structs,
+    /// - tuples can be deserialized into sequences, and
+    /// - sets may be written as `{1, 2}`, `set()`, or wrapped in
+    ///   `frozenset(..)`.
+    PythonRepr,
+}
+
+/// Undo the escaping done by python's `repr()` for strings.
+///
+/// `text` should not include the surrounding quotes.
+pub(crate) fn unescape_python(mut text: &str) -> Result<Cow<'_, str>, Error> {
+    let mut next = match text.find('\\') {
+        Some(pos) => pos,
+        None => return Ok(Cow::Borrowed(text)),
+    };
+
+    let mut escaped = String::with_capacity(text.len());
+
+    loop {
+        let (head, rest) = text.split_at(next);
+        escaped.push_str(head);
+
+        let mut chars = rest[1..].chars();
+        let kind = chars
+            .next()
+            .ok_or_else(|| Error::invalid_string_literal(rest, "truncated escape sequence"))?;
+
+        let (c, len) = match kind {
+            '\\' => ('\\', 2),
+            '\'' => ('\'', 2),
+            '"' => ('"', 2),
+            'n' => ('\n', 2),
+            'r' => ('\r', 2),
+            't' => ('\t', 2),
+            '0' => ('\0', 2),
+            'a' => ('\u{07}', 2),
+            'b' => ('\u{08}', 2),
+            'f' => ('\u{0c}', 2),
+            'v' => ('\u{0b}', 2),
+            'x' => (hex_escape(rest, 2)?, 4),
+            'u' => (hex_escape(rest, 4)?, 6),
+            'U' => (hex_escape(rest, 8)?, 10),
+            _ => {
+                return Err(Error::invalid_string_literal(
+                    rest,
+                    format!("invalid escape sequence '\\{kind}'"),
+                ))
+            }
+        };
+
+        escaped.push(c);
+        text = &rest[len..];
+
+        next = match text.find('\\') {
+            Some(pos) => pos,
+            None => break,
+        };
+    }
+
+    escaped.push_str(text);
+    Ok(Cow::Owned(escaped))
+}
+
+/// Parse an escape like `\xNN` with exactly `digits` hex digits.
+fn hex_escape(escape: &str, digits: usize) -> Result<char, Error> {
+    let hex = escape
+        .get(2..2 + digits)
+        .filter(|hex| hex.bytes().all(|b| b.is_ascii_hexdigit()))
+        .ok_or_else(|| Error::invalid_string_literal(escape, "invalid hex escape"))?;
+
+    u32::from_str_radix(hex, 16)
+        .ok()
+        .and_then(char::from_u32)
+        .ok_or_else(|| {
+            Error::invalid_string_literal(escape, "escape was not a valid unicode codepoint")
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn python_escapes() {
+        assert_eq!(unescape_python("plain").unwrap(), "plain");
+        assert_eq!(
+            unescape_python(r"it\'s \x1b[0m é \U0001f980 \\").unwrap(),
+            "it's \x1b[0m \u{e9} \u{1f980} \\"
+        );
+        assert!(unescape_python(r"\x1").is_err());
+        assert!(unescape_python(r"\ud800").is_err());
+        assert!(unescape_python(r"\q").is_err());
+        assert!(unescape_python("\\").is_err());
+    }
+}
@@ -1,19 +1,70 @@
-use std::borrow::Cow;
-use std::fmt;
+#[cfg(feature = "alloc")]
+use alloc::borrow::Cow;
+#[cfg(feature = "alloc")]
+use alloc::string::{String, ToString};
+use core::fmt;
+use core::ops::Deref;
+#[cfg(feature = "std")]
+use std::sync::Arc;
 
-use crate::lex::{Token, TokenKind};
+#[cfg(feature = "alloc")]
+use crate::lex::Token;
+use crate::lex::TokenKind;
 
+/// An error from the lexer.
+///
+/// This only borrows from the input so that the lexer never needs to
+/// allocate. It is converted into an owned [`Error`] when it leaves the
+/// lexer.
 #[derive(Clone, Debug)]
-pub(crate) struct LexerError {
-    pub(crate) found: String,
+pub(crate) struct LexerError<'a> {
+    pub(crate) found: Text<'a>,
     pub(crate) expected: Expected,
 }
 
-impl LexerError {
+/// Why the lexer was stopped before the end of the input.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[cfg(feature = "alloc")]
+pub(crate) enum Interrupt {
+    Cancelled,
+    #[cfg(feature = "std")]
+    TimedOut,
+}
+
+/// Text within a [`LexerError`].
+///
+/// This works like a `Cow<str>`, except that without `alloc` it can only be
+/// borrowed.
+#[derive(Clone, Eq, PartialEq)]
+pub(crate) enum Text<'a> {
+    Borrowed(&'a str),
+    #[cfg(feature = "alloc")]
+    Owned(String),
+}
+
+impl Deref for Text<'_> {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        match self {
+            Self::Borrowed(text) => text,
+            #[cfg(feature = "alloc")]
+            Self::Owned(text) => text,
+        }
+    }
+}
+
+impl fmt::Debug for Text<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        (**self).fmt(f)
+    }
+}
+
+impl<'a> LexerError<'a> {
     #[cold]
-    pub(crate) fn unexpected_token(found: &str, expected: impl Into<Expected>) -> Self {
+    pub(crate) fn unexpected_token(found: &'a str, expected: impl Into<Expected>) -> Self {
         Self {
-            found: found.into(),
+            found: Text::Borrowed(found),
             expected: expected.into(),
         }
     }
@@ -21,28 +72,79 @@ impl LexerError {
     #[cold]
     pub(crate) fn unexpected_eof(expected: impl Into<Expected>) -> Self {
         Self {
-            found: "".into(),
+            found: Text::Borrowed(""),
             expected: expected.into(),
         }
     }
+
+    /// Lexing was stopped by one of the checks in the options rather than by
+    /// a problem with the input.
+    #[cold]
+    #[cfg(feature = "alloc")]
+    pub(crate) fn interrupted(interrupt: Interrupt) -> Self {
+        Self {
+            found: Text::Borrowed(""),
+            expected: Expected::Interrupted(interrupt),
+        }
+    }
+
+    /// The unexpected token, as long as it still borrows from the input.
+    pub(crate) fn borrowed_found(&self) -> Option<&'a str> {
+        match self.found {
+            Text::Borrowed(found) => Some(found),
+            #[cfg(feature = "alloc")]
+            Text::Owned(_) => None,
+        }
+    }
+
+    #[cfg(feature = "alloc")]
+    pub(crate) fn into_owned(self) -> LexerError<'static> {
+        LexerError {
+            found: Text::Owned(self.found.to_string()),
+            expected: self.expected,
+        }
+    }
 }
 
-impl fmt::Display for LexerError {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+impl LexerError<'_> {
+    fn fmt_limited(&self, f: &mut fmt::Formatter<'_>, limit: Option<usize>) -> fmt::Result {
         if self.found.is_empty() {
             write!(f, "unexpected end of file, expected {}", self.expected)
         } else {
             write!(
                 f,
                 "unexpected token `{}`, expected {}",
-                self.found, self.expected
+                Truncated(&self.found, limit),
+                self.expected
             )
         }
     }
 }
 
+impl fmt::Display for LexerError<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.fmt_limited(f, None)
+    }
+}
+
+/// Displays a span of the input, cut off after `limit` characters.
+struct Truncated<'a>(&'a str, Option<usize>);
+
+impl fmt::Display for Truncated<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Self(text, limit) = *self;
+        match limit.and_then(|limit| text.char_indices().nth(limit)) {
+            Some((index, _)) => write!(f, "{}…", &text[..index]),
+            None => f.write_str(text),
+        }
+    }
+}
+
+#[cfg(feature = "alloc")]
 mod detail {
-    use std::borrow::Cow;
+    use alloc::borrow::Cow;
+    #[cfg(feature = "std")]
+    use std::sync::Arc;
 
     use super::*;
 
@@ -50,49 +152,144 @@ mod detail {
     #[derive(Clone, Debug)]
     pub(crate) enum Error {
         Custom(String),
-        Lexer(LexerError),
+        Data {
+            kind: ErrorKind,
+            message: String,
+        },
+        Lexer(LexerError<'static>),
         ParseInt {
             value: String,
-            error: std::num::ParseIntError,
+            error: core::num::ParseIntError,
         },
         ParseFloat {
             value: String,
-            error: std::num::ParseFloatError,
+            error: core::num::ParseFloatError,
         },
         InvalidStringLiteral {
             message: Cow<'static, str>,
         },
+        Cancelled,
+        #[cfg(feature = "std")]
+        TimedOut,
+        DepthLimitExceeded {
+            limit: usize,
+        },
+        AddressNotAllowed {
+            address: String,
+        },
+        NotBorrowed {
+            span: String,
+        },
+        #[cfg(feature = "std")]
+        Io(Arc<std::io::Error>),
     }
 }
 
+#[cfg(feature = "alloc")]
 pub(crate) use self::detail::Error as ErrorDetail;
 
+/// The number of characters of a span that are included in an error message
+/// by default.
+#[cfg(feature = "alloc")]
+const DEFAULT_SPAN_LIMIT: usize = 100;
+
+#[cfg(feature = "alloc")]
 #[derive(Clone)]
-pub struct Error(ErrorDetail);
+pub struct Error {
+    detail: ErrorDetail,
+
+    /// The maximum number of characters of the input to include in the error
+    /// message.
+    span_limit: Option<usize>,
+
+    location: Option<Location>,
+}
+
+/// The position within the input at which an error occurred.
+///
+/// This is returned by [`Error::location`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+#[cfg(feature = "alloc")]
+pub struct Location {
+    /// The line number, starting from 1.
+    pub line: usize,
+
+    /// The column number in characters, starting from 1.
+    pub column: usize,
+
+    /// The offset from the start of the input in bytes.
+    pub byte_offset: usize,
+}
 
+#[cfg(feature = "alloc")]
+impl Location {
+    /// The location of the byte at `offset` within `input`.
+    pub(crate) fn new(input: &str, offset: usize) -> Self {
+        let before = &input[..offset];
+        let line_start = before.rfind('\n').map(|index| index + 1).unwrap_or(0);
+
+        Self {
+            line: before.matches('\n').count() + 1,
+            column: before[line_start..].chars().count() + 1,
+            byte_offset: offset,
+        }
+    }
+
+    /// Convert a location within text that starts at `start` into a location
+    /// within the whole input.
+    #[cfg(feature = "std")]
+    pub(crate) fn within(self, start: Location) -> Self {
+        Self {
+            line: start.line + self.line - 1,
+            column: match self.line {
+                1 => start.column + self.column - 1,
+                _ => self.column,
+            },
+            byte_offset: start.byte_offset + self.byte_offset,
+        }
+    }
+}
+
+#[cfg(feature = "alloc")]
 impl Error {
+    fn new(detail: ErrorDetail) -> Self {
+        Self {
+            detail,
+            span_limit: Some(DEFAULT_SPAN_LIMIT),
+            location: None,
+        }
+    }
+
     #[cold]
-    pub(crate) fn parse_int(value: &str, error: std::num::ParseIntError) -> Self {
-        Self(ErrorDetail::ParseInt {
+    pub(crate) fn parse_int(value: &str, error: core::num::ParseIntError) -> Self {
+        Self::new(ErrorDetail::ParseInt {
             value: value.into(),
             error,
         })
     }
 
     #[cold]
-    pub(crate) fn parse_float(value: &str, error: std::num::ParseFloatError) -> Self {
-        Self(ErrorDetail::ParseFloat {
+    pub(crate) fn parse_float(value: &str, error: core::num::ParseFloatError) -> Self {
+        Self::new(ErrorDetail::ParseFloat {
             value: value.into(),
             error,
         })
     }
 
+    #[cold]
+    pub(crate) fn invalid_hex_float(value: &str) -> Self {
+        serde::de::Error::invalid_value(
+            serde::de::Unexpected::Str(value),
+            &"a hexadecimal float literal",
+        )
+    }
+
     #[cold]
     pub(crate) fn unexpected_token(token: Token, expected: impl Into<Expected>) -> Self {
-        Self(ErrorDetail::Lexer(LexerError::unexpected_token(
-            token.value,
-            expected,
-        )))
+        Self::new(ErrorDetail::Lexer(
+            LexerError::unexpected_token(token.value, expected).into_owned(),
+        ))
     }
 
     #[cold]
@@ -100,58 +297,538 @@ impl Error {
         _value: &str,
         message: impl Into<Cow<'static, str>>,
     ) -> Self {
-        Self(ErrorDetail::InvalidStringLiteral {
+        Self::new(ErrorDetail::InvalidStringLiteral {
             message: message.into(),
         })
     }
 }
 
-impl From<LexerError> for Error {
-    fn from(error: LexerError) -> Self {
-        Self(ErrorDetail::Lexer(error))
+#[cfg(feature = "alloc")]
+impl Error {
+    #[cold]
+    pub(crate) fn cancelled() -> Self {
+        Self::new(ErrorDetail::Cancelled)
+    }
+
+    /// Whether this error was caused by the parse being cancelled through
+    /// [`Options::cancellation_token`](crate::Options::cancellation_token).
+    pub fn is_cancelled(&self) -> bool {
+        matches!(self.detail, ErrorDetail::Cancelled)
+    }
+
+    #[cfg(feature = "std")]
+    #[cold]
+    pub(crate) fn timed_out() -> Self {
+        Self::new(ErrorDetail::TimedOut)
+    }
+
+    /// Whether this error was caused by the parse running past the
+    /// [`Options::time_budget`](crate::Options::time_budget).
+    #[cfg(feature = "std")]
+    pub fn is_timed_out(&self) -> bool {
+        matches!(self.detail, ErrorDetail::TimedOut)
+    }
+
+    #[cold]
+    pub(crate) fn depth_limit_exceeded(limit: usize) -> Self {
+        Self::new(ErrorDetail::DepthLimitExceeded { limit })
+    }
+
+    #[cold]
+    pub(crate) fn address_not_allowed(address: &str) -> Self {
+        Self::new(ErrorDetail::AddressNotAllowed {
+            address: address.into(),
+        })
+    }
+
+    /// Whether this error was caused by a pointer address in the input while
+    /// [`Options::addresses`](crate::Options::addresses) is set to
+    /// [`Addresses::Error`](crate::Addresses::Error).
+    pub fn is_address_not_allowed(&self) -> bool {
+        matches!(self.detail, ErrorDetail::AddressNotAllowed { .. })
+    }
+
+    #[cold]
+    pub(crate) fn not_borrowed(span: &str) -> Self {
+        Self::new(ErrorDetail::NotBorrowed { span: span.into() })
+    }
+
+    /// Whether this error was caused by a string that could not be borrowed
+    /// from the input while
+    /// [`Options::require_borrowed`](crate::Options::require_borrowed) is
+    /// enabled.
+    pub fn is_not_borrowed(&self) -> bool {
+        matches!(self.detail, ErrorDetail::NotBorrowed { .. })
+    }
+
+    #[cfg(feature = "std")]
+    #[cold]
+    pub(crate) fn io(error: std::io::Error) -> Self {
+        Self::new(ErrorDetail::Io(Arc::new(error)))
+    }
+
+    /// Whether this error was caused by a failure to read the input, such as
+    /// in [`from_reader`](crate::from_reader).
+    #[cfg(feature = "std")]
+    pub fn is_io(&self) -> bool {
+        matches!(self.detail, ErrorDetail::Io(_))
+    }
+
+    /// Whether this error was caused by the input being nested more deeply
+    /// than [`Options::max_depth`](crate::Options::max_depth) allows.
+    pub fn is_depth_limit_exceeded(&self) -> bool {
+        matches!(self.detail, ErrorDetail::DepthLimitExceeded { .. })
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl Error {
+    /// The category of this error.
+    ///
+    /// This allows callers to branch on the type of error without having to
+    /// match on the error message. Use [`expected`](Self::expected) and
+    /// [`found_token`](Self::found_token) to get more details about
+    /// unexpected tokens.
+    ///
+    /// # Example
+    /// ```
+    /// use serde::Deserialize;
+    /// use serde_dbgfmt::ErrorKind;
+    ///
+    /// #[derive(Debug, Deserialize)]
+    /// enum Color {
+    ///     Red,
+    ///     Green,
+    /// }
+    ///
+    /// let error = serde_dbgfmt::from_str::<Color>("Blue").unwrap_err();
+    /// assert_eq!(error.kind(), ErrorKind::UnknownVariant);
+    ///
+    /// let error = serde_dbgfmt::from_str::<Color>("Red Green").unwrap_err();
+    /// assert_eq!(error.kind(), ErrorKind::TrailingData);
+    /// ```
+    pub fn kind(&self) -> ErrorKind {
+        match &self.detail {
+            ErrorDetail::Custom(_) => ErrorKind::Custom,
+            ErrorDetail::Data { kind, .. } => *kind,
+            ErrorDetail::Lexer(error) if error.found.is_empty() => ErrorKind::Eof,
+            ErrorDetail::Lexer(error) if error.expected == Expected::Token(TokenKind::Eof) => {
+                ErrorKind::TrailingData
+            }
+            ErrorDetail::Lexer(_) => ErrorKind::UnexpectedToken,
+            ErrorDetail::ParseInt { .. } => ErrorKind::ParseInt,
+            ErrorDetail::ParseFloat { .. } => ErrorKind::ParseFloat,
+            ErrorDetail::InvalidStringLiteral { .. } => ErrorKind::InvalidStringLiteral,
+            ErrorDetail::Cancelled => ErrorKind::Cancelled,
+            #[cfg(feature = "std")]
+            ErrorDetail::TimedOut => ErrorKind::TimedOut,
+            ErrorDetail::DepthLimitExceeded { .. } => ErrorKind::DepthLimitExceeded,
+            ErrorDetail::AddressNotAllowed { .. } => ErrorKind::AddressNotAllowed,
+            ErrorDetail::NotBorrowed { .. } => ErrorKind::NotBorrowed,
+            #[cfg(feature = "std")]
+            ErrorDetail::Io(_) => ErrorKind::Io,
+        }
+    }
+
+    /// What the deserializer expected to find when it encountered an
+    /// unexpected token.
+    ///
+    /// This returns `None` for errors that were not caused by an unexpected
+    /// token, such as invalid literals or errors raised by a `Deserialize`
+    /// impl.
+    ///
+    /// # Example
+    /// ```
+    /// use serde_dbgfmt::ExpectedKind;
+    ///
+    /// let error = serde_dbgfmt::from_str::<(u32, u32)>("(1 2)").unwrap_err();
+    ///
+    /// assert_eq!(error.expected(), Some(ExpectedKind::Punct(',')));
+    /// assert_eq!(error.found_token(), Some("2"));
+    /// ```
+    pub fn expected(&self) -> Option<ExpectedKind<'_>> {
+        match &self.detail {
+            ErrorDetail::Lexer(error) => Some(match &error.expected {
+                Expected::Interrupted(_) => return None,
+                Expected::Token(kind) => match kind {
+                    TokenKind::Ident => ExpectedKind::Ident,
+                    TokenKind::Punct => ExpectedKind::AnyPunct,
+                    TokenKind::Integer => ExpectedKind::Integer,
+                    TokenKind::Float => ExpectedKind::Float,
+                    TokenKind::String => ExpectedKind::String,
+                    TokenKind::Char => ExpectedKind::Char,
+                    TokenKind::Address => ExpectedKind::Address,
+                    TokenKind::Duration => ExpectedKind::Duration,
+                    TokenKind::Eof => ExpectedKind::Eof,
+                },
+                Expected::Punct(c) => ExpectedKind::Punct(*c),
+                Expected::Custom(msg) => ExpectedKind::Other(msg),
+            }),
+            _ => None,
+        }
+    }
+
+    /// The text of the unexpected token that caused this error.
+    ///
+    /// This returns `None` if the error was not caused by an unexpected token
+    /// or if the input ended where a token was expected.
+    pub fn found_token(&self) -> Option<&str> {
+        match &self.detail {
+            ErrorDetail::Lexer(error) if !error.found.is_empty() => Some(&error.found),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl Error {
+    /// The full text of the input that caused this error.
+    ///
+    /// Unlike the error message, this is never truncated. This returns `None`
+    /// for errors that are not about a specific part of the input, or if the
+    /// input ended early.
+    pub fn span(&self) -> Option<&str> {
+        match &self.detail {
+            ErrorDetail::Lexer(error) if !error.found.is_empty() => Some(&error.found),
+            ErrorDetail::ParseInt { value, .. } | ErrorDetail::ParseFloat { value, .. } => {
+                Some(value)
+            }
+            ErrorDetail::AddressNotAllowed { address } => Some(address),
+            ErrorDetail::NotBorrowed { span } => Some(span),
+            _ => None,
+        }
+    }
+
+    /// Where in the input the error occurred.
+    ///
+    /// This is filled in by [`from_str`](crate::from_str) and the other
+    /// functions which deserialize a complete value, and by
+    /// [`Deserializer::end`](crate::Deserializer::end). Errors that are not
+    /// caused by the input, such as cancellation, have no location.
+    ///
+    /// # Example
+    /// ```
+    /// let text = "[\n    1,\n    two,\n]";
+    /// let error = serde_dbgfmt::from_str::<Vec<u32>>(text).unwrap_err();
+    /// let location = error.location().unwrap();
+    ///
+    /// assert_eq!((location.line, location.column), (3, 5));
+    /// assert_eq!(&text[location.byte_offset..][..3], "two");
+    /// assert_eq!(
+    ///     error.to_string(),
+    ///     "unexpected token `two`, expected an integer at line 3 column 5"
+    /// );
+    /// ```
+    pub fn location(&self) -> Option<Location> {
+        self.location
+    }
+
+    /// Record that the error occurred at byte `offset` of `input`, unless the
+    /// error already has a location.
+    pub(crate) fn locate(mut self, input: &str, offset: usize) -> Self {
+        let positional = match self.detail {
+            ErrorDetail::Cancelled => false,
+            #[cfg(feature = "std")]
+            ErrorDetail::TimedOut | ErrorDetail::Io(_) => false,
+            _ => true,
+        };
+
+        if positional && self.location.is_none() {
+            self.location = Some(Location::new(input, offset));
+        }
+
+        self
+    }
+
+    /// Move the location of this error, which occurred within text that
+    /// starts at `start`, so that it is relative to the whole input instead.
+    #[cfg(feature = "std")]
+    pub(crate) fn relocate(mut self, start: Location) -> Self {
+        if let Some(location) = &mut self.location {
+            *location = location.within(start);
+        }
+
+        self
+    }
+
+    /// Set the maximum number of characters of the input to include in the
+    /// error message.
+    ///
+    /// Longer spans are cut off and end with `…`. The full text remains
+    /// available through [`span`](Self::span). Passing `None` removes the
+    /// limit. The default limit is 100 characters.
+    ///
+    /// See also [`Options::error_span_limit`](crate::Options::error_span_limit).
+    ///
+    /// # Example
+    /// ```
+    /// let text = format!("[1 {}]", "9".repeat(1000));
+    /// let mut error = serde_dbgfmt::from_str::<Vec<u32>>(&text).unwrap_err();
+    /// error.set_span_limit(Some(4));
+    ///
+    /// assert_eq!(
+    ///     error.to_string(),
+    ///     "unexpected token `9999…`, expected `,` at line 1 column 4"
+    /// );
+    /// assert_eq!(error.span().unwrap().len(), 1000);
+    /// ```
+    pub fn set_span_limit(&mut self, limit: Option<usize>) {
+        self.span_limit = limit;
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl From<LexerError<'_>> for Error {
+    fn from(error: LexerError<'_>) -> Self {
+        match error.expected {
+            Expected::Interrupted(Interrupt::Cancelled) => Self::cancelled(),
+            #[cfg(feature = "std")]
+            Expected::Interrupted(Interrupt::TimedOut) => Self::timed_out(),
+            _ => Self::new(ErrorDetail::Lexer(error.into_owned())),
+        }
     }
 }
 
+#[cfg(feature = "alloc")]
 impl fmt::Debug for Error {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        self.0.fmt(f)
+        self.detail.fmt(f)
     }
 }
 
+#[cfg(feature = "alloc")]
 impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        match &self.0 {
+        self.fmt_message(f)?;
+
+        match &self.location {
+            Some(location) => write!(f, " at line {} column {}", location.line, location.column),
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl Error {
+    fn fmt_message(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.detail {
             ErrorDetail::Custom(msg) => f.write_str(msg),
-            ErrorDetail::Lexer(err) => err.fmt(f),
+            ErrorDetail::Data { message, .. } => f.write_str(message),
+            ErrorDetail::Lexer(err) => err.fmt_limited(f, self.span_limit),
             ErrorDetail::ParseInt { value, error } => {
+                let value = Truncated(value, self.span_limit);
                 write!(f, "invalid integer literal `{value}`: {error}")
             }
             ErrorDetail::ParseFloat { value, error } => {
+                let value = Truncated(value, self.span_limit);
                 write!(f, "invalid float literal `{value}`: {error}")
             }
             ErrorDetail::InvalidStringLiteral { message } => {
                 write!(f, "invalid string literal: {message}")
             }
+            ErrorDetail::Cancelled => f.write_str("deserialization was cancelled"),
+            #[cfg(feature = "std")]
+            ErrorDetail::TimedOut => f.write_str("deserialization exceeded its time budget"),
+            ErrorDetail::DepthLimitExceeded { limit } => {
+                write!(f, "input exceeded the maximum nesting depth of {limit}")
+            }
+            ErrorDetail::AddressNotAllowed { address } => {
+                write!(f, "unexpected pointer address `{address}`")
+            }
+            ErrorDetail::NotBorrowed { span } => {
+                let span = Truncated(span, self.span_limit);
+                write!(
+                    f,
+                    "string {span} contains escape sequences and cannot be borrowed"
+                )
+            }
+            #[cfg(feature = "std")]
+            ErrorDetail::Io(error) => write!(f, "failed to read the input: {error}"),
         }
     }
 }
 
+#[cfg(feature = "std")]
 impl std::error::Error for Error {
     fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
-        match &self.0 {
+        match &self.detail {
             ErrorDetail::ParseInt { error, .. } => Some(error),
             ErrorDetail::ParseFloat { error, .. } => Some(error),
+            ErrorDetail::Io(error) => Some(&**error),
             _ => None,
         }
     }
 }
 
+#[cfg(all(feature = "alloc", not(feature = "std")))]
+impl serde::de::StdError for Error {}
+
+#[cfg(feature = "alloc")]
+impl Error {
+    /// Create an error of the given kind, using the same message that serde
+    /// would use by default.
+    #[cold]
+    fn data(kind: ErrorKind, error: serde::de::value::Error) -> Self {
+        Self::new(ErrorDetail::Data {
+            kind,
+            message: error.to_string(),
+        })
+    }
+}
+
+#[cfg(feature = "alloc")]
 impl serde::de::Error for Error {
     fn custom<T>(msg: T) -> Self
     where
         T: fmt::Display,
     {
-        Self(ErrorDetail::Custom(msg.to_string()))
+        Self::new(ErrorDetail::Custom(msg.to_string()))
+    }
+
+    fn invalid_type(unexp: serde::de::Unexpected, exp: &dyn serde::de::Expected) -> Self {
+        let error = serde::de::Error::invalid_type(unexp, exp);
+        Self::data(ErrorKind::InvalidType, error)
+    }
+
+    fn invalid_value(unexp: serde::de::Unexpected, exp: &dyn serde::de::Expected) -> Self {
+        let error = serde::de::Error::invalid_value(unexp, exp);
+        Self::data(ErrorKind::InvalidValue, error)
+    }
+
+    fn invalid_length(len: usize, exp: &dyn serde::de::Expected) -> Self {
+        let error = serde::de::Error::invalid_length(len, exp);
+        Self::data(ErrorKind::InvalidLength, error)
+    }
+
+    fn unknown_variant(variant: &str, expected: &'static [&'static str]) -> Self {
+        let error = serde::de::Error::unknown_variant(variant, expected);
+        Self::data(ErrorKind::UnknownVariant, error)
+    }
+
+    fn unknown_field(field: &str, expected: &'static [&'static str]) -> Self {
+        let error = serde::de::Error::unknown_field(field, expected);
+        Self::data(ErrorKind::UnknownField, error)
+    }
+
+    fn missing_field(field: &'static str) -> Self {
+        let error = serde::de::Error::missing_field(field);
+        Self::data(ErrorKind::MissingField, error)
+    }
+
+    fn duplicate_field(field: &'static str) -> Self {
+        let error = serde::de::Error::duplicate_field(field);
+        Self::data(ErrorKind::DuplicateField, error)
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl serde::ser::Error for Error {
+    fn custom<T>(msg: T) -> Self
+    where
+        T: fmt::Display,
+    {
+        Self::new(ErrorDetail::Custom(msg.to_string()))
+    }
+}
+
+/// What the deserializer was expecting when it found an unexpected token.
+///
+/// This is returned by [`Error::expected`].
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+#[cfg(feature = "alloc")]
+pub enum ExpectedKind<'a> {
+    /// An identifier, such as a struct name or `true`.
+    Ident,
+    /// A specific punctuation character.
+    Punct(char),
+    /// Any punctuation token.
+    AnyPunct,
+    Integer,
+    Float,
+    String,
+    Char,
+    /// A pointer address, such as `0x7f3a2c001230`.
+    Address,
+    /// A `Duration`, such as `1.5s`.
+    Duration,
+    /// The end of the input.
+    Eof,
+    /// Anything else, described in the same way as in the error message.
+    Other(&'a str),
+}
+
+/// The category of an [`Error`].
+///
+/// This is returned by [`Error::kind`].
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+#[cfg(feature = "alloc")]
+pub enum ErrorKind {
+    /// A token that did not fit the value being deserialized.
+    UnexpectedToken,
+    /// The input ended before the value was complete.
+    Eof,
+    /// There was more input after the end of the value.
+    TrailingData,
+    /// An integer literal that could not be parsed or did not fit the target
+    /// type.
+    ParseInt,
+    /// A float literal that could not be parsed.
+    ParseFloat,
+    /// A string or char literal with an invalid escape sequence.
+    InvalidStringLiteral,
+    /// A value of the wrong type, reported by a `Deserialize` impl.
+    InvalidType,
+    /// A value of the right type but with an invalid value, reported by a
+    /// `Deserialize` impl.
+    InvalidValue,
+    /// A sequence or map with the wrong number of elements.
+    InvalidLength,
+    /// An enum variant that the target type does not have.
+    UnknownVariant,
+    /// A struct field that the target type does not have.
+    UnknownField,
+    /// A struct that is missing a required field.
+    MissingField,
+    /// A struct with the same field more than once.
+    DuplicateField,
+    /// A pointer address while [`Addresses::Error`](crate::Addresses::Error)
+    /// is in effect.
+    AddressNotAllowed,
+    /// A string that could not be borrowed from the input while
+    /// [`Options::require_borrowed`](crate::Options::require_borrowed) is
+    /// enabled.
+    NotBorrowed,
+    /// The input was nested more deeply than
+    /// [`Options::max_depth`](crate::Options::max_depth) allows.
+    DepthLimitExceeded,
+    /// The parse was cancelled.
+    Cancelled,
+    /// The parse ran past its time budget.
+    TimedOut,
+    /// The input could not be read.
+    Io,
+    /// Any other error, such as a custom error from a `Deserialize` impl.
+    Custom,
+}
+
+#[cfg(feature = "alloc")]
+impl fmt::Display for ExpectedKind<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Ident => TokenKind::Ident.fmt(f),
+            Self::Punct(c) => write!(f, "`{c}`"),
+            Self::AnyPunct => TokenKind::Punct.fmt(f),
+            Self::Integer => TokenKind::Integer.fmt(f),
+            Self::Float => TokenKind::Float.fmt(f),
+            Self::String => TokenKind::String.fmt(f),
+            Self::Char => TokenKind::Char.fmt(f),
+            Self::Address => TokenKind::Address.fmt(f),
+            Self::Duration => TokenKind::Duration.fmt(f),
+            Self::Eof => TokenKind::Eof.fmt(f),
+            Self::Other(msg) => f.write_str(msg),
+        }
     }
 }
 
@@ -159,7 +836,22 @@ impl serde::de::Error for Error {
 pub(crate) enum Expected {
     Token(TokenKind),
     Punct(char),
-    Custom(String),
+    Custom(Text<'static>),
+
+    /// Not an expectation; see [`LexerError::interrupted`].
+    #[cfg(feature = "alloc")]
+    Interrupted(Interrupt),
+}
+
+impl Expected {
+    /// Expect the punctuation `punct`.
+    pub(crate) fn punct(punct: &'static str) -> Self {
+        let mut chars = punct.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) => Self::Punct(c),
+            _ => Self::from(punct),
+        }
+    }
 }
 
 impl From<TokenKind> for Expected {
@@ -168,9 +860,16 @@ impl From<TokenKind> for Expected {
     }
 }
 
-impl<'de> From<&'de str> for Expected {
-    fn from(value: &'de str) -> Self {
-        Self::Custom(value.into())
+impl From<&'static str> for Expected {
+    fn from(value: &'static str) -> Self {
+        Self::Custom(Text::Borrowed(value))
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl From<String> for Expected {
+    fn from(value: String) -> Self {
+        Self::Custom(Text::Owned(value))
     }
 }
 
@@ -186,6 +885,8 @@ impl fmt::Display for Expected {
             Self::Punct(c) => write!(f, "`{c}`"),
             Self::Custom(msg) => f.write_str(msg),
             Self::Token(kind) => kind.fmt(f),
+            #[cfg(feature = "alloc")]
+            Self::Interrupted(_) => f.write_str("the rest of the input"),
         }
     }
 }
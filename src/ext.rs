@@ -0,0 +1,81 @@
+use alloc::format;
+use core::fmt::Debug;
+
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+
+use crate::Error;
+
+/// Extension trait for parsing debug representations out of strings.
+///
+/// This is the method form of [`from_str`](crate::from_str).
+///
+/// # Example
+/// ```
+/// use serde_dbgfmt::DebugParseExt;
+///
+/// let value: Vec<u32> = "[1, 2, 3]".parse_debug().unwrap();
+/// assert_eq!(value, [1, 2, 3]);
+/// ```
+pub trait DebugParseExt {
+    /// Parse a `T` from the debug representation in `self`.
+    fn parse_debug<'de, T>(&'de self) -> Result<T, Error>
+    where
+        T: Deserialize<'de>;
+}
+
+impl DebugParseExt for str {
+    fn parse_debug<'de, T>(&'de self) -> Result<T, Error>
+    where
+        T: Deserialize<'de>,
+    {
+        crate::from_str(self)
+    }
+}
+
+/// Extension trait for reinterpreting a value as another type by way of its
+/// debug representation.
+///
+/// This is the method form of [`from_dbg`](crate::from_dbg) and is
+/// implemented for every type that implements [`Debug`].
+///
+/// Note that this always works on the debug representation of `self`. Calling
+/// it on a string will parse the string as a quoted string literal, use
+/// [`DebugParseExt::parse_debug`] to parse the contents of a string instead.
+///
+/// # Example
+/// ```
+/// use serde::Deserialize;
+/// use serde_dbgfmt::DebugReparse;
+///
+/// mod internal {
+///     #[derive(Debug)]
+///     pub struct Point {
+///         pub x: i32,
+///         pub y: i32,
+///         pub label: &'static str,
+///     }
+/// }
+///
+/// #[derive(Deserialize)]
+/// struct Point {
+///     x: i64,
+///     y: i64,
+/// }
+///
+/// let point = internal::Point { x: 1, y: -2, label: "origin" };
+/// let point: Point = point.reparse().unwrap();
+///
+/// assert_eq!((point.x, point.y), (1, -2));
+/// ```
+pub trait DebugReparse: Debug {
+    /// Parse the debug representation of `self` as a `T`.
+    fn reparse<T>(&self) -> Result<T, Error>
+    where
+        T: DeserializeOwned,
+    {
+        crate::from_string(format!("{self:?}"))
+    }
+}
+
+impl<U: Debug + ?Sized> DebugReparse for U {}
@@ -0,0 +1,197 @@
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt;
+
+use crate::canonical::canonical;
+use crate::literal::{parse_float_literal, parse_int_literal};
+use crate::tree::{Node, NodeKind};
+use crate::util::unescape;
+use crate::{Error, Options};
+
+/// A leaf value produced by [`flatten`].
+///
+/// The [`Display`](fmt::Display) impl prints the value as it would appear in
+/// debug output.
+#[derive(Clone, Debug, PartialEq)]
+#[non_exhaustive]
+pub enum Scalar {
+    Bool(bool),
+    /// An integer that fits within an `i128`.
+    Int(i128),
+    /// A positive integer that is too large for an `i128`.
+    UInt(u128),
+    Float(f64),
+    Str(String),
+    Char(char),
+    /// A bare identifier, such as a unit struct, unit variant, or `None`.
+    Ident(String),
+    /// Any other leaf, such as a range or an empty collection, as it appears
+    /// in canonical form.
+    Other(String),
+}
+
+impl fmt::Display for Scalar {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Bool(value) => value.fmt(f),
+            Self::Int(value) => value.fmt(f),
+            Self::UInt(value) => value.fmt(f),
+            Self::Float(value) => fmt::Debug::fmt(value, f),
+            Self::Str(value) => fmt::Debug::fmt(value, f),
+            Self::Char(value) => fmt::Debug::fmt(value, f),
+            Self::Ident(value) | Self::Other(value) => f.write_str(value),
+        }
+    }
+}
+
+/// Flatten a value into a list of paths and the leaf value found at each.
+///
+/// Paths are built from the field names and indices leading to each leaf,
+/// e.g. `server.ports[0]`. Struct fields and map entries with string keys
+/// that are valid identifiers are joined using `.`. Elements of lists, sets,
+/// tuples, and tuple structs are written as an index within `[]`, the same as
+/// for [`get`]. Any other map keys are written within `[]` in canonical form.
+/// The path to the top level value is empty.
+///
+/// Empty collections are kept as leaves so that they still show up in the
+/// output.
+///
+/// # Example
+/// ```
+/// use serde_dbgfmt::Scalar;
+///
+/// let text = r#"Config { server: Server { host: "localhost", ports: [8080, 8443] }, debug: false }"#;
+/// let leaves = serde_dbgfmt::flatten(text).unwrap();
+///
+/// assert_eq!(
+///     leaves,
+///     [
+///         ("server.host".to_owned(), Scalar::Str("localhost".into())),
+///         ("server.ports[0]".to_owned(), Scalar::Int(8080)),
+///         ("server.ports[1]".to_owned(), Scalar::Int(8443)),
+///         ("debug".to_owned(), Scalar::Bool(false)),
+///     ]
+/// );
+///
+/// let lines: Vec<_> = leaves.iter().map(|(path, value)| format!("{path} = {value}")).collect();
+/// assert_eq!(lines[0], r#"server.host = "localhost""#);
+/// ```
+///
+/// [`get`]: crate::get
+pub fn flatten(input: &str) -> Result<Vec<(String, Scalar)>, Error> {
+    flatten_node(&Node::parse(input)?)
+}
+
+/// Flatten a value using the provided options to lex it.
+///
+/// The same options apply as for [`canonicalize_with`]. Strings produced by
+/// the [unknown token callback](Options::on_unknown_token) become
+/// [`Scalar::Str`] leaves containing their text as-is.
+///
+/// [`canonicalize_with`]: crate::canonicalize_with
+pub fn flatten_with(input: &str, options: &Options) -> Result<Vec<(String, Scalar)>, Error> {
+    flatten_node(&Node::parse_with(input, options)?)
+}
+
+fn flatten_node(node: &Node) -> Result<Vec<(String, Scalar)>, Error> {
+    let mut leaves = Vec::new();
+    visit(node, &mut String::new(), &mut leaves)?;
+    Ok(leaves)
+}
+
+fn visit(node: &Node, path: &mut String, leaves: &mut Vec<(String, Scalar)>) -> Result<(), Error> {
+    let len = path.len();
+    let separator = match path.is_empty() {
+        true => "",
+        false => ".",
+    };
+
+    let mut child = |segment: fmt::Arguments, node: &Node| {
+        use core::fmt::Write;
+
+        path.write_fmt(segment).unwrap();
+        let result = visit(node, path, leaves);
+        path.truncate(len);
+        result
+    };
+
+    match &node.kind {
+        NodeKind::Struct { fields, .. } if !fields.is_empty() => {
+            for (name, value) in fields {
+                child(format_args!("{separator}{name}"), value)?;
+            }
+        }
+        NodeKind::TupleStruct { elements, .. }
+        | NodeKind::Tuple(elements)
+        | NodeKind::List(elements)
+        | NodeKind::Set(elements)
+            if !elements.is_empty() =>
+        {
+            for (index, element) in elements.iter().enumerate() {
+                child(format_args!("[{index}]"), element)?;
+            }
+        }
+        NodeKind::Map(entries) if !entries.is_empty() => {
+            for (key, value) in entries {
+                let field = match key.kind {
+                    NodeKind::String => Some(key.string()?),
+                    _ => None,
+                };
+
+                match field {
+                    Some(field) if is_ident(&field) => {
+                        child(format_args!("{separator}{field}"), value)?
+                    }
+                    _ => child(format_args!("[{}]", canonical(key)), value)?,
+                }
+            }
+        }
+        _ => leaves.push((path.clone(), scalar(node)?)),
+    }
+
+    Ok(())
+}
+
+pub(crate) fn scalar(node: &Node) -> Result<Scalar, Error> {
+    let inner = || &node.span[1..node.span.len() - 1];
+
+    Ok(match node.kind {
+        NodeKind::Ident("true") => Scalar::Bool(true),
+        NodeKind::Ident("false") => Scalar::Bool(false),
+        NodeKind::Ident(name) => Scalar::Ident(name.into()),
+        NodeKind::Integer => match parse_int_literal(node.span) {
+            Ok(value) => Scalar::Int(value),
+            Err(e) => Scalar::UInt(parse_int_literal(node.span).map_err(|_| e)?),
+        },
+        NodeKind::Float => Scalar::Float(match node.span {
+            "inf" | "+inf" => f64::INFINITY,
+            "-inf" => f64::NEG_INFINITY,
+            span => parse_float_literal(span)?,
+        }),
+        NodeKind::String => Scalar::Str(node.string()?.into_owned()),
+        NodeKind::Char => {
+            let text = unescape(inner())?;
+            let mut chars = text.chars();
+            match (chars.next(), chars.next()) {
+                (Some(c), None) => Scalar::Char(c),
+                _ => {
+                    return Err(Error::invalid_string_literal(
+                        node.span,
+                        "char literal must contain exactly one character",
+                    ))
+                }
+            }
+        }
+        _ => Scalar::Other(canonical(node)),
+    })
+}
+
+pub(crate) fn is_ident(text: &str) -> bool {
+    let mut chars = text.chars();
+    match chars.next() {
+        Some(c) if unicode_ident::is_xid_start(c) || c == '_' => {
+            chars.all(unicode_ident::is_xid_continue)
+        }
+        _ => false,
+    }
+}
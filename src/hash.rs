@@ -0,0 +1,643 @@
+use std::borrow::Cow;
+use std::cmp::Reverse;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::hash::{Hash, Hasher};
+
+use crate::canonical::canonical;
+use crate::flatten::is_ident;
+use crate::lex::{is_pointer, Lexer, TokenKind};
+use crate::redact::glob_match;
+use crate::tree::{Node, NodeKind};
+use crate::{Error, Options, Value, ValueString};
+
+/// Hash the structure and content of the value in `input`.
+///
+/// Formatting is ignored, so the compact (`{:?}`) and pretty (`{:#?}`) forms
+/// of a value hash the same. The order of map entries is significant. Use
+/// [`StructuralHasher`] to ignore it.
+///
+/// The hash is computed from the parsed structure without building any
+/// intermediate strings, making it cheap enough to bucket large numbers of
+/// log records by their debug payloads. It is stable for a given version of
+/// this crate and of Rust but should not be persisted.
+///
+/// # Example
+/// ```
+/// let compact = r#"Event { kind: Login, user: "alice" }"#;
+/// let pretty = "Event {\n    kind: Login,\n    user: \"alice\",\n}";
+///
+/// assert_eq!(
+///     serde_dbgfmt::structural_hash(compact).unwrap(),
+///     serde_dbgfmt::structural_hash(pretty).unwrap()
+/// );
+/// assert_ne!(
+///     serde_dbgfmt::structural_hash(compact).unwrap(),
+///     serde_dbgfmt::structural_hash(r#"Event { kind: Login, user: "bob" }"#).unwrap()
+/// );
+/// ```
+pub fn structural_hash(input: &str) -> Result<u64, Error> {
+    StructuralHasher::new().hash(input)
+}
+
+/// Configurable version of [`structural_hash`].
+///
+/// # Example
+/// ```
+/// use serde_dbgfmt::StructuralHasher;
+///
+/// let hasher = StructuralHasher::new().ignore_map_order(true);
+///
+/// assert_eq!(
+///     hasher.hash(r#"{"a": 1, "b": 2}"#).unwrap(),
+///     hasher.hash(r#"{"b": 2, "a": 1}"#).unwrap()
+/// );
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct StructuralHasher {
+    ignore_map_order: bool,
+    normalize_pointers: bool,
+    ignored: Vec<String>,
+    options: Options,
+}
+
+impl StructuralHasher {
+    /// Create a hasher with the same behaviour as [`structural_hash`].
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Ignore the order of map entries and set elements.
+    ///
+    /// `HashMap` and `HashSet` print their contents in an arbitrary order, so
+    /// two dumps of the same value will often only differ in this way.
+    #[must_use]
+    pub fn ignore_map_order(mut self, ignore: bool) -> Self {
+        self.ignore_map_order = ignore;
+        self
+    }
+
+    /// Treat every pointer address as equal.
+    ///
+    /// Addresses are detected in the same way as for
+    /// [`normalize_pointers`](crate::rewrite::normalize_pointers).
+    #[must_use]
+    pub fn normalize_pointers(mut self, normalize: bool) -> Self {
+        self.normalize_pointers = normalize;
+        self
+    }
+
+    /// Ignore the values at paths matching `pattern`.
+    ///
+    /// Paths use the same syntax as [`flatten`](crate::flatten), e.g.
+    /// `request.items[0].id`. A `*` within the pattern matches any sequence of
+    /// characters, so `items[*].id` matches the `id` field of every element
+    /// and `*timestamp` matches any path ending in `timestamp`. Only the value
+    /// is ignored, the field or key itself is still part of the hash.
+    ///
+    /// This can be called multiple times to ignore several paths.
+    #[must_use]
+    pub fn ignore_path(mut self, pattern: impl Into<String>) -> Self {
+        self.ignored.push(pattern.into());
+        self
+    }
+
+    /// Use `options` when lexing the input.
+    ///
+    /// The same options apply as for
+    /// [`canonicalize_with`](crate::canonicalize_with).
+    #[must_use]
+    pub fn options(mut self, options: Options) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Parse `input` and return its structural hash.
+    pub fn hash(&self, input: &str) -> Result<u64, Error> {
+        let node = Node::parse_with(input, &self.options)?;
+        self.write(Tree::Node(&node))
+    }
+
+    /// Return the structural hash of `value`.
+    ///
+    /// This is the same as [`hash`](Self::hash) of the
+    /// [`Display`](std::fmt::Display) output of `value`, so a value hashes the
+    /// same as the input it was parsed from unless parsing normalized that
+    /// input. For example, `0x10` is displayed as `16` and so hashes the same
+    /// as `16`. The value is hashed directly rather than through its text, so
+    /// it may be nested arbitrarily deeply.
+    ///
+    /// This only fails if `value` was built by hand and its display output
+    /// cannot be parsed, e.g. if an [`Ident`](Value::Ident) is not a valid
+    /// identifier.
+    pub fn hash_value(&self, value: &Value) -> Result<u64, Error> {
+        self.write(Tree::Value(value))
+    }
+
+    /// Group `inputs` by their structural hash and count the number of
+    /// inputs within each group.
+    ///
+    /// Groups are returned with the most common first. Groups with the same
+    /// count are kept in the order in which they first appeared. This stops
+    /// at the first input that fails to parse.
+    ///
+    /// # Example
+    /// ```
+    /// use serde_dbgfmt::StructuralHasher;
+    ///
+    /// let logs = [
+    ///     r#"Error { code: 404, path: "/a", at: 1001 }"#,
+    ///     r#"Error { code: 500, path: "/b", at: 1002 }"#,
+    ///     r#"Error { code: 404, path: "/a", at: 1003 }"#,
+    /// ];
+    ///
+    /// let groups = StructuralHasher::new().ignore_path("at").group(logs).unwrap();
+    ///
+    /// assert_eq!(groups.len(), 2);
+    /// assert_eq!(groups[0].count, 2);
+    /// assert_eq!(groups[0].example, logs[0]);
+    /// assert_eq!(groups[1].count, 1);
+    /// ```
+    pub fn group<I>(&self, inputs: I) -> Result<Vec<Group>, Error>
+    where
+        I: IntoIterator,
+        I::Item: AsRef<str>,
+    {
+        let mut groups = Vec::new();
+        let mut indices = HashMap::new();
+
+        for input in inputs {
+            let input = input.as_ref();
+            let hash = self.hash(input)?;
+
+            let index = *indices.entry(hash).or_insert_with(|| {
+                groups.push(Group {
+                    hash,
+                    count: 0,
+                    example: input.to_owned(),
+                });
+                groups.len() - 1
+            });
+            groups[index].count += 1;
+        }
+
+        // This is a stable sort so ties keep their original order.
+        groups.sort_by_key(|group| Reverse(group.count));
+        Ok(groups)
+    }
+
+    /// Hash `tree`.
+    ///
+    /// This uses an explicit stack instead of recursing so that values of any
+    /// depth can be hashed. The path to each node is only tracked when there
+    /// are ignored paths.
+    fn write<'a, 'n>(&self, tree: Tree<'a, 'n>) -> Result<u64, Error> {
+        let tracked = !self.ignored.is_empty();
+        let mut stack = vec![Step::Tree(tree, tracked)];
+        let mut path = String::new();
+        let mut lens = Vec::new();
+
+        // The states for the items of unordered collections that are being
+        // hashed separately, and the hashes of the items that are done.
+        let mut root = DefaultHasher::new();
+        let mut states: Vec<DefaultHasher> = Vec::new();
+        let mut sorted: Vec<Vec<u64>> = Vec::new();
+
+        while let Some(step) = stack.pop() {
+            let state = states.last_mut().unwrap_or(&mut root);
+            let (tree, tracked) = match step {
+                Step::Tree(tree, tracked) => (tree, tracked),
+                Step::Str(value) => {
+                    value.hash(state);
+                    continue;
+                }
+                Step::Bool(value) => {
+                    value.hash(state);
+                    continue;
+                }
+                Step::Field(name) => {
+                    lens.push(path.len());
+                    push_field(&mut path, name);
+                    continue;
+                }
+                Step::Index(index) => {
+                    lens.push(path.len());
+                    write!(path, "[{index}]").unwrap();
+                    continue;
+                }
+                Step::Key(key) => {
+                    lens.push(path.len());
+                    push_key(&mut path, key)?;
+                    continue;
+                }
+                Step::Leave => {
+                    path.truncate(lens.pop().unwrap_or(0));
+                    continue;
+                }
+                Step::BeginItem => {
+                    states.push(DefaultHasher::new());
+                    continue;
+                }
+                Step::EndItem => {
+                    let hash = states.pop().map_or(0, |state| state.finish());
+                    sorted.last_mut().unwrap().push(hash);
+                    continue;
+                }
+                Step::EndSorted => {
+                    let mut hashes = sorted.pop().unwrap_or_default();
+                    hashes.sort_unstable();
+                    hashes.hash(state);
+                    continue;
+                }
+            };
+
+            if tracked
+                && self
+                    .ignored
+                    .iter()
+                    .any(|pattern| glob_match(pattern, &path))
+            {
+                continue;
+            }
+
+            let shape = tree.shape()?;
+            shape.kind_name().hash(state);
+
+            // Steps are pushed in reverse, so that they are taken off the
+            // stack in order.
+            let child = |stack: &mut Vec<_>, segment, tree| match tracked {
+                true => stack.extend([Step::Leave, Step::Tree(tree, true), segment]),
+                false => stack.push(Step::Tree(tree, false)),
+            };
+            let mut seq = |stack: &mut Vec<_>, state: &mut DefaultHasher, len: usize, sort| {
+                len.hash(state);
+                if sort {
+                    sorted.push(Vec::with_capacity(len));
+                    stack.push(Step::EndSorted);
+                }
+                sort
+            };
+
+            match shape {
+                Shape::Struct {
+                    name,
+                    fields,
+                    non_exhaustive,
+                } => {
+                    name.hash(state);
+                    fields.len().hash(state);
+                    stack.push(Step::Bool(non_exhaustive));
+                    for i in (0..fields.len()).rev() {
+                        let (name, value) = fields.get(i);
+                        child(&mut stack, Step::Field(name), value);
+                        stack.push(Step::Str(name));
+                    }
+                }
+                Shape::TupleStruct { name, elements } => {
+                    name.hash(state);
+                    seq(&mut stack, state, elements.len(), false);
+                    for i in (0..elements.len()).rev() {
+                        child(&mut stack, Step::Index(i), elements.get(i));
+                    }
+                }
+                Shape::Tuple(elements) | Shape::List(elements) | Shape::Set(elements) => {
+                    let sort = matches!(shape, Shape::Set(_)) && self.ignore_map_order;
+                    let sort = seq(&mut stack, state, elements.len(), sort);
+                    for i in (0..elements.len()).rev() {
+                        if sort {
+                            stack.push(Step::EndItem);
+                        }
+                        child(&mut stack, Step::Index(i), elements.get(i));
+                        if sort {
+                            stack.push(Step::BeginItem);
+                        }
+                    }
+                }
+                Shape::Map(entries) => {
+                    let sort = seq(&mut stack, state, entries.len(), self.ignore_map_order);
+                    for i in (0..entries.len()).rev() {
+                        let (key, value) = entries.get(i);
+                        if sort {
+                            stack.push(Step::EndItem);
+                        }
+                        // Ignored paths only apply to values, so keys are
+                        // always hashed in full.
+                        child(&mut stack, Step::Key(key), value);
+                        stack.push(Step::Tree(key, false));
+                        if sort {
+                            stack.push(Step::BeginItem);
+                        }
+                    }
+                }
+                Shape::Ident(name) => name.hash(state),
+                // The span of an operator expression may contain whitespace,
+                // so only hash the tokens within it.
+                Shape::Other(span) => {
+                    let mut lexer = Lexer::new(span);
+                    while let Ok(token) = lexer.parse_token() {
+                        if token.kind == TokenKind::Eof {
+                            break;
+                        }
+                        token.value.hash(state);
+                    }
+                }
+                Shape::Scalar("integer", span) if self.normalize_pointers && is_pointer(&span) => {
+                    "0x".hash(state)
+                }
+                Shape::Scalar(_, span) => span.hash(state),
+            }
+        }
+
+        Ok(root.finish())
+    }
+}
+
+/// Either a node parsed from text or a [`Value`]. A value is hashed the same
+/// as the node that its display output parses to.
+#[derive(Clone, Copy)]
+enum Tree<'a, 'n> {
+    Node(&'a Node<'n>),
+    Value(&'a Value),
+}
+
+/// A step in [`StructuralHasher::write`].
+enum Step<'a, 'n> {
+    /// Hash a tree, along with whether the path to it is tracked.
+    Tree(Tree<'a, 'n>, bool),
+    Str(&'a str),
+    Bool(bool),
+    /// Append a segment to the path, until the matching `Leave`.
+    Field(&'a str),
+    Index(usize),
+    Key(Tree<'a, 'n>),
+    Leave,
+    /// Hash an item of an unordered collection into its own state.
+    BeginItem,
+    EndItem,
+    /// Combine the sorted hashes of the items of an unordered collection.
+    EndSorted,
+}
+
+/// The parts of a [`Tree`] that are hashed.
+enum Shape<'a, 'n> {
+    Struct {
+        name: &'a str,
+        fields: Fields<'a, 'n>,
+        non_exhaustive: bool,
+    },
+    TupleStruct {
+        name: &'a str,
+        elements: Elements<'a, 'n>,
+    },
+    Tuple(Elements<'a, 'n>),
+    List(Elements<'a, 'n>),
+    Set(Elements<'a, 'n>),
+    Map(Entries<'a, 'n>),
+    Ident(&'a str),
+    Other(&'a str),
+    /// Any other value, hashed by its kind and its text.
+    Scalar(&'static str, Cow<'a, str>),
+}
+
+#[derive(Clone, Copy)]
+enum Fields<'a, 'n> {
+    Node(&'a [(&'n str, Node<'n>)]),
+    Value(&'a [(ValueString, Value)]),
+}
+
+#[derive(Clone, Copy)]
+enum Elements<'a, 'n> {
+    Node(&'a [Node<'n>]),
+    Value(&'a [Value]),
+}
+
+#[derive(Clone, Copy)]
+enum Entries<'a, 'n> {
+    Node(&'a [(Node<'n>, Node<'n>)]),
+    Value(&'a [(Value, Value)]),
+}
+
+impl<'a, 'n> Tree<'a, 'n> {
+    fn shape(self) -> Result<Shape<'a, 'n>, Error> {
+        let value = match self {
+            Self::Node(node) => return Ok(node_shape(node)),
+            Self::Value(value) => value.untruncated(),
+        };
+
+        Ok(match value {
+            Value::Bool(true) => Shape::Ident("true"),
+            Value::Bool(false) => Shape::Ident("false"),
+            Value::Int(_) | Value::UInt(_) => Shape::Scalar("integer", value.to_string().into()),
+            Value::Float(_) => Shape::Scalar("float", value.to_string().into()),
+            Value::Str(_) => Shape::Scalar("string", value.to_string().into()),
+            Value::Char(_) => Shape::Scalar("char", value.to_string().into()),
+            Value::Unit => Shape::Tuple(Elements::Value(&[])),
+            Value::Option(None) => Shape::Ident("None"),
+            Value::Option(Some(inner)) => Shape::TupleStruct {
+                name: "Some",
+                elements: Elements::Value(core::slice::from_ref(&**inner)),
+            },
+            Value::Ident(name) if matches!(name.as_str(), "NaN" | "inf") => {
+                Shape::Scalar("float", Cow::Borrowed(name))
+            }
+            Value::Ident(name) => ident(name)?,
+            // Structs without any fields are displayed as a bare identifier.
+            Value::Struct {
+                name,
+                fields,
+                non_exhaustive: false,
+            } if fields.is_empty() => ident(name)?,
+            Value::TupleStruct { name, elements } if elements.is_empty() => ident(name)?,
+            Value::Struct {
+                name,
+                fields,
+                non_exhaustive,
+            } => {
+                check_ident(name)?;
+                Shape::Struct {
+                    name,
+                    fields: Fields::Value(fields),
+                    non_exhaustive: *non_exhaustive,
+                }
+            }
+            Value::TupleStruct { name, elements } => {
+                check_ident(name)?;
+                Shape::TupleStruct {
+                    name,
+                    elements: Elements::Value(elements),
+                }
+            }
+            Value::Tuple(elements) => Shape::Tuple(Elements::Value(elements)),
+            Value::Seq(elements) => Shape::List(Elements::Value(elements)),
+            // Empty braces are always parsed as a map.
+            Value::Set(elements) if elements.is_empty() => Shape::Map(Entries::Value(&[])),
+            Value::Set(elements) => Shape::Set(Elements::Value(elements)),
+            Value::Map(entries) => Shape::Map(Entries::Value(entries)),
+            Value::Other(text) => Shape::Other(text),
+            Value::Truncated { .. } => unreachable!("untruncated value is truncated"),
+        })
+    }
+}
+
+fn node_shape<'a, 'n>(node: &'a Node<'n>) -> Shape<'a, 'n> {
+    match &node.kind {
+        NodeKind::Struct {
+            name,
+            fields,
+            non_exhaustive,
+        } => Shape::Struct {
+            name,
+            fields: Fields::Node(fields),
+            non_exhaustive: *non_exhaustive,
+        },
+        NodeKind::TupleStruct { name, elements } => Shape::TupleStruct {
+            name,
+            elements: Elements::Node(elements),
+        },
+        NodeKind::Tuple(elements) => Shape::Tuple(Elements::Node(elements)),
+        NodeKind::List(elements) => Shape::List(Elements::Node(elements)),
+        NodeKind::Set(elements) => Shape::Set(Elements::Node(elements)),
+        NodeKind::Map(entries) => Shape::Map(Entries::Node(entries)),
+        NodeKind::Ident(name) => Shape::Ident(name),
+        NodeKind::Other => Shape::Other(node.span),
+        _ => Shape::Scalar(node.kind_name(), Cow::Borrowed(node.span)),
+    }
+}
+
+impl Shape<'_, '_> {
+    /// The same as [`Node::kind_name`].
+    fn kind_name(&self) -> &'static str {
+        match self {
+            Self::Struct { .. } => "struct",
+            Self::TupleStruct { .. } => "tuple struct",
+            Self::Ident("true" | "false") => "bool",
+            Self::Ident(_) => "unit",
+            Self::Tuple(_) => "tuple",
+            Self::List(_) => "list",
+            Self::Set(_) => "set",
+            Self::Map(_) => "map",
+            Self::Other(_) => "other",
+            Self::Scalar(kind, _) => kind,
+        }
+    }
+}
+
+impl<'a, 'n> Fields<'a, 'n> {
+    fn len(self) -> usize {
+        match self {
+            Self::Node(fields) => fields.len(),
+            Self::Value(fields) => fields.len(),
+        }
+    }
+
+    fn get(self, index: usize) -> (&'a str, Tree<'a, 'n>) {
+        match self {
+            Self::Node(fields) => (fields[index].0, Tree::Node(&fields[index].1)),
+            Self::Value(fields) => (&fields[index].0, Tree::Value(&fields[index].1)),
+        }
+    }
+}
+
+impl<'a, 'n> Elements<'a, 'n> {
+    fn len(self) -> usize {
+        match self {
+            Self::Node(elements) => elements.len(),
+            Self::Value(elements) => elements.len(),
+        }
+    }
+
+    fn get(self, index: usize) -> Tree<'a, 'n> {
+        match self {
+            Self::Node(elements) => Tree::Node(&elements[index]),
+            Self::Value(elements) => Tree::Value(&elements[index]),
+        }
+    }
+}
+
+impl<'a, 'n> Entries<'a, 'n> {
+    fn len(self) -> usize {
+        match self {
+            Self::Node(entries) => entries.len(),
+            Self::Value(entries) => entries.len(),
+        }
+    }
+
+    fn get(self, index: usize) -> (Tree<'a, 'n>, Tree<'a, 'n>) {
+        match self {
+            Self::Node(entries) => (Tree::Node(&entries[index].0), Tree::Node(&entries[index].1)),
+            Self::Value(entries) => (
+                Tree::Value(&entries[index].0),
+                Tree::Value(&entries[index].1),
+            ),
+        }
+    }
+}
+
+fn ident<'a, 'n>(name: &'a str) -> Result<Shape<'a, 'n>, Error> {
+    check_ident(name)?;
+    Ok(Shape::Ident(name))
+}
+
+/// Check that `name`, from a value that may have been built by hand, would
+/// parse back as an identifier.
+fn check_ident(name: &str) -> Result<(), Error> {
+    let mut lexer = Lexer::new(name);
+    let token = lexer.parse_token()?;
+    if token.kind != TokenKind::Ident {
+        return Err(Error::unexpected_token(token, TokenKind::Ident));
+    }
+
+    let token = lexer.parse_token()?;
+    match token.kind {
+        TokenKind::Eof => Ok(()),
+        _ => Err(Error::unexpected_token(token, TokenKind::Eof)),
+    }
+}
+
+/// A group of structurally identical inputs returned by
+/// [`StructuralHasher::group`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Group {
+    /// The structural hash shared by every input in the group.
+    pub hash: u64,
+
+    /// The number of inputs in the group.
+    pub count: usize,
+
+    /// The first input that was placed in the group.
+    pub example: String,
+}
+
+fn push_field(path: &mut String, name: &str) {
+    if !path.is_empty() {
+        path.push('.');
+    }
+    path.push_str(name);
+}
+
+/// Append the path segment for a map key, in the same form as
+/// [`flatten`](crate::flatten).
+fn push_key(path: &mut String, key: Tree) -> Result<(), Error> {
+    let node = match key {
+        Tree::Node(node) => node,
+        Tree::Value(Value::Str(field)) if is_ident(field) => {
+            push_field(path, field);
+            return Ok(());
+        }
+        // Keys are rarely nested, so it is fine to go through their text.
+        Tree::Value(value) => return push_key(path, Tree::Node(&Node::parse(&value.to_string())?)),
+    };
+
+    if let NodeKind::String = node.kind {
+        let field = node.string()?;
+        if is_ident(&field) {
+            push_field(path, &field);
+            return Ok(());
+        }
+    }
+
+    write!(path, "[{}]", canonical(node)).unwrap();
+    Ok(())
+}
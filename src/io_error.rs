@@ -0,0 +1,223 @@
+use std::fmt;
+use std::io::{self, ErrorKind};
+
+use serde::de::{self, Deserializer, Visitor};
+use serde::Deserialize;
+
+use crate::de::RAW_VALUE_TOKEN;
+use crate::lex::{Lexer, TokenKind};
+use crate::util::unescape;
+
+/// A mirror of the debug representation of [`std::io::Error`].
+///
+/// The [`Debug`] impl for [`io::Error`] does not look like the error type
+/// itself. Depending on how the error was created it will print as one of
+/// - `Os { code: 2, kind: NotFound, message: "No such file or directory" }`,
+/// - `Kind(NotFound)`,
+/// - `Error { kind: NotFound, message: "..." }`, or
+/// - `Custom { kind: Other, error: "..." }`.
+///
+/// `IoError` accepts all of these forms and can be converted into an
+/// [`io::Error`]. If you want to deserialize a field to [`io::Error`]
+/// directly then use [`with::io_error`](crate::with::io_error) instead.
+///
+/// # Example
+/// ```
+/// use std::io::ErrorKind;
+///
+/// use serde_dbgfmt::IoError;
+///
+/// let text = r#"Os { code: 2, kind: NotFound, message: "No such file or directory" }"#;
+/// let error: IoError = serde_dbgfmt::from_str(text).unwrap();
+///
+/// assert_eq!(error.kind(), ErrorKind::NotFound);
+/// assert_eq!(error.code, Some(2));
+/// assert_eq!(error.message.as_deref(), Some("No such file or directory"));
+/// ```
+///
+/// [`Debug`]: std::fmt::Debug
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct IoError {
+    /// The name of the [`ErrorKind`], as it was printed.
+    ///
+    /// This is kept as a string since newer versions of rust may print kinds
+    /// that are not available in the current version.
+    pub kind_name: String,
+
+    /// The raw OS error code, if this was an OS error.
+    pub code: Option<i32>,
+
+    /// The message attached to the error, if any.
+    ///
+    /// For custom errors this is the debug representation of the inner error,
+    /// unless the inner error was a plain string message.
+    pub message: Option<String>,
+}
+
+impl IoError {
+    /// The [`ErrorKind`] of this error.
+    ///
+    /// Kinds that are not recognized are mapped to [`ErrorKind::Other`].
+    pub fn kind(&self) -> ErrorKind {
+        kind_from_name(&self.kind_name)
+    }
+}
+
+impl From<IoError> for io::Error {
+    fn from(error: IoError) -> Self {
+        match error {
+            IoError {
+                code: Some(code), ..
+            } => io::Error::from_raw_os_error(code),
+            IoError {
+                message: Some(message),
+                ..
+            } => io::Error::new(kind_from_name(&error.kind_name), message),
+            _ => kind_from_name(&error.kind_name).into(),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for IoError {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        enum Repr {
+            Os {
+                code: i32,
+                kind: KindName,
+                message: String,
+            },
+            Kind(KindName),
+            Error {
+                kind: KindName,
+                message: String,
+            },
+            Custom {
+                kind: KindName,
+                error: ErrorMessage,
+            },
+        }
+
+        Ok(match Repr::deserialize(deserializer)? {
+            Repr::Os {
+                code,
+                kind,
+                message,
+            } => IoError {
+                kind_name: kind.0,
+                code: Some(code),
+                message: Some(message),
+            },
+            Repr::Kind(kind) => IoError {
+                kind_name: kind.0,
+                code: None,
+                message: None,
+            },
+            Repr::Error { kind, message } => IoError {
+                kind_name: kind.0,
+                code: None,
+                message: Some(message),
+            },
+            Repr::Custom { kind, error } => IoError {
+                kind_name: kind.0,
+                code: None,
+                message: Some(error.0),
+            },
+        })
+    }
+}
+
+/// The name of an [`ErrorKind`], which is printed as a bare identifier.
+struct KindName(String);
+
+impl<'de> Deserialize<'de> for KindName {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct KindVisitor;
+
+        impl<'de> Visitor<'de> for KindVisitor {
+            type Value = KindName;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("an io error kind")
+            }
+
+            fn visit_str<E: de::Error>(self, v: &str) -> Result<Self::Value, E> {
+                Ok(KindName(v.into()))
+            }
+        }
+
+        deserializer.deserialize_identifier(KindVisitor)
+    }
+}
+
+/// The inner error of a custom io error.
+///
+/// This can be any value at all so it is captured as raw text. Plain string
+/// messages are unescaped since they are by far the most common case.
+struct ErrorMessage(String);
+
+impl<'de> Deserialize<'de> for ErrorMessage {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct MessageVisitor;
+
+        impl<'de> Visitor<'de> for MessageVisitor {
+            type Value = ErrorMessage;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("an error value")
+            }
+
+            fn visit_str<E: de::Error>(self, v: &str) -> Result<Self::Value, E> {
+                let mut lexer = Lexer::new(v);
+                let message = match (lexer.parse_token(), lexer.parse_token()) {
+                    (Ok(token), Ok(eof))
+                        if token.kind == TokenKind::String && eof.kind == TokenKind::Eof =>
+                    {
+                        unescape(&token.value[1..token.value.len() - 1])
+                            .map_err(E::custom)?
+                            .into_owned()
+                    }
+                    _ => v.into(),
+                };
+
+                Ok(ErrorMessage(message))
+            }
+        }
+
+        deserializer.deserialize_newtype_struct(RAW_VALUE_TOKEN, MessageVisitor)
+    }
+}
+
+fn kind_from_name(name: &str) -> ErrorKind {
+    match name {
+        "NotFound" => ErrorKind::NotFound,
+        "PermissionDenied" => ErrorKind::PermissionDenied,
+        "ConnectionRefused" => ErrorKind::ConnectionRefused,
+        "ConnectionReset" => ErrorKind::ConnectionReset,
+        "ConnectionAborted" => ErrorKind::ConnectionAborted,
+        "NotConnected" => ErrorKind::NotConnected,
+        "AddrInUse" => ErrorKind::AddrInUse,
+        "AddrNotAvailable" => ErrorKind::AddrNotAvailable,
+        "BrokenPipe" => ErrorKind::BrokenPipe,
+        "AlreadyExists" => ErrorKind::AlreadyExists,
+        "WouldBlock" => ErrorKind::WouldBlock,
+        "InvalidInput" => ErrorKind::InvalidInput,
+        "InvalidData" => ErrorKind::InvalidData,
+        "TimedOut" => ErrorKind::TimedOut,
+        "WriteZero" => ErrorKind::WriteZero,
+        "Interrupted" => ErrorKind::Interrupted,
+        "Unsupported" => ErrorKind::Unsupported,
+        "UnexpectedEof" => ErrorKind::UnexpectedEof,
+        "OutOfMemory" => ErrorKind::OutOfMemory,
+        _ => ErrorKind::Other,
+    }
+}
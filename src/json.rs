@@ -0,0 +1,201 @@
+//! Parsing for the debug output of [`serde_json::Value`].
+//!
+//! The [`Debug`] impl for `Value` does not print the JSON text. Instead it
+//! prints the enum variants, with arrays and objects using the list and map
+//! helpers directly after the variant name:
+//!
+//! ```text
+//! Object {"id": Number(7), "tags": Array [String("a"), Null]}
+//! ```
+//!
+//! This is not a shape that can be described using serde's data model so this
+//! module provides a dedicated parser instead.
+//!
+//! [`Debug`]: std::fmt::Debug
+
+use serde_json::{Map, Number, Value};
+
+use crate::lex::{Lexer, TokenKind};
+use crate::util::unescape;
+use crate::{Error, Options};
+
+/// The nesting depth used when the options do not set one. This matches the
+/// recursion limit that `serde_json` uses when parsing JSON.
+const DEFAULT_MAX_DEPTH: usize = 128;
+
+/// Parse the debug output of a [`serde_json::Value`].
+///
+/// Arrays and objects may be nested at most 128 levels deep. Use
+/// [`parse_json_value_with`] to change the limit.
+///
+/// # Example
+/// ```
+/// use serde_json::json;
+/// use serde_dbgfmt::json::parse_json_value;
+///
+/// let text = r#"Object {"id": Number(7), "tags": Array [String("a"), Null]}"#;
+/// let value = parse_json_value(text).unwrap();
+///
+/// assert_eq!(value, json!({ "id": 7, "tags": ["a", null] }));
+/// ```
+pub fn parse_json_value(text: &str) -> Result<Value, Error> {
+    parse_json_value_with(text, &Options::default())
+}
+
+/// Parse the debug output of a [`serde_json::Value`] using the provided
+/// options.
+///
+/// [`Options::max_depth`] limits how deeply arrays and objects may be nested.
+/// The unknown token handler, the cancellation token, and the time budget
+/// apply as well.
+///
+/// # Example
+/// ```
+/// use serde_dbgfmt::json::parse_json_value_with;
+/// use serde_dbgfmt::Options;
+///
+/// let options = Options::new().max_depth(2);
+/// assert!(parse_json_value_with("Array [Array [Null]]", &options).is_ok());
+///
+/// let error = parse_json_value_with("Array [Array [Array []]]", &options).unwrap_err();
+/// assert!(error.is_depth_limit_exceeded());
+/// ```
+pub fn parse_json_value_with(text: &str, options: &Options) -> Result<Value, Error> {
+    let mut lexer = Lexer::new(text);
+    lexer.set_options(text, options);
+
+    let limit = options.config().max_depth.unwrap_or(DEFAULT_MAX_DEPTH);
+    let value = parse_value(&mut lexer, 0, limit)?;
+
+    let token = lexer.parse_token()?;
+    match token.kind {
+        TokenKind::Eof => Ok(value),
+        _ => Err(Error::unexpected_token(token, TokenKind::Eof)),
+    }
+}
+
+/// Parse a single value that is nested `depth` levels deep, failing if it
+/// would go deeper than `limit`.
+fn parse_value(lexer: &mut Lexer, depth: usize, limit: usize) -> Result<Value, Error> {
+    let token = lexer.parse_token()?;
+    if token.kind != TokenKind::Ident {
+        return Err(Error::unexpected_token(token, "a JSON value"));
+    }
+
+    let value = match token.value {
+        "Null" => Value::Null,
+        "Bool" => {
+            lexer.expect_punct("(")?;
+            let token = lexer.parse_token()?;
+            let value = match token.value {
+                "true" if token.kind == TokenKind::Ident => true,
+                "false" if token.kind == TokenKind::Ident => false,
+                _ => return Err(Error::unexpected_token(token, "`true` or `false`")),
+            };
+            lexer.expect_punct(")")?;
+
+            Value::Bool(value)
+        }
+        "Number" => {
+            lexer.expect_punct("(")?;
+            let number = parse_number(lexer)?;
+            lexer.expect_punct(")")?;
+
+            Value::Number(number)
+        }
+        "String" => {
+            lexer.expect_punct("(")?;
+            let value = parse_string(lexer)?;
+            lexer.expect_punct(")")?;
+
+            Value::String(value)
+        }
+        "Array" => {
+            let depth = enter(depth, limit)?;
+            lexer.expect_punct("[")?;
+
+            let mut values = Vec::new();
+            while !lexer.peek_token()?.is_punct("]") {
+                values.push(parse_value(lexer, depth, limit)?);
+
+                if !lexer.peek_token()?.is_punct("]") {
+                    lexer.expect_punct(",")?;
+                }
+            }
+            lexer.expect_punct("]")?;
+
+            Value::Array(values)
+        }
+        "Object" => {
+            let depth = enter(depth, limit)?;
+            lexer.expect_punct("{")?;
+
+            let mut map = Map::new();
+            while !lexer.peek_token()?.is_punct("}") {
+                let key = parse_string(lexer)?;
+                lexer.expect_punct(":")?;
+                map.insert(key, parse_value(lexer, depth, limit)?);
+
+                if !lexer.peek_token()?.is_punct("}") {
+                    lexer.expect_punct(",")?;
+                }
+            }
+            lexer.expect_punct("}")?;
+
+            Value::Object(map)
+        }
+        _ => return Err(Error::unexpected_token(token, "a JSON value")),
+    };
+
+    Ok(value)
+}
+
+/// Enter an array or object, returning the depth of its elements.
+fn enter(depth: usize, limit: usize) -> Result<usize, Error> {
+    match depth < limit {
+        true => Ok(depth + 1),
+        false => Err(Error::depth_limit_exceeded(limit)),
+    }
+}
+
+fn parse_number(lexer: &mut Lexer) -> Result<Number, Error> {
+    let mut token = lexer.parse_token()?;
+    let negative = token.is_punct("-");
+    if negative {
+        token = lexer.parse_token()?;
+    }
+
+    let text = match negative {
+        true => format!("-{}", token.value),
+        false => token.value.to_owned(),
+    };
+
+    match token.kind {
+        // serde_json stores integers as either a u64 or an i64 and only falls
+        // back to a float if they are out of range for both.
+        TokenKind::Integer | TokenKind::Address => {
+            if let Ok(value) = text.parse::<u64>() {
+                return Ok(value.into());
+            }
+            if let Ok(value) = text.parse::<i64>() {
+                return Ok(value.into());
+            }
+        }
+        TokenKind::Float => (),
+        _ => return Err(Error::unexpected_token(token, "a number")),
+    }
+
+    let value: f64 = text.parse().map_err(|e| Error::parse_float(&text, e))?;
+    Number::from_f64(value).ok_or_else(|| {
+        serde::de::Error::invalid_value(serde::de::Unexpected::Float(value), &"a finite number")
+    })
+}
+
+fn parse_string(lexer: &mut Lexer) -> Result<String, Error> {
+    let token = lexer.parse_token()?;
+    if token.kind != TokenKind::String {
+        return Err(Error::unexpected_token(token, TokenKind::String));
+    }
+
+    Ok(unescape(&token.value[1..token.value.len() - 1])?.into_owned())
+}
@@ -1,13 +1,46 @@
-use std::fmt;
+#[cfg(feature = "alloc")]
+use alloc::sync::Arc;
+use core::cell::Cell;
+use core::fmt;
+#[cfg(feature = "alloc")]
+use core::iter::FusedIterator;
+#[cfg(feature = "alloc")]
+use core::sync::atomic::{AtomicUsize, Ordering};
+#[cfg(feature = "std")]
+use std::time::Instant;
 
+#[cfg(feature = "alloc")]
+use crate::error::Interrupt;
 use crate::error::{Expected, LexerError};
+#[cfg(feature = "alloc")]
+use crate::options::{Callback, UnknownTokenFn};
+#[cfg(feature = "alloc")]
+use crate::{Error, Options, UnknownToken};
 
+/// How many bytes of input are lexed between checks of the cancellation
+/// token and the time budget.
+#[cfg(feature = "alloc")]
+const CHECK_INTERVAL: usize = 4 * 1024;
+
+/// How many bytes of input are consumed between calls to the progress
+/// callback.
+#[cfg(feature = "alloc")]
+const PROGRESS_INTERVAL: usize = 64 * 1024;
+
+/// The kind of a token within debug-formatted text.
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
-pub(crate) enum TokenKind {
+#[non_exhaustive]
+pub enum TokenKind {
     /// An alphanumeric identifier token. It must start with a letter but then
     /// can be any series of valid identifier tokens.
     ///
-    /// Examples include `TokenKind`, `true`, and `false`.
+    /// The identifier may be directly followed by a list of generic arguments
+    /// in angle brackets, which is included in the token. This is how
+    /// `PhantomData` prints the type that it wraps. Paths made up of several
+    /// identifiers separated by `::` are a single token as well.
+    ///
+    /// Examples include `TokenKind`, `true`, `false`, `PhantomData<u32>`, and
+    /// `std::io::ErrorKind`.
     Ident,
 
     /// A punctuation token.
@@ -16,9 +49,10 @@ pub(crate) enum TokenKind {
     /// - `:`
     /// - `{` and `}`
     /// - `[` and `]`
-    /// - `..`
+    /// - `..` and `..=`
     /// - `,`
     /// - `-`
+    /// - `|`
     Punct,
 
     /// Any integer value.
@@ -37,6 +71,7 @@ pub(crate) enum TokenKind {
     /// - `42.0`
     /// - `5e+3`
     /// - `99.1212e-22`
+    /// - `0x1.8p3`
     Float,
 
     /// A string value, in quotes.
@@ -51,17 +86,51 @@ pub(crate) enum TokenKind {
     /// A character value in single quotes.
     Char,
 
+    /// A pointer address, as printed by `{:p}` or the `Debug` impl of raw
+    /// pointers.
+    ///
+    /// This is any hexadecimal integer with a lowercase `0x` prefix and at
+    /// least 8 digits. Such integers are almost always addresses, but they
+    /// can still be deserialized as integers.
+    ///
+    /// Examples:
+    /// - `0x7f3a2c001230`
+    /// - `0x0000600003e1c000`
+    Address,
+
+    /// A decimal number followed by a unit, as printed by the `Debug` impl
+    /// of `std::time::Duration`.
+    ///
+    /// Examples:
+    /// - `1.5s`
+    /// - `250ms`
+    /// - `10µs`
+    /// - `7ns`
+    Duration,
+
     /// The end-of-file token.
     Eof,
 }
 
-#[derive(Copy, Clone, Debug)]
-pub(crate) struct Token<'de> {
+/// A single token within debug-formatted text.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Token<'de> {
+    /// What kind of token this is.
     pub kind: TokenKind,
+
+    /// The text that makes up the token, exactly as it appears in the input.
+    /// String and character tokens include their quotes.
     pub value: &'de str,
 }
 
 impl<'de> Token<'de> {
+    /// Create a token of the given kind from its text.
+    pub fn new(kind: TokenKind, value: &'de str) -> Self {
+        Self { kind, value }
+    }
+
+    /// Whether this is the punctuation token `punct`.
     pub fn is_punct(&self, punct: &str) -> bool {
         self.kind == TokenKind::Punct && self.value == punct
     }
@@ -72,6 +141,8 @@ impl fmt::Display for TokenKind {
         f.write_str(match self {
             Self::String => "a string",
             Self::Char => "a character literal",
+            Self::Address => "a pointer address",
+            Self::Duration => "a duration",
             Self::Integer => "an integer",
             Self::Float => "a floating-point number",
             Self::Punct => "a punctuation token",
@@ -81,14 +152,239 @@ impl fmt::Display for TokenKind {
     }
 }
 
+/// Splits debug-formatted text into [`Token`]s.
+///
+/// The lexer is an iterator over the tokens in its input. It stops at the end
+/// of the input, or after returning an error for text that is not a valid
+/// token. The tokens can be filtered or rewritten, collected into a
+/// [`TokenBuffer`](crate::TokenBuffer) using
+/// [`TokenBuffer::from_tokens`](crate::TokenBuffer::from_tokens), and then
+/// deserialized.
+///
+/// The lexer is only an iterator with the `alloc` feature. Without it, the
+/// lexer is still used by [`validate`](crate::validate).
+///
+/// # Example
+/// ```
+/// use serde_dbgfmt::{Lexer, TokenKind};
+///
+/// let tokens: Vec<_> = Lexer::new("Point { x: 1 }")
+///     .map(|token| token.map(|token| (token.kind, token.value)))
+///     .collect::<Result<_, _>>()
+///     .unwrap();
+///
+/// assert_eq!(
+///     tokens,
+///     [
+///         (TokenKind::Ident, "Point"),
+///         (TokenKind::Punct, "{"),
+///         (TokenKind::Ident, "x"),
+///         (TokenKind::Punct, ":"),
+///         (TokenKind::Integer, "1"),
+///         (TokenKind::Punct, "}"),
+///     ]
+/// );
+/// ```
 #[derive(Clone, Debug)]
-pub(crate) struct Lexer<'de> {
+pub struct Lexer<'de> {
     data: &'de str,
+    tokens: usize,
+
+    /// Tokens that have already been lexed from the input, in order, along
+    /// with the index of the next one.
+    buffer: &'de [Token<'de>],
+    index: usize,
+
+    /// The last token returned by [`peek_token`](Self::peek_token). Peeking
+    /// is far more common than any other operation, so this ensures that each
+    /// token only needs to be lexed once.
+    peeked: Cell<Option<Token<'de>>>,
+
+    /// Called when the input contains something that is not a valid token.
+    #[cfg(feature = "alloc")]
+    on_unknown: Option<Callback<UnknownTokenFn>>,
+
+    /// The checks to make while lexing, and the amount of remaining input at
+    /// which they are next made.
+    #[cfg(feature = "alloc")]
+    checks: Option<Arc<Checks>>,
+    #[cfg(feature = "alloc")]
+    check_at: usize,
+}
+
+/// The progress, cancellation, and time budget checks from the [`Options`].
+///
+/// These are made every few kilobytes of input from within the lexer, so that
+/// they also apply to large scalars and to values that are skipped over. The
+/// checks are shared between copies of the lexer.
+#[derive(Debug)]
+#[cfg(feature = "alloc")]
+pub(crate) struct Checks {
+    options: Options,
+
+    /// The whole input, which progress is measured against.
+    start: usize,
+    total: usize,
+
+    /// The number of bytes consumed when progress was last reported.
+    reported: AtomicUsize,
+
+    /// When the time budget set in the options runs out.
+    #[cfg(feature = "std")]
+    deadline: Option<Instant>,
+}
+
+#[cfg(feature = "alloc")]
+impl Checks {
+    /// Set up the checks for deserializing `input`, or return `None` if the
+    /// options do not ask for any.
+    ///
+    /// The time budget starts counting down now.
+    pub(crate) fn new(input: &str, options: &Options) -> Option<Arc<Self>> {
+        let config = options.config();
+        #[cfg(feature = "std")]
+        let deadline = config
+            .time_budget
+            .and_then(|budget| Instant::now().checked_add(budget));
+        #[cfg(feature = "std")]
+        let timed = config.time_budget.is_some();
+        #[cfg(not(feature = "std"))]
+        let timed = false;
+
+        if config.on_progress.is_none() && config.cancellation_token.is_none() && !timed {
+            return None;
+        }
+
+        Some(Arc::new(Self {
+            options: options.clone(),
+            start: input.as_ptr() as usize,
+            total: input.len(),
+            reported: AtomicUsize::new(0),
+            #[cfg(feature = "std")]
+            deadline,
+        }))
+    }
+
+    fn check(&self, remaining: &str) -> Result<(), Interrupt> {
+        let config = self.options.config();
+
+        if let Some(callback) = &config.on_progress {
+            let consumed = remaining.as_ptr() as usize - self.start;
+            let reported = self.reported.load(Ordering::Relaxed);
+            // The final report is made once the end of the input is reached.
+            if consumed < self.total && consumed >= reported + PROGRESS_INTERVAL {
+                self.reported.store(consumed, Ordering::Relaxed);
+                (callback.0)(consumed, self.total);
+            }
+        }
+
+        if let Some(token) = &config.cancellation_token {
+            if token.load(Ordering::Relaxed) {
+                return Err(Interrupt::Cancelled);
+            }
+        }
+
+        #[cfg(feature = "std")]
+        if let Some(deadline) = self.deadline {
+            if Instant::now() >= deadline {
+                return Err(Interrupt::TimedOut);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Report that everything up to the end of `remaining` has been consumed.
+    fn finish(&self, remaining: &str) {
+        if let Some(callback) = &self.options.config().on_progress {
+            let consumed = remaining.as_ptr() as usize + remaining.len() - self.start;
+            self.reported.store(consumed, Ordering::Relaxed);
+            (callback.0)(consumed, self.total);
+        }
+    }
 }
 
 impl<'de> Lexer<'de> {
+    /// Create a lexer over `data`.
     pub fn new(data: &'de str) -> Self {
-        Self { data }
+        Self::with_buffer(data, &[])
+    }
+
+    /// Create a lexer that takes tokens from `buffer` instead of lexing them
+    /// again, wherever possible.
+    ///
+    /// The tokens in `buffer` must have been lexed from `data`. Parts of the
+    /// input that are not covered by the buffer are lexed as usual.
+    pub(crate) fn with_buffer(data: &'de str, buffer: &'de [Token<'de>]) -> Self {
+        Self {
+            data,
+            tokens: 0,
+            buffer,
+            index: 0,
+            peeked: Cell::new(None),
+            #[cfg(feature = "alloc")]
+            on_unknown: None,
+            #[cfg(feature = "alloc")]
+            checks: None,
+            #[cfg(feature = "alloc")]
+            check_at: 0,
+        }
+    }
+
+    /// Call `callback` to decide what to do with text that is not a valid
+    /// token.
+    #[cfg(feature = "alloc")]
+    pub(crate) fn set_unknown_handler(&mut self, callback: Option<Callback<UnknownTokenFn>>) {
+        self.on_unknown = callback;
+    }
+
+    /// Make `checks` periodically while lexing, starting with the next token.
+    ///
+    /// The input of this lexer must be part of the input that `checks` was
+    /// created for.
+    #[cfg(feature = "alloc")]
+    pub(crate) fn set_checks(&mut self, checks: Option<Arc<Checks>>) {
+        self.check_at = match checks {
+            Some(_) => self.data.len(),
+            None => 0,
+        };
+        self.checks = checks;
+    }
+
+    /// Apply both the unknown token handler and the checks from `options`,
+    /// measuring progress against `input`.
+    #[cfg(feature = "alloc")]
+    pub(crate) fn set_options(&mut self, input: &str, options: &Options) {
+        self.set_unknown_handler(options.config().on_unknown_token.clone());
+        self.set_checks(Checks::new(input, options));
+    }
+
+    /// Report progress up to the end of the input of this lexer, which is
+    /// usually the end of the whole input.
+    #[cfg(feature = "alloc")]
+    pub(crate) fn finish_progress(&self) {
+        if let Some(checks) = &self.checks {
+            checks.finish(self.data);
+        }
+    }
+
+    /// The checks made by this lexer, so that they can be shared with other
+    /// lexers over the same input.
+    #[cfg(feature = "alloc")]
+    pub(crate) fn checks(&self) -> Option<Arc<Checks>> {
+        self.checks.clone()
+    }
+
+    /// The number of tokens, not including end-of-file, that have been parsed
+    /// so far.
+    #[cfg(feature = "alloc")]
+    pub(crate) fn token_count(&self) -> usize {
+        self.tokens
+    }
+
+    /// The input that has not yet been consumed by the lexer.
+    pub fn remaining(&self) -> &'de str {
+        self.data
     }
 
     fn skip_whitespace(&mut self) {
@@ -100,8 +396,8 @@ impl<'de> Lexer<'de> {
     }
 
     fn take_char(&mut self) -> Option<char> {
-        let (index, c) = self.data.char_indices().next()?;
-        self.advance(index);
+        let c = self.data.chars().next()?;
+        self.advance(c.len_utf8());
         Some(c)
     }
 
@@ -109,17 +405,17 @@ impl<'de> Lexer<'de> {
         self.data.chars().next()
     }
 
-    fn unexpected_token(&self, expected: impl Into<Expected>) -> LexerError {
+    fn unexpected_token(&self, expected: impl Into<Expected>) -> LexerError<'de> {
         LexerError::unexpected_token(first_char(self.data), expected)
     }
 
-    fn unexpected_eof(&self, expected: impl Into<Expected>) -> LexerError {
+    fn unexpected_eof(&self, expected: impl Into<Expected>) -> LexerError<'de> {
         LexerError::unexpected_token(&self.data[self.data.len()..], expected)
     }
 
-    fn try_parse<F, T>(&mut self, func: F) -> Result<T, LexerError>
+    fn try_parse<F, T>(&mut self, func: F) -> Result<T, LexerError<'de>>
     where
-        F: FnOnce(&mut Self) -> Result<T, LexerError>,
+        F: FnOnce(&mut Self) -> Result<T, LexerError<'de>>,
     {
         let mut copy = self.clone();
         let result = func(&mut copy);
@@ -131,9 +427,9 @@ impl<'de> Lexer<'de> {
         result
     }
 
-    fn parse_consumed<F>(&mut self, func: F) -> Result<Token<'de>, LexerError>
+    fn parse_consumed<F>(&mut self, func: F) -> Result<Token<'de>, LexerError<'de>>
     where
-        F: FnOnce(&mut Self) -> Result<TokenKind, LexerError>,
+        F: FnOnce(&mut Self) -> Result<TokenKind, LexerError<'de>>,
     {
         let copy = self.data;
         let kind = self.try_parse(func)?;
@@ -149,94 +445,320 @@ impl<'de> Lexer<'de> {
         })
     }
 
-    pub fn parse_token(&mut self) -> Result<Token<'de>, LexerError> {
+    /// Return the next token without consuming it.
+    pub(crate) fn peek_token(&self) -> Result<Token<'de>, LexerError<'de>> {
+        let position = self.data.trim_start().as_ptr();
+        if let Some(token) = self.peeked.get() {
+            if token.value.as_ptr() == position {
+                return Ok(token);
+            }
+        }
+
+        let token = self.clone().parse_token()?;
+        self.peeked.set(Some(token));
+        Ok(token)
+    }
+
+    pub(crate) fn parse_token(&mut self) -> Result<Token<'de>, LexerError<'de>> {
         self.skip_whitespace();
-        self.parse_consumed(|this| match this.peek_char() {
+
+        #[cfg(feature = "alloc")]
+        if self.data.len() <= self.check_at {
+            self.check()?;
+        }
+
+        let peeked = self.peeked.take();
+        if let Some(token) = peeked.filter(|token| token.value.as_ptr() == self.data.as_ptr()) {
+            self.advance(token.value.len());
+            if token.kind != TokenKind::Eof {
+                self.tokens += 1;
+            }
+
+            return Ok(token);
+        }
+
+        if let Some(token) = self.buffered_token() {
+            self.advance(token.value.len());
+            if token.kind != TokenKind::Eof {
+                self.tokens += 1;
+            }
+
+            return Ok(token);
+        }
+
+        #[cfg(feature = "alloc")]
+        if let Some(token) = self.parse_unknown()? {
+            return Ok(token);
+        }
+
+        let token = self.parse_consumed(|this| match this.peek_char() {
             None => Ok(TokenKind::Eof),
             Some('\"') => this.parse_string(),
             Some('\'') => this.parse_char(),
             Some('0'..='9') => this.parse_number(),
             Some(c) if unicode_ident::is_xid_start(c) => this.parse_ident(),
             Some('.') => this.parse_dotdot(),
-            Some('{' | '}' | '[' | ']' | ':' | ',' | '(' | ')' | '+' | '-') => {
+            Some('{' | '}' | '[' | ']' | ':' | ',' | '(' | ')' | '+' | '-' | '|') => {
                 this.advance(1);
                 Ok(TokenKind::Punct)
             }
             Some(_) => Err(this.unexpected_token("a valid token")),
-        })
+        })?;
+
+        if token.kind != TokenKind::Eof {
+            self.tokens += 1;
+        }
+
+        Ok(token)
     }
 
-    fn parse_string(&mut self) -> Result<TokenKind, LexerError> {
-        self.data = match self.data.strip_prefix('"') {
-            Some(rest) => rest,
-            None => return Err(self.unexpected_token(TokenKind::String)),
-        };
+    #[cfg(feature = "alloc")]
+    #[inline(never)]
+    fn check(&mut self) -> Result<(), LexerError<'de>> {
+        self.check_at = self.data.len().saturating_sub(CHECK_INTERVAL);
+        match &self.checks {
+            Some(checks) => checks.check(self.data).map_err(LexerError::interrupted),
+            None => Ok(()),
+        }
+    }
 
-        while let Some(idx) = self.data.find('\"') {
-            self.advance(idx);
+    /// Parse the next token, failing unless it is the punctuation `punct`.
+    pub(crate) fn expect_punct(&mut self, punct: &'static str) -> Result<(), LexerError<'de>> {
+        self.expect_punct_with(punct, Expected::punct(punct))
+    }
 
-            if self.data.as_bytes().get(idx - 1) == Some(&b'\\') {
-                continue;
+    /// Like [`expect_punct`](Self::expect_punct), but with a custom
+    /// description of what was expected for the error.
+    pub(crate) fn expect_punct_with(
+        &mut self,
+        punct: &str,
+        expected: impl Into<Expected>,
+    ) -> Result<(), LexerError<'de>> {
+        let token = self.parse_token()?;
+        match token.is_punct(punct) {
+            true => Ok(()),
+            false => Err(LexerError::unexpected_token(token.value, expected)),
+        }
+    }
+
+    /// Use the unknown token callback to handle text that is not a valid
+    /// token, if there is a callback.
+    ///
+    /// Skipped text is handled in a loop so that a long run of skipped tokens
+    /// cannot overflow the stack.
+    #[cfg(feature = "alloc")]
+    fn parse_unknown(&mut self) -> Result<Option<Token<'de>>, LexerError<'de>> {
+        loop {
+            let Some(callback) = &self.on_unknown else {
+                return Ok(None);
+            };
+            match self.data.chars().next() {
+                Some(c) if !is_token_start(c) => (),
+                _ => return Ok(None),
             }
 
-            break;
+            let action = (callback.0)(self.data);
+            let len = match action {
+                UnknownToken::Error => return Ok(None),
+                UnknownToken::Skip(len) | UnknownToken::String(len) => len,
+            };
+            if len == 0 || !self.data.is_char_boundary(len) {
+                return Err(self.unexpected_token("a valid token"));
+            }
+
+            let value = &self.data[..len];
+            self.advance(len);
+
+            if let UnknownToken::String(_) = action {
+                self.tokens += 1;
+                return Ok(Some(Token {
+                    kind: TokenKind::String,
+                    value,
+                }));
+            }
+
+            self.skip_whitespace();
+        }
+    }
+
+    /// Take the token at the current position from the buffer, if there is
+    /// one.
+    fn buffered_token(&mut self) -> Option<Token<'de>> {
+        let position = self.data.as_ptr();
+
+        // Usually the next token is the one we want. If the input was advanced
+        // some other way then we need to search for it instead.
+        let next = self.buffer.get(self.index);
+        if next.map(|token| token.value.as_ptr()) != Some(position) {
+            self.index = self
+                .buffer
+                .partition_point(|token| token.value.as_ptr() < position);
+        }
+
+        let token = *self.buffer.get(self.index)?;
+        if token.value.as_ptr() != position {
+            return None;
         }
 
-        match self.data.as_bytes().first() {
-            Some(b'\"') => {
-                self.advance(1);
-                Ok(TokenKind::String)
+        self.index += 1;
+        Some(token)
+    }
+
+    /// Skip over the next value in the input, returning the text that makes it
+    /// up.
+    ///
+    /// This works purely on tokens and only requires brackets to be balanced,
+    /// so it will accept values that do not otherwise follow the debug format.
+    #[cfg(feature = "alloc")]
+    pub(crate) fn skip_value(&mut self) -> Result<&'de str, LexerError<'de>> {
+        self.skip_until(|punct| matches!(punct, "," | ")" | "]" | "}"))
+    }
+
+    /// Skip over the next map key in the input, returning the text that makes
+    /// it up.
+    ///
+    /// This is the same as [`skip_value`](Self::skip_value) except that it
+    /// also stops at a `:`.
+    #[cfg(feature = "alloc")]
+    pub(crate) fn skip_key(&mut self) -> Result<&'de str, LexerError<'de>> {
+        self.skip_until(|punct| matches!(punct, ":" | "," | ")" | "]" | "}"))
+    }
+
+    #[cfg(feature = "alloc")]
+    fn skip_until<F>(&mut self, stop: F) -> Result<&'de str, LexerError<'de>>
+    where
+        F: Fn(&str) -> bool,
+    {
+        self.skip_whitespace();
+
+        let start = self.data;
+        let mut depth = 0usize;
+
+        loop {
+            let token = self.peek_token()?;
+            match (token.kind, token.value) {
+                (TokenKind::Eof, _) => break,
+                (TokenKind::Punct, punct) if depth == 0 && stop(punct) => break,
+                (TokenKind::Punct, "(" | "[" | "{") => depth += 1,
+                (TokenKind::Punct, ")" | "]" | "}") => depth = depth.saturating_sub(1),
+                _ => (),
             }
-            _ => Err(LexerError::unexpected_token(self.data, TokenKind::String)),
+
+            self.parse_token()?;
+        }
+
+        match &start[..start.len() - self.data.len()] {
+            "" => Err(self.unexpected_token("a value")),
+            value => Ok(value),
         }
     }
 
-    fn parse_char(&mut self) -> Result<TokenKind, LexerError> {
-        self.data = match self.data.strip_prefix('\'') {
-            Some(rest) => rest,
-            None => return Err(self.unexpected_token(TokenKind::Char)),
-        };
+    /// Skip over the next value in the input without splitting it into
+    /// tokens, returning the text that makes it up.
+    ///
+    /// The value ends at the first `,` or unmatched closing bracket that is
+    /// not within a string or character literal. Whitespace around the value
+    /// is not included.
+    #[cfg(feature = "alloc")]
+    pub(crate) fn skip_raw(&mut self) -> Result<&'de str, LexerError<'de>> {
+        self.skip_whitespace();
 
-        while let Some(idx) = self.data.find('\'') {
-            self.advance(idx);
+        let bytes = self.data.as_bytes();
+        let mut depth = 0usize;
+        let mut quote = None;
+        let mut index = 0;
 
-            if self.data.as_bytes().get(idx - 1) == Some(&b'\\') {
-                continue;
+        while let Some(&byte) = bytes.get(index) {
+            match (quote, byte) {
+                (Some(_), b'\\') => index += 1,
+                (Some(q), _) if q == byte => quote = None,
+                (Some(_), _) => (),
+                (None, b'"' | b'\'') => quote = Some(byte),
+                (None, b'(' | b'[' | b'{') => depth += 1,
+                (None, b',') if depth == 0 => break,
+                (None, b')' | b']' | b'}') if depth == 0 => break,
+                (None, b')' | b']' | b'}') => depth -= 1,
+                _ => (),
             }
 
-            break;
+            index += 1;
+        }
+
+        let value = self.data[..index.min(bytes.len())].trim_end();
+        if value.is_empty() {
+            return Err(self.unexpected_token("a value"));
+        }
+
+        self.advance(value.len());
+        Ok(value)
+    }
+
+    fn parse_string(&mut self) -> Result<TokenKind, LexerError<'de>> {
+        self.parse_quoted(b'"', TokenKind::String)
+    }
+
+    fn parse_char(&mut self) -> Result<TokenKind, LexerError<'de>> {
+        self.parse_quoted(b'\'', TokenKind::Char)
+    }
+
+    /// Parse a literal delimited by `quote`, skipping over escape sequences.
+    ///
+    /// This is a single forward pass over the input so it always runs in
+    /// linear time, no matter how many escaped quotes the literal contains.
+    fn parse_quoted(&mut self, quote: u8, kind: TokenKind) -> Result<TokenKind, LexerError<'de>> {
+        let bytes = self.data.as_bytes();
+        if bytes.first() != Some(&quote) {
+            return Err(self.unexpected_token(kind));
         }
 
-        match self.data.as_bytes().first() {
-            Some(b'\'') => {
-                self.advance(1);
-                Ok(TokenKind::Char)
+        let mut index = 1;
+        while let Some(&byte) = bytes.get(index) {
+            match byte {
+                // Both the backslash and the quote are ASCII so skipping a single
+                // byte here can never skip over a byte that matters, even if the
+                // escaped character is multi-byte.
+                b'\\' => index += 2,
+                _ if byte == quote => {
+                    self.advance(index + 1);
+                    return Ok(kind);
+                }
+                _ => index += 1,
             }
-            _ => Err(LexerError::unexpected_token(self.data, TokenKind::Char)),
         }
+
+        Err(LexerError::unexpected_eof(kind))
     }
 
-    fn parse_ident(&mut self) -> Result<TokenKind, LexerError> {
+    fn parse_ident(&mut self) -> Result<TokenKind, LexerError<'de>> {
         match self.data.chars().next() {
             Some(c) if unicode_ident::is_xid_start(c) => (),
             Some(_) => return Err(self.unexpected_token(TokenKind::Ident)),
             None => return Err(self.unexpected_eof(TokenKind::Ident)),
         };
 
-        let index = self
-            .data
-            .char_indices()
-            .skip(1)
-            .find(|&(_, c)| !unicode_ident::is_xid_continue(c))
-            .map(|(idx, _)| idx)
-            .unwrap_or(self.data.len());
+        let mut len = 0;
+        loop {
+            let rest = &self.data[len..];
+            len += rest
+                .char_indices()
+                .skip(1)
+                .find(|&(_, c)| !unicode_ident::is_xid_continue(c))
+                .map(|(idx, _)| idx)
+                .unwrap_or(rest.len());
+            len += generics_len(&self.data[len..]).unwrap_or(0);
 
-        self.advance(index);
+            match self.data[len..].strip_prefix("::") {
+                Some(next) if next.starts_with(unicode_ident::is_xid_start) => len += 2,
+                _ => break,
+            }
+        }
+
+        self.advance(len);
 
         Ok(TokenKind::Ident)
     }
 
-    fn parse_number(&mut self) -> Result<TokenKind, LexerError> {
+    fn parse_number(&mut self) -> Result<TokenKind, LexerError<'de>> {
         // This token parsing method is somewhat different from the others
         // because can return two different token types depending on what it
         // parses: integers and floating point numbers.
@@ -244,17 +766,29 @@ impl<'de> Lexer<'de> {
         // The regexes that match either look like this:
         // - number: ([0-9]|0[xob][0-9A-Fa-f])[0-9A-Fa-f]*
         // - float:  [0-9]+\.[0-9]+([eE](+|-)?[0-9]+)?
+        // - float:  0[xX][0-9A-Fa-f]+(\.[0-9A-Fa-f]+)?[pP](+|-)?[0-9]+
 
         // First off, we need to check for the `0[xob]` prefix.
         match self.take_char() {
             Some('0') => {
-                if matches!(self.peek_char(), Some('x' | 'X' | 'o' | 'O' | 'b' | 'B')) {
+                if let Some(prefix @ ('x' | 'X' | 'o' | 'O' | 'b' | 'B')) = self.peek_char() {
                     self.advance(1);
 
-                    // We definitely have an integer and just need to parse the
+                    // We have an integer (or a hex float) and just need to parse the
                     // remaining digits in the number.
+                    let start = self.data.len();
                     self.parse_once(TokenKind::Integer, |c| c.is_ascii_hexdigit())?;
                     self.parse_repeated(|c| c.is_ascii_hexdigit());
+                    let digits = start - self.data.len();
+
+                    if matches!(prefix, 'x' | 'X') && self.at_hex_float_suffix() {
+                        return self.parse_hex_float_suffix();
+                    }
+
+                    if prefix == 'x' && digits >= POINTER_DIGITS {
+                        return Ok(TokenKind::Address);
+                    }
+
                     return Ok(TokenKind::Integer);
                 }
             }
@@ -269,11 +803,11 @@ impl<'de> Lexer<'de> {
         // float but if we've parsed a number then we should be done now. The next char
         // decides what it will be.
 
-        match self.peek_char() {
+        match self.data.as_bytes() {
             // We've got a float.
-            Some('.' | 'e' | 'E') => (),
+            [b'.', b'0'..=b'9', ..] | [b'e' | b'E', ..] => (),
             // Anything else means we've got an integer.
-            _ => return Ok(TokenKind::Integer),
+            _ => return Ok(self.parse_duration_unit(TokenKind::Integer)),
         }
 
         // Parse the `\.[0-9]+` part of the float.
@@ -283,27 +817,90 @@ impl<'de> Lexer<'de> {
             self.parse_repeated(|c| c.is_ascii_digit());
         }
 
-        if matches!(self.peek_char(), Some('e' | 'E')) {
+        if !matches!(self.peek_char(), Some('e' | 'E')) {
+            return Ok(self.parse_duration_unit(TokenKind::Float));
+        }
+
+        self.advance(1);
+
+        if matches!(self.peek_char(), Some('+' | '-')) {
             self.advance(1);
+        }
+
+        self.parse_once(TokenKind::Float, |c| c.is_ascii_digit())?;
+        self.parse_repeated(|c| c.is_ascii_digit());
 
-            if matches!(self.peek_char(), Some('+' | '-')) {
-                self.advance(1);
+        Ok(TokenKind::Float)
+    }
+
+    /// Parse the unit following a decimal number if there is one, turning the
+    /// number into a [`TokenKind::Duration`]. Otherwise `kind` is returned.
+    fn parse_duration_unit(&mut self, kind: TokenKind) -> TokenKind {
+        for unit in ["ns", "µs", "us", "ms", "s"] {
+            let Some(rest) = self.data.strip_prefix(unit) else {
+                continue;
+            };
+
+            if !rest.starts_with(unicode_ident::is_xid_continue) {
+                self.advance(unit.len());
+                return TokenKind::Duration;
             }
+        }
 
-            self.parse_once(TokenKind::Float, |c| c.is_ascii_digit())?;
-            self.parse_repeated(|c| c.is_ascii_digit());
+        kind
+    }
+
+    /// Whether the lexer is positioned at the fractional part or binary
+    /// exponent of a C99-style hex float (e.g. the `.8p3` in `0x1.8p3`).
+    fn at_hex_float_suffix(&self) -> bool {
+        match self.data.as_bytes() {
+            [b'.', next, ..] => next.is_ascii_hexdigit(),
+            [b'p' | b'P', ..] => true,
+            _ => false,
+        }
+    }
+
+    /// Parse the `(\.[0-9A-Fa-f]+)?[pP](+|-)?[0-9]+` part of a hex float.
+    ///
+    /// Unlike decimal floats, the exponent is required here. Otherwise there
+    /// would be no way to tell whether the `e` in `0x1.e` was a digit or
+    /// the start of an exponent.
+    fn parse_hex_float_suffix(&mut self) -> Result<TokenKind, LexerError<'de>> {
+        if matches!(self.peek_char(), Some('.')) {
+            self.advance(1);
+            self.parse_once(TokenKind::Float, |c| c.is_ascii_hexdigit())?;
+            self.parse_repeated(|c| c.is_ascii_hexdigit());
         }
 
+        self.parse_once("a binary exponent", |c| matches!(c, 'p' | 'P'))?;
+
+        if matches!(self.peek_char(), Some('+' | '-')) {
+            self.advance(1);
+        }
+
+        self.parse_once(TokenKind::Float, |c| c.is_ascii_digit())?;
+        self.parse_repeated(|c| c.is_ascii_digit());
+
         Ok(TokenKind::Float)
     }
 
-    fn parse_dotdot(&mut self) -> Result<TokenKind, LexerError> {
+    fn parse_dotdot(&mut self) -> Result<TokenKind, LexerError<'de>> {
         self.parse_once("..", |c| c == '.')?;
         self.parse_once("..", |c| c == '.')?;
+
+        // Inclusive ranges are printed as `start..=end`.
+        if matches!(self.peek_char(), Some('=')) {
+            self.advance(1);
+        }
+
         Ok(TokenKind::Punct)
     }
 
-    fn parse_once<F>(&mut self, expected: impl Into<Expected>, pred: F) -> Result<(), LexerError>
+    fn parse_once<F>(
+        &mut self,
+        expected: impl Into<Expected>,
+        pred: F,
+    ) -> Result<(), LexerError<'de>>
     where
         F: FnOnce(char) -> bool,
     {
@@ -336,9 +933,104 @@ impl<'de> Lexer<'de> {
     }
 }
 
+/// Whether `c` can start a valid token.
+#[cfg(feature = "alloc")]
+fn is_token_start(c: char) -> bool {
+    matches!(
+        c,
+        '"' | '\'' | '0'
+            ..='9' | '.' | '{' | '}' | '[' | ']' | ':' | ',' | '(' | ')' | '+' | '-' | '|'
+    ) || unicode_ident::is_xid_start(c)
+}
+
 fn first_char(s: &str) -> &str {
     match s.chars().next() {
         Some(c) => &s[..c.len_utf8()],
         None => s,
     }
 }
+
+/// Whether `token` joins two values into one, as in a range or a set of
+/// flags.
+pub(crate) fn is_operator(token: &Token) -> bool {
+    token.kind == TokenKind::Punct && matches!(token.value, ".." | "..=" | "|")
+}
+
+/// Whether `token` ends a value.
+pub(crate) fn is_terminator(token: &Token) -> bool {
+    token.kind == TokenKind::Eof
+        || (token.kind == TokenKind::Punct && matches!(token.value, "," | ")" | "]" | "}" | ":"))
+}
+
+/// Remove the generic arguments from an identifier token, so that
+/// `Wrapper<u32>` becomes `Wrapper`.
+#[cfg(feature = "alloc")]
+impl<'de> Iterator for Lexer<'de> {
+    type Item = Result<Token<'de>, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.parse_token() {
+            Ok(token) if token.kind == TokenKind::Eof => None,
+            Ok(token) => Some(Ok(token)),
+            Err(e) => {
+                // Skip the rest of the input so that iteration stops here.
+                self.advance(self.data.len());
+                Some(Err(e.into()))
+            }
+        }
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl FusedIterator for Lexer<'_> {}
+
+/// The minimum number of hex digits in an integer literal for it to be
+/// treated as a pointer address.
+const POINTER_DIGITS: usize = 8;
+
+/// Whether an integer literal looks like a pointer address.
+#[cfg(feature = "std")]
+pub(crate) fn is_pointer(literal: &str) -> bool {
+    match literal.strip_prefix("0x") {
+        Some(digits) => {
+            digits.len() >= POINTER_DIGITS && digits.bytes().all(|b| b.is_ascii_hexdigit())
+        }
+        None => false,
+    }
+}
+
+#[cfg(feature = "alloc")]
+pub(crate) fn strip_generics(ident: &str) -> &str {
+    ident.find('<').map_or(ident, |index| &ident[..index])
+}
+
+/// The length of the list of generic arguments at the start of `text`, such as
+/// `<Vec<u8>, fn() -> u8>`, if there is one.
+///
+/// The list may contain anything that `std::any::type_name` can produce, so
+/// only the angle brackets are matched up. It has to end on the same line.
+fn generics_len(text: &str) -> Option<usize> {
+    let bytes = text.as_bytes();
+    if bytes.first() != Some(&b'<') {
+        return None;
+    }
+
+    let mut depth = 0usize;
+    for (index, &byte) in bytes.iter().enumerate() {
+        match byte {
+            b'<' => depth += 1,
+            // The `>` in the `->` of a function pointer type.
+            b'>' if index > 0 && bytes[index - 1] == b'-' => (),
+            b'>' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(index + 1);
+                }
+            }
+            b'\n' => break,
+            _ => (),
+        }
+    }
+
+    None
+}
@@ -27,6 +27,13 @@
 //! assert_eq!(value.message, "Hello, World!");
 //! ```
 //! 
+//! # Pretty-printed output
+//! Whitespace between tokens is not significant so the multi-line output of
+//! `{:#?}` can be passed to [`from_str`] in the same way as the output of
+//! `{:?}`, including the trailing comma that follows the last field or element
+//! in alternate mode. There is no need to know ahead of time which of the two
+//! was used.
+//!
 //! # Caveats and Limitations
 //! - This library parses the format emitted by the debug helpers in
 //!   [`std::fmt`]. Custom debug representations will not necessarily use these
@@ -38,37 +45,503 @@
 //!   debug representation. You can use `#[serde(rename = "..")]` if you want to
 //!   use a different struct name in your codebase.
 //!
+//! # `no_std` support
+//! The crate can be used without the standard library by disabling the
+//! default `std` feature and enabling the `alloc` feature instead. This leaves
+//! out the parts that need an operating system, such as `from_reader`,
+//! `Options::time_budget`, and the helpers for `std::io` and `std::time`
+//! types.
+//!
+//! Without `alloc` as well, only the [`Lexer`] and [`validate`] are
+//! available. Iterating over the lexer needs `alloc` since it produces
+//! owned errors, but `validate` works on any target.
+//!
 //! [`Debug`]: std::fmt::Debug
 //! [`Serialize`]: serde::Serialize
 
-use std::fmt::Debug;
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+
+#[cfg(feature = "alloc")]
+extern crate alloc;
+
+#[cfg(feature = "alloc")]
+use alloc::format;
+#[cfg(feature = "alloc")]
+use alloc::string::String;
+#[cfg(feature = "alloc")]
+use core::fmt::{Debug, Write};
+#[cfg(feature = "std")]
+use std::io;
 
-use serde::de::DeserializeOwned;
+#[cfg(feature = "alloc")]
+use serde::de::{DeserializeOwned, Error as _};
+#[cfg(feature = "alloc")]
 use serde::Deserialize;
 
+#[cfg(feature = "alloc")]
+use crate::de::Nested;
+
+#[cfg(feature = "alloc")]
+pub mod backtrace;
+#[cfg(feature = "alloc")]
+mod buffer;
+#[cfg(feature = "alloc")]
+mod canonical;
+#[cfg(feature = "alloc")]
+mod compare;
+#[cfg(feature = "alloc")]
 mod de;
+#[cfg(feature = "alloc")]
+mod dialect;
 mod error;
+#[cfg(feature = "alloc")]
+mod ext;
+#[cfg(feature = "alloc")]
+mod flatten;
+#[cfg(feature = "std")]
+mod hash;
+#[cfg(feature = "std")]
+mod io_error;
+#[cfg(feature = "json")]
+pub mod json;
 mod lex;
+#[cfg(feature = "alloc")]
+mod literal;
+#[cfg(feature = "alloc")]
+mod merge;
+#[cfg(feature = "alloc")]
+mod options;
+#[cfg(feature = "alloc")]
+mod owned;
+#[cfg(feature = "alloc")]
+mod path;
+#[cfg(feature = "std")]
+mod read;
+#[cfg(feature = "alloc")]
+mod reader;
+#[cfg(feature = "alloc")]
+mod redact;
+#[cfg(feature = "alloc")]
+pub mod render;
+#[cfg(feature = "alloc")]
+pub mod rewrite;
+#[cfg(feature = "json")]
+pub mod schema;
+#[cfg(feature = "alloc")]
+pub mod ser;
+#[cfg(feature = "alloc")]
+mod stats;
+#[cfg(feature = "alloc")]
+mod stream;
+#[cfg(feature = "alloc")]
+mod summary;
+#[cfg(feature = "std")]
+mod time;
+#[cfg(feature = "alloc")]
+mod tree;
+#[cfg(feature = "alloc")]
+pub mod util;
+mod validate;
+#[cfg(feature = "alloc")]
+mod value;
+#[cfg(feature = "alloc")]
+mod walk;
+#[cfg(feature = "alloc")]
+mod warning;
+#[cfg(feature = "alloc")]
+pub mod with;
 
+#[cfg(feature = "alloc")]
+pub use crate::buffer::TokenBuffer;
+#[cfg(feature = "alloc")]
+pub use crate::canonical::{canonicalize, canonicalize_with};
+#[cfg(feature = "alloc")]
+pub use crate::compare::{diff, structurally_equals, Comparator, Difference};
+#[cfg(feature = "alloc")]
 pub use crate::de::Deserializer;
-pub use crate::error::Error;
+#[cfg(feature = "alloc")]
+pub use crate::dialect::Dialect;
+#[cfg(feature = "alloc")]
+pub use crate::error::{Error, ErrorKind, ExpectedKind, Location};
+#[cfg(feature = "alloc")]
+pub use crate::ext::{DebugParseExt, DebugReparse};
+#[cfg(feature = "alloc")]
+pub use crate::flatten::{flatten, flatten_with, Scalar};
+#[cfg(feature = "std")]
+pub use crate::hash::{structural_hash, Group, StructuralHasher};
+#[cfg(feature = "std")]
+pub use crate::io_error::IoError;
+pub use crate::lex::{Lexer, Token, TokenKind};
+#[cfg(feature = "alloc")]
+pub use crate::literal::{parse_float_literal, parse_int_literal, FloatLiteral, IntLiteral};
+#[cfg(feature = "alloc")]
+pub use crate::merge::MergeSeed;
+#[cfg(feature = "alloc")]
+pub use crate::options::{Addresses, DuplicateKeys, FormatVersion, Options, UnknownToken};
+#[cfg(feature = "alloc")]
+pub use crate::owned::OwnedDeserializer;
+#[cfg(feature = "alloc")]
+pub use crate::path::{get, get_with};
+#[cfg(feature = "alloc")]
+pub use crate::reader::MapReader;
+#[cfg(feature = "alloc")]
+pub use crate::redact::Redactor;
+#[cfg(feature = "alloc")]
+pub use crate::ser::{to_string, to_string_pretty};
+#[cfg(feature = "alloc")]
+pub use crate::stats::Stats;
+#[cfg(feature = "alloc")]
+pub use crate::stream::StreamDeserializer;
+#[cfg(feature = "alloc")]
+pub use crate::summary::{summarize, summarize_with, Summary};
+pub use crate::validate::{validate, ValidationError};
+#[cfg(feature = "alloc")]
+pub use crate::value::{to_map, to_map_with, Value, ValueString};
+#[cfg(feature = "alloc")]
+pub use crate::walk::{Path, PathSegment, Walk};
+#[cfg(feature = "alloc")]
+pub use crate::warning::Warning;
 
 /// Parse a `T` from the string containing its debug representation.
+#[cfg(feature = "alloc")]
 pub fn from_str<'de, T>(str: &'de str) -> Result<T, Error>
 where
     T: Deserialize<'de>,
 {
     let mut de = Deserializer::new(str);
-    let value = T::deserialize(&mut de)?;
+    let value = T::deserialize(Nested(&mut de)).map_err(|e| de.locate(e))?;
     de.end()?;
     Ok(value)
 }
 
+/// Parse a `T` from the string containing its debug representation using the
+/// provided options.
+#[cfg(feature = "alloc")]
+pub fn from_str_with<'de, T>(str: &'de str, options: &Options) -> Result<T, Error>
+where
+    T: Deserialize<'de>,
+{
+    let mut de = Deserializer::with_options(str, options.clone());
+    let result = T::deserialize(Nested(&mut de))
+        .map_err(|e| de.locate(e))
+        .and_then(|value| de.end().map(|()| value));
+    result.map_err(|e| options.config().adjust_error(e))
+}
+
+/// Parse a `T` from the string containing its debug representation, failing
+/// if any string in it cannot be borrowed from the input.
+///
+/// This is a shorthand for [`from_str_with`] with
+/// [`Options::require_borrowed`] enabled. It is meant for checking that
+/// deserializing into `&str` fields never needs to copy.
+///
+/// # Example
+/// ```
+/// use serde::Deserialize;
+///
+/// #[derive(Debug, Deserialize)]
+/// struct Line<'a> {
+///     text: &'a str,
+/// }
+///
+/// let line: Line = serde_dbgfmt::from_str_borrowed(r#"Line { text: "hi" }"#).unwrap();
+/// assert_eq!(line.text, "hi");
+///
+/// let error = serde_dbgfmt::from_str_borrowed::<Line>(r#"Line { text: "\"hi\"" }"#)
+///     .unwrap_err();
+/// assert!(error.is_not_borrowed());
+/// ```
+#[cfg(feature = "alloc")]
+pub fn from_str_borrowed<'de, T>(str: &'de str) -> Result<T, Error>
+where
+    T: Deserialize<'de>,
+{
+    from_str_with(str, &Options::new().require_borrowed(true))
+}
+
+/// Parse a `T` from the string containing its debug representation, tracking
+/// the path to the value that failed to deserialize.
+///
+/// The path is recorded using [`serde_path_to_error`]. The inner error still
+/// carries its [`location`](Error::location) and [`span`](Error::span) so
+/// that both the path and the offending part of the input can be reported
+/// together.
+///
+/// # Example
+/// ```
+/// use serde::Deserialize;
+///
+/// #[derive(Debug, Deserialize)]
+/// #[allow(dead_code)]
+/// struct Item {
+///     id: u32,
+/// }
+///
+/// let text = "[Item { id: 1 }, Item { id: two }]";
+/// let error = serde_dbgfmt::from_str_with_path::<Vec<Item>>(text).unwrap_err();
+/// let location = error.inner().location().unwrap();
+///
+/// assert_eq!(error.path().to_string(), "[1].id");
+/// assert_eq!(error.inner().span(), Some("two"));
+/// assert_eq!(&text[location.byte_offset..][..3], "two");
+/// ```
+#[cfg(feature = "path_to_error")]
+pub fn from_str_with_path<'de, T>(str: &'de str) -> Result<T, serde_path_to_error::Error<Error>>
+where
+    T: Deserialize<'de>,
+{
+    let mut de = Deserializer::new(str);
+    let mut track = serde_path_to_error::Track::new();
+    let result = T::deserialize(serde_path_to_error::Deserializer::new(
+        Nested(&mut de),
+        &mut track,
+    ))
+    .map_err(|e| de.locate(e))
+    .and_then(|value| de.end().map(|()| value));
+    result.map_err(|e| serde_path_to_error::Error::new(track.path(), e))
+}
+
+/// Parse a `T` from an owned string containing its debug representation.
+///
+/// This accepts anything that can be converted into a `String`, including a
+/// `Cow<str>`. Since the input is consumed, `T` cannot borrow from it.
+#[cfg(feature = "alloc")]
+pub fn from_string<T>(str: impl Into<String>) -> Result<T, Error>
+where
+    T: DeserializeOwned,
+{
+    let mut de = OwnedDeserializer::new(str);
+    let value = de.deserialize()?;
+    de.end()?;
+    Ok(value)
+}
+
+/// Parse a `T` from the debug representation read from `reader`.
+///
+/// The input is read a piece at a time as it is needed. A list at the top
+/// level, such as a dump of a `Vec`, is deserialized one element at a time, so
+/// only the text of the element being deserialized is held in memory. Any
+/// other value is read in full before it is deserialized.
+///
+/// When a list is read this way, [`Options::on_progress`] is only called once
+/// the end of it is reached, since the length of the input is not known ahead
+/// of time.
+///
+/// # Example
+/// ```
+/// let input = std::io::Cursor::new(b"[(1, 'a'), (2, 'b')]");
+/// let pairs: Vec<(u8, char)> = serde_dbgfmt::from_reader(input).unwrap();
+///
+/// assert_eq!(pairs, [(1, 'a'), (2, 'b')]);
+/// ```
+#[cfg(feature = "std")]
+pub fn from_reader<R, T>(reader: R) -> Result<T, Error>
+where
+    R: io::Read,
+    T: DeserializeOwned,
+{
+    from_reader_with(reader, &Options::default())
+}
+
+/// Parse a `T` from the debug representation read from `reader` using the
+/// provided options.
+///
+/// See [`from_reader`].
+#[cfg(feature = "std")]
+pub fn from_reader_with<R, T>(reader: R, options: &Options) -> Result<T, Error>
+where
+    R: io::Read,
+    T: DeserializeOwned,
+{
+    crate::read::from_reader(reader, options)
+}
+
+/// Call `func` with each element of the list, set, or map in `input`.
+///
+/// Elements are deserialized one at a time and are never collected together,
+/// so memory use stays flat no matter how many elements there are. The
+/// entries of a map are passed as a `(key, value)` tuple.
+///
+/// See [`try_for_each_element`] for a version that allows stopping early.
+///
+/// # Example
+/// ```
+/// let mut total = 0;
+/// serde_dbgfmt::for_each_element("[1, 2, 3]", |x: u64| total += x).unwrap();
+/// assert_eq!(total, 6);
+///
+/// let mut keys = Vec::new();
+/// serde_dbgfmt::for_each_element(r#"{"a": 1, "b": 2}"#, |(k, _): (&str, u32)| keys.push(k))
+///     .unwrap();
+/// assert_eq!(keys, ["a", "b"]);
+/// ```
+#[cfg(feature = "alloc")]
+pub fn for_each_element<'de, T, F>(input: &'de str, mut func: F) -> Result<(), Error>
+where
+    T: Deserialize<'de>,
+    F: FnMut(T),
+{
+    try_for_each_element(input, |element| {
+        func(element);
+        Ok(())
+    })
+}
+
+/// Call a fallible `func` with each element of the list, set, or map in
+/// `input`.
+///
+/// This stops at the first error, whether it came from parsing the input or
+/// from `func`.
+///
+/// # Example
+/// ```
+/// use serde_dbgfmt::Error;
+///
+/// let mut seen = Vec::new();
+/// let result = serde_dbgfmt::try_for_each_element("[1, 2, 3]", |x: u32| {
+///     if x == 2 {
+///         return Err(<Error as serde::de::Error>::custom("found 2"));
+///     }
+///
+///     seen.push(x);
+///     Ok(())
+/// });
+///
+/// assert!(result.is_err());
+/// assert_eq!(seen, [1]);
+/// ```
+#[cfg(feature = "alloc")]
+pub fn try_for_each_element<'de, T, E, F>(input: &'de str, func: F) -> Result<(), E>
+where
+    T: Deserialize<'de>,
+    E: From<Error>,
+    F: FnMut(T) -> Result<(), E>,
+{
+    let mut de = Deserializer::new(input);
+    de.for_each_element(func)?;
+    de.end()?;
+    Ok(())
+}
+
+/// Parse the debug representation of a `T` into an existing `place`.
+///
+/// This reuses the allocations already owned by `place` wherever the
+/// [`Deserialize`] impl for `T` supports it. For types like `String` and
+/// `Vec<T>` this means that repeatedly parsing records of the same shape
+/// avoids allocating for each one.
+///
+/// If an error occurs then `place` may be left partially updated.
+///
+/// # Example
+/// ```
+/// let mut names: Vec<String> = Vec::new();
+///
+/// serde_dbgfmt::from_str_in_place(r#"["alice", "bob"]"#, &mut names).unwrap();
+/// assert_eq!(names, ["alice", "bob"]);
+///
+/// serde_dbgfmt::from_str_in_place(r#"["carol"]"#, &mut names).unwrap();
+/// assert_eq!(names, ["carol"]);
+/// ```
+#[cfg(feature = "alloc")]
+pub fn from_str_in_place<'de, T>(str: &'de str, place: &mut T) -> Result<(), Error>
+where
+    T: Deserialize<'de>,
+{
+    let mut de = Deserializer::new(str);
+    T::deserialize_in_place(&mut de, place).map_err(|e| de.locate(e))?;
+    de.end()
+}
+
 /// Parse the debug representation of `U` as a `T`.
+#[cfg(feature = "alloc")]
 pub fn from_dbg<T, U>(value: &U) -> Result<T, Error>
 where
     T: DeserializeOwned,
     U: Debug,
 {
-    from_str(&format!("{value:?}"))
+    from_string(format!("{value:?}"))
+}
+
+/// Parse the debug representation of `U` as a `T`, formatting it into `buf`.
+///
+/// Unlike [`from_dbg`] the text is kept in the caller-provided buffer, so `T`
+/// can borrow from it. Any existing contents of `buf` are cleared first, which
+/// also allows the buffer to be reused across calls.
+///
+/// Strings that contain escape sequences in their debug representation can
+/// only be deserialized into owned types such as `String`.
+///
+/// # Example
+/// ```
+/// use serde::Deserialize;
+///
+/// #[derive(Debug)]
+/// struct Request {
+///     path: String,
+///     method: &'static str,
+/// }
+///
+/// #[derive(Deserialize)]
+/// #[serde(rename = "Request")]
+/// struct RequestRef<'a> {
+///     path: &'a str,
+///     method: &'a str,
+/// }
+///
+/// let request = Request {
+///     path: "/index.html".into(),
+///     method: "GET",
+/// };
+///
+/// let mut buf = String::new();
+/// let parsed: RequestRef = serde_dbgfmt::from_dbg_buf(&request, &mut buf).unwrap();
+///
+/// assert_eq!(parsed.path, "/index.html");
+/// assert_eq!(parsed.method, "GET");
+/// ```
+#[cfg(feature = "alloc")]
+pub fn from_dbg_buf<'a, T, U>(value: &U, buf: &'a mut String) -> Result<T, Error>
+where
+    T: Deserialize<'a>,
+    U: Debug,
+{
+    buf.clear();
+    write!(buf, "{value:?}").map_err(|_| Error::custom("a Debug impl returned an error"))?;
+    from_str(buf)
+}
+
+/// Parse the debug representation of `U` as a `T` using the provided options.
+///
+/// # Example
+/// ```
+/// use serde_dbgfmt::Options;
+///
+/// #[derive(Debug)]
+/// struct Sample {
+///     ratio: f64,
+/// }
+///
+/// mod target {
+///     #[derive(serde::Deserialize)]
+///     pub struct Sample {
+///         pub ratio: u32,
+///     }
+/// }
+///
+/// let options = Options::new().coerce_numbers(true);
+/// let sample: target::Sample =
+///     serde_dbgfmt::from_dbg_with(&Sample { ratio: 4.0 }, &options).unwrap();
+///
+/// assert_eq!(sample.ratio, 4);
+/// ```
+#[cfg(feature = "alloc")]
+pub fn from_dbg_with<T, U>(value: &U, options: &Options) -> Result<T, Error>
+where
+    T: DeserializeOwned,
+    U: Debug,
+{
+    let mut de = OwnedDeserializer::with_options(format!("{value:?}"), options.clone());
+    let value = de.deserialize()?;
+    de.end()?;
+    Ok(value)
 }
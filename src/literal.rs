@@ -0,0 +1,686 @@
+//! Conversions from literal text to numeric values.
+
+#[cfg(feature = "half")]
+use alloc::format;
+use alloc::string::String;
+#[cfg(feature = "half")]
+use alloc::string::ToString;
+use core::num::{FpCategory, IntErrorKind, ParseFloatError, ParseIntError};
+use core::time::Duration;
+
+use crate::{Deserializer, Error};
+
+mod private {
+    use core::num::{FpCategory, ParseFloatError};
+
+    pub trait Int: Sized {
+        /// Convert the magnitude of a literal, along with its sign, into
+        /// `Self`.
+        ///
+        /// Returns `None` if the value is out of range.
+        fn from_magnitude(negative: bool, magnitude: u128) -> Option<Self>;
+    }
+
+    pub trait Float: Sized + core::fmt::Debug {
+        const NAN: Self;
+        const INFINITY: Self;
+
+        fn parse_decimal(text: &str) -> Result<Self, ParseFloatError>;
+        fn parse_hex(text: &str) -> Option<Self>;
+        fn from_u128(value: u128) -> Self;
+        fn negate(self) -> Self;
+        fn category(&self) -> FpCategory;
+    }
+}
+
+/// An integer type that can be parsed by [`parse_int_literal`].
+///
+/// This trait is sealed and cannot be implemented outside of this crate.
+pub trait IntLiteral: private::Int {}
+
+/// A floating-point type that can be parsed by [`parse_float_literal`].
+///
+/// This trait is sealed and cannot be implemented outside of this crate.
+pub trait FloatLiteral: private::Float {}
+
+/// Parse an integer literal using the same rules as the deserializer.
+///
+/// The literal may have a leading `+` or `-` sign and may use a `0x`, `0o`, or
+/// `0b` radix prefix. Leading zeros are permitted. Values that don't fit within
+/// `T` result in an error.
+///
+/// # Example
+/// ```
+/// use serde_dbgfmt::parse_int_literal;
+///
+/// assert_eq!(parse_int_literal::<i32>("-0x1F").unwrap(), -31);
+/// assert_eq!(parse_int_literal::<u8>("000255").unwrap(), 255);
+/// assert!(parse_int_literal::<u8>("256").is_err());
+/// ```
+pub fn parse_int_literal<T: IntLiteral>(text: &str) -> Result<T, Error> {
+    let mut de = Deserializer::new(text);
+    let value = de.parse_int_value()?;
+    de.end()?;
+    Ok(value)
+}
+
+/// Parse a floating-point literal using the same rules as the deserializer.
+///
+/// This accepts everything that the debug output of `f32` and `f64` may
+/// contain, including `NaN` and `inf`, along with an optional leading `+` or
+/// `-` sign and C99-style hexadecimal floats (e.g. `0x1.8p3`).
+///
+/// # Example
+/// ```
+/// use serde_dbgfmt::parse_float_literal;
+///
+/// assert_eq!(parse_float_literal::<f64>("-1.5e3").unwrap(), -1500.0);
+/// assert_eq!(parse_float_literal::<f32>("0x1.8p3").unwrap(), 12.0);
+/// assert!(parse_float_literal::<f64>("NaN").unwrap().is_nan());
+/// assert_eq!(parse_float_literal::<f64>("-inf").unwrap(), f64::NEG_INFINITY);
+/// ```
+pub fn parse_float_literal<T: FloatLiteral>(text: &str) -> Result<T, Error> {
+    let mut de = Deserializer::new(text);
+    let value = de.parse_float_value()?;
+    de.end()?;
+    Ok(value)
+}
+
+/// Whether `ident` is a float that is written as an identifier, such as `NaN`
+/// or `inf`.
+///
+/// `Infinity` is also accepted since it is how many other languages write
+/// infinite floats. The comparison ignores case.
+pub(crate) fn is_float_ident(ident: &str) -> bool {
+    ["NaN", "inf", "infinity"]
+        .iter()
+        .any(|name| ident.eq_ignore_ascii_case(name))
+}
+
+/// Whether `ident` is a float exactly as `Debug` writes it.
+///
+/// This is used when the target type is unknown, where the looser spellings
+/// accepted by [`is_float_ident`] could just as well be the name of a unit
+/// struct or variant.
+pub(crate) fn is_debug_float_ident(ident: &str) -> bool {
+    matches!(ident, "NaN" | "inf")
+}
+
+macro_rules! float_literal {
+    ($float:ty, $format:expr, $bits:ty) => {
+        impl FloatLiteral for $float {}
+
+        impl private::Float for $float {
+            const NAN: Self = <$float>::NAN;
+            const INFINITY: Self = <$float>::INFINITY;
+
+            fn parse_decimal(text: &str) -> Result<Self, ParseFloatError> {
+                // Errors are rare, so parse again using std to get its error.
+                #[cfg(feature = "fast_float")]
+                if let Ok(value) = fast_float2::parse(text) {
+                    return Ok(value);
+                }
+
+                text.parse()
+            }
+
+            fn parse_hex(text: &str) -> Option<Self> {
+                parse_hex_float(text, $format).map(|bits| Self::from_bits(bits as $bits))
+            }
+
+            fn from_u128(value: u128) -> Self {
+                value as Self
+            }
+
+            fn negate(self) -> Self {
+                -self
+            }
+
+            fn category(&self) -> FpCategory {
+                self.classify()
+            }
+        }
+    };
+}
+
+float_literal!(f32, FloatFormat::F32, u32);
+float_literal!(f64, FloatFormat::F64, u64);
+
+/// Implement the float traits for a type from the `half` crate.
+///
+/// Decimal literals are first parsed as an `f64` and then narrowed. This can
+/// round twice, so ties in the narrower type are resolved using the literal.
+#[cfg(feature = "half")]
+macro_rules! half_float_literal {
+    ($float:ty, $format:expr) => {
+        impl FloatLiteral for $float {}
+
+        impl private::Float for $float {
+            const NAN: Self = <$float>::NAN;
+            const INFINITY: Self = <$float>::INFINITY;
+
+            fn parse_decimal(text: &str) -> Result<Self, ParseFloatError> {
+                let wide: f64 = text.parse()?;
+                let narrow = <$float>::from_f64(wide);
+                if narrow.is_infinite() || narrow.to_f64() == wide {
+                    return Ok(narrow);
+                }
+
+                // The neighbour of `narrow` on the other side of `wide`.
+                let other = match narrow.to_f64() < wide {
+                    true => <$float>::from_bits(narrow.to_bits() + 1),
+                    false => <$float>::from_bits(narrow.to_bits() - 1),
+                };
+
+                let midpoint = (narrow.to_f64() + other.to_f64()) / 2.0;
+                if midpoint != wide {
+                    return Ok(narrow);
+                }
+
+                // The f64 rounding landed exactly on the midpoint so the literal
+                // decides which way to round. The midpoint is a short binary
+                // fraction so 150 decimal places always prints it exactly.
+                let (low, high) = match narrow.to_f64() < other.to_f64() {
+                    true => (narrow, other),
+                    false => (other, narrow),
+                };
+                Ok(match compare_decimal(text, &format!("{midpoint:.150}")) {
+                    core::cmp::Ordering::Less => low,
+                    core::cmp::Ordering::Greater => high,
+                    core::cmp::Ordering::Equal => narrow,
+                })
+            }
+
+            fn parse_hex(text: &str) -> Option<Self> {
+                parse_hex_float(text, $format).map(|bits| Self::from_bits(bits as u16))
+            }
+
+            fn from_u128(value: u128) -> Self {
+                Self::parse_decimal(&value.to_string()).unwrap_or(Self::INFINITY)
+            }
+
+            fn negate(self) -> Self {
+                -self
+            }
+
+            fn category(&self) -> FpCategory {
+                self.classify()
+            }
+        }
+    };
+}
+
+#[cfg(feature = "half")]
+half_float_literal!(half::f16, FloatFormat::F16);
+#[cfg(feature = "half")]
+half_float_literal!(half::bf16, FloatFormat::BF16);
+
+macro_rules! unsigned_int_literal {
+    ($( $int:ty ),*) => {$(
+        impl IntLiteral for $int {}
+
+        impl private::Int for $int {
+            fn from_magnitude(negative: bool, magnitude: u128) -> Option<Self> {
+                match negative && magnitude != 0 {
+                    true => None,
+                    false => Self::try_from(magnitude).ok(),
+                }
+            }
+        }
+    )*}
+}
+
+macro_rules! signed_int_literal {
+    ($( $int:ty ),*) => {$(
+        impl IntLiteral for $int {}
+
+        impl private::Int for $int {
+            fn from_magnitude(negative: bool, magnitude: u128) -> Option<Self> {
+                if !negative {
+                    return Self::try_from(magnitude).ok();
+                }
+
+                // The magnitude of MIN is one larger than MAX so it needs to be
+                // handled separately.
+                let min = <$int>::MIN.unsigned_abs() as u128;
+                match magnitude.cmp(&min) {
+                    core::cmp::Ordering::Less => Some(-(magnitude as $int)),
+                    core::cmp::Ordering::Equal => Some(<$int>::MIN),
+                    core::cmp::Ordering::Greater => None,
+                }
+            }
+        }
+    )*}
+}
+
+unsigned_int_literal!(u8, u16, u32, u64, u128, usize);
+signed_int_literal!(i8, i16, i32, i64, i128, isize);
+
+/// Parse the digits of an integer literal, with an optional `0x`, `0o`, or
+/// `0b` radix prefix, into an integer type.
+///
+/// The sign is passed separately since it is a separate token in the debug
+/// output. The digits are parsed directly without any copying so leading zeros
+/// are permitted and overflow is always detected, no matter how long the
+/// literal is.
+pub(crate) fn parse_int<T: IntLiteral>(negative: bool, literal: &str) -> Result<T, ParseIntError> {
+    let (digits, radix) = match literal.get(..2) {
+        Some("0x" | "0X") => (&literal[2..], 16),
+        Some("0o" | "0O") => (&literal[2..], 8),
+        Some("0b" | "0B") => (&literal[2..], 2),
+        _ => (literal, 10),
+    };
+
+    if digits.is_empty() {
+        return Err(int_error(IntErrorKind::Empty));
+    }
+
+    let overflow = || match negative {
+        true => int_error(IntErrorKind::NegOverflow),
+        false => int_error(IntErrorKind::PosOverflow),
+    };
+
+    let mut magnitude: u128 = 0;
+    for c in digits.chars() {
+        let digit = c
+            .to_digit(radix)
+            .ok_or_else(|| int_error(IntErrorKind::InvalidDigit))?;
+
+        magnitude = magnitude
+            .checked_mul(radix as u128)
+            .and_then(|value| value.checked_add(digit as u128))
+            .ok_or_else(overflow)?;
+    }
+
+    T::from_magnitude(negative, magnitude).ok_or_else(overflow)
+}
+
+/// Create a [`ParseIntError`] with the requested kind.
+///
+/// There is no way to construct one directly so we instead get the standard
+/// library to create one by parsing a string that results in the desired error.
+#[cold]
+fn int_error(kind: IntErrorKind) -> ParseIntError {
+    let text = match kind {
+        IntErrorKind::Empty => "",
+        IntErrorKind::PosOverflow => "128",
+        IntErrorKind::NegOverflow => "-129",
+        _ => "-",
+    };
+
+    text.parse::<i8>().unwrap_err()
+}
+
+/// The parameters of an IEEE 754 binary floating-point format.
+#[derive(Copy, Clone, Debug)]
+pub(crate) struct FloatFormat {
+    /// The number of bits of precision, including the implicit leading bit.
+    precision: u32,
+    /// The exponent of the smallest normal number.
+    min_exp: i64,
+    /// The exponent of the largest finite number.
+    max_exp: i64,
+}
+
+impl FloatFormat {
+    pub(crate) const F32: Self = Self {
+        precision: f32::MANTISSA_DIGITS,
+        min_exp: f32::MIN_EXP as i64 - 1,
+        max_exp: f32::MAX_EXP as i64 - 1,
+    };
+
+    pub(crate) const F64: Self = Self {
+        precision: f64::MANTISSA_DIGITS,
+        min_exp: f64::MIN_EXP as i64 - 1,
+        max_exp: f64::MAX_EXP as i64 - 1,
+    };
+
+    #[cfg(feature = "half")]
+    pub(crate) const F16: Self = Self {
+        precision: half::f16::MANTISSA_DIGITS,
+        min_exp: half::f16::MIN_EXP as i64 - 1,
+        max_exp: half::f16::MAX_EXP as i64 - 1,
+    };
+
+    #[cfg(feature = "half")]
+    pub(crate) const BF16: Self = Self {
+        precision: half::bf16::MANTISSA_DIGITS,
+        min_exp: half::bf16::MIN_EXP as i64 - 1,
+        max_exp: half::bf16::MAX_EXP as i64 - 1,
+    };
+}
+
+/// Whether the mantissa of the float literal `text` has any non-zero digits.
+pub(crate) fn has_nonzero_mantissa(text: &str) -> bool {
+    match text.strip_prefix("0x").or_else(|| text.strip_prefix("0X")) {
+        Some(hex) => hex
+            .chars()
+            .take_while(|&c| !matches!(c, 'p' | 'P'))
+            .any(|c| c.is_ascii_hexdigit() && c != '0'),
+        None => text
+            .chars()
+            .take_while(|&c| !matches!(c, 'e' | 'E'))
+            .any(|c| matches!(c, '1'..='9')),
+    }
+}
+
+/// Whether the unsigned decimal literal `text` has exactly the same value as
+/// `shortest`, the debug representation of the float it was parsed into.
+///
+/// Since the debug representation of a float is the shortest decimal that
+/// round-trips, the float represents the literal exactly only if both are the
+/// same decimal number.
+pub(crate) fn is_exact_decimal(text: &str, shortest: &str) -> bool {
+    match (normalize_decimal(text), normalize_decimal(shortest)) {
+        (Some(a), Some(b)) => a == b,
+        _ => false,
+    }
+}
+
+/// Compare the values of two unsigned decimal literals.
+#[cfg(feature = "half")]
+fn compare_decimal(a: &str, b: &str) -> core::cmp::Ordering {
+    use core::cmp::Ordering;
+
+    let (Some((a_digits, a_exp)), Some((b_digits, b_exp))) =
+        (normalize_decimal(a), normalize_decimal(b))
+    else {
+        return Ordering::Equal;
+    };
+
+    // Zero has no digits and is smaller than everything else.
+    match (a_digits.is_empty(), b_digits.is_empty()) {
+        (true, true) => return Ordering::Equal,
+        (true, false) => return Ordering::Less,
+        (false, true) => return Ordering::Greater,
+        (false, false) => (),
+    }
+
+    // Compare the position of the leading digits first, then the digits.
+    let a_top = a_exp + a_digits.len() as i64;
+    let b_top = b_exp + b_digits.len() as i64;
+    a_top.cmp(&b_top).then_with(|| a_digits.cmp(&b_digits))
+}
+
+/// Split an unsigned decimal literal into its significant digits and the
+/// power of ten that they are multiplied by.
+fn normalize_decimal(text: &str) -> Option<(String, i64)> {
+    let (mantissa, exponent) = match text.split_once(['e', 'E']) {
+        Some((mantissa, exponent)) => (mantissa, exponent.replace('_', "").parse().ok()?),
+        None => (text, 0i64),
+    };
+    let (int, frac) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+
+    let mut digits: String = int
+        .chars()
+        .chain(frac.chars())
+        .filter(|&c| c != '_')
+        .collect();
+    if !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+
+    let frac_len = frac.chars().filter(|&c| c != '_').count() as i64;
+    let trailing = digits.len() - digits.trim_end_matches('0').len();
+    digits.truncate(digits.len() - trailing);
+
+    let digits = digits.trim_start_matches('0');
+    if digits.is_empty() {
+        return Some((String::new(), 0));
+    }
+
+    Some((digits.into(), exponent - frac_len + trailing as i64))
+}
+
+/// Whether `text` is a C99-style hexadecimal float literal (e.g. `0x1.8p3`).
+pub(crate) fn is_hex_float(text: &str) -> bool {
+    matches!(text.get(..2), Some("0x" | "0X")) && text.contains(['p', 'P'])
+}
+
+/// Convert an unsigned C99-style hexadecimal float literal (e.g. `0x1.8p3`) to
+/// the bits of the nearest float in `format`, rounding ties to even.
+///
+/// Returns `None` if `text` is not a valid hex float literal.
+pub(crate) fn parse_hex_float(text: &str, format: FloatFormat) -> Option<u64> {
+    // Exponents beyond this are going to overflow to infinity or underflow to 0
+    // no matter how many digits are in the mantissa.
+    const EXP_LIMIT: i64 = 1 << 40;
+
+    let text = text
+        .strip_prefix("0x")
+        .or_else(|| text.strip_prefix("0X"))?;
+    let (mantissa, exponent) = text.split_once(['p', 'P'])?;
+    let (int, frac) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+
+    let digits = exponent.trim_start_matches(['+', '-']);
+    if int.is_empty() || digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+
+    let mut exp: i64 = digits.parse::<i64>().unwrap_or(EXP_LIMIT).min(EXP_LIMIT);
+    if exponent.starts_with('-') {
+        exp = -exp;
+    }
+
+    // Accumulate the mantissa into `mant` so that the value is `mant * 2^exp`.
+    // Once `mant` is full the remaining digits only matter for rounding, so we
+    // just record whether any of them are non-zero.
+    let mut mant: u64 = 0;
+    let mut sticky = false;
+    for (index, c) in int.chars().chain(frac.chars()).enumerate() {
+        let digit = c.to_digit(16)? as u64;
+        let is_frac = index >= int.len();
+
+        if mant >> 60 == 0 {
+            mant = (mant << 4) | digit;
+            if is_frac {
+                exp -= 4;
+            }
+        } else {
+            sticky |= digit != 0;
+            if !is_frac {
+                exp += 4;
+            }
+        }
+    }
+
+    let precision = format.precision as i64;
+    let exp_bits = precision - 1;
+    let bias = format.max_exp;
+    let infinity = ((2 * bias + 1) as u64) << exp_bits;
+
+    if mant == 0 {
+        return Some(0);
+    }
+
+    // The exponent of the leading bit of the value and the exponent of the least
+    // significant bit that can be represented at that magnitude.
+    let top = exp + (64 - mant.leading_zeros() as i64) - 1;
+    if top > format.max_exp {
+        return Some(infinity);
+    }
+    let mut quantum = top.max(format.min_exp) - precision + 1;
+
+    let shift = quantum - exp;
+    let mut result = if shift <= 0 {
+        mant << -shift
+    } else if shift > 64 {
+        0
+    } else {
+        let mant = mant as u128;
+        let truncated = (mant >> shift) as u64;
+        let rem = mant & ((1u128 << shift) - 1);
+        let half = 1u128 << (shift - 1);
+
+        let round_up = rem > half || (rem == half && (sticky || truncated & 1 == 1));
+        truncated + round_up as u64
+    };
+
+    // Rounding may have carried into the next power of two.
+    if result >> precision != 0 {
+        result >>= 1;
+        quantum += 1;
+    }
+
+    if result >> exp_bits == 0 {
+        // Subnormal numbers (and zero) have a biased exponent of 0.
+        return Some(result);
+    }
+
+    let biased = quantum + exp_bits + bias;
+    if biased > 2 * bias {
+        return Some(infinity);
+    }
+
+    let fraction = result & ((1 << exp_bits) - 1);
+    Some(((biased as u64) << exp_bits) | fraction)
+}
+
+/// Parse a duration in the format printed by the `Debug` impl of
+/// [`Duration`], such as `1.5s` or `250ms`. `us` is accepted in place of `µs`.
+///
+/// Returns `None` if `text` is not a valid duration or it is out of range.
+pub(crate) fn parse_duration(text: &str) -> Option<Duration> {
+    let split = text
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(text.len());
+    let (number, unit) = text.split_at(split);
+
+    let scale: u128 = match unit.trim_start() {
+        "ns" => 1,
+        "µs" | "us" => 1_000,
+        "ms" => 1_000_000,
+        "s" => 1_000_000_000,
+        _ => return None,
+    };
+
+    let (int, frac) = number.split_once('.').unwrap_or((number, ""));
+    if int.is_empty() || (number.contains('.') && frac.is_empty()) {
+        return None;
+    }
+
+    // Digits beyond the 9th past the decimal point are always below a
+    // nanosecond, even for seconds.
+    let frac = &frac[..frac.len().min(9)];
+    let frac_nanos = match frac.is_empty() {
+        true => 0,
+        false => frac.parse::<u128>().ok()? * scale / 10u128.pow(frac.len() as u32),
+    };
+    let nanos = int.parse::<u128>().ok()?.checked_mul(scale)? + frac_nanos;
+
+    let secs = u64::try_from(nanos / 1_000_000_000).ok()?;
+    Some(Duration::new(secs, (nanos % 1_000_000_000) as u32))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn int_basic() {
+        assert_eq!(parse_int::<u8>(false, "100"), Ok(100));
+        assert_eq!(parse_int::<i32>(true, "100"), Ok(-100));
+        assert_eq!(parse_int::<u32>(false, "0x1F"), Ok(31));
+        assert_eq!(parse_int::<i8>(true, "0o17"), Ok(-15));
+        assert_eq!(parse_int::<u16>(false, "0b101"), Ok(5));
+        assert_eq!(parse_int::<u8>(true, "0"), Ok(0));
+    }
+
+    #[test]
+    fn int_limits() {
+        assert_eq!(parse_int::<i8>(true, "128"), Ok(i8::MIN));
+        assert_eq!(
+            parse_int::<i128>(true, &i128::MIN.unsigned_abs().to_string()),
+            Ok(i128::MIN)
+        );
+        assert_eq!(
+            parse_int::<u128>(false, &u128::MAX.to_string()),
+            Ok(u128::MAX)
+        );
+        assert_eq!(
+            parse_int::<i128>(true, "0x80000000000000000000000000000000"),
+            Ok(i128::MIN)
+        );
+        assert_eq!(
+            parse_int::<u128>(false, "0xffffffffffffffffffffffffffffffff"),
+            Ok(u128::MAX)
+        );
+    }
+
+    #[test]
+    fn int_zero_padded() {
+        let padded = format!("{}127", "0".repeat(1000));
+        assert_eq!(parse_int::<i8>(false, &padded), Ok(127));
+        assert_eq!(parse_int::<i8>(true, &padded), Ok(-127));
+
+        let padded = format!("0x{}ff", "0".repeat(1000));
+        assert_eq!(parse_int::<u8>(false, &padded), Ok(255));
+    }
+
+    #[test]
+    fn int_errors() {
+        fn error<T: IntLiteral + core::fmt::Debug>(negative: bool, literal: &str) -> ParseIntError {
+            parse_int::<T>(negative, literal).unwrap_err()
+        }
+
+        assert_eq!(error::<i8>(false, "128").kind(), &IntErrorKind::PosOverflow);
+        assert_eq!(error::<i8>(true, "129").kind(), &IntErrorKind::NegOverflow);
+        assert_eq!(error::<u8>(true, "1").kind(), &IntErrorKind::NegOverflow);
+        assert_eq!(
+            error::<u128>(false, &"9".repeat(100)).kind(),
+            &IntErrorKind::PosOverflow
+        );
+        assert_eq!(
+            error::<i128>(true, &"9".repeat(100)).kind(),
+            &IntErrorKind::NegOverflow
+        );
+        assert_eq!(error::<u32>(false, "0x").kind(), &IntErrorKind::Empty);
+        assert_eq!(
+            error::<u32>(false, "0b012").kind(),
+            &IntErrorKind::InvalidDigit
+        );
+    }
+
+    fn hex_f64(text: &str) -> f64 {
+        f64::from_bits(parse_hex_float(text, FloatFormat::F64).expect("invalid hex float"))
+    }
+
+    fn hex_f32(text: &str) -> f32 {
+        f32::from_bits(parse_hex_float(text, FloatFormat::F32).expect("invalid hex float") as u32)
+    }
+
+    #[test]
+    fn hex_float_basic() {
+        assert_eq!(hex_f64("0x1.8p3"), 12.0);
+        assert_eq!(hex_f64("0x1p-1"), 0.5);
+        assert_eq!(hex_f64("0X0.0p0"), 0.0);
+        assert_eq!(hex_f64("0xA.8P+0"), 10.5);
+        assert_eq!(hex_f32("0x1.8p3"), 12.0);
+    }
+
+    #[test]
+    fn hex_float_limits() {
+        assert_eq!(hex_f64("0x1.fffffffffffffp1023"), f64::MAX);
+        assert_eq!(hex_f64("0x1p-1022"), f64::MIN_POSITIVE);
+        assert_eq!(hex_f64("0x1p-1074"), f64::from_bits(1));
+        assert_eq!(hex_f64("0x1p1024"), f64::INFINITY);
+        assert_eq!(hex_f64("0x1p-1076"), 0.0);
+        assert_eq!(hex_f64("0x1p99999999999999999999"), f64::INFINITY);
+
+        assert_eq!(hex_f32("0x1.fffffep127"), f32::MAX);
+        assert_eq!(hex_f32("0x1p-149"), f32::from_bits(1));
+        assert_eq!(hex_f32("0x1p128"), f32::INFINITY);
+    }
+
+    #[test]
+    fn hex_float_rounding() {
+        // Ties round to even.
+        assert_eq!(hex_f32("0x1.000001p0"), 1.0);
+        assert_eq!(hex_f32("0x1.000003p0"), 1.0 + 2.0 * f32::EPSILON);
+        // Anything above a tie rounds up.
+        assert_eq!(hex_f32("0x1.0000010000000000000001p0"), 1.0 + f32::EPSILON);
+        // Rounding up can carry into the exponent.
+        assert_eq!(hex_f32("0x1.ffffffp0"), 2.0);
+        assert_eq!(hex_f64("0x1.fffffffffffff8p1023"), f64::INFINITY);
+    }
+}
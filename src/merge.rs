@@ -0,0 +1,632 @@
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt;
+
+use serde::de::value::StrDeserializer;
+use serde::de::{
+    Deserialize, DeserializeSeed, Deserializer, EnumAccess, Error as _, MapAccess, SeqAccess,
+    Visitor,
+};
+use serde::Serialize;
+
+use crate::de::VALUE_TOKEN;
+use crate::{Value, ValueString};
+
+/// A [`DeserializeSeed`] which applies a partial debug representation on top
+/// of an existing value.
+///
+/// Only the fields that are present in the input are updated, every other
+/// field keeps its current value. Nested structs are merged recursively,
+/// including those within `Some(..)` and newtype structs, and the input may
+/// use `..` to indicate that fields have been left out. Any other value in
+/// the input, such as an enum or a list, replaces the current value as a
+/// whole. This makes it possible to patch a value using a snippet captured
+/// from a log.
+///
+/// The input is read field by field from the deserializer that the seed is
+/// given, so the [`Options`](crate::Options) of that deserializer apply to
+/// it. The fields that are not in the input are taken from the current value
+/// of the target, which is captured using its `Serialize` impl.
+///
+/// # Example
+/// ```
+/// use serde::de::DeserializeSeed;
+/// use serde::{Deserialize, Serialize};
+/// use serde_dbgfmt::MergeSeed;
+///
+/// #[derive(Debug, Deserialize, Serialize, PartialEq)]
+/// struct Database {
+///     host: String,
+///     port: u16,
+/// }
+///
+/// #[derive(Debug, Deserialize, Serialize, PartialEq)]
+/// struct Config {
+///     name: String,
+///     database: Database,
+/// }
+///
+/// let mut config = Config {
+///     name: "prod".into(),
+///     database: Database {
+///         host: "localhost".into(),
+///         port: 5432,
+///     },
+/// };
+///
+/// let mut de = serde_dbgfmt::Deserializer::new("Config { database: Database { port: 6543, .. }, .. }");
+/// MergeSeed::new(&mut config).deserialize(&mut de).unwrap();
+/// de.end().unwrap();
+///
+/// assert_eq!(config.name, "prod");
+/// assert_eq!(config.database.host, "localhost");
+/// assert_eq!(config.database.port, 6543);
+/// ```
+pub struct MergeSeed<'a, T> {
+    target: &'a mut T,
+}
+
+impl<'a, T> MergeSeed<'a, T> {
+    /// Create a seed which will merge its input into `target`.
+    pub fn new(target: &'a mut T) -> Self {
+        Self { target }
+    }
+}
+
+impl<'de, T> DeserializeSeed<'de> for MergeSeed<'_, T>
+where
+    T: Serialize + Deserialize<'de>,
+{
+    type Value = ();
+
+    fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let base: Value = crate::to_string(&*self.target)
+            .and_then(|text| text.parse())
+            .map_err(D::Error::custom)?;
+
+        *self.target = T::deserialize(Merge {
+            patch: deserializer,
+            base: &base,
+        })?;
+        Ok(())
+    }
+}
+
+/// Deserializes from `patch`, filling in anything it leaves out from `base`.
+struct Merge<'b, D> {
+    patch: D,
+    base: &'b Value,
+}
+
+macro_rules! forward_to_patch {
+    ($($method:ident($($arg:ident: $ty:ty),*))*) => {$(
+        fn $method<V>(self, $($arg: $ty,)* visitor: V) -> Result<V::Value, Self::Error>
+        where
+            V: Visitor<'de>,
+        {
+            self.patch.$method($($arg,)* visitor)
+        }
+    )*};
+}
+
+impl<'de, D> Deserializer<'de> for Merge<'_, D>
+where
+    D: Deserializer<'de>,
+{
+    type Error = D::Error;
+
+    fn deserialize_option<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.patch.deserialize_option(MergeVisitor {
+            inner: visitor,
+            base: self.base,
+        })
+    }
+
+    fn deserialize_newtype_struct<V>(
+        self,
+        name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        // Untyped values are replaced as a whole.
+        if name == VALUE_TOKEN {
+            return self.patch.deserialize_newtype_struct(name, visitor);
+        }
+
+        self.patch.deserialize_newtype_struct(
+            name,
+            MergeVisitor {
+                inner: visitor,
+                base: self.base,
+            },
+        )
+    }
+
+    fn deserialize_struct<V>(
+        self,
+        name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.patch.deserialize_struct(
+            name,
+            fields,
+            MergeVisitor {
+                inner: visitor,
+                base: self.base,
+            },
+        )
+    }
+
+    fn is_human_readable(&self) -> bool {
+        self.patch.is_human_readable()
+    }
+
+    forward_to_patch! {
+        deserialize_any()
+        deserialize_bool()
+        deserialize_i8()
+        deserialize_i16()
+        deserialize_i32()
+        deserialize_i64()
+        deserialize_i128()
+        deserialize_u8()
+        deserialize_u16()
+        deserialize_u32()
+        deserialize_u64()
+        deserialize_u128()
+        deserialize_f32()
+        deserialize_f64()
+        deserialize_char()
+        deserialize_str()
+        deserialize_string()
+        deserialize_bytes()
+        deserialize_byte_buf()
+        deserialize_unit()
+        deserialize_unit_struct(name: &'static str)
+        deserialize_seq()
+        deserialize_tuple(len: usize)
+        deserialize_tuple_struct(name: &'static str, len: usize)
+        deserialize_map()
+        deserialize_enum(name: &'static str, variants: &'static [&'static str])
+        deserialize_identifier()
+        deserialize_ignored_any()
+    }
+}
+
+/// Passes everything through to `inner`, except for the parts of the patch
+/// that can be merged with `base`.
+struct MergeVisitor<'b, V> {
+    inner: V,
+    base: &'b Value,
+}
+
+macro_rules! forward_to_inner {
+    ($($method:ident($($arg:ident: $ty:ty),*))*) => {$(
+        fn $method<E>(self, $($arg: $ty),*) -> Result<Self::Value, E>
+        where
+            E: serde::de::Error,
+        {
+            self.inner.$method($($arg),*)
+        }
+    )*};
+}
+
+impl<'de, V> Visitor<'de> for MergeVisitor<'_, V>
+where
+    V: Visitor<'de>,
+{
+    type Value = V::Value;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.inner.expecting(f)
+    }
+
+    fn visit_some<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        match self.base {
+            Value::Option(Some(base)) => self.inner.visit_some(Merge {
+                patch: deserializer,
+                base,
+            }),
+            _ => self.inner.visit_some(deserializer),
+        }
+    }
+
+    fn visit_newtype_struct<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        match self.base {
+            Value::TupleStruct { elements, .. } if elements.len() == 1 => {
+                self.inner.visit_newtype_struct(Merge {
+                    patch: deserializer,
+                    base: &elements[0],
+                })
+            }
+            _ => self.inner.visit_newtype_struct(deserializer),
+        }
+    }
+
+    fn visit_map<A>(self, map: A) -> Result<Self::Value, A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        match self.base {
+            Value::Struct { fields, .. } => self.inner.visit_map(MergeMap {
+                patch: Some(map),
+                base: fields,
+                merged: vec![false; fields.len()],
+                next: 0,
+                value: None,
+            }),
+            _ => self.inner.visit_map(map),
+        }
+    }
+
+    fn visit_seq<A>(self, seq: A) -> Result<Self::Value, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        self.inner.visit_seq(seq)
+    }
+
+    fn visit_enum<A>(self, data: A) -> Result<Self::Value, A::Error>
+    where
+        A: EnumAccess<'de>,
+    {
+        self.inner.visit_enum(data)
+    }
+
+    fn visit_none<E>(self) -> Result<Self::Value, E>
+    where
+        E: serde::de::Error,
+    {
+        self.inner.visit_none()
+    }
+
+    fn visit_unit<E>(self) -> Result<Self::Value, E>
+    where
+        E: serde::de::Error,
+    {
+        self.inner.visit_unit()
+    }
+
+    fn visit_borrowed_str<E>(self, v: &'de str) -> Result<Self::Value, E>
+    where
+        E: serde::de::Error,
+    {
+        self.inner.visit_borrowed_str(v)
+    }
+
+    fn visit_borrowed_bytes<E>(self, v: &'de [u8]) -> Result<Self::Value, E>
+    where
+        E: serde::de::Error,
+    {
+        self.inner.visit_borrowed_bytes(v)
+    }
+
+    forward_to_inner! {
+        visit_bool(v: bool)
+        visit_i8(v: i8)
+        visit_i16(v: i16)
+        visit_i32(v: i32)
+        visit_i64(v: i64)
+        visit_i128(v: i128)
+        visit_u8(v: u8)
+        visit_u16(v: u16)
+        visit_u32(v: u32)
+        visit_u64(v: u64)
+        visit_u128(v: u128)
+        visit_f32(v: f32)
+        visit_f64(v: f64)
+        visit_char(v: char)
+        visit_str(v: &str)
+        visit_string(v: String)
+        visit_bytes(v: &[u8])
+        visit_byte_buf(v: Vec<u8>)
+    }
+}
+
+/// The fields of a struct in the patch, followed by the fields of the base
+/// that the patch did not mention.
+struct MergeMap<'b, A> {
+    /// The fields of the patch, until they run out.
+    patch: Option<A>,
+    base: &'b [(ValueString, Value)],
+    /// Which fields of the base were present in the patch.
+    merged: Vec<bool>,
+    /// The next field of the base to check once the patch is done.
+    next: usize,
+    /// The base value of the field whose key was returned last.
+    value: Option<&'b Value>,
+}
+
+impl<'de, 'b, A> MapAccess<'de> for MergeMap<'b, A>
+where
+    A: MapAccess<'de>,
+{
+    type Error = A::Error;
+
+    fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>, Self::Error>
+    where
+        K: DeserializeSeed<'de>,
+    {
+        let mut seed = Some(seed);
+        if let Some(patch) = &mut self.patch {
+            let mut name = None;
+            let key = patch.next_key_seed(KeySeed {
+                inner: &mut seed,
+                name: &mut name,
+            })?;
+
+            let Some(key) = key else {
+                self.patch = None;
+                return self.next_base_key(seed);
+            };
+
+            self.value = None;
+            if let Some(name) = name {
+                let index = self
+                    .base
+                    .iter()
+                    .position(|(field, _)| *field == name)
+                    .ok_or_else(|| A::Error::unknown_field(&name, &[]))?;
+
+                self.merged[index] = true;
+                self.value = Some(&self.base[index].1);
+            }
+
+            return Ok(Some(key));
+        }
+
+        self.next_base_key(seed)
+    }
+
+    fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value, Self::Error>
+    where
+        V: DeserializeSeed<'de>,
+    {
+        let base = self.value.take();
+        match &mut self.patch {
+            Some(patch) => patch.next_value_seed(ValueSeed { inner: seed, base }),
+            None => match base {
+                Some(base) => seed.deserialize(base).map_err(A::Error::custom),
+                None => Err(A::Error::custom("value is missing")),
+            },
+        }
+    }
+}
+
+impl<'de, A> MergeMap<'_, A>
+where
+    A: MapAccess<'de>,
+{
+    /// Return the name of the next field of the base that was not in the
+    /// patch.
+    fn next_base_key<K>(&mut self, seed: Option<K>) -> Result<Option<K::Value>, A::Error>
+    where
+        K: DeserializeSeed<'de>,
+    {
+        let base = self.base;
+        while let Some((name, value)) = base.get(self.next) {
+            let merged = self.merged[self.next];
+            self.next += 1;
+            if merged {
+                continue;
+            }
+
+            // The patch only takes the seed if it returns a key.
+            let seed = seed.ok_or_else(|| A::Error::custom("map key was not returned"))?;
+            self.value = Some(value);
+            return seed
+                .deserialize(StrDeserializer::<A::Error>::new(name))
+                .map(Some);
+        }
+
+        Ok(None)
+    }
+}
+
+/// Records the name of a field while it is deserialized by `inner`.
+struct KeySeed<'k, K> {
+    inner: &'k mut Option<K>,
+    name: &'k mut Option<String>,
+}
+
+impl<'de, K> DeserializeSeed<'de> for KeySeed<'_, K>
+where
+    K: DeserializeSeed<'de>,
+{
+    type Value = K::Value;
+
+    fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let seed = self
+            .inner
+            .take()
+            .ok_or_else(|| D::Error::custom("map key was already deserialized"))?;
+
+        seed.deserialize(KeyDeserializer {
+            inner: deserializer,
+            name: self.name,
+        })
+    }
+}
+
+/// Passes through to `inner`, recording the name of the field if it is
+/// deserialized as a string.
+struct KeyDeserializer<'k, D> {
+    inner: D,
+    name: &'k mut Option<String>,
+}
+
+macro_rules! forward_to_key {
+    ($($method:ident($($arg:ident: $ty:ty),*))*) => {$(
+        fn $method<V>(self, $($arg: $ty,)* visitor: V) -> Result<V::Value, Self::Error>
+        where
+            V: Visitor<'de>,
+        {
+            self.inner.$method($($arg,)* visitor)
+        }
+    )*};
+}
+
+macro_rules! record_key {
+    ($($method:ident)*) => {$(
+        fn $method<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+        where
+            V: Visitor<'de>,
+        {
+            self.inner.$method(KeyVisitor {
+                inner: visitor,
+                name: self.name,
+            })
+        }
+    )*};
+}
+
+impl<'de, D> Deserializer<'de> for KeyDeserializer<'_, D>
+where
+    D: Deserializer<'de>,
+{
+    type Error = D::Error;
+
+    fn is_human_readable(&self) -> bool {
+        self.inner.is_human_readable()
+    }
+
+    record_key! {
+        deserialize_any
+        deserialize_str
+        deserialize_string
+        deserialize_identifier
+    }
+
+    forward_to_key! {
+        deserialize_bool()
+        deserialize_i8()
+        deserialize_i16()
+        deserialize_i32()
+        deserialize_i64()
+        deserialize_i128()
+        deserialize_u8()
+        deserialize_u16()
+        deserialize_u32()
+        deserialize_u64()
+        deserialize_u128()
+        deserialize_f32()
+        deserialize_f64()
+        deserialize_char()
+        deserialize_bytes()
+        deserialize_byte_buf()
+        deserialize_option()
+        deserialize_unit()
+        deserialize_unit_struct(name: &'static str)
+        deserialize_newtype_struct(name: &'static str)
+        deserialize_seq()
+        deserialize_tuple(len: usize)
+        deserialize_tuple_struct(name: &'static str, len: usize)
+        deserialize_map()
+        deserialize_struct(name: &'static str, fields: &'static [&'static str])
+        deserialize_enum(name: &'static str, variants: &'static [&'static str])
+        deserialize_ignored_any()
+    }
+}
+
+struct KeyVisitor<'k, V> {
+    inner: V,
+    name: &'k mut Option<String>,
+}
+
+impl<'de, V> Visitor<'de> for KeyVisitor<'_, V>
+where
+    V: Visitor<'de>,
+{
+    type Value = V::Value;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.inner.expecting(f)
+    }
+
+    fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+    where
+        E: serde::de::Error,
+    {
+        *self.name = Some(v.to_string());
+        self.inner.visit_str(v)
+    }
+
+    fn visit_borrowed_str<E>(self, v: &'de str) -> Result<Self::Value, E>
+    where
+        E: serde::de::Error,
+    {
+        *self.name = Some(v.to_string());
+        self.inner.visit_borrowed_str(v)
+    }
+
+    fn visit_string<E>(self, v: String) -> Result<Self::Value, E>
+    where
+        E: serde::de::Error,
+    {
+        *self.name = Some(v.clone());
+        self.inner.visit_string(v)
+    }
+
+    fn visit_u64<E>(self, v: u64) -> Result<Self::Value, E>
+    where
+        E: serde::de::Error,
+    {
+        self.inner.visit_u64(v)
+    }
+
+    fn visit_bytes<E>(self, v: &[u8]) -> Result<Self::Value, E>
+    where
+        E: serde::de::Error,
+    {
+        self.inner.visit_bytes(v)
+    }
+}
+
+/// Deserializes the value of a field in the patch, merging it with the value
+/// of the same field in the base if there is one.
+struct ValueSeed<'b, S> {
+    inner: S,
+    base: Option<&'b Value>,
+}
+
+impl<'de, S> DeserializeSeed<'de> for ValueSeed<'_, S>
+where
+    S: DeserializeSeed<'de>,
+{
+    type Value = S::Value;
+
+    fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        match self.base {
+            Some(base) => self.inner.deserialize(Merge {
+                patch: deserializer,
+                base,
+            }),
+            None => self.inner.deserialize(deserializer),
+        }
+    }
+}
@@ -0,0 +1,1168 @@
+use alloc::collections::BTreeMap;
+use alloc::string::String;
+use alloc::sync::Arc;
+use core::fmt;
+use core::sync::atomic::AtomicBool;
+#[cfg(feature = "std")]
+use core::time::Duration;
+
+use crate::{Dialect, Error, Warning};
+
+/// Options that control how a [`Deserializer`] parses its input.
+///
+/// The default options only accept the format emitted by the debug helpers in
+/// [`std::fmt`]. Individual options can be enabled to make the deserializer
+/// more lenient when the input doesn't quite match the types it is being
+/// deserialized into.
+///
+/// Cloning an `Options` is cheap so the same set of options can be shared
+/// between many deserializers.
+///
+/// # Example
+/// ```
+/// use serde::Deserialize;
+/// use serde_dbgfmt::Options;
+///
+/// #[derive(Debug, Deserialize, PartialEq)]
+/// enum Status {
+///     Running,
+///     Stopped,
+/// }
+///
+/// let options = Options::new().alias_variant("Halted", "Stopped");
+/// let status: Status = serde_dbgfmt::from_str_with("Halted", &options).unwrap();
+///
+/// assert_eq!(status, Status::Stopped);
+/// ```
+///
+/// [`Deserializer`]: crate::Deserializer
+#[derive(Clone, Debug, Default)]
+pub struct Options(Arc<Config>);
+
+/// How to handle text that is not a valid token.
+///
+/// This is returned by the callback installed with
+/// [`Options::on_unknown_token`].
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum UnknownToken {
+    /// Return an error, as if there were no callback.
+    Error,
+
+    /// Skip over the given number of bytes as if they were whitespace.
+    Skip(usize),
+
+    /// Treat the given number of bytes as a string. The text is used as-is,
+    /// without unescaping it.
+    String(usize),
+}
+
+/// The actual option values, as read by the deserializer.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Config {
+    pub(crate) variant_aliases: BTreeMap<String, String>,
+    pub(crate) coerce_numbers: bool,
+    pub(crate) coerce_bools: bool,
+    pub(crate) exact_floats: bool,
+    pub(crate) replace_lone_surrogates: bool,
+    #[cfg(feature = "unicode_normalization")]
+    pub(crate) normalize_identifiers: bool,
+    pub(crate) digit_grouping: bool,
+    pub(crate) stringify_nested: bool,
+    pub(crate) on_warning: Option<Callback<WarningFn>>,
+    pub(crate) on_progress: Option<Callback<ProgressFn>>,
+    pub(crate) on_unknown_token: Option<Callback<UnknownTokenFn>>,
+    pub(crate) cancellation_token: Option<Arc<AtomicBool>>,
+    #[cfg(feature = "std")]
+    pub(crate) time_budget: Option<Duration>,
+    pub(crate) error_span_limit: Option<Option<usize>>,
+    pub(crate) collect_stats: bool,
+    pub(crate) dialect: Dialect,
+    pub(crate) format_version: FormatVersion,
+    pub(crate) duplicate_keys: DuplicateKeys,
+    pub(crate) addresses: Addresses,
+    pub(crate) unwrap_std_wrappers: bool,
+    pub(crate) unwrap_some: bool,
+    pub(crate) none_as_missing: bool,
+    pub(crate) strict_field_order: bool,
+    pub(crate) collect_unknown_fields: bool,
+    pub(crate) require_borrowed: bool,
+    pub(crate) max_depth: Option<usize>,
+    pub(crate) truncate_collections: Option<usize>,
+    pub(crate) allow_trailing_data: bool,
+    pub(crate) ignore_struct_names: bool,
+    pub(crate) ignore_type_paths: bool,
+}
+
+/// What to do when a map contains the same key more than once.
+///
+/// See [`Options::duplicate_keys`].
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum DuplicateKeys {
+    /// Pass every entry through to the type being deserialized. For a
+    /// `HashMap` or `BTreeMap` this means the last entry wins.
+    #[default]
+    Allow,
+
+    /// Return an error that includes the offsets of both keys.
+    Error,
+
+    /// Keep the first entry with each key and skip the rest.
+    KeepFirst,
+
+    /// Keep the last entry with each key and skip the ones before it.
+    KeepLast,
+}
+
+/// How to deserialize pointer addresses, such as `0x7f3a2c001230`.
+///
+/// See [`Options::addresses`].
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum Addresses {
+    /// Treat addresses as integers, so they can be deserialized as a `u64`
+    /// or `usize`.
+    #[default]
+    Integer,
+
+    /// Treat addresses as if they were `()`. An `Option` becomes `None` and
+    /// `deserialize_any` visits a unit. Integers still accept addresses.
+    Skip,
+
+    /// Return an error for which [`Error::is_address_not_allowed`] is true.
+    ///
+    /// [`Error::is_address_not_allowed`]: crate::Error::is_address_not_allowed
+    Error,
+}
+
+/// The version of the std `Debug` output that the input was captured from.
+///
+/// See [`Options::format_version`].
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum FormatVersion {
+    /// The output of current versions of std.
+    #[default]
+    Current,
+
+    /// The output of Rust 1.26 and earlier.
+    ///
+    /// `Duration` used a derived `Debug` impl until Rust 1.27, so it printed
+    /// as `Duration { secs: 1, nanos: 500000000 }` instead of `1.5s`. A
+    /// [`Value`](crate::Value) parsed from such a struct is the same as one
+    /// parsed from the newer form.
+    Rust1_26,
+}
+
+type WarningFn = dyn Fn(&Warning<'_>) + Send + Sync;
+type ProgressFn = dyn Fn(usize, usize) + Send + Sync;
+pub(crate) type UnknownTokenFn = dyn Fn(&str) -> UnknownToken + Send + Sync;
+
+/// A user-provided callback stored within the options.
+pub(crate) struct Callback<F: ?Sized>(pub(crate) Arc<F>);
+
+impl<F: ?Sized> Clone for Callback<F> {
+    fn clone(&self) -> Self {
+        Self(self.0.clone())
+    }
+}
+
+impl<F: ?Sized> fmt::Debug for Callback<F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("<callback>")
+    }
+}
+
+impl Options {
+    /// Create a new set of options with everything set to its default value.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Accept the enum variant `alias` in the input as if it were `variant`.
+    ///
+    /// This is useful when a variant has been renamed but you still need to
+    /// parse debug output that was captured before the rename. Unlike
+    /// `#[serde(alias = "..")]`, this only affects this crate and not any
+    /// other formats the enum is used with.
+    ///
+    /// The alias applies to variants of every enum that is deserialized.
+    #[must_use]
+    pub fn alias_variant(mut self, alias: impl Into<String>, variant: impl Into<String>) -> Self {
+        self.config_mut()
+            .variant_aliases
+            .insert(alias.into(), variant.into());
+        self
+    }
+
+    /// Allow numeric values to be deserialized into a numeric type other than
+    /// the one they were printed as.
+    ///
+    /// When enabled:
+    /// - Integer fields will accept float literals, as long as the literal has
+    ///   an integral value that fits within the integer type. This allows
+    ///   reading values printed as `1e6` or `42.0` into a `u32`.
+    /// - Float fields will accept integer literals.
+    ///
+    /// This is disabled by default.
+    #[must_use]
+    pub fn coerce_numbers(mut self, enabled: bool) -> Self {
+        self.config_mut().coerce_numbers = enabled;
+        self
+    }
+
+    /// Allow boolean values to be written as `yes`/`no` or `on`/`off`.
+    ///
+    /// Some configuration types have `Debug` impls that print these words
+    /// instead of `true` and `false`. When enabled, they are accepted in any
+    /// ASCII case wherever a `bool` is expected.
+    ///
+    /// This is disabled by default.
+    ///
+    /// # Example
+    /// ```
+    /// use serde_dbgfmt::Options;
+    ///
+    /// let options = Options::new().coerce_bools(true);
+    /// let flags: Vec<bool> = serde_dbgfmt::from_str_with("[yes, Off, true]", &options).unwrap();
+    ///
+    /// assert_eq!(flags, [true, false, true]);
+    /// ```
+    #[must_use]
+    pub fn coerce_bools(mut self, enabled: bool) -> Self {
+        self.config_mut().coerce_bools = enabled;
+        self
+    }
+
+    /// Replace `\u{..}` escapes of lone surrogates within strings and chars
+    /// with U+FFFD REPLACEMENT CHARACTER instead of returning an error.
+    ///
+    /// On Windows, the debug output of `OsStr` and `Path` escapes unpaired
+    /// surrogates this way (e.g. `"\u{d800}"`). These can't be represented in
+    /// a Rust `str` so they normally cause an error.
+    ///
+    /// This is disabled by default.
+    ///
+    /// # Example
+    /// ```
+    /// use serde_dbgfmt::Options;
+    ///
+    /// let text = r#""C:\\temp\\\u{d800}.txt""#;
+    /// assert!(serde_dbgfmt::from_str::<String>(text).is_err());
+    ///
+    /// let options = Options::new().replace_lone_surrogates(true);
+    /// let path: String = serde_dbgfmt::from_str_with(text, &options).unwrap();
+    /// assert_eq!(path, "C:\\temp\\\u{fffd}.txt");
+    /// ```
+    #[must_use]
+    pub fn replace_lone_surrogates(mut self, enabled: bool) -> Self {
+        self.config_mut().replace_lone_surrogates = enabled;
+        self
+    }
+
+    /// Normalize identifiers in the input to Unicode NFC before matching them
+    /// against struct names, field names, and enum variants.
+    ///
+    /// Identifiers captured from other systems occasionally use a different
+    /// normalization form than the names in the Rust source (e.g. `café`
+    /// written with a combining accent). The names in the Rust source are
+    /// expected to already be in NFC, which is what `rustc` requires.
+    ///
+    /// This is disabled by default.
+    ///
+    /// # Example
+    /// ```
+    /// use serde::Deserialize;
+    /// use serde_dbgfmt::Options;
+    ///
+    /// #[derive(Debug, Deserialize, PartialEq)]
+    /// struct Menu {
+    ///     café: u32,
+    /// }
+    ///
+    /// let text = "Menu { cafe\u{301}: 3 }";
+    /// assert!(serde_dbgfmt::from_str::<Menu>(text).is_err());
+    ///
+    /// let options = Options::new().normalize_identifiers(true);
+    /// let menu: Menu = serde_dbgfmt::from_str_with(text, &options).unwrap();
+    /// assert_eq!(menu, Menu { café: 3 });
+    /// ```
+    #[cfg(feature = "unicode_normalization")]
+    #[must_use]
+    pub fn normalize_identifiers(mut self, enabled: bool) -> Self {
+        self.config_mut().normalize_identifiers = enabled;
+        self
+    }
+
+    /// Reject decimal float literals that cannot be represented exactly by the
+    /// float type they are being deserialized into.
+    ///
+    /// Rust prints floats using the shortest decimal that round-trips, so a
+    /// literal printed from an `f32` is always accepted by an `f32` field. A
+    /// literal printed from an `f64` will usually be rejected by an `f32`
+    /// field since it has more precision than an `f32` can hold. This is
+    /// useful for validating that data survives a round-trip without loss.
+    ///
+    /// Integer literals accepted via [`coerce_numbers`](Self::coerce_numbers)
+    /// are checked as well. Hexadecimal float literals are not checked.
+    ///
+    /// This is disabled by default.
+    ///
+    /// # Example
+    /// ```
+    /// use serde_dbgfmt::Options;
+    ///
+    /// let options = Options::new().exact_floats(true);
+    ///
+    /// assert!(serde_dbgfmt::from_str_with::<f32>("0.1", &options).is_ok());
+    /// assert!(serde_dbgfmt::from_str_with::<f64>("0.30000000000000004", &options).is_ok());
+    /// assert!(serde_dbgfmt::from_str_with::<f32>("0.30000000000000004", &options).is_err());
+    /// ```
+    #[must_use]
+    pub fn exact_floats(mut self, enabled: bool) -> Self {
+        self.config_mut().exact_floats = enabled;
+        self
+    }
+
+    /// Accept numbers that use `,` as a thousands separator (e.g. `1,000,000`
+    /// or `1,234.5`).
+    ///
+    /// Since `,` also separates elements, a number is only treated as grouped
+    /// when it follows the digit-grouping pattern exactly: every group after
+    /// the first has exactly 3 digits and there is no whitespace within the
+    /// number. This means that `[1,000]` is a list with one element when this
+    /// option is enabled but `[1, 000]` still has two.
+    ///
+    /// This is disabled by default.
+    #[must_use]
+    pub fn digit_grouping(mut self, enabled: bool) -> Self {
+        self.config_mut().digit_grouping = enabled;
+        self
+    }
+
+    /// Allow string fields to capture the raw text of any value.
+    ///
+    /// When enabled, deserializing a string from something that is not a
+    /// string literal (e.g. a nested struct, a list, or a number) will produce
+    /// the text of that value exactly as it appears in the input instead of
+    /// returning an error. This allows a loosely-typed mirror struct to defer
+    /// interpreting parts of the input until later.
+    ///
+    /// String literals are still unescaped as usual.
+    ///
+    /// This is disabled by default.
+    ///
+    /// # Example
+    /// ```
+    /// use serde::Deserialize;
+    /// use serde_dbgfmt::Options;
+    ///
+    /// #[derive(Deserialize)]
+    /// struct Event {
+    ///     kind: String,
+    ///     payload: String,
+    /// }
+    ///
+    /// let options = Options::new().stringify_nested(true);
+    /// let text = r#"Event { kind: "login", payload: User { id: 5, roles: [Admin] } }"#;
+    /// let event: Event = serde_dbgfmt::from_str_with(text, &options).unwrap();
+    ///
+    /// assert_eq!(event.kind, "login");
+    /// assert_eq!(event.payload, "User { id: 5, roles: [Admin] }");
+    /// ```
+    #[must_use]
+    pub fn stringify_nested(mut self, enabled: bool) -> Self {
+        self.config_mut().stringify_nested = enabled;
+        self
+    }
+
+    /// Choose how maps containing duplicate keys are handled.
+    ///
+    /// Duplicate keys don't occur in the output of std collections but can
+    /// show up with buggy custom [`Debug`](std::fmt::Debug) impls or when
+    /// fragments of output are concatenated. Keys are compared by their text
+    /// in the input. A [`Warning::DuplicateKey`] is emitted for every entry
+    /// that is skipped.
+    ///
+    /// By default every entry is passed through as-is.
+    ///
+    /// # Example
+    /// ```
+    /// use std::collections::BTreeMap;
+    ///
+    /// use serde_dbgfmt::{DuplicateKeys, Options};
+    ///
+    /// let text = r#"{"a": 1, "b": 2, "a": 3}"#;
+    ///
+    /// let options = Options::new().duplicate_keys(DuplicateKeys::KeepFirst);
+    /// let map: BTreeMap<String, u32> = serde_dbgfmt::from_str_with(text, &options).unwrap();
+    /// assert_eq!(map["a"], 1);
+    ///
+    /// let options = Options::new().duplicate_keys(DuplicateKeys::Error);
+    /// let error = serde_dbgfmt::from_str_with::<BTreeMap<String, u32>>(text, &options).unwrap_err();
+    /// assert_eq!(
+    ///     error.to_string(),
+    ///     r#"duplicate map key `"a"` at offset 17, first seen at offset 1 at line 1 column 18"#
+    /// );
+    /// ```
+    #[must_use]
+    pub fn duplicate_keys(mut self, duplicate_keys: DuplicateKeys) -> Self {
+        self.config_mut().duplicate_keys = duplicate_keys;
+        self
+    }
+
+    /// Set how pointer addresses within the input are deserialized.
+    ///
+    /// Raw pointers and `{:p}` print addresses as hexadecimal integers, which
+    /// are lexed as [`TokenKind::Address`] tokens. By default they are
+    /// deserialized as integers. See [`Addresses`] for the alternatives.
+    ///
+    /// Addresses within values that are skipped, such as unknown struct
+    /// fields, are always accepted.
+    ///
+    /// # Example
+    /// ```
+    /// use serde::Deserialize;
+    /// use serde_dbgfmt::{Addresses, Options};
+    ///
+    /// #[derive(Debug, Deserialize)]
+    /// struct Handle {
+    ///     ptr: Option<usize>,
+    /// }
+    ///
+    /// let text = "Handle { ptr: 0x7f3a2c001230 }";
+    ///
+    /// let options = Options::new().addresses(Addresses::Skip);
+    /// let handle: Handle = serde_dbgfmt::from_str_with(text, &options).unwrap();
+    /// assert_eq!(handle.ptr, None);
+    ///
+    /// let options = Options::new().addresses(Addresses::Error);
+    /// let error = serde_dbgfmt::from_str_with::<Handle>(text, &options).unwrap_err();
+    /// assert_eq!(
+    ///     error.to_string(),
+    ///     "unexpected pointer address `0x7f3a2c001230` at line 1 column 15"
+    /// );
+    /// ```
+    ///
+    /// [`TokenKind::Address`]: crate::TokenKind::Address
+    #[must_use]
+    pub fn addresses(mut self, addresses: Addresses) -> Self {
+        self.config_mut().addresses = addresses;
+        self
+    }
+
+    /// Deserialize values wrapped in a `Cell`, `RefCell`, `Mutex`, or `RwLock`
+    /// as if they were not wrapped.
+    ///
+    /// The `Debug` impls of these types print the value within a struct, such
+    /// as `RefCell { value: 42 }` or `Mutex { data: 42, poisoned: false, .. }`.
+    /// With this enabled a field of type `u32` accepts either of those. `Rc`,
+    /// `Arc`, and `Box` print the value they hold directly, so they do not
+    /// need unwrapping.
+    ///
+    /// Wrappers are unwrapped around the top level value as well as wherever a
+    /// value is nested within another one, such as struct fields, sequence
+    /// elements, map values, and the contents of `Some`. A struct with the
+    /// same name as one of these wrappers, such as a `struct Cell { value: u8
+    /// }` of your own, is deserialized as-is. Values that are currently
+    /// borrowed or locked print a placeholder instead and cannot be
+    /// deserialized.
+    ///
+    /// This is disabled by default.
+    ///
+    /// # Example
+    /// ```
+    /// use std::cell::RefCell;
+    /// use std::sync::Mutex;
+    ///
+    /// use serde_dbgfmt::Options;
+    ///
+    /// #[derive(Debug)]
+    /// struct Counter {
+    ///     hits: RefCell<u32>,
+    ///     names: Mutex<Vec<&'static str>>,
+    /// }
+    ///
+    /// mod plain {
+    ///     #[derive(Debug, serde::Deserialize, PartialEq)]
+    ///     pub struct Counter {
+    ///         pub hits: u32,
+    ///         pub names: Vec<String>,
+    ///     }
+    /// }
+    ///
+    /// let counter = Counter {
+    ///     hits: RefCell::new(3),
+    ///     names: Mutex::new(vec!["a"]),
+    /// };
+    ///
+    /// let options = Options::new().unwrap_std_wrappers(true);
+    /// let plain: plain::Counter = serde_dbgfmt::from_dbg_with(&counter, &options).unwrap();
+    /// assert_eq!(plain, plain::Counter { hits: 3, names: vec!["a".into()] });
+    /// ```
+    #[must_use]
+    pub fn unwrap_std_wrappers(mut self, enabled: bool) -> Self {
+        self.config_mut().unwrap_std_wrappers = enabled;
+        self
+    }
+
+    /// Accept `Some(..)` around values that are not deserialized as an
+    /// `Option`.
+    ///
+    /// This allows deserializing into a type that drops the `Option` that the
+    /// original type had. Fields that are still an `Option` deserialize as
+    /// before. Use [`none_as_missing`](Self::none_as_missing) to accept
+    /// `None` for these fields as well.
+    ///
+    /// `Some` is unwrapped around the top level value as well as wherever a
+    /// value is nested within another one, such as struct fields, sequence
+    /// elements, and map values. It is not unwrapped for enums with a variant
+    /// named `Some`.
+    ///
+    /// This is disabled by default.
+    ///
+    /// # Example
+    /// ```
+    /// use serde::Deserialize;
+    /// use serde_dbgfmt::Options;
+    ///
+    /// #[derive(Debug, Deserialize, PartialEq)]
+    /// struct Config {
+    ///     port: u16,
+    ///     name: Option<String>,
+    /// }
+    ///
+    /// let text = r#"Config { port: Some(8080), name: Some("api") }"#;
+    /// assert!(serde_dbgfmt::from_str::<Config>(text).is_err());
+    ///
+    /// let options = Options::new().unwrap_some(true);
+    /// let config: Config = serde_dbgfmt::from_str_with(text, &options).unwrap();
+    /// assert_eq!(config, Config { port: 8080, name: Some("api".into()) });
+    /// ```
+    #[must_use]
+    pub fn unwrap_some(mut self, enabled: bool) -> Self {
+        self.config_mut().unwrap_some = enabled;
+        self
+    }
+
+    /// Treat struct fields set to `None` as if they were missing.
+    ///
+    /// This is the counterpart to [`unwrap_some`](Self::unwrap_some) for
+    /// fields that were `None` in the original type. Such a field uses the
+    /// `#[serde(default)]` value of the field, or fails with a missing field
+    /// error if it has none. A field that is still an `Option` is `None`
+    /// either way.
+    ///
+    /// The type of a field is not known when its name is read, so this
+    /// applies to every field whose value is exactly `None`. That includes
+    /// fields holding an enum with its own `None` variant, which then take
+    /// their default value instead of that variant. Leave this disabled when
+    /// deserializing such types.
+    ///
+    /// This is disabled by default.
+    ///
+    /// # Example
+    /// ```
+    /// use serde::Deserialize;
+    /// use serde_dbgfmt::Options;
+    ///
+    /// #[derive(Debug, Deserialize, PartialEq)]
+    /// struct Config {
+    ///     port: u16,
+    ///     #[serde(default)]
+    ///     retries: u32,
+    /// }
+    ///
+    /// let text = "Config { port: Some(8080), retries: None }";
+    /// let options = Options::new().unwrap_some(true);
+    /// assert!(serde_dbgfmt::from_str_with::<Config>(text, &options).is_err());
+    ///
+    /// let options = options.none_as_missing(true);
+    /// let config: Config = serde_dbgfmt::from_str_with(text, &options).unwrap();
+    /// assert_eq!(config, Config { port: 8080, retries: 0 });
+    /// ```
+    #[must_use]
+    pub fn none_as_missing(mut self, enabled: bool) -> Self {
+        self.config_mut().none_as_missing = enabled;
+        self
+    }
+
+    /// Require the fields of a struct to appear in the order in which they
+    /// are declared.
+    ///
+    /// Derived `Debug` impls always print fields in declaration order, so
+    /// this is useful to check that text round-trips exactly. A field that
+    /// appears before one declared ahead of it is rejected with an error
+    /// naming both fields. Fields may still be missing, and fields the struct
+    /// does not know about are not checked.
+    ///
+    /// This is disabled by default.
+    ///
+    /// # Example
+    /// ```
+    /// use serde::Deserialize;
+    /// use serde_dbgfmt::Options;
+    ///
+    /// #[derive(Debug, Deserialize)]
+    /// struct Point {
+    ///     x: i32,
+    ///     y: i32,
+    /// }
+    ///
+    /// let text = "Point { y: 2, x: 1 }";
+    /// assert!(serde_dbgfmt::from_str::<Point>(text).is_ok());
+    ///
+    /// let options = Options::new().strict_field_order(true);
+    /// let error = serde_dbgfmt::from_str_with::<Point>(text, &options).unwrap_err();
+    /// assert_eq!(
+    ///     error.to_string(),
+    ///     "field `x` is out of order, it should come before `y` at line 1 column 15"
+    /// );
+    /// ```
+    #[must_use]
+    pub fn strict_field_order(mut self, enabled: bool) -> Self {
+        self.config_mut().strict_field_order = enabled;
+        self
+    }
+
+    /// Keep the values of struct fields that the type being deserialized does
+    /// not know about.
+    ///
+    /// Normally such fields are skipped over and discarded. With this enabled
+    /// their values are parsed into a [`Value`] and can be retrieved using
+    /// [`Deserializer::unknown_fields`] once deserialization is done. Fields
+    /// nested within an unknown field are part of its value, and are not
+    /// collected separately.
+    ///
+    /// This is disabled by default.
+    ///
+    /// [`Value`]: crate::Value
+    /// [`Deserializer::unknown_fields`]: crate::Deserializer::unknown_fields
+    #[must_use]
+    pub fn collect_unknown_fields(mut self, enabled: bool) -> Self {
+        self.config_mut().collect_unknown_fields = enabled;
+        self
+    }
+
+    /// Return an error if a string cannot be borrowed from the input.
+    ///
+    /// Strings that contain escape sequences have to be unescaped into a new
+    /// buffer before they are passed to the `Deserialize` impl. With this
+    /// enabled they result in an error for which [`Error::is_not_borrowed`]
+    /// is true instead. This is useful for asserting that deserializing into
+    /// `&str` and `Cow<str>` fields never allocates.
+    ///
+    /// Whether a borrowed string is then copied is up to the type being
+    /// deserialized. `String` always copies, for example.
+    ///
+    /// This is disabled by default.
+    ///
+    /// # Example
+    /// ```
+    /// use serde_dbgfmt::Options;
+    ///
+    /// let options = Options::new().require_borrowed(true);
+    ///
+    /// let value: Vec<&str> = serde_dbgfmt::from_str_with(r#"["a", "b"]"#, &options).unwrap();
+    /// assert_eq!(value, ["a", "b"]);
+    ///
+    /// let error = serde_dbgfmt::from_str_with::<Vec<String>>(r#"["a", "b\n"]"#, &options)
+    ///     .unwrap_err();
+    /// assert!(error.is_not_borrowed());
+    /// ```
+    ///
+    /// [`Error::is_not_borrowed`]: crate::Error::is_not_borrowed
+    #[must_use]
+    pub fn require_borrowed(mut self, enabled: bool) -> Self {
+        self.config_mut().require_borrowed = enabled;
+        self
+    }
+
+    /// Set the syntax that the input is expected to follow.
+    ///
+    /// By default this is [`Dialect::Rust`]. Setting this to
+    /// [`Dialect::PythonRepr`] allows parsing the output of python's `repr()`
+    /// using the same types.
+    ///
+    /// # Example
+    /// ```
+    /// use serde::Deserialize;
+    /// use serde_dbgfmt::{Dialect, Options};
+    ///
+    /// #[derive(Debug, Deserialize, PartialEq)]
+    /// struct Record {
+    ///     key: bool,
+    ///     x: Option<u32>,
+    ///     items: Vec<u32>,
+    /// }
+    ///
+    /// let options = Options::new().dialect(Dialect::PythonRepr);
+    /// let text = "{'key': True, 'x': None, 'items': [1, 2]}";
+    /// let record: Record = serde_dbgfmt::from_str_with(text, &options).unwrap();
+    ///
+    /// assert_eq!(record, Record { key: true, x: None, items: vec![1, 2] });
+    /// ```
+    #[must_use]
+    pub fn dialect(mut self, dialect: Dialect) -> Self {
+        self.config_mut().dialect = dialect;
+        self
+    }
+
+    /// Set the version of std that the input was printed by.
+    ///
+    /// Most changes to the `Debug` output of std types are accepted no matter
+    /// which version is selected, such as strings that escape characters that
+    /// newer versions print as-is. This is only needed for the changes that
+    /// alter how a value is interpreted. See [`FormatVersion`] for what each
+    /// version changes.
+    ///
+    /// Typed fields, such as a `Duration`, accept the output of every version
+    /// regardless of this option.
+    ///
+    /// This is [`FormatVersion::Current`] by default.
+    ///
+    /// # Example
+    /// ```
+    /// use serde_dbgfmt::{FormatVersion, Options, Value};
+    ///
+    /// let old = "Timing { elapsed: Duration { secs: 1, nanos: 500000000 } }";
+    /// let new: Value = "Timing { elapsed: 1.5s }".parse().unwrap();
+    ///
+    /// assert_ne!(old.parse::<Value>().unwrap(), new);
+    ///
+    /// let options = Options::new().format_version(FormatVersion::Rust1_26);
+    /// let old: Value = serde_dbgfmt::from_str_with(old, &options).unwrap();
+    /// assert_eq!(old, new);
+    /// ```
+    #[must_use]
+    pub fn format_version(mut self, version: FormatVersion) -> Self {
+        self.config_mut().format_version = version;
+        self
+    }
+
+    /// Collect [`Stats`](crate::Stats) about the input while deserializing.
+    ///
+    /// The statistics can be retrieved using
+    /// [`Deserializer::stats`](crate::Deserializer::stats).
+    ///
+    /// This is disabled by default.
+    #[must_use]
+    pub fn collect_stats(mut self, enabled: bool) -> Self {
+        self.config_mut().collect_stats = enabled;
+        self
+    }
+
+    /// Accept any name for structs, tuple structs, and unit structs.
+    ///
+    /// By default the name in the input must match the name of the type
+    /// being deserialized into (or its `#[serde(rename = "..")]`). This is
+    /// inconvenient when the debug output was produced by types from another
+    /// crate that are named differently. Enum variant names still need to
+    /// match since they decide which variant is deserialized.
+    ///
+    /// # Example
+    /// ```
+    /// use serde::Deserialize;
+    /// use serde_dbgfmt::Options;
+    ///
+    /// #[derive(Debug, Deserialize, PartialEq)]
+    /// struct Point {
+    ///     x: i32,
+    ///     y: i32,
+    /// }
+    ///
+    /// let text = "Vec2 { x: 1, y: 2 }";
+    /// assert!(serde_dbgfmt::from_str::<Point>(text).is_err());
+    ///
+    /// let options = Options::new().ignore_struct_names(true);
+    /// let point: Point = serde_dbgfmt::from_str_with(text, &options).unwrap();
+    /// assert_eq!(point, Point { x: 1, y: 2 });
+    /// ```
+    #[must_use]
+    pub fn ignore_struct_names(mut self, enabled: bool) -> Self {
+        self.config_mut().ignore_struct_names = enabled;
+        self
+    }
+
+    /// Match names that are written as a path (e.g. `my_crate::model::User`)
+    /// using only their final segment.
+    ///
+    /// `Debug` impls that print `std::any::type_name` include the full path
+    /// of the type, but serde only knows the name of the type itself. This
+    /// applies to the names of structs and enum variants, as well as to
+    /// `Some` and `None`.
+    ///
+    /// This is disabled by default.
+    ///
+    /// # Example
+    /// ```
+    /// use serde::Deserialize;
+    /// use serde_dbgfmt::Options;
+    ///
+    /// #[derive(Debug, Deserialize, PartialEq)]
+    /// struct User {
+    ///     id: u32,
+    /// }
+    ///
+    /// let text = "my_crate::model::User { id: 7 }";
+    /// assert!(serde_dbgfmt::from_str::<User>(text).is_err());
+    ///
+    /// let options = Options::new().ignore_type_paths(true);
+    /// let user: User = serde_dbgfmt::from_str_with(text, &options).unwrap();
+    /// assert_eq!(user, User { id: 7 });
+    /// ```
+    #[must_use]
+    pub fn ignore_type_paths(mut self, enabled: bool) -> Self {
+        self.config_mut().ignore_type_paths = enabled;
+        self
+    }
+
+    /// Limit how deeply collections (structs, tuples, sequences, and maps)
+    /// may be nested within the input.
+    ///
+    /// Deserializing deeply nested input recurses once per level, so a small
+    /// input made up of nothing but opening brackets can overflow the stack.
+    /// Input nested more deeply than `limit` is rejected with an error for
+    /// which [`Error::is_depth_limit_exceeded`] is true. The depth is counted
+    /// in the same way as [`Stats::max_depth`].
+    ///
+    /// This also applies to the helpers that take options and work on the
+    /// text alone, such as [`canonicalize_with`] and [`summarize_with`].
+    ///
+    /// There is no limit by default when deserializing. The text helpers use a
+    /// limit of 128 when none is set.
+    ///
+    /// # Example
+    /// ```
+    /// use serde_dbgfmt::Options;
+    ///
+    /// let options = Options::new().max_depth(2);
+    /// assert!(serde_dbgfmt::from_str_with::<Vec<Vec<u8>>>("[[1], [2]]", &options).is_ok());
+    ///
+    /// let error = serde_dbgfmt::from_str_with::<Vec<Vec<Vec<u8>>>>("[[[1]]]", &options)
+    ///     .unwrap_err();
+    /// assert!(error.is_depth_limit_exceeded());
+    /// ```
+    ///
+    /// [`Error::is_depth_limit_exceeded`]: crate::Error::is_depth_limit_exceeded
+    /// [`Stats::max_depth`]: crate::Stats::max_depth
+    /// [`canonicalize_with`]: crate::canonicalize_with
+    /// [`summarize_with`]: crate::summarize_with
+    #[must_use]
+    pub fn max_depth(mut self, limit: usize) -> Self {
+        self.config_mut().max_depth = Some(limit);
+        self
+    }
+
+    /// Keep only the first `limit` elements of each sequence and set, and the
+    /// first `limit` entries of each map, when deserializing a
+    /// [`Value`](crate::Value).
+    ///
+    /// The rest of each collection is still parsed, so it must be valid, but
+    /// it is then dropped. Collections that were cut short are wrapped in
+    /// [`Value::Truncated`](crate::Value::Truncated) along with their full
+    /// length. Structs and tuples are always kept whole, as are values
+    /// deserialized into any other type.
+    ///
+    /// # Example
+    /// ```
+    /// use serde_dbgfmt::{Options, Value};
+    ///
+    /// let options = Options::new().truncate_collections(2);
+    /// let value: Value = serde_dbgfmt::from_str_with("Log { lines: [1, 2, 3, 4] }", &options)
+    ///     .unwrap();
+    ///
+    /// let lines = value.field("lines").unwrap();
+    /// assert_eq!(lines.elements(), Some(&[Value::Int(1), Value::Int(2)][..]));
+    /// assert!(matches!(lines, Value::Truncated { len: 4, .. }));
+    /// ```
+    #[must_use]
+    pub fn truncate_collections(mut self, limit: usize) -> Self {
+        self.config_mut().truncate_collections = Some(limit);
+        self
+    }
+
+    /// Ignore any input that follows the value.
+    ///
+    /// Normally [`Deserializer::end`] and [`from_str_with`] return an error if
+    /// there is anything other than whitespace after the value. This is
+    /// useful when the debug output is followed by other text, such as the
+    /// rest of a log line.
+    ///
+    /// # Example
+    /// ```
+    /// use serde_dbgfmt::Options;
+    ///
+    /// let text = "[1, 2] (took 3ms)";
+    /// assert!(serde_dbgfmt::from_str::<Vec<u32>>(text).is_err());
+    ///
+    /// let options = Options::new().allow_trailing_data(true);
+    /// let values: Vec<u32> = serde_dbgfmt::from_str_with(text, &options).unwrap();
+    /// assert_eq!(values, [1, 2]);
+    /// ```
+    ///
+    /// [`Deserializer::end`]: crate::Deserializer::end
+    /// [`from_str_with`]: crate::from_str_with
+    #[must_use]
+    pub fn allow_trailing_data(mut self, enabled: bool) -> Self {
+        self.config_mut().allow_trailing_data = enabled;
+        self
+    }
+
+    /// Install a callback that is invoked whenever the deserializer emits a
+    /// [`Warning`].
+    ///
+    /// Warnings are emitted for input that was accepted but had to be changed
+    /// or discarded along the way: unknown struct fields, floats that
+    /// saturated to infinity, numbers converted by
+    /// [`coerce_numbers`](Self::coerce_numbers), and so on. This allows the
+    /// lenient modes to remain observable instead of silently changing data.
+    ///
+    /// # Example
+    /// ```
+    /// use std::sync::{Arc, Mutex};
+    ///
+    /// use serde::Deserialize;
+    /// use serde_dbgfmt::Options;
+    ///
+    /// #[derive(Deserialize)]
+    /// struct Config {
+    ///     name: String,
+    /// }
+    ///
+    /// let warnings = Arc::new(Mutex::new(Vec::new()));
+    /// let options = Options::new().on_warning({
+    ///     let warnings = warnings.clone();
+    ///     move |warning| warnings.lock().unwrap().push(warning.to_string())
+    /// });
+    ///
+    /// let text = r#"Config { name: "test", secret: 5 }"#;
+    /// let _: Config = serde_dbgfmt::from_str_with(text, &options).unwrap();
+    ///
+    /// assert_eq!(*warnings.lock().unwrap(), ["ignored unknown field `secret`"]);
+    /// ```
+    #[must_use]
+    pub fn on_warning<F>(mut self, callback: F) -> Self
+    where
+        F: Fn(&Warning<'_>) + Send + Sync + 'static,
+    {
+        self.config_mut().on_warning = Some(Callback(Arc::new(callback)));
+        self
+    }
+
+    /// Install a callback that decides what to do with text that is not a
+    /// valid token.
+    ///
+    /// The callback is passed the rest of the input, starting at the first
+    /// character that could not be split into a token, and chooses how many
+    /// bytes to consume and how to treat them. This allows records containing
+    /// syntax from exotic custom [`Debug`](std::fmt::Debug) impls, or from
+    /// future versions of std, to still be read. The number of bytes must be
+    /// non-zero and end on a character boundary, otherwise an error is
+    /// returned.
+    ///
+    /// The callback also applies when deserializing a [`Value`](crate::Value)
+    /// and in the text helpers that take options, such as
+    /// [`canonicalize_with`](crate::canonicalize_with) and
+    /// [`flatten_with`](crate::flatten_with).
+    ///
+    /// # Example
+    /// ```
+    /// use serde::Deserialize;
+    /// use serde_dbgfmt::{Options, UnknownToken};
+    ///
+    /// #[derive(Debug, Deserialize)]
+    /// struct Task {
+    ///     id: u32,
+    ///     state: String,
+    /// }
+    ///
+    /// let text = "Task { id: 3, state: <running> }";
+    /// assert!(serde_dbgfmt::from_str::<Task>(text).is_err());
+    ///
+    /// // Treat anything within `<>` as a string.
+    /// let options = Options::new().on_unknown_token(|text| match text.find('>') {
+    ///     Some(end) if text.starts_with('<') => UnknownToken::String(end + 1),
+    ///     _ => UnknownToken::Error,
+    /// });
+    /// let task: Task = serde_dbgfmt::from_str_with(text, &options).unwrap();
+    /// assert_eq!(task.state, "<running>");
+    /// ```
+    #[must_use]
+    pub fn on_unknown_token<F>(mut self, callback: F) -> Self
+    where
+        F: Fn(&str) -> UnknownToken + Send + Sync + 'static,
+    {
+        self.config_mut().on_unknown_token = Some(Callback(Arc::new(callback)));
+        self
+    }
+
+    /// Install a callback that is periodically invoked with the number of
+    /// bytes of input consumed so far and the total length of the input.
+    ///
+    /// The callback is invoked roughly every 64 KiB of input, and once more
+    /// with both arguments equal when the deserializer reaches the end of the
+    /// input. This makes it possible for front-ends to show a progress bar
+    /// while parsing very large dumps.
+    ///
+    /// # Example
+    /// ```
+    /// use std::sync::{Arc, Mutex};
+    ///
+    /// use serde_dbgfmt::Options;
+    ///
+    /// let reports = Arc::new(Mutex::new(Vec::new()));
+    /// let options = Options::new().on_progress({
+    ///     let reports = reports.clone();
+    ///     move |consumed, total| reports.lock().unwrap().push((consumed, total))
+    /// });
+    ///
+    /// let _: Vec<u32> = serde_dbgfmt::from_str_with("[1, 2, 3]", &options).unwrap();
+    ///
+    /// assert_eq!(*reports.lock().unwrap(), [(9, 9)]);
+    /// ```
+    #[must_use]
+    pub fn on_progress<F>(mut self, callback: F) -> Self
+    where
+        F: Fn(usize, usize) + Send + Sync + 'static,
+    {
+        self.config_mut().on_progress = Some(Callback(Arc::new(callback)));
+        self
+    }
+
+    /// Abort deserialization once `token` is set to `true`.
+    ///
+    /// The token is checked by the lexer before the first token and then
+    /// every few kilobytes of input, including input that is only skipped
+    /// over. Once it has been set the deserializer stops and returns an
+    /// error for which [`Error::is_cancelled`] is true. This allows a UI or
+    /// server to abort parsing a huge or hostile input from another thread.
+    ///
+    /// # Example
+    /// ```
+    /// use std::sync::atomic::{AtomicBool, Ordering};
+    /// use std::sync::Arc;
+    ///
+    /// use serde_dbgfmt::Options;
+    ///
+    /// let token = Arc::new(AtomicBool::new(false));
+    /// let options = Options::new().cancellation_token(token.clone());
+    ///
+    /// token.store(true, Ordering::Relaxed);
+    /// let error = serde_dbgfmt::from_str_with::<Vec<u32>>("[1, 2, 3]", &options).unwrap_err();
+    ///
+    /// assert!(error.is_cancelled());
+    /// ```
+    ///
+    /// [`Error::is_cancelled`]: crate::Error::is_cancelled
+    #[must_use]
+    pub fn cancellation_token(mut self, token: Arc<AtomicBool>) -> Self {
+        self.config_mut().cancellation_token = Some(token);
+        self
+    }
+
+    /// Abort deserialization if it takes longer than `budget`.
+    ///
+    /// The budget starts counting when the [`Deserializer`] is created, or
+    /// when a lookup using [`get_with`](crate::get_with) starts, and is
+    /// checked at the same points as the
+    /// [`cancellation_token`](Self::cancellation_token). Once it has run out
+    /// the deserializer returns an error for which [`Error::is_timed_out`] is
+    /// true. This protects request handlers that parse user-supplied input.
+    ///
+    /// # Example
+    /// ```
+    /// use std::time::Duration;
+    ///
+    /// use serde_dbgfmt::Options;
+    ///
+    /// let options = Options::new().time_budget(Duration::from_secs(1));
+    /// let values: Vec<u32> = serde_dbgfmt::from_str_with("[1, 2, 3]", &options).unwrap();
+    ///
+    /// assert_eq!(values, [1, 2, 3]);
+    /// ```
+    ///
+    /// [`Deserializer`]: crate::Deserializer
+    /// [`Error::is_timed_out`]: crate::Error::is_timed_out
+    #[cfg(feature = "std")]
+    #[must_use]
+    pub fn time_budget(mut self, budget: Duration) -> Self {
+        self.config_mut().time_budget = Some(budget);
+        self
+    }
+
+    /// Set the maximum number of characters of the input that are included
+    /// in error messages.
+    ///
+    /// Errors quote the part of the input that caused them. When that part
+    /// is a megabyte-long string or number this can make logs explode, so
+    /// longer spans are cut off and end with `…`. The full text is still
+    /// available through [`Error::span`]. Passing `None` removes the limit.
+    ///
+    /// The default limit is 100 characters. This applies to the errors
+    /// returned by [`from_str_with`] and [`OwnedDeserializer`]. Errors
+    /// returned from a [`Deserializer`] can be changed using
+    /// [`Error::set_span_limit`].
+    ///
+    /// # Example
+    /// ```
+    /// use serde_dbgfmt::Options;
+    ///
+    /// let text = "9".repeat(1000);
+    /// let options = Options::new().error_span_limit(Some(3));
+    /// let error = serde_dbgfmt::from_str_with::<u8>(&text, &options).unwrap_err();
+    ///
+    /// assert_eq!(
+    ///     error.to_string(),
+    ///     "invalid integer literal `999…`: number too large to fit in target type at line 1 column 1"
+    /// );
+    /// assert_eq!(error.span(), Some(text.as_str()));
+    /// ```
+    ///
+    /// [`Deserializer`]: crate::Deserializer
+    /// [`Error::span`]: crate::Error::span
+    /// [`Error::set_span_limit`]: crate::Error::set_span_limit
+    /// [`from_str_with`]: crate::from_str_with
+    /// [`OwnedDeserializer`]: crate::OwnedDeserializer
+    #[must_use]
+    pub fn error_span_limit(mut self, limit: Option<usize>) -> Self {
+        self.config_mut().error_span_limit = Some(limit);
+        self
+    }
+
+    fn config_mut(&mut self) -> &mut Config {
+        Arc::make_mut(&mut self.0)
+    }
+
+    pub(crate) fn config(&self) -> &Config {
+        &self.0
+    }
+
+    /// These options without the progress callback, for deserializing part
+    /// of an input whose progress is reported as a whole.
+    #[cfg(feature = "std")]
+    pub(crate) fn without_progress(&self) -> Self {
+        let mut options = self.clone();
+        if options.config().on_progress.is_some() {
+            options.config_mut().on_progress = None;
+        }
+
+        options
+    }
+}
+
+impl Config {
+    /// Apply the options that affect how errors are displayed.
+    pub(crate) fn adjust_error(&self, mut error: Error) -> Error {
+        if let Some(limit) = self.error_span_limit {
+            error.set_span_limit(limit);
+        }
+
+        error
+    }
+
+    pub(crate) fn variant_alias(&self, alias: &str) -> Option<&str> {
+        self.variant_aliases.get(alias).map(String::as_str)
+    }
+}
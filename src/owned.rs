@@ -0,0 +1,102 @@
+use alloc::string::String;
+
+use serde::de::DeserializeOwned;
+
+use crate::de::Nested;
+use crate::lex::Lexer;
+use crate::{Deserializer, Error, Options};
+
+/// A deserializer which owns its input.
+///
+/// [`Deserializer`] borrows the text it is parsing, which makes it awkward to
+/// store or to send to another thread when the text was produced on the fly.
+/// `OwnedDeserializer` instead takes ownership of the input and only produces
+/// values which do not borrow from it.
+///
+/// # Example
+/// ```
+/// use serde_dbgfmt::OwnedDeserializer;
+///
+/// let text = format!("{:?} {:?}", vec![1, 2], Some("three"));
+/// let mut de = OwnedDeserializer::new(text);
+///
+/// let handle = std::thread::spawn(move || {
+///     let list: Vec<u32> = de.deserialize()?;
+///     let item: Option<String> = de.deserialize()?;
+///     de.end()?;
+///
+///     Ok::<_, serde_dbgfmt::Error>((list, item))
+/// });
+///
+/// let (list, item) = handle.join().unwrap().unwrap();
+/// assert_eq!(list, [1, 2]);
+/// assert_eq!(item.as_deref(), Some("three"));
+/// ```
+#[derive(Clone, Debug)]
+pub struct OwnedDeserializer {
+    input: String,
+    offset: usize,
+    options: Options,
+}
+
+impl OwnedDeserializer {
+    /// Create a deserializer which takes ownership of `input`.
+    ///
+    /// This accepts anything that can be converted into a `String`, including
+    /// a `Cow<str>`.
+    pub fn new(input: impl Into<String>) -> Self {
+        Self::with_options(input, Options::default())
+    }
+
+    /// Create a deserializer which takes ownership of `input` and uses the
+    /// provided options.
+    pub fn with_options(input: impl Into<String>, options: Options) -> Self {
+        Self {
+            input: input.into(),
+            offset: 0,
+            options,
+        }
+    }
+
+    /// The input which has not been deserialized yet.
+    pub fn remaining(&self) -> &str {
+        &self.input[self.offset..]
+    }
+
+    /// Deserialize the next value from the input.
+    ///
+    /// This can be called multiple times to parse a stream of values. The
+    /// input is not advanced if an error occurs.
+    pub fn deserialize<T>(&mut self) -> Result<T, Error>
+    where
+        T: DeserializeOwned,
+    {
+        let mut de = self.deserializer();
+        let value = T::deserialize(Nested(&mut de))
+            .map_err(|e| self.options.config().adjust_error(de.locate(e)))?;
+
+        self.offset = self.input.len() - de.remaining().len();
+        Ok(value)
+    }
+
+    /// Validate that the rest of the input is empty or only whitespace.
+    ///
+    /// See [`Deserializer::end`].
+    pub fn end(&mut self) -> Result<(), Error> {
+        self.deserializer()
+            .end()
+            .map_err(|e| self.options.config().adjust_error(e))
+    }
+
+    /// A deserializer for the remaining input which reports error locations
+    /// relative to the start of the whole input.
+    fn deserializer(&self) -> Deserializer<'_> {
+        let lexer = Lexer::new(self.remaining());
+        Deserializer::with_lexer(&self.input, lexer, self.options.clone())
+    }
+
+    /// Take back the input string.
+    pub fn into_inner(self) -> String {
+        self.input
+    }
+}
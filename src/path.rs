@@ -0,0 +1,193 @@
+use alloc::vec::Vec;
+
+use serde::de::Error as _;
+use serde::Deserialize;
+
+use crate::de::Nested;
+use crate::lex::{Checks, Lexer, TokenKind};
+use crate::util::unescape;
+use crate::{Deserializer, Error, MapReader, Options};
+
+/// Deserialize only the value at `path` within `input`.
+///
+/// The path is made up of field names separated by `.` and indices within
+/// `[]`, e.g. `response.items[3].id`. Field names match struct fields as well
+/// as map keys that are strings. Indices select an element of a list, tuple,
+/// or tuple struct. The fields of a tuple struct can also be selected using a
+/// numeric field name as in `point.0`.
+///
+/// Values which are not on the path are skipped over without being parsed
+/// beyond what is needed to find where they end. This makes it much cheaper
+/// than deserializing the whole input just to read a single field out of it.
+///
+/// # Example
+/// ```
+/// let text = r#"Response { status: 200, items: [Item { id: 1 }, Item { id: 2 }] }"#;
+///
+/// let id: u32 = serde_dbgfmt::get(text, "items[1].id").unwrap();
+/// assert_eq!(id, 2);
+/// ```
+pub fn get<'de, T>(input: &'de str, path: &str) -> Result<T, Error>
+where
+    T: Deserialize<'de>,
+{
+    get_with(input, path, &Options::default())
+}
+
+/// Deserialize only the value at `path` within `input` using the provided
+/// options.
+///
+/// The options also apply while searching for the value, so a
+/// [`cancellation_token`](Options::cancellation_token) or
+/// [`time_budget`](Options::time_budget) bounds the whole lookup and not just
+/// the final value.
+///
+/// # Example
+/// ```
+/// use serde_dbgfmt::{Options, UnknownToken};
+///
+/// let text = r#"Task { handle: <opaque>, state: "running" }"#;
+/// let options = Options::new().on_unknown_token(|text| match text.find('>') {
+///     Some(end) if text.starts_with('<') => UnknownToken::String(end + 1),
+///     _ => UnknownToken::Error,
+/// });
+///
+/// assert!(serde_dbgfmt::get::<String>(text, "state").is_err());
+/// let state: String = serde_dbgfmt::get_with(text, "state", &options).unwrap();
+/// assert_eq!(state, "running");
+/// ```
+pub fn get_with<'de, T>(input: &'de str, path: &str, options: &Options) -> Result<T, Error>
+where
+    T: Deserialize<'de>,
+{
+    let checks = Checks::new(input, options);
+    let lexer = |value| {
+        let mut lexer = Lexer::new(value);
+        lexer.set_unknown_handler(options.config().on_unknown_token.clone());
+        lexer.set_checks(checks.clone());
+        lexer
+    };
+
+    let mut value = input;
+    for segment in parse_path(path)? {
+        value = match segment {
+            Segment::Field(name) => match name.parse() {
+                Ok(index) if !is_braced(lexer(value))? => element(lexer(value), index)?,
+                _ => field(lexer(value), name)?,
+            },
+            Segment::Index(index) => element(lexer(value), index)?,
+        };
+    }
+
+    let mut de = Deserializer::with_lexer(value, lexer(value), options.clone());
+    let result = T::deserialize(Nested(&mut de))
+        .map_err(|e| de.locate(e))
+        .and_then(|value| de.end().map(|()| value));
+    result.map_err(|e| options.config().adjust_error(e))
+}
+
+#[derive(Copy, Clone, Debug)]
+enum Segment<'p> {
+    Field(&'p str),
+    Index(usize),
+}
+
+fn parse_path(path: &str) -> Result<Vec<Segment<'_>>, Error> {
+    let invalid = || Error::custom(format_args!("invalid path `{path}`"));
+
+    let mut segments = Vec::new();
+    let mut rest = path;
+    let mut first = true;
+
+    while !rest.is_empty() {
+        if let Some(tail) = rest.strip_prefix('[') {
+            let (index, tail) = tail.split_once(']').ok_or_else(invalid)?;
+            segments.push(Segment::Index(index.parse().map_err(|_| invalid())?));
+            rest = tail;
+        } else {
+            let tail = match first {
+                true => rest,
+                false => rest.strip_prefix('.').ok_or_else(invalid)?,
+            };
+            let end = tail.find(['.', '[']).unwrap_or(tail.len());
+            if end == 0 {
+                return Err(invalid());
+            }
+
+            segments.push(Segment::Field(&tail[..end]));
+            rest = &tail[end..];
+        }
+
+        first = false;
+    }
+
+    Ok(segments)
+}
+
+/// Whether `value` is a struct or map, as opposed to a tuple-like value.
+fn is_braced(mut lexer: Lexer<'_>) -> Result<bool, Error> {
+    let mut token = lexer.parse_token()?;
+    if token.kind == TokenKind::Ident {
+        token = lexer.parse_token()?;
+    }
+
+    Ok(token.is_punct("{"))
+}
+
+fn field<'de>(lexer: Lexer<'de>, name: &str) -> Result<&'de str, Error> {
+    for entry in MapReader::with_lexer(lexer)? {
+        let (key, value) = entry?;
+        let matches = match key.strip_prefix('"').and_then(|key| key.strip_suffix('"')) {
+            Some(key) => unescape(key)? == name,
+            None => key == name,
+        };
+
+        if matches {
+            return Ok(value);
+        }
+    }
+
+    Err(Error::custom(format_args!(
+        "no field or key named `{name}`"
+    )))
+}
+
+fn element(mut lexer: Lexer<'_>, index: usize) -> Result<&str, Error> {
+    let mut token = lexer.parse_token()?;
+    if token.kind == TokenKind::Ident {
+        token = lexer.parse_token()?;
+    }
+
+    let close = match token.value {
+        "[" if token.kind == TokenKind::Punct => "]",
+        "(" if token.kind == TokenKind::Punct => ")",
+        _ => return Err(Error::unexpected_token(token, "a list or tuple")),
+    };
+
+    let out_of_bounds = |len: usize| {
+        Error::custom(format_args!(
+            "index {index} is out of bounds for a value with {len} elements"
+        ))
+    };
+
+    let mut current = 0;
+    loop {
+        if lexer.peek_token()?.is_punct(close) {
+            return Err(out_of_bounds(current));
+        }
+
+        let element = lexer.skip_value()?;
+        if current == index {
+            return Ok(element);
+        }
+        current += 1;
+
+        let token = lexer.parse_token()?;
+        if token.is_punct(close) {
+            return Err(out_of_bounds(current));
+        }
+        if !token.is_punct(",") {
+            return Err(Error::unexpected_token(token, ','));
+        }
+    }
+}
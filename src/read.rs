@@ -0,0 +1,809 @@
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::marker::PhantomData;
+use core::ops::Range;
+use core::str;
+use std::io;
+use std::time::Instant;
+
+use serde::de::{
+    DeserializeOwned, DeserializeSeed, EnumAccess, MapAccess, SeqAccess, VariantAccess, Visitor,
+};
+
+use crate::de::Nested;
+use crate::error::LexerError;
+use crate::lex::{Lexer, Token, TokenKind};
+use crate::{Deserializer, Error, Location, Options};
+
+/// The smallest amount of input to ask the reader for at a time.
+const READ_SIZE: usize = 8 * 1024;
+
+/// How far past the end of a token the lexer may look to decide where the
+/// token ends, such as to tell `1.5` apart from the range `1..5` or to find a
+/// duration unit.
+const LOOKAHEAD: usize = 8;
+
+/// Deserialize a `T` from `reader`, reading the input a piece at a time.
+///
+/// A list at the top level is deserialized one element at a time as it is
+/// read, so only the element being deserialized is held in memory. Any other
+/// value is read in full before it is deserialized.
+pub(crate) fn from_reader<R, T>(reader: R, options: &Options) -> Result<T, Error>
+where
+    R: io::Read,
+    T: DeserializeOwned,
+{
+    let mut input = Input::new(reader, options);
+    T::deserialize(ReaderDeserializer(&mut input)).map_err(|e| options.config().adjust_error(e))
+}
+
+/// Input that is read from an [`io::Read`] as it is needed.
+struct Input<R> {
+    reader: R,
+    options: Options,
+
+    /// The options for deserializing each element of a list. Progress is
+    /// reported for the input as a whole instead.
+    part: Options,
+    deadline: Option<Instant>,
+
+    /// The input that has been read so far, less any that has been dropped
+    /// once it was consumed.
+    buf: String,
+    /// The amount of `buf` that has been consumed.
+    pos: usize,
+    /// The location of the start of `buf` within the whole input.
+    start: Location,
+
+    /// Bytes at the end of the last read that do not make up a whole
+    /// character yet.
+    partial: Vec<u8>,
+    eof: bool,
+}
+
+/// The progress made scanning over a value that has only been partly read.
+struct Scan {
+    /// Where the value starts within the rest of the input.
+    start: Option<usize>,
+    /// The end of the last token that was scanned.
+    end: usize,
+    depth: usize,
+}
+
+impl<R: io::Read> Input<R> {
+    fn new(reader: R, options: &Options) -> Self {
+        let deadline = options
+            .config()
+            .time_budget
+            .and_then(|budget| Instant::now().checked_add(budget));
+
+        Self {
+            reader,
+            options: options.clone(),
+            part: options.without_progress(),
+            deadline,
+            buf: String::new(),
+            pos: 0,
+            start: Location::new("", 0),
+            partial: Vec::new(),
+            eof: false,
+        }
+    }
+
+    /// The input that has been read but not consumed yet.
+    fn rest(&self) -> &str {
+        &self.buf[self.pos..]
+    }
+
+    fn lexer<'a>(&self, input: &'a str) -> Lexer<'a> {
+        let mut lexer = Lexer::new(input);
+        lexer.set_unknown_handler(self.options.config().on_unknown_token.clone());
+        lexer
+    }
+
+    /// The location of byte `offset` of `buf` within the whole input.
+    fn location(&self, offset: usize) -> Location {
+        Location::new(&self.buf, offset).within(self.start)
+    }
+
+    /// Locate an error at the start of the rest of the input, or at the token
+    /// it is about, the same as [`Deserializer::locate`].
+    fn error(&self, error: Error) -> Error {
+        let mut offset = self.pos;
+        if let Some(span) = error.span() {
+            let rest = self.rest().trim_start();
+            if rest.starts_with(span) {
+                offset = self.buf.len() - rest.len();
+            }
+        }
+
+        error.locate(&self.buf, offset).relocate(self.start)
+    }
+
+    /// The offset within `buf` of the end of `token`, which must have come
+    /// from [`token`](Self::token).
+    fn end_of(&self, token: Token) -> usize {
+        token.value.as_ptr() as usize + token.value.len() - self.buf.as_ptr() as usize
+    }
+
+    /// Read some more of the input, returning false once the end of it has
+    /// been reached.
+    fn fill(&mut self) -> Result<bool, Error> {
+        if self.eof {
+            return Ok(false);
+        }
+
+        // Only drop the consumed input once it makes up most of the buffer, so
+        // that each byte is moved a bounded number of times.
+        if self.pos > self.buf.len() / 2 {
+            self.start = self.location(self.pos);
+            self.buf.drain(..self.pos);
+            self.pos = 0;
+        }
+
+        // Reading at least as much as is already buffered means that a large
+        // value is only scanned a few times while it is being read.
+        let mut bytes = core::mem::take(&mut self.partial);
+        let len = bytes.len();
+        bytes.resize(len + self.rest().len().max(READ_SIZE), 0);
+        let read = loop {
+            match self.reader.read(&mut bytes[len..]) {
+                Ok(read) => break read,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(Error::io(e)),
+            }
+        };
+        bytes.truncate(len + read);
+
+        if read == 0 {
+            self.eof = true;
+            if !bytes.is_empty() {
+                return Err(invalid_utf8());
+            }
+            return Ok(false);
+        }
+
+        let valid = match str::from_utf8(&bytes) {
+            Ok(text) => text.len(),
+            // The last character may be split across two reads.
+            Err(e) if e.error_len().is_none() => e.valid_up_to(),
+            Err(_) => return Err(invalid_utf8()),
+        };
+        let (text, partial) = bytes.split_at(valid);
+        self.buf
+            .push_str(str::from_utf8(text).map_err(|_| invalid_utf8())?);
+        self.partial = partial.to_vec();
+
+        Ok(true)
+    }
+
+    /// Read until the next token is complete.
+    fn fill_token(&mut self) -> Result<(), Error> {
+        loop {
+            // Long runs of whitespace are dropped as they are read so that they
+            // cannot pile up.
+            let rest = self.rest();
+            if rest.len() > READ_SIZE && rest.trim_start().is_empty() {
+                self.pos = self.buf.len();
+            }
+
+            let mut lexer = self.lexer(self.rest());
+            let complete = match lexer.parse_token() {
+                Ok(token) => is_complete(token, lexer.remaining()),
+                Err(error) => !is_truncated(&error, self.rest()),
+            };
+            if complete || !self.fill()? {
+                return Ok(());
+            }
+        }
+    }
+
+    /// The next token, once it has been read with
+    /// [`fill_token`](Self::fill_token).
+    fn token(&self) -> Result<Token<'_>, Error> {
+        let mut lexer = self.lexer(self.rest());
+        match lexer.parse_token() {
+            Ok(token) => Ok(token),
+            Err(error) => {
+                let error = Error::from(error);
+                Err(self.error(error))
+            }
+        }
+    }
+
+    /// Read until the whole of the next value is buffered, along with the token
+    /// after it, and return where the value is within the rest of the input.
+    ///
+    /// The value ends at the first `,` or unmatched closing bracket, the same
+    /// as when it is skipped. Errors are left for the deserializer to report.
+    fn value(&mut self) -> Result<Range<usize>, Error> {
+        let mut scan = Scan {
+            start: None,
+            end: 0,
+            depth: 0,
+        };
+
+        loop {
+            if let Some(range) = self.scan(&mut scan) {
+                return Ok(range);
+            }
+
+            self.fill()?;
+        }
+    }
+
+    /// Continue scanning over a value, returning `None` if more of the input
+    /// needs to be read first.
+    fn scan(&self, scan: &mut Scan) -> Option<Range<usize>> {
+        let rest = self.rest();
+        let mut lexer = self.lexer(&rest[scan.end..]);
+
+        loop {
+            let offset = rest.len() - lexer.remaining().len();
+            let token = match lexer.parse_token() {
+                Ok(token) if self.eof || is_complete(token, lexer.remaining()) => token,
+                Err(error) if self.eof || !is_truncated(&error, rest) => {
+                    return Some(scan.start.unwrap_or(offset)..rest.len());
+                }
+                _ => return None,
+            };
+
+            let start = *scan.start.get_or_insert(offset);
+            match (token.kind, token.value) {
+                (TokenKind::Eof, _) => return Some(start..scan.end.max(start)),
+                // An empty element is left for the deserializer to report.
+                (TokenKind::Punct, "," | ")" | "]" | "}") if scan.depth == 0 => {
+                    let end = if scan.end > start {
+                        scan.end
+                    } else {
+                        rest.len() - lexer.remaining().len()
+                    };
+                    return Some(start..end);
+                }
+                (TokenKind::Punct, "(" | "[" | "{") => scan.depth += 1,
+                (TokenKind::Punct, ")" | "]" | "}") => scan.depth -= 1,
+                _ => (),
+            }
+
+            scan.end = rest.len() - lexer.remaining().len();
+        }
+    }
+
+    /// Whether the next token is the punctuation `punct`.
+    fn starts_with(&mut self, punct: &str) -> Result<bool, Error> {
+        self.fill_token()?;
+        match self.token() {
+            Ok(token) => Ok(token.is_punct(punct)),
+            Err(error) if error.is_io() => Err(error),
+            // Invalid input is reported when the value is deserialized.
+            Err(_) => Ok(false),
+        }
+    }
+
+    /// Deserialize `range` within the rest of the input using `func`, as an
+    /// element of a list.
+    fn deserialize_part<T, F>(&self, range: Range<usize>, func: F) -> Result<T, Error>
+    where
+        F: FnOnce(&mut Deserializer<'_>) -> Result<T, Error>,
+    {
+        let mut options = self.part.clone();
+        if let Some(deadline) = self.deadline {
+            options = options.time_budget(deadline.saturating_duration_since(Instant::now()));
+        }
+
+        let text = &self.rest()[range.clone()];
+        let mut de = Deserializer::with_options(text, options);
+        let result = de
+            .enter()
+            .and_then(|()| func(&mut de))
+            .map_err(|e| de.locate(e))
+            .and_then(|value| match de.remaining().trim_start() {
+                "" => Ok(value),
+                rest => {
+                    let error = match self.lexer(rest).parse_token() {
+                        Ok(token) => Error::unexpected_token(token, ','),
+                        Err(error) => error.into(),
+                    };
+                    Err(de.locate(error))
+                }
+            });
+
+        result.map_err(|e| e.relocate(self.location(self.pos + range.start)))
+    }
+
+    /// Read the rest of the input and deserialize it all at once using `func`.
+    fn deserialize_all<T, F>(&mut self, func: F) -> Result<T, Error>
+    where
+        F: FnOnce(&mut Deserializer<'_>) -> Result<T, Error>,
+    {
+        while self.fill()? {}
+
+        let mut de = Deserializer::with_options(self.rest(), self.options.clone());
+        let result = func(&mut de)
+            .map_err(|e| de.locate(e))
+            .and_then(|value| de.end().map(|()| value));
+
+        result.map_err(|e| e.relocate(self.location(self.pos)))
+    }
+
+    /// Deserialize a list one element at a time.
+    fn deserialize_list<'de, V>(&mut self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.fill_token()?;
+        self.pos = self.end_of(self.token()?);
+
+        if self.options.config().max_depth == Some(0) {
+            return Err(self.error(Error::depth_limit_exceeded(0)));
+        }
+
+        let value = visitor.visit_seq(ReaderSeqAccess(self))?;
+
+        self.fill_token()?;
+        let token = self.token()?;
+        if !token.is_punct("]") {
+            let error = Error::unexpected_token(token, ']');
+            return Err(self.error(error));
+        }
+        self.pos = self.end_of(token);
+
+        self.end()?;
+        Ok(value)
+    }
+
+    /// Check that only whitespace is left after the value, unless trailing
+    /// data is allowed, and report the final progress.
+    fn end(&mut self) -> Result<(), Error> {
+        if !self.options.config().allow_trailing_data {
+            self.fill_token()?;
+            let token = self.token()?;
+            if token.kind != TokenKind::Eof {
+                let error = Error::unexpected_token(token, TokenKind::Eof);
+                return Err(self.error(error));
+            }
+        }
+
+        if let Some(callback) = &self.options.config().on_progress {
+            let consumed = self.start.byte_offset + self.pos;
+            (callback.0)(consumed, consumed);
+        }
+
+        Ok(())
+    }
+}
+
+fn invalid_utf8() -> Error {
+    Error::io(io::Error::new(
+        io::ErrorKind::InvalidData,
+        "stream did not contain valid UTF-8",
+    ))
+}
+
+/// Whether `token` is known to be whole, given the input that has been read
+/// after it.
+///
+/// Brackets and separators are always a single character. Other tokens may
+/// still change once more of the input is read, unless they are followed by
+/// whitespace. Generic arguments after an identifier, as in
+/// `PhantomData<u32>`, can be any length so they need to be read in full.
+fn is_complete(token: Token, after: &str) -> bool {
+    match token.kind {
+        TokenKind::Punct if !token.value.starts_with('.') => true,
+        TokenKind::Ident if after.starts_with('<') => false,
+        _ => after.starts_with(char::is_whitespace) || after.len() >= LOOKAHEAD,
+    }
+}
+
+/// Whether the lexer failed on `input` because it needs more of the input
+/// than has been read so far, rather than because the input is invalid.
+fn is_truncated(error: &LexerError, input: &str) -> bool {
+    let end = input.as_ptr() as usize + input.len();
+    let found = error.found.as_ptr() as usize + error.found.len();
+    error.found.is_empty() || end - found < LOOKAHEAD
+}
+
+/// Deserializes from an [`Input`], streaming a list at the top level.
+struct ReaderDeserializer<'a, R>(&'a mut Input<R>);
+
+macro_rules! deserialize_all {
+    ($($deserialize:ident($($arg:ident: $ty:ty),*);)*) => {$(
+        fn $deserialize<V>(self, $($arg: $ty,)* visitor: V) -> Result<V::Value, Self::Error>
+        where
+            V: Visitor<'de>,
+        {
+            self.0
+                .deserialize_all(|de| Copied::new(Nested(de)).$deserialize($($arg,)* visitor))
+        }
+    )*};
+}
+
+impl<'de, R: io::Read> serde::Deserializer<'de> for ReaderDeserializer<'_, R> {
+    type Error = Error;
+
+    fn is_human_readable(&self) -> bool {
+        true
+    }
+
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        match self.0.starts_with("[")? {
+            true => self.0.deserialize_list(visitor),
+            false => self
+                .0
+                .deserialize_all(|de| Copied::new(Nested(de)).deserialize_any(visitor)),
+        }
+    }
+
+    fn deserialize_seq<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        match self.0.starts_with("[")? {
+            true => self.0.deserialize_list(visitor),
+            false => self
+                .0
+                .deserialize_all(|de| Copied::new(Nested(de)).deserialize_seq(visitor)),
+        }
+    }
+
+    deserialize_all! {
+        deserialize_bool();
+        deserialize_i8();
+        deserialize_i16();
+        deserialize_i32();
+        deserialize_i64();
+        deserialize_i128();
+        deserialize_u8();
+        deserialize_u16();
+        deserialize_u32();
+        deserialize_u64();
+        deserialize_u128();
+        deserialize_f32();
+        deserialize_f64();
+        deserialize_char();
+        deserialize_str();
+        deserialize_string();
+        deserialize_bytes();
+        deserialize_byte_buf();
+        deserialize_option();
+        deserialize_unit();
+        deserialize_unit_struct(name: &'static str);
+        deserialize_newtype_struct(name: &'static str);
+        deserialize_tuple(len: usize);
+        deserialize_tuple_struct(name: &'static str, len: usize);
+        deserialize_map();
+        deserialize_struct(name: &'static str, fields: &'static [&'static str]);
+        deserialize_enum(name: &'static str, variants: &'static [&'static str]);
+        deserialize_identifier();
+        deserialize_ignored_any();
+    }
+}
+
+/// Accesses the elements of a list at the top level as they are read.
+struct ReaderSeqAccess<'a, R>(&'a mut Input<R>);
+
+impl<'de, R: io::Read> SeqAccess<'de> for ReaderSeqAccess<'_, R> {
+    type Error = Error;
+
+    fn next_element_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>, Self::Error>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        let input = &mut *self.0;
+
+        input.fill_token()?;
+        let token = input.token()?;
+        if token.kind == TokenKind::Punct && matches!(token.value, "]" | "}") {
+            return Ok(None);
+        }
+
+        let range = input.value()?;
+        let value = input.deserialize_part(range.clone(), |de| {
+            seed.deserialize(Copied::new(Nested(de)))
+        })?;
+        input.pos += range.end;
+
+        // Trailing commas are permitted to be missing only if there is a
+        // closing brace there instead.
+        input.fill_token()?;
+        let token = input.token()?;
+        match (token.kind, token.value) {
+            (TokenKind::Punct, ",") => input.pos = input.end_of(token),
+            (TokenKind::Punct, "]" | "}") => (),
+            _ => {
+                let error = Error::unexpected_token(token, ',');
+                return Err(input.error(error));
+            }
+        }
+
+        Ok(Some(value))
+    }
+}
+
+/// Passes a value from a deserializer that borrows from a short-lived buffer
+/// to a visitor that expects input borrowed for `'de`.
+///
+/// Strings and bytes borrowed from the buffer are passed on as copies. `'a` is
+/// the lifetime that the wrapped value works with, which is the buffer for
+/// deserializers and `'de` for visitors and seeds.
+struct Copied<'a, T>(T, PhantomData<&'a ()>);
+
+impl<T> Copied<'_, T> {
+    fn new(inner: T) -> Self {
+        Self(inner, PhantomData)
+    }
+}
+
+macro_rules! forward_deserialize {
+    ($($deserialize:ident($($arg:ident: $ty:ty),*);)*) => {$(
+        fn $deserialize<V>(self, $($arg: $ty,)* visitor: V) -> Result<V::Value, Self::Error>
+        where
+            V: Visitor<'de>,
+        {
+            self.0.$deserialize($($arg,)* Copied::new(visitor))
+        }
+    )*};
+}
+
+impl<'de, 'a, D> serde::Deserializer<'de> for Copied<'a, D>
+where
+    D: serde::Deserializer<'a>,
+{
+    type Error = D::Error;
+
+    fn is_human_readable(&self) -> bool {
+        self.0.is_human_readable()
+    }
+
+    forward_deserialize! {
+        deserialize_any();
+        deserialize_bool();
+        deserialize_i8();
+        deserialize_i16();
+        deserialize_i32();
+        deserialize_i64();
+        deserialize_i128();
+        deserialize_u8();
+        deserialize_u16();
+        deserialize_u32();
+        deserialize_u64();
+        deserialize_u128();
+        deserialize_f32();
+        deserialize_f64();
+        deserialize_char();
+        deserialize_str();
+        deserialize_string();
+        deserialize_bytes();
+        deserialize_byte_buf();
+        deserialize_option();
+        deserialize_unit();
+        deserialize_unit_struct(name: &'static str);
+        deserialize_newtype_struct(name: &'static str);
+        deserialize_seq();
+        deserialize_tuple(len: usize);
+        deserialize_tuple_struct(name: &'static str, len: usize);
+        deserialize_map();
+        deserialize_struct(name: &'static str, fields: &'static [&'static str]);
+        deserialize_enum(name: &'static str, variants: &'static [&'static str]);
+        deserialize_identifier();
+        deserialize_ignored_any();
+    }
+}
+
+macro_rules! forward_visit {
+    ($($visit:ident($ty:ty);)*) => {$(
+        fn $visit<E>(self, value: $ty) -> Result<Self::Value, E>
+        where
+            E: serde::de::Error,
+        {
+            self.0.$visit(value)
+        }
+    )*};
+}
+
+impl<'a, 'de, V> Visitor<'a> for Copied<'de, V>
+where
+    V: Visitor<'de>,
+{
+    type Value = V::Value;
+
+    fn expecting(&self, formatter: &mut core::fmt::Formatter) -> core::fmt::Result {
+        self.0.expecting(formatter)
+    }
+
+    forward_visit! {
+        visit_bool(bool);
+        visit_i8(i8);
+        visit_i16(i16);
+        visit_i32(i32);
+        visit_i64(i64);
+        visit_i128(i128);
+        visit_u8(u8);
+        visit_u16(u16);
+        visit_u32(u32);
+        visit_u64(u64);
+        visit_u128(u128);
+        visit_f32(f32);
+        visit_f64(f64);
+        visit_char(char);
+        visit_str(&str);
+        visit_string(String);
+        visit_bytes(&[u8]);
+        visit_byte_buf(Vec<u8>);
+    }
+
+    fn visit_borrowed_str<E>(self, value: &'a str) -> Result<Self::Value, E>
+    where
+        E: serde::de::Error,
+    {
+        self.0.visit_str(value)
+    }
+
+    fn visit_borrowed_bytes<E>(self, value: &'a [u8]) -> Result<Self::Value, E>
+    where
+        E: serde::de::Error,
+    {
+        self.0.visit_bytes(value)
+    }
+
+    fn visit_none<E>(self) -> Result<Self::Value, E>
+    where
+        E: serde::de::Error,
+    {
+        self.0.visit_none()
+    }
+
+    fn visit_unit<E>(self) -> Result<Self::Value, E>
+    where
+        E: serde::de::Error,
+    {
+        self.0.visit_unit()
+    }
+
+    fn visit_some<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: serde::Deserializer<'a>,
+    {
+        self.0.visit_some(Copied::new(deserializer))
+    }
+
+    fn visit_newtype_struct<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: serde::Deserializer<'a>,
+    {
+        self.0.visit_newtype_struct(Copied::new(deserializer))
+    }
+
+    fn visit_seq<A>(self, seq: A) -> Result<Self::Value, A::Error>
+    where
+        A: SeqAccess<'a>,
+    {
+        self.0.visit_seq(Copied::new(seq))
+    }
+
+    fn visit_map<A>(self, map: A) -> Result<Self::Value, A::Error>
+    where
+        A: MapAccess<'a>,
+    {
+        self.0.visit_map(Copied::new(map))
+    }
+
+    fn visit_enum<A>(self, data: A) -> Result<Self::Value, A::Error>
+    where
+        A: EnumAccess<'a>,
+    {
+        self.0.visit_enum(Copied::new(data))
+    }
+}
+
+impl<'a, 'de, T> DeserializeSeed<'a> for Copied<'de, T>
+where
+    T: DeserializeSeed<'de>,
+{
+    type Value = T::Value;
+
+    fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: serde::Deserializer<'a>,
+    {
+        self.0.deserialize(Copied::new(deserializer))
+    }
+}
+
+impl<'de, 'a, A> SeqAccess<'de> for Copied<'a, A>
+where
+    A: SeqAccess<'a>,
+{
+    type Error = A::Error;
+
+    fn next_element_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>, Self::Error>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        self.0.next_element_seed(Copied::new(seed))
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        self.0.size_hint()
+    }
+}
+
+impl<'de, 'a, A> MapAccess<'de> for Copied<'a, A>
+where
+    A: MapAccess<'a>,
+{
+    type Error = A::Error;
+
+    fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>, Self::Error>
+    where
+        K: DeserializeSeed<'de>,
+    {
+        self.0.next_key_seed(Copied::new(seed))
+    }
+
+    fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value, Self::Error>
+    where
+        V: DeserializeSeed<'de>,
+    {
+        self.0.next_value_seed(Copied::new(seed))
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        self.0.size_hint()
+    }
+}
+
+impl<'de, 'a, A> EnumAccess<'de> for Copied<'a, A>
+where
+    A: EnumAccess<'a>,
+{
+    type Error = A::Error;
+    type Variant = Copied<'a, A::Variant>;
+
+    fn variant_seed<V>(self, seed: V) -> Result<(V::Value, Self::Variant), Self::Error>
+    where
+        V: DeserializeSeed<'de>,
+    {
+        let (value, variant) = self.0.variant_seed(Copied::new(seed))?;
+        Ok((value, Copied::new(variant)))
+    }
+}
+
+impl<'de, 'a, A> VariantAccess<'de> for Copied<'a, A>
+where
+    A: VariantAccess<'a>,
+{
+    type Error = A::Error;
+
+    fn unit_variant(self) -> Result<(), Self::Error> {
+        self.0.unit_variant()
+    }
+
+    fn newtype_variant_seed<T>(self, seed: T) -> Result<T::Value, Self::Error>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        self.0.newtype_variant_seed(Copied::new(seed))
+    }
+
+    fn tuple_variant<V>(self, len: usize, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.0.tuple_variant(len, Copied::new(visitor))
+    }
+
+    fn struct_variant<V>(
+        self,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.0.struct_variant(fields, Copied::new(visitor))
+    }
+}
@@ -0,0 +1,162 @@
+use core::iter::FusedIterator;
+
+use crate::lex::{Lexer, TokenKind};
+use crate::Error;
+
+/// A lazy reader over the entries of a top-level struct or map.
+///
+/// `MapReader` yields each entry as a pair of the key and the raw text of the
+/// value without parsing the value itself. This makes it cheap to pull a
+/// couple of fields out of a very large record. Individual values can then be
+/// deserialized using [`from_str`](crate::from_str) if needed.
+///
+/// For structs the key is the field name. For maps the key is the raw text
+/// of the key, so string keys will still have their quotes.
+///
+/// # Example
+/// ```
+/// use serde_dbgfmt::MapReader;
+///
+/// let text = r#"Request { id: 42, headers: [("host", "example.com")], body: Body { .. } }"#;
+/// let reader = MapReader::new(text).unwrap();
+/// assert_eq!(reader.name(), Some("Request"));
+///
+/// let entries: Vec<_> = reader.collect::<Result<_, _>>().unwrap();
+/// assert_eq!(
+///     entries,
+///     [
+///         ("id", "42"),
+///         ("headers", r#"[("host", "example.com")]"#),
+///         ("body", "Body { .. }"),
+///     ]
+/// );
+///
+/// let id: u32 = serde_dbgfmt::from_str(entries[0].1).unwrap();
+/// assert_eq!(id, 42);
+/// ```
+#[derive(Clone, Debug)]
+pub struct MapReader<'de> {
+    lexer: Lexer<'de>,
+    name: Option<&'de str>,
+    kind: EntryKind,
+    done: bool,
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+enum EntryKind {
+    Field,
+    Key,
+}
+
+impl<'de> MapReader<'de> {
+    /// Create a reader over the struct or map in `input`.
+    ///
+    /// This only parses the opening of the struct or map. Errors within the
+    /// entries are reported as they are reached.
+    pub fn new(input: &'de str) -> Result<Self, Error> {
+        Self::with_lexer(Lexer::new(input))
+    }
+
+    pub(crate) fn with_lexer(mut lexer: Lexer<'de>) -> Result<Self, Error> {
+        let mut name = None;
+
+        let mut token = lexer.parse_token()?;
+        if token.kind == TokenKind::Ident {
+            name = Some(token.value);
+            token = lexer.parse_token()?;
+
+            // Structs with no fields are printed without any braces.
+            if token.kind == TokenKind::Eof {
+                return Ok(Self {
+                    lexer,
+                    name,
+                    kind: EntryKind::Field,
+                    done: true,
+                });
+            }
+        }
+
+        if !token.is_punct("{") {
+            return Err(Error::unexpected_token(token, '{'));
+        }
+
+        Ok(Self {
+            lexer,
+            name,
+            kind: match name {
+                Some(_) => EntryKind::Field,
+                None => EntryKind::Key,
+            },
+            done: false,
+        })
+    }
+
+    /// The name of the struct, or `None` if the input is a map.
+    pub fn name(&self) -> Option<&'de str> {
+        self.name
+    }
+
+    fn finish(&mut self) -> Result<(), Error> {
+        self.done = true;
+
+        let token = self.lexer.parse_token()?;
+        match token.kind {
+            TokenKind::Eof => Ok(()),
+            _ => Err(Error::unexpected_token(token, TokenKind::Eof)),
+        }
+    }
+
+    fn next_entry(&mut self) -> Result<Option<(&'de str, &'de str)>, Error> {
+        let token = self.lexer.peek_token()?;
+        if token.is_punct("}") || (self.kind == EntryKind::Field && token.is_punct("..")) {
+            self.lexer.parse_token()?;
+            if token.is_punct("..") {
+                self.lexer.expect_punct("}")?;
+            }
+
+            self.finish()?;
+            return Ok(None);
+        }
+
+        let key = match self.kind {
+            EntryKind::Field => {
+                let token = self.lexer.parse_token()?;
+                if token.kind != TokenKind::Ident {
+                    return Err(Error::unexpected_token(token, TokenKind::Ident));
+                }
+
+                token.value
+            }
+            EntryKind::Key => self.lexer.skip_key()?,
+        };
+
+        self.lexer.expect_punct(":")?;
+        let value = self.lexer.skip_value()?;
+
+        if !self.lexer.peek_token()?.is_punct("}") {
+            self.lexer.expect_punct_with(",", "`,` or `}`")?;
+        }
+
+        Ok(Some((key, value)))
+    }
+}
+
+impl<'de> Iterator for MapReader<'de> {
+    type Item = Result<(&'de str, &'de str), Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        match self.next_entry() {
+            Ok(entry) => entry.map(Ok),
+            Err(e) => {
+                self.done = true;
+                Some(Err(e))
+            }
+        }
+    }
+}
+
+impl FusedIterator for MapReader<'_> {}
@@ -0,0 +1,184 @@
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use crate::tree::{Node, NodeKind};
+use crate::{Error, Options};
+
+const REDACTED: &str = "\"<redacted>\"";
+
+/// Replace string values in debug-formatted text with `"<redacted>"`.
+///
+/// By default every string value is redacted. Use [`field`](Self::field) to
+/// only redact strings within particular fields. Everything other than the
+/// redacted strings, including whitespace, is kept exactly as it was in the
+/// input. This makes it possible to share captured debug output without
+/// leaking secrets.
+///
+/// # Example
+/// ```
+/// use serde_dbgfmt::Redactor;
+///
+/// let text = r#"Login { user: "alice", password: "hunter2", token: Some("abc") }"#;
+///
+/// let redacted = Redactor::new()
+///     .field("password")
+///     .field("*token")
+///     .redact(text)
+///     .unwrap();
+/// assert_eq!(
+///     redacted,
+///     r#"Login { user: "alice", password: "<redacted>", token: Some("<redacted>") }"#
+/// );
+///
+/// let redacted = Redactor::new().redact(text).unwrap();
+/// assert_eq!(
+///     redacted,
+///     r#"Login { user: "<redacted>", password: "<redacted>", token: Some("<redacted>") }"#
+/// );
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct Redactor {
+    fields: Vec<String>,
+    options: Options,
+}
+
+impl Redactor {
+    /// Create a redactor which redacts every string.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Only redact strings within fields whose name matches `pattern`.
+    ///
+    /// Patterns match struct field names and string map keys. A `*` within
+    /// the pattern matches any sequence of characters, so `*token*` matches
+    /// both `token` and `api_token_v2`. Every string nested anywhere within a
+    /// matching field is redacted.
+    ///
+    /// This can be called multiple times to redact several fields.
+    #[must_use]
+    pub fn field(mut self, pattern: impl Into<String>) -> Self {
+        self.fields.push(pattern.into());
+        self
+    }
+
+    /// Use `options` when lexing the input.
+    ///
+    /// The same options apply as for
+    /// [`canonicalize_with`](crate::canonicalize_with). Strings produced by the
+    /// unknown token callback are redacted like any other string.
+    #[must_use]
+    pub fn options(mut self, options: Options) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Parse `input` and return it with the selected strings redacted.
+    pub fn redact(&self, input: &str) -> Result<String, Error> {
+        let node = Node::parse_with(input, &self.options)?;
+
+        let mut spans = Vec::new();
+        self.collect(&node, self.fields.is_empty(), &mut spans)?;
+
+        let mut output = String::with_capacity(input.len());
+        let mut rest = input;
+        for span in spans {
+            let offset = span.as_ptr() as usize - rest.as_ptr() as usize;
+            output.push_str(&rest[..offset]);
+            output.push_str(REDACTED);
+            rest = &rest[offset + span.len()..];
+        }
+        output.push_str(rest);
+
+        Ok(output)
+    }
+
+    /// Collect the spans of the strings to redact, in the order that they
+    /// appear in the input.
+    fn collect<'a>(
+        &self,
+        node: &Node<'a>,
+        redact: bool,
+        spans: &mut Vec<&'a str>,
+    ) -> Result<(), Error> {
+        match &node.kind {
+            NodeKind::String if redact => spans.push(node.span),
+            NodeKind::Struct { fields, .. } => {
+                for (name, value) in fields {
+                    self.collect(value, redact || self.matches(name), spans)?;
+                }
+            }
+            NodeKind::Map(entries) => {
+                for (key, value) in entries {
+                    let matches = match key.kind {
+                        NodeKind::String => self.matches(&key.string()?),
+                        _ => false,
+                    };
+
+                    // Keys are left alone since they are not values.
+                    self.collect(value, redact || matches, spans)?;
+                }
+            }
+            NodeKind::TupleStruct { elements, .. }
+            | NodeKind::Tuple(elements)
+            | NodeKind::List(elements)
+            | NodeKind::Set(elements) => {
+                for element in elements {
+                    self.collect(element, redact, spans)?;
+                }
+            }
+            _ => (),
+        }
+
+        Ok(())
+    }
+
+    fn matches(&self, name: &str) -> bool {
+        self.fields.iter().any(|pattern| glob_match(pattern, name))
+    }
+}
+
+/// Match `text` against `pattern`, where `*` matches any sequence of
+/// characters.
+pub(crate) fn glob_match(pattern: &str, text: &str) -> bool {
+    let mut parts = pattern.split('*');
+
+    // There is always at least one part, even for an empty pattern.
+    let first = parts.next().unwrap_or("");
+    let mut rest = match text.strip_prefix(first) {
+        Some(rest) => rest,
+        None => return false,
+    };
+
+    let mut parts = parts.peekable();
+    while let Some(part) = parts.next() {
+        if parts.peek().is_none() {
+            return rest.ends_with(part);
+        }
+
+        match rest.find(part) {
+            Some(index) => rest = &rest[index + part.len()..],
+            None => return false,
+        }
+    }
+
+    rest.is_empty()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::glob_match;
+
+    #[test]
+    fn glob() {
+        assert!(glob_match("token", "token"));
+        assert!(!glob_match("token", "tokens"));
+        assert!(glob_match("*", ""));
+        assert!(glob_match("*token", "api_token"));
+        assert!(glob_match("token*", "token_v2"));
+        assert!(glob_match("*tok*en*", "my_tok_x_en"));
+        assert!(!glob_match("*tok*en", "en_tok"));
+        assert!(glob_match("a*a", "aa"));
+        assert!(!glob_match("a*a", "a"));
+    }
+}
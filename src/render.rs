@@ -0,0 +1,195 @@
+//! Render debug-formatted text as a tree for visualization.
+//!
+//! Deeply nested values can be hard to read even when pretty-printed. The
+//! functions in this module parse the input without needing to know its
+//! types and draw it either as an indented text tree or as a [Graphviz] graph.
+//!
+//! [Graphviz]: https://graphviz.org
+
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt::Write;
+
+use crate::canonical::canonical;
+use crate::tree::{Node, NodeKind};
+use crate::{Error, Options};
+
+/// Render the value in `input` as an indented ASCII tree.
+///
+/// Each struct field, element, or map entry is drawn on its own line along
+/// with the name or index it was found under.
+///
+/// # Example
+/// ```
+/// let text = r#"Config { name: "prod", hosts: [Host { port: 80 }], mode: Fast }"#;
+///
+/// assert_eq!(
+///     serde_dbgfmt::render::ascii_tree(text).unwrap(),
+///     r#"Config
+/// |-- name: "prod"
+/// |-- hosts: list (1)
+/// |   `-- [0]: Host
+/// |       `-- port: 80
+/// `-- mode: Fast
+/// "#
+/// );
+/// ```
+pub fn ascii_tree(input: &str) -> Result<String, Error> {
+    Ok(render_ascii(&Node::parse(input)?))
+}
+
+/// Render the value in `input` as an indented ASCII tree, using the provided
+/// options to lex it.
+///
+/// The same options apply as for
+/// [`canonicalize_with`](crate::canonicalize_with).
+pub fn ascii_tree_with(input: &str, options: &Options) -> Result<String, Error> {
+    Ok(render_ascii(&Node::parse_with(input, options)?))
+}
+
+fn render_ascii(node: &Node) -> String {
+    let mut out = String::new();
+    out.push_str(&label(node));
+    out.push('\n');
+    write_ascii(node, &mut String::new(), &mut out);
+    out
+}
+
+/// Render the value in `input` as a graph in the Graphviz DOT language.
+///
+/// Each value becomes a node in the graph, with edges labelled by the field
+/// name, index, or map key that the child was found under.
+///
+/// # Example
+/// ```
+/// let dot = serde_dbgfmt::render::dot("Point { x: 1, y: 2 }").unwrap();
+///
+/// assert_eq!(
+///     dot,
+///     r#"digraph {
+///     n0 [label="Point"];
+///     n1 [label="1"];
+///     n0 -> n1 [label="x"];
+///     n2 [label="2"];
+///     n0 -> n2 [label="y"];
+/// }
+/// "#
+/// );
+/// ```
+pub fn dot(input: &str) -> Result<String, Error> {
+    Ok(render_dot(&Node::parse(input)?))
+}
+
+/// Render the value in `input` as a Graphviz graph, using the provided
+/// options to lex it.
+///
+/// The same options apply as for
+/// [`canonicalize_with`](crate::canonicalize_with).
+pub fn dot_with(input: &str, options: &Options) -> Result<String, Error> {
+    Ok(render_dot(&Node::parse_with(input, options)?))
+}
+
+fn render_dot(node: &Node) -> String {
+    let mut out = String::from("digraph {\n");
+    writeln!(out, "    n0 [label=\"{}\"];", escape_dot(&label(node))).unwrap();
+    write_dot(node, 0, &mut 1, &mut out);
+    out.push_str("}\n");
+    out
+}
+
+/// The text shown for a node, not including its children.
+fn label(node: &Node) -> String {
+    match &node.kind {
+        NodeKind::Struct { name, .. } | NodeKind::TupleStruct { name, .. } => (*name).into(),
+        NodeKind::Tuple(elements) => format!("tuple ({})", elements.len()),
+        NodeKind::List(elements) => format!("list ({})", elements.len()),
+        NodeKind::Set(elements) => format!("set ({})", elements.len()),
+        NodeKind::Map(entries) => format!("map ({})", entries.len()),
+        _ => node.span.into(),
+    }
+}
+
+/// The children of a node along with the edge label for each.
+fn children<'n, 'a>(node: &'n Node<'a>) -> Vec<(String, &'n Node<'a>)> {
+    let indexed = |elements: &'n [Node<'a>]| {
+        elements
+            .iter()
+            .enumerate()
+            .map(|(index, element)| (format!("[{index}]"), element))
+            .collect()
+    };
+
+    match &node.kind {
+        NodeKind::Struct { fields, .. } => fields
+            .iter()
+            .map(|(name, value)| ((*name).into(), value))
+            .collect(),
+        NodeKind::TupleStruct { elements, .. }
+        | NodeKind::Tuple(elements)
+        | NodeKind::List(elements)
+        | NodeKind::Set(elements) => indexed(elements),
+        NodeKind::Map(entries) => entries
+            .iter()
+            .map(|(key, value)| (canonical(key), value))
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+fn write_ascii(node: &Node, prefix: &mut String, out: &mut String) {
+    let children = children(node);
+    let count = children.len();
+
+    for (index, (edge, child)) in children.into_iter().enumerate() {
+        let last = index + 1 == count;
+        let (branch, indent) = match last {
+            true => ("`-- ", "    "),
+            false => ("|-- ", "|   "),
+        };
+
+        writeln!(out, "{prefix}{branch}{edge}: {}", label(child)).unwrap();
+
+        prefix.push_str(indent);
+        write_ascii(child, prefix, out);
+        prefix.truncate(prefix.len() - indent.len());
+    }
+}
+
+/// Write the children of `node`, which has already been written with `id`.
+fn write_dot(node: &Node, id: usize, next: &mut usize, out: &mut String) {
+    for (edge, child) in children(node) {
+        let child_id = *next;
+        *next += 1;
+
+        writeln!(
+            out,
+            "    n{child_id} [label=\"{}\"];",
+            escape_dot(&label(child))
+        )
+        .unwrap();
+        writeln!(
+            out,
+            "    n{id} -> n{child_id} [label=\"{}\"];",
+            escape_dot(&edge)
+        )
+        .unwrap();
+        write_dot(child, child_id, next, out);
+    }
+}
+
+fn escape_dot(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '"' | '\\' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            '\n' => escaped.push_str("\\n"),
+            c => escaped.push(c),
+        }
+    }
+
+    escaped
+}
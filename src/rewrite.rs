@@ -0,0 +1,143 @@
+//! Rewrite debug-formatted text one token at a time.
+//!
+//! [`rewrite`] splits the input into tokens and passes each one to a callback
+//! which decides whether to keep, drop, or replace it. Whitespace between
+//! tokens is always kept as it was in the input, so untouched parts of the
+//! text come out exactly as they went in. This makes it easy to write custom
+//! scrubbers that don't need to understand the structure of the value.
+//!
+//! The input only needs to be made of valid tokens. It is not parsed as a
+//! value so the callback is free to produce text that no longer is one.
+
+use alloc::borrow::Cow;
+use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::string::String;
+use core::ops::Range;
+
+use crate::lex::Lexer;
+pub use crate::lex::TokenKind;
+use crate::Error;
+
+/// A single token within the input passed to [`rewrite`].
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct Token<'a> {
+    kind: TokenKind,
+    text: &'a str,
+    offset: usize,
+}
+
+impl<'a> Token<'a> {
+    /// The kind of this token.
+    pub fn kind(&self) -> TokenKind {
+        self.kind
+    }
+
+    /// The text of this token, exactly as it appears in the input.
+    pub fn text(&self) -> &'a str {
+        self.text
+    }
+
+    /// The byte range of this token within the input.
+    pub fn span(&self) -> Range<usize> {
+        self.offset..self.offset + self.text.len()
+    }
+}
+
+/// What to do with a token passed to the callback of [`rewrite`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Action<'a> {
+    /// Keep the token as it is.
+    Keep,
+    /// Remove the token from the output.
+    Drop,
+    /// Replace the token with some other text.
+    ///
+    /// New tokens can be inserted by replacing a token with text that
+    /// includes the original.
+    Replace(Cow<'a, str>),
+}
+
+/// Pass each token in `input` to `func` and build the output from the actions
+/// that it returns.
+///
+/// # Example
+/// ```
+/// use serde_dbgfmt::rewrite::{rewrite, Action, TokenKind};
+///
+/// let text = r#"Session { id: 42, cookie: "abc123" }"#;
+/// let output = rewrite(text, |token| match token.kind() {
+///     TokenKind::String => Action::Replace("\"***\"".into()),
+///     TokenKind::Integer => Action::Replace(format!("{}u64", token.text()).into()),
+///     _ => Action::Keep,
+/// })
+/// .unwrap();
+///
+/// assert_eq!(output, r#"Session { id: 42u64, cookie: "***" }"#);
+/// ```
+pub fn rewrite<'a, F>(input: &'a str, mut func: F) -> Result<String, Error>
+where
+    F: FnMut(Token<'a>) -> Action<'a>,
+{
+    let mut lexer = Lexer::new(input);
+    let mut output = String::with_capacity(input.len());
+    let mut end = 0;
+
+    loop {
+        let token = lexer.parse_token()?;
+        let offset = token.value.as_ptr() as usize - input.as_ptr() as usize;
+        output.push_str(&input[end..offset]);
+
+        if token.kind == TokenKind::Eof {
+            break;
+        }
+
+        end = offset + token.value.len();
+        match func(Token {
+            kind: token.kind,
+            text: token.value,
+            offset,
+        }) {
+            Action::Keep => output.push_str(token.value),
+            Action::Drop => (),
+            Action::Replace(text) => output.push_str(&text),
+        }
+    }
+
+    Ok(output)
+}
+
+/// Replace pointer addresses in `input` with stable placeholders.
+///
+/// Raw pointers and the addresses printed by `{:p}` change from one run to
+/// the next, which makes diffs and deduplication of debug output noisy. Every
+/// hex integer literal with at least 8 digits (e.g. `0x7f8e4c003a20`) is
+/// treated as an address and replaced with `0x1`, `0x2`, and so on, numbered
+/// in the order in which each distinct address first appears. The output is
+/// still valid debug-formatted text, and two values that pointed to the same
+/// address still do so afterwards.
+///
+/// # Example
+/// ```
+/// use serde_dbgfmt::rewrite::normalize_pointers;
+///
+/// let text = "Node { ptr: 0x7f8e4c003a20, next: 0x7f8e4c003b80, prev: 0x7f8e4c003a20, tag: 0xff }";
+///
+/// assert_eq!(
+///     normalize_pointers(text).unwrap(),
+///     "Node { ptr: 0x1, next: 0x2, prev: 0x1, tag: 0xff }"
+/// );
+/// ```
+pub fn normalize_pointers(input: &str) -> Result<String, Error> {
+    let mut seen = BTreeMap::new();
+
+    rewrite(input, |token| {
+        if token.kind() != TokenKind::Address {
+            return Action::Keep;
+        }
+
+        let next = seen.len() + 1;
+        let index = *seen.entry(token.text()).or_insert(next);
+        Action::Replace(format!("{index:#x}").into())
+    })
+}
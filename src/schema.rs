@@ -0,0 +1,335 @@
+//! Infer a JSON Schema describing debug-formatted values.
+//!
+//! This is useful when debug output is transcoded into JSON for consumption
+//! by other tools. Given one or more samples, [`infer_schema`] produces a
+//! [JSON Schema] (draft 2020-12) that all of the samples conform to.
+//!
+//! Values are mapped to JSON types as follows:
+//! - Structs become objects. A field is only `required` if it is present in
+//!   every sample. The struct name is used as the `title`.
+//! - Lists become arrays and sets become arrays with `uniqueItems`.
+//! - Maps become objects with `additionalProperties`.
+//! - Tuples and tuple structs become arrays with `prefixItems`.
+//! - `Some(x)` is treated as `x` and `None` as `null`.
+//! - `true` and `false` become booleans. Any other bare identifiers are assumed
+//!   to be unit enum variants and become a string `enum`.
+//! - Integers, floats, strings and chars map to the matching JSON types.
+//!
+//! [JSON Schema]: https://json-schema.org
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use serde_json::{json, Map, Value};
+
+use crate::tree::{Node, NodeKind};
+use crate::{Error, Options};
+
+/// Infer a JSON Schema that matches every one of `samples`.
+///
+/// # Example
+/// ```
+/// use serde_json::json;
+///
+/// let schema = serde_dbgfmt::schema::infer_schema([
+///     "User { id: 1, name: \"a\", role: Admin }",
+///     "User { id: 2, role: Guest, email: Some(\"b@example.com\") }",
+/// ])
+/// .unwrap();
+///
+/// assert_eq!(
+///     schema,
+///     json!({
+///         "$schema": "https://json-schema.org/draft/2020-12/schema",
+///         "type": "object",
+///         "title": "User",
+///         "properties": {
+///             "email": { "type": "string" },
+///             "id": { "type": "integer" },
+///             "name": { "type": "string" },
+///             "role": { "type": "string", "enum": ["Admin", "Guest"] },
+///         },
+///         "required": ["id", "role"],
+///     })
+/// );
+/// ```
+pub fn infer_schema<'a, I>(samples: I) -> Result<Value, Error>
+where
+    I: IntoIterator<Item = &'a str>,
+{
+    infer_schema_with(samples, &Options::default())
+}
+
+/// Infer a JSON Schema that matches every one of `samples`, using the
+/// provided options to lex them.
+///
+/// The same options apply as for
+/// [`canonicalize_with`](crate::canonicalize_with). Strings produced by the
+/// unknown token callback are treated as strings.
+pub fn infer_schema_with<'a, I>(samples: I, options: &Options) -> Result<Value, Error>
+where
+    I: IntoIterator<Item = &'a str>,
+{
+    let mut schema = None;
+    for sample in samples {
+        let sample = Schema::from_node(&Node::parse_with(sample, options)?);
+        schema = Some(match schema {
+            Some(schema) => merge(schema, sample),
+            None => sample,
+        });
+    }
+
+    let mut value = match schema {
+        Some(schema) => schema.to_json(),
+        None => json!({}),
+    };
+
+    if let Value::Object(map) = &mut value {
+        map.insert(
+            "$schema".into(),
+            "https://json-schema.org/draft/2020-12/schema".into(),
+        );
+    }
+
+    Ok(value)
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Schema {
+    Null,
+    Bool,
+    Integer,
+    Number,
+    String,
+    Enum(BTreeSet<String>),
+    Object {
+        title: String,
+        properties: BTreeMap<String, Schema>,
+        required: BTreeSet<String>,
+    },
+    Map(Option<Box<Schema>>),
+    Array {
+        items: Option<Box<Schema>>,
+        unique: bool,
+    },
+    Tuple {
+        title: Option<String>,
+        items: Vec<Schema>,
+    },
+    AnyOf(Vec<Schema>),
+}
+
+impl Schema {
+    fn from_node(node: &Node) -> Self {
+        let merged = |nodes: &mut dyn Iterator<Item = &Node>| {
+            nodes.map(Self::from_node).reduce(merge).map(Box::new)
+        };
+
+        match &node.kind {
+            NodeKind::Struct { name, fields, .. } => Self::Object {
+                title: (*name).into(),
+                properties: fields
+                    .iter()
+                    .map(|(name, value)| ((*name).into(), Self::from_node(value)))
+                    .collect(),
+                required: fields.iter().map(|(name, _)| (*name).into()).collect(),
+            },
+            NodeKind::TupleStruct {
+                name: "Some",
+                elements,
+            } if elements.len() == 1 => Self::from_node(&elements[0]),
+            NodeKind::TupleStruct { name, elements } => Self::Tuple {
+                title: Some((*name).into()),
+                items: elements.iter().map(Self::from_node).collect(),
+            },
+            NodeKind::Ident("true" | "false") => Self::Bool,
+            NodeKind::Ident("None") => Self::Null,
+            NodeKind::Ident(name) => Self::Enum(BTreeSet::from([(*name).into()])),
+            NodeKind::Tuple(elements) => Self::Tuple {
+                title: None,
+                items: elements.iter().map(Self::from_node).collect(),
+            },
+            NodeKind::List(elements) => Self::Array {
+                items: merged(&mut elements.iter()),
+                unique: false,
+            },
+            NodeKind::Set(elements) => Self::Array {
+                items: merged(&mut elements.iter()),
+                unique: true,
+            },
+            NodeKind::Map(entries) => Self::Map(merged(&mut entries.iter().map(|(_, v)| v))),
+            NodeKind::Integer => Self::Integer,
+            NodeKind::Float => Self::Number,
+            NodeKind::String | NodeKind::Char | NodeKind::Other => Self::String,
+        }
+    }
+
+    fn to_json(&self) -> Value {
+        let simple = |ty: &str| json!({ "type": ty });
+
+        match self {
+            Self::Null => simple("null"),
+            Self::Bool => simple("boolean"),
+            Self::Integer => simple("integer"),
+            Self::Number => simple("number"),
+            Self::String => simple("string"),
+            Self::Enum(variants) => json!({ "type": "string", "enum": variants }),
+            Self::Object {
+                title,
+                properties,
+                required,
+            } => {
+                let properties: Map<_, _> = properties
+                    .iter()
+                    .map(|(name, schema)| (name.clone(), schema.to_json()))
+                    .collect();
+
+                json!({
+                    "type": "object",
+                    "title": title,
+                    "properties": properties,
+                    "required": required,
+                })
+            }
+            Self::Map(values) => {
+                let mut schema = simple("object");
+                if let Some(values) = values {
+                    schema["additionalProperties"] = values.to_json();
+                }
+                schema
+            }
+            Self::Array { items, unique } => {
+                let mut schema = simple("array");
+                if let Some(items) = items {
+                    schema["items"] = items.to_json();
+                }
+                if *unique {
+                    schema["uniqueItems"] = true.into();
+                }
+                schema
+            }
+            Self::Tuple { title, items } => {
+                let mut schema = simple("array");
+                if let Some(title) = title {
+                    schema["title"] = title.as_str().into();
+                }
+                schema["prefixItems"] = items.iter().map(Self::to_json).collect();
+                schema["items"] = false.into();
+                schema
+            }
+            Self::AnyOf(options) => json!({
+                "anyOf": options.iter().map(Self::to_json).collect::<Vec<_>>(),
+            }),
+        }
+    }
+}
+
+/// Combine two schemas into one that accepts values matching either.
+fn merge(a: Schema, b: Schema) -> Schema {
+    match (a, b) {
+        (Schema::AnyOf(mut options), other) | (other, Schema::AnyOf(mut options)) => {
+            let others = match other {
+                Schema::AnyOf(others) => others,
+                other => vec![other],
+            };
+
+            for other in others {
+                add_option(&mut options, other);
+            }
+            Schema::AnyOf(options)
+        }
+        (a, b) => match try_merge(a, b) {
+            Ok(merged) => merged,
+            Err(rejected) => {
+                let (a, b) = *rejected;
+                Schema::AnyOf(vec![a, b])
+            }
+        },
+    }
+}
+
+fn add_option(options: &mut Vec<Schema>, mut schema: Schema) {
+    for option in options.iter_mut() {
+        let existing = std::mem::replace(option, Schema::Null);
+        match try_merge(existing, schema) {
+            Ok(merged) => {
+                *option = merged;
+                return;
+            }
+            Err(rejected) => {
+                (*option, schema) = *rejected;
+            }
+        }
+    }
+
+    options.push(schema);
+}
+
+/// Merge two schemas of the same kind, or return them unchanged if they are
+/// of different kinds.
+fn try_merge(a: Schema, b: Schema) -> Result<Schema, Box<(Schema, Schema)>> {
+    let merge_items = |a: Option<Box<Schema>>, b: Option<Box<Schema>>| match (a, b) {
+        (Some(a), Some(b)) => Some(Box::new(merge(*a, *b))),
+        (a, b) => a.or(b),
+    };
+
+    Ok(match (a, b) {
+        (a, b) if a == b => a,
+        (Schema::Integer | Schema::Number, Schema::Integer | Schema::Number) => Schema::Number,
+        (Schema::Enum(mut a), Schema::Enum(b)) => {
+            a.extend(b);
+            Schema::Enum(a)
+        }
+        (
+            Schema::Object {
+                title,
+                properties: mut props_a,
+                required: required_a,
+            },
+            Schema::Object {
+                title: title_b,
+                properties: props_b,
+                required: required_b,
+            },
+        ) if title == title_b => {
+            for (name, schema) in props_b {
+                let schema = match props_a.remove(&name) {
+                    Some(existing) => merge(existing, schema),
+                    None => schema,
+                };
+                props_a.insert(name, schema);
+            }
+
+            Schema::Object {
+                title,
+                properties: props_a,
+                required: required_a.intersection(&required_b).cloned().collect(),
+            }
+        }
+        (Schema::Map(a), Schema::Map(b)) => Schema::Map(merge_items(a, b)),
+        // Empty sets print the same as empty maps (`{}`) and are always parsed
+        // as maps.
+        (Schema::Map(None), set @ Schema::Array { unique: true, .. })
+        | (set @ Schema::Array { unique: true, .. }, Schema::Map(None)) => set,
+        (
+            Schema::Array { items: a, unique },
+            Schema::Array {
+                items: b,
+                unique: unique_b,
+            },
+        ) if unique == unique_b => Schema::Array {
+            items: merge_items(a, b),
+            unique,
+        },
+        (
+            Schema::Tuple { title, items: a },
+            Schema::Tuple {
+                title: title_b,
+                items: b,
+            },
+        ) if title == title_b && a.len() == b.len() => Schema::Tuple {
+            title,
+            items: a.into_iter().zip(b).map(|(a, b)| merge(a, b)).collect(),
+        },
+        (a, b) => return Err(Box::new((a, b))),
+    })
+}
@@ -0,0 +1,693 @@
+//! Serialize values into their debug representation.
+//!
+//! The output matches what `#[derive(Debug)]` would produce for a type with
+//! the same shape as the serde data model of the value: structs are written
+//! as `Name { field: value }`, sequences as `[a, b]`, maps as `{k: v}`, and
+//! so on. This allows types to implement [`Debug`](fmt::Debug) by delegating
+//! to their [`Serialize`] impl.
+//!
+//! [`Duration`]s are written as `1.5s`, the same way their `Debug` impl
+//! prints them. Other types whose serde representation differs from their
+//! debug output, like `IpAddr` which serializes as a string, are written
+//! using their serde representation.
+
+use alloc::string::String;
+use core::cell::Cell;
+use core::fmt::{self, Write};
+use core::time::Duration;
+
+use serde::ser::{self, Error as _, Serialize};
+
+use crate::Error;
+
+/// Display `value` using its debug representation.
+///
+/// The text is written directly into the target formatter without building
+/// an intermediate `String`. The alternate flag (`{:#}`) is respected, and
+/// produces the same output as `{:#?}` would.
+///
+/// If `value` fails to serialize then formatting it returns [`fmt::Error`].
+///
+/// # Example
+/// ```
+/// use std::fmt;
+///
+/// use serde::Serialize;
+///
+/// #[derive(Serialize)]
+/// struct Point {
+///     x: i32,
+///     y: Option<f64>,
+/// }
+///
+/// impl fmt::Debug for Point {
+///     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+///         fmt::Display::fmt(&serde_dbgfmt::ser::display(self), f)
+///     }
+/// }
+///
+/// let point = Point { x: 1, y: Some(2.5) };
+/// assert_eq!(format!("{point:?}"), "Point { x: 1, y: Some(2.5) }");
+/// ```
+pub fn display<T>(value: &T) -> impl fmt::Display + '_
+where
+    T: ?Sized + Serialize,
+{
+    Display(value)
+}
+
+/// Write the debug representation of `value` into `writer`.
+///
+/// This does not allocate, and only needs [`core::fmt::Write`], so it can be
+/// used to emit debug-formatted frames from environments without an
+/// allocator.
+///
+/// # Example
+/// ```
+/// use serde::Serialize;
+///
+/// #[derive(Serialize)]
+/// struct Reading {
+///     sensor: u8,
+///     celsius: f32,
+/// }
+///
+/// let mut text = String::new();
+/// serde_dbgfmt::ser::to_writer(&mut text, &Reading { sensor: 2, celsius: 21.5 }).unwrap();
+/// assert_eq!(text, "Reading { sensor: 2, celsius: 21.5 }");
+/// ```
+pub fn to_writer<W, T>(writer: &mut W, value: &T) -> Result<(), Error>
+where
+    W: ?Sized + fmt::Write,
+    T: ?Sized + Serialize,
+{
+    let mut writer = TrackedWriter {
+        inner: writer,
+        failed: false,
+    };
+    let error = Cell::new(None);
+    let result = write!(
+        writer,
+        "{}",
+        Adapter {
+            value,
+            error: &error
+        }
+    );
+
+    match (result, error.take()) {
+        (Ok(()), _) => Ok(()),
+        (Err(_), Some(error)) if !writer.failed => Err(error),
+        (Err(_), _) => Err(Error::custom("the writer returned an error")),
+    }
+}
+
+/// Write the debug representation of `value` into `buffer`, returning the
+/// number of bytes written.
+///
+/// The output is always valid UTF-8. An error is returned if it does not fit
+/// in `buffer`.
+///
+/// # Example
+/// ```
+/// let mut buffer = [0u8; 32];
+/// let len = serde_dbgfmt::ser::to_slice(&mut buffer, &(1, "two")).unwrap();
+/// assert_eq!(&buffer[..len], br#"(1, "two")"#);
+///
+/// assert!(serde_dbgfmt::ser::to_slice(&mut buffer[..4], &(1, "two")).is_err());
+/// ```
+pub fn to_slice<T>(buffer: &mut [u8], value: &T) -> Result<usize, Error>
+where
+    T: ?Sized + Serialize,
+{
+    let mut writer = SliceWriter { buffer, len: 0 };
+    to_writer(&mut writer, value).map_err(|e| match writer.len == writer.buffer.len() {
+        true => Error::custom("the buffer is too small"),
+        false => e,
+    })?;
+
+    Ok(writer.len)
+}
+
+/// Serialize `value` into a `String` containing its debug representation.
+///
+/// The output follows the serde data model of `value`, so it only matches
+/// `format!("{value:?}")` where that model has the same shape as the `Debug`
+/// impl. Sets serialize as sequences and are written as `[a, b]` rather than
+/// `{a, b}`, and non-empty arrays serialize as tuples and are written as
+/// `(a, b)`. See the [module docs](crate::ser) for other types this affects.
+///
+/// # Example
+/// ```
+/// use serde::Serialize;
+///
+/// #[derive(Debug, Serialize)]
+/// enum Shape {
+///     Circle { radius: f64 },
+///     Square(u32),
+/// }
+///
+/// let shapes = vec![Shape::Circle { radius: 0.5 }, Shape::Square(2)];
+/// let text = serde_dbgfmt::to_string(&shapes).unwrap();
+/// assert_eq!(text, "[Circle { radius: 0.5 }, Square(2)]");
+/// assert_eq!(text, format!("{shapes:?}"));
+/// ```
+pub fn to_string<T>(value: &T) -> Result<String, Error>
+where
+    T: ?Sized + Serialize,
+{
+    let mut text = String::new();
+    to_writer(&mut text, value)?;
+    Ok(text)
+}
+
+/// Serialize `value` into a `String` using the alternate debug
+/// representation.
+///
+/// As with [`to_string`], sets and non-empty arrays are written as lists and
+/// tuples, so the output only matches `format!("{value:#?}")` for types whose
+/// serde data model has the same shape as their `Debug` impl.
+///
+/// # Example
+/// ```
+/// use serde::Serialize;
+///
+/// #[derive(Debug, Serialize)]
+/// struct Point {
+///     x: i32,
+///     y: i32,
+/// }
+///
+/// let point = Point { x: 1, y: 2 };
+/// let text = serde_dbgfmt::to_string_pretty(&point).unwrap();
+/// assert_eq!(text, "Point {\n    x: 1,\n    y: 2,\n}");
+/// assert_eq!(text, format!("{point:#?}"));
+/// ```
+pub fn to_string_pretty<T>(value: &T) -> Result<String, Error>
+where
+    T: ?Sized + Serialize,
+{
+    let mut text = String::new();
+    let error = Cell::new(None);
+    let result = write!(
+        text,
+        "{:#}",
+        Adapter {
+            value,
+            error: &error
+        }
+    );
+
+    match (result, error.take()) {
+        (Ok(()), _) => Ok(text),
+        (Err(_), Some(error)) => Err(error),
+        (Err(_), None) => Err(Error::custom("a Display impl returned an error")),
+    }
+}
+
+struct Display<'a, T: ?Sized>(&'a T);
+
+impl<T> fmt::Display for Display<'_, T>
+where
+    T: ?Sized + Serialize,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let error = Cell::new(None);
+        fmt::Display::fmt(
+            &Adapter {
+                value: self.0,
+                error: &error,
+            },
+            f,
+        )
+    }
+}
+
+/// Formats a value by serializing it into the formatter.
+///
+/// Formatting can only fail with [`fmt::Error`], so the first error returned
+/// by a [`Serialize`] impl is stored in `error` instead.
+struct Adapter<'a, T: ?Sized> {
+    value: &'a T,
+    error: &'a Cell<Option<Error>>,
+}
+
+impl<T> fmt::Display for Adapter<'_, T>
+where
+    T: ?Sized + Serialize,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let serializer = Serializer {
+            f,
+            error: self.error,
+        };
+
+        self.value.serialize(serializer).map_err(|e| {
+            let first = self.error.take().unwrap_or(e);
+            self.error.set(Some(first));
+            fmt::Error
+        })
+    }
+}
+
+impl<T> fmt::Debug for Adapter<'_, T>
+where
+    T: ?Sized + Serialize,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+/// Tracks whether the inner writer has failed.
+struct TrackedWriter<'a, W: ?Sized> {
+    inner: &'a mut W,
+    failed: bool,
+}
+
+impl<W: ?Sized + fmt::Write> fmt::Write for TrackedWriter<'_, W> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        let result = self.inner.write_str(s);
+        self.failed |= result.is_err();
+        result
+    }
+}
+
+struct SliceWriter<'a> {
+    buffer: &'a mut [u8],
+    len: usize,
+}
+
+impl fmt::Write for SliceWriter<'_> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        let rest = &mut self.buffer[self.len..];
+        if s.len() > rest.len() {
+            // Mark the buffer as full so that the error can be identified.
+            self.len = self.buffer.len();
+            return Err(fmt::Error);
+        }
+
+        rest[..s.len()].copy_from_slice(s.as_bytes());
+        self.len += s.len();
+        Ok(())
+    }
+}
+
+fn check(result: fmt::Result) -> Result<(), Error> {
+    result.map_err(|_| Error::custom("an error occurred when formatting a value"))
+}
+
+struct Serializer<'a, 'b> {
+    f: &'a mut fmt::Formatter<'b>,
+    error: &'a Cell<Option<Error>>,
+}
+
+impl<'a, 'b> Serializer<'a, 'b> {
+    fn debug<T: fmt::Debug + ?Sized>(self, value: &T) -> Result<(), Error> {
+        check(value.fmt(self.f))
+    }
+
+    /// Write a value using a [`fmt::DebugTuple`] with a single field.
+    fn debug_tuple<T>(self, name: &str, value: &T) -> Result<(), Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        let value = Adapter {
+            value,
+            error: self.error,
+        };
+        check(self.f.debug_tuple(name).field(&value).finish())
+    }
+}
+
+impl<'a, 'b: 'a> ser::Serializer for Serializer<'a, 'b> {
+    type Ok = ();
+    type Error = Error;
+
+    type SerializeSeq = List<'a, 'b>;
+    type SerializeTuple = Tuple<'a, 'b>;
+    type SerializeTupleStruct = Tuple<'a, 'b>;
+    type SerializeTupleVariant = Tuple<'a, 'b>;
+    type SerializeMap = Map<'a, 'b>;
+    type SerializeStruct = Struct<'a, 'b>;
+    type SerializeStructVariant = Struct<'a, 'b>;
+
+    fn serialize_bool(self, v: bool) -> Result<(), Error> {
+        self.debug(&v)
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<(), Error> {
+        self.debug(&v)
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<(), Error> {
+        self.debug(&v)
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<(), Error> {
+        self.debug(&v)
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<(), Error> {
+        self.debug(&v)
+    }
+
+    fn serialize_i128(self, v: i128) -> Result<(), Error> {
+        self.debug(&v)
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<(), Error> {
+        self.debug(&v)
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<(), Error> {
+        self.debug(&v)
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<(), Error> {
+        self.debug(&v)
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<(), Error> {
+        self.debug(&v)
+    }
+
+    fn serialize_u128(self, v: u128) -> Result<(), Error> {
+        self.debug(&v)
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<(), Error> {
+        self.debug(&v)
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<(), Error> {
+        self.debug(&v)
+    }
+
+    fn serialize_char(self, v: char) -> Result<(), Error> {
+        self.debug(&v)
+    }
+
+    fn serialize_str(self, v: &str) -> Result<(), Error> {
+        self.debug(v)
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<(), Error> {
+        self.debug(v)
+    }
+
+    fn serialize_none(self) -> Result<(), Error> {
+        check(self.f.write_str("None"))
+    }
+
+    fn serialize_some<T>(self, value: &T) -> Result<(), Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.debug_tuple("Some", value)
+    }
+
+    fn serialize_unit(self) -> Result<(), Error> {
+        check(self.f.write_str("()"))
+    }
+
+    fn serialize_unit_struct(self, name: &'static str) -> Result<(), Error> {
+        check(self.f.write_str(name))
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+    ) -> Result<(), Error> {
+        check(self.f.write_str(variant))
+    }
+
+    fn serialize_newtype_struct<T>(self, name: &'static str, value: &T) -> Result<(), Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.debug_tuple(name, value)
+    }
+
+    fn serialize_newtype_variant<T>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<(), Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.debug_tuple(variant, value)
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<List<'a, 'b>, Error> {
+        Ok(List(self.f.debug_list(), self.error))
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<Tuple<'a, 'b>, Error> {
+        // Only `[T; 0]` serializes as an empty tuple (`()` is a unit), and an
+        // unnamed `DebugTuple` without fields writes nothing at all.
+        if len == 0 {
+            check(self.f.write_str("[]"))?;
+        }
+
+        Ok(Tuple(self.f.debug_tuple(""), self.error))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        name: &'static str,
+        _len: usize,
+    ) -> Result<Tuple<'a, 'b>, Error> {
+        Ok(Tuple(self.f.debug_tuple(name), self.error))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        _len: usize,
+    ) -> Result<Tuple<'a, 'b>, Error> {
+        Ok(Tuple(self.f.debug_tuple(variant), self.error))
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Map<'a, 'b>, Error> {
+        Ok(Map(self.f.debug_map(), self.error))
+    }
+
+    fn serialize_struct(self, name: &'static str, len: usize) -> Result<Struct<'a, 'b>, Error> {
+        // `Duration` serializes as a struct but has a custom debug format.
+        if name == "Duration" && len == 2 {
+            return Ok(Struct::Duration {
+                f: self.f,
+                secs: None,
+                nanos: None,
+            });
+        }
+
+        Ok(Struct::Fields(self.f.debug_struct(name), self.error))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        _len: usize,
+    ) -> Result<Struct<'a, 'b>, Error> {
+        Ok(Struct::Fields(self.f.debug_struct(variant), self.error))
+    }
+}
+
+struct List<'a, 'b>(fmt::DebugList<'a, 'b>, &'a Cell<Option<Error>>);
+
+impl ser::SerializeSeq for List<'_, '_> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_element<T>(&mut self, value: &T) -> Result<(), Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.0.entry(&Adapter {
+            value,
+            error: self.1,
+        });
+        Ok(())
+    }
+
+    fn end(mut self) -> Result<(), Error> {
+        check(self.0.finish())
+    }
+}
+
+struct Tuple<'a, 'b>(fmt::DebugTuple<'a, 'b>, &'a Cell<Option<Error>>);
+
+impl ser::SerializeTuple for Tuple<'_, '_> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_element<T>(&mut self, value: &T) -> Result<(), Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.0.field(&Adapter {
+            value,
+            error: self.1,
+        });
+        Ok(())
+    }
+
+    fn end(mut self) -> Result<(), Error> {
+        check(self.0.finish())
+    }
+}
+
+impl ser::SerializeTupleStruct for Tuple<'_, '_> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T>(&mut self, value: &T) -> Result<(), Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        ser::SerializeTuple::serialize_element(self, value)
+    }
+
+    fn end(self) -> Result<(), Error> {
+        ser::SerializeTuple::end(self)
+    }
+}
+
+impl ser::SerializeTupleVariant for Tuple<'_, '_> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T>(&mut self, value: &T) -> Result<(), Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        ser::SerializeTuple::serialize_element(self, value)
+    }
+
+    fn end(self) -> Result<(), Error> {
+        ser::SerializeTuple::end(self)
+    }
+}
+
+struct Map<'a, 'b>(fmt::DebugMap<'a, 'b>, &'a Cell<Option<Error>>);
+
+impl ser::SerializeMap for Map<'_, '_> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_key<T>(&mut self, key: &T) -> Result<(), Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.0.key(&Adapter {
+            value: key,
+            error: self.1,
+        });
+        Ok(())
+    }
+
+    fn serialize_value<T>(&mut self, value: &T) -> Result<(), Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.0.value(&Adapter {
+            value,
+            error: self.1,
+        });
+        Ok(())
+    }
+
+    fn end(mut self) -> Result<(), Error> {
+        check(self.0.finish())
+    }
+}
+
+enum Struct<'a, 'b> {
+    Fields(fmt::DebugStruct<'a, 'b>, &'a Cell<Option<Error>>),
+    Duration {
+        f: &'a mut fmt::Formatter<'b>,
+        secs: Option<u64>,
+        nanos: Option<u32>,
+    },
+}
+
+/// Read back an integer field of a `Duration` by formatting it.
+fn duration_field<T, V>(key: &str, value: &V) -> Result<T, Error>
+where
+    T: core::str::FromStr,
+    V: ?Sized + Serialize,
+{
+    let mut buffer = [0u8; 20];
+    to_slice(&mut buffer, value)
+        .ok()
+        .and_then(|len| core::str::from_utf8(&buffer[..len]).ok()?.parse().ok())
+        .ok_or_else(|| Error::custom(format_args!("invalid Duration field `{key}`")))
+}
+
+impl ser::SerializeStruct for Struct<'_, '_> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T>(&mut self, key: &'static str, value: &T) -> Result<(), Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        match self {
+            Self::Fields(debug, error) => {
+                debug.field(key, &Adapter { value, error });
+            }
+            Self::Duration { secs, .. } if key == "secs" => {
+                *secs = Some(duration_field(key, value)?)
+            }
+            Self::Duration { nanos, .. } if key == "nanos" => {
+                *nanos = Some(duration_field(key, value)?)
+            }
+            Self::Duration { .. } => {
+                return Err(Error::custom(format_args!(
+                    "unexpected Duration field `{key}`"
+                )))
+            }
+        }
+
+        Ok(())
+    }
+
+    fn end(self) -> Result<(), Error> {
+        match self {
+            Self::Fields(mut debug, _) => check(debug.finish()),
+            Self::Duration {
+                f,
+                secs: Some(secs),
+                nanos: Some(nanos),
+            } if nanos < 1_000_000_000 => check(fmt::Debug::fmt(&Duration::new(secs, nanos), f)),
+            Self::Duration { .. } => Err(Error::custom("invalid Duration")),
+        }
+    }
+}
+
+impl ser::SerializeStructVariant for Struct<'_, '_> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T>(&mut self, key: &'static str, value: &T) -> Result<(), Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        ser::SerializeStruct::serialize_field(self, key, value)
+    }
+
+    fn end(self) -> Result<(), Error> {
+        ser::SerializeStruct::end(self)
+    }
+}
@@ -0,0 +1,87 @@
+use alloc::vec::Vec;
+
+/// Statistics about the input collected while deserializing.
+///
+/// Collection is disabled by default since it has a small cost for every
+/// value. Enable it with [`Options::collect_stats`] and then read the results
+/// using [`Deserializer::stats`] once the value has been deserialized. This is
+/// useful for profiling production inputs and for picking sensible limits.
+///
+/// The statistics only cover the parts of the input that have actually been
+/// deserialized and are only complete if deserialization succeeded.
+///
+/// # Example
+/// ```
+/// use serde_dbgfmt::{Deserializer, Options};
+/// use serde::Deserialize;
+///
+/// let options = Options::new().collect_stats(true);
+/// let mut de = Deserializer::with_options(r#"([[1, 2], [3]], "a\tb")"#, options);
+/// let _: (Vec<Vec<u32>>, String) = Deserialize::deserialize(&mut de).unwrap();
+/// de.end().unwrap();
+///
+/// let stats = de.stats().unwrap();
+/// assert_eq!(stats.tokens, 15);
+/// assert_eq!(stats.max_depth, 3);
+/// assert_eq!(stats.collection_sizes, [2, 1, 2, 2]);
+/// assert_eq!(stats.unescaped_bytes, 3);
+/// assert_eq!((stats.borrowed_strings, stats.owned_strings), (0, 1));
+/// ```
+///
+/// [`Options::collect_stats`]: crate::Options::collect_stats
+/// [`Deserializer::stats`]: crate::Deserializer::stats
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Stats {
+    /// The number of tokens parsed.
+    pub tokens: usize,
+
+    /// The deepest level of nested collections (structs, tuples, sequences,
+    /// and maps) encountered.
+    pub max_depth: usize,
+
+    /// The number of elements in each collection, in the order that the
+    /// collections were closed.
+    pub collection_sizes: Vec<usize>,
+
+    /// The total length of all strings and characters which contained escape
+    /// sequences, after unescaping them.
+    pub unescaped_bytes: usize,
+
+    /// The number of strings that were passed to the `Deserialize` impl as a
+    /// slice of the input.
+    pub borrowed_strings: usize,
+
+    /// The number of strings that contained escape sequences and so had to be
+    /// copied out of the input before being passed to the `Deserialize` impl.
+    pub owned_strings: usize,
+}
+
+/// Bookkeeping for collecting [`Stats`].
+#[derive(Clone, Debug, Default)]
+pub(crate) struct StatsCollector {
+    pub(crate) stats: Stats,
+
+    /// The number of elements seen so far in each of the collections that are
+    /// currently open.
+    open: Vec<usize>,
+}
+
+impl StatsCollector {
+    pub(crate) fn enter(&mut self) {
+        self.open.push(0);
+        self.stats.max_depth = self.stats.max_depth.max(self.open.len());
+    }
+
+    pub(crate) fn element(&mut self) {
+        if let Some(count) = self.open.last_mut() {
+            *count += 1;
+        }
+    }
+
+    pub(crate) fn leave(&mut self) {
+        if let Some(count) = self.open.pop() {
+            self.stats.collection_sizes.push(count);
+        }
+    }
+}
@@ -0,0 +1,88 @@
+use core::iter::FusedIterator;
+use core::marker::PhantomData;
+
+use serde::Deserialize;
+
+use crate::de::Nested;
+use crate::{Deserializer, Error};
+
+/// An iterator over a sequence of values separated by whitespace.
+///
+/// This is created by [`Deserializer::into_iter`]. Each call to `next`
+/// deserializes one value and returns `None` once only whitespace is left.
+/// Iteration stops after the first error since the rest of the input can no
+/// longer be split into values reliably.
+///
+/// # Example
+/// ```
+/// use serde::Deserialize;
+/// use serde_dbgfmt::Deserializer;
+///
+/// #[derive(Debug, Deserialize, PartialEq)]
+/// struct Event {
+///     id: u32,
+/// }
+///
+/// let log = "Event { id: 1 }\nEvent { id: 2 }\n";
+/// let events: Vec<Event> = Deserializer::new(log)
+///     .into_iter()
+///     .collect::<Result<_, _>>()
+///     .unwrap();
+///
+/// assert_eq!(events, [Event { id: 1 }, Event { id: 2 }]);
+/// ```
+pub struct StreamDeserializer<'de, T> {
+    de: Deserializer<'de>,
+    input: &'de str,
+    offset: usize,
+    done: bool,
+    _marker: PhantomData<fn() -> T>,
+}
+
+impl<'de, T> StreamDeserializer<'de, T>
+where
+    T: Deserialize<'de>,
+{
+    pub(crate) fn new(de: Deserializer<'de>) -> Self {
+        Self {
+            input: de.remaining(),
+            de,
+            offset: 0,
+            done: false,
+            _marker: PhantomData,
+        }
+    }
+
+    /// The number of bytes of the input that have been successfully
+    /// deserialized so far.
+    ///
+    /// After an error this is the end of the last value that was deserialized
+    /// successfully.
+    pub fn byte_offset(&self) -> usize {
+        self.offset
+    }
+}
+
+impl<'de, T> Iterator for StreamDeserializer<'de, T>
+where
+    T: Deserialize<'de>,
+{
+    type Item = Result<T, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done || self.de.remaining().trim_start().is_empty() {
+            self.done = true;
+            return None;
+        }
+
+        let result = T::deserialize(Nested(&mut self.de)).map_err(|e| self.de.locate(e));
+        match result {
+            Ok(_) => self.offset = self.input.len() - self.de.remaining().len(),
+            Err(_) => self.done = true,
+        }
+
+        Some(result)
+    }
+}
+
+impl<'de, T> FusedIterator for StreamDeserializer<'de, T> where T: Deserialize<'de> {}
@@ -0,0 +1,113 @@
+use alloc::collections::BTreeMap;
+use alloc::string::String;
+use core::fmt;
+
+use crate::tree::{Node, NodeKind};
+use crate::{Error, Options};
+
+/// An overview of the contents of some debug-formatted text.
+///
+/// This is returned by [`summarize`]. It is meant to help characterize an
+/// unfamiliar dump before writing types to deserialize it into. The
+/// [`Display`](fmt::Display) impl prints a short human-readable report.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Summary {
+    /// The total number of values, including nested ones.
+    pub nodes: usize,
+
+    /// The number of values of each kind (e.g. `struct`, `list`, `integer`).
+    pub kinds: BTreeMap<&'static str, usize>,
+
+    /// The number of times each struct or enum variant name appears.
+    pub names: BTreeMap<String, usize>,
+
+    /// The deepest level of nesting. A value with no children has a depth
+    /// of 1.
+    pub max_depth: usize,
+
+    /// The total length of all string values in bytes, after unescaping.
+    pub string_bytes: usize,
+}
+
+/// Produce a [`Summary`] of the value in `input`.
+///
+/// This works on any debug-formatted value and does not need to know the
+/// types it was printed from.
+///
+/// # Example
+/// ```
+/// let text = r#"Config { name: "prod", hosts: [Host { port: 80 }, Host { port: 443 }], mode: Fast }"#;
+/// let summary = serde_dbgfmt::summarize(text).unwrap();
+///
+/// assert_eq!(summary.names["Host"], 2);
+/// assert_eq!(summary.kinds["integer"], 2);
+/// assert_eq!(summary.max_depth, 4);
+/// assert_eq!(summary.string_bytes, 4);
+/// ```
+pub fn summarize(input: &str) -> Result<Summary, Error> {
+    Summary::of(&Node::parse(input)?)
+}
+
+/// Produce a [`Summary`] of the value in `input`, using the provided options
+/// to lex it.
+///
+/// The same options apply as for
+/// [`canonicalize_with`](crate::canonicalize_with).
+pub fn summarize_with(input: &str, options: &Options) -> Result<Summary, Error> {
+    Summary::of(&Node::parse_with(input, options)?)
+}
+
+impl Summary {
+    fn of(node: &Node) -> Result<Self, Error> {
+        let mut summary = Self::default();
+        summary.visit(node, 1)?;
+        Ok(summary)
+    }
+}
+
+impl Summary {
+    fn visit(&mut self, node: &Node, depth: usize) -> Result<(), Error> {
+        self.nodes += 1;
+        self.max_depth = self.max_depth.max(depth);
+        *self.kinds.entry(node.kind_name()).or_default() += 1;
+
+        if let Some(name) = node.name() {
+            *self.names.entry(name.into()).or_default() += 1;
+        }
+
+        if let NodeKind::String = node.kind {
+            self.string_bytes += node.string()?.len();
+        }
+
+        let mut result = Ok(());
+        node.for_each_child(|child| {
+            if result.is_ok() {
+                result = self.visit(child, depth + 1);
+            }
+        });
+        result
+    }
+}
+
+impl fmt::Display for Summary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "nodes: {}", self.nodes)?;
+        writeln!(f, "max depth: {}", self.max_depth)?;
+        writeln!(f, "string bytes: {}", self.string_bytes)?;
+
+        writeln!(f, "kinds:")?;
+        for (kind, count) in &self.kinds {
+            writeln!(f, "  {kind}: {count}")?;
+        }
+
+        if !self.names.is_empty() {
+            writeln!(f, "names:")?;
+            for (name, count) in &self.names {
+                writeln!(f, "  {name}: {count}")?;
+            }
+        }
+
+        Ok(())
+    }
+}
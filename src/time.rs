@@ -0,0 +1,121 @@
+//! Parsing for the debug output of [`Instant`] and [`SystemTime`].
+//!
+//! Neither type has a stable debug representation. They print the fields of
+//! the platform-specific value they wrap, which looks like one of these:
+//!
+//! ```text
+//! SystemTime { tv_sec: 1700000000, tv_nsec: 5 }          // Unix
+//! Instant { t: Timespec { tv_sec: 12, tv_nsec: 5 } }     // Older Unix
+//! Instant { t: 12.000000005s }                           // Windows
+//! SystemTime { intervals: 133444736000000000 }           // Windows
+//! ```
+//!
+//! [`Instant`]: std::time::Instant
+
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::literal::{parse_duration, parse_int_literal};
+use crate::tree::{Node, NodeKind};
+use crate::Error;
+
+/// The number of 100ns intervals between the Windows epoch (1601-01-01) and
+/// the Unix epoch.
+const WINDOWS_EPOCH_OFFSET: i128 = 116_444_736_000_000_000;
+
+/// The value wrapped by an `Instant` or a `SystemTime`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub(crate) enum RawTime {
+    /// A `timespec`, used on Unix platforms. For a `SystemTime` this is
+    /// relative to the Unix epoch.
+    Timespec { secs: i64, nanos: u32 },
+    /// A duration since an unspecified point, used by `Instant` on Windows.
+    Duration(Duration),
+    /// The number of 100ns intervals since 1601-01-01, used by `SystemTime`
+    /// on Windows.
+    Intervals(u64),
+}
+
+impl RawTime {
+    /// Parse the debug output of a struct called `name` wrapping a time.
+    pub fn parse(text: &str, name: &str) -> Result<Self, Error> {
+        let node = Node::parse(text)?;
+        match node.kind {
+            NodeKind::Struct { name: found, .. } if found == name => Self::from_node(&node),
+            _ => Err(invalid(name, text)),
+        }
+    }
+
+    fn from_node(node: &Node) -> Result<Self, Error> {
+        let fields = match &node.kind {
+            NodeKind::Struct { fields, .. } => fields.as_slice(),
+            NodeKind::Other => {
+                return parse_duration(node.span)
+                    .map(Self::Duration)
+                    .ok_or_else(|| invalid("Duration", node.span));
+            }
+            _ => &[],
+        };
+
+        let int = |node: &Node| -> Result<i128, Error> {
+            match node.kind {
+                NodeKind::Integer => parse_int_literal(node.span),
+                _ => Err(invalid("integer", node.span)),
+            }
+        };
+
+        match fields {
+            [("t", inner)] => Self::from_node(inner),
+            [("intervals", intervals)] => Ok(Self::Intervals(
+                int(intervals)?
+                    .try_into()
+                    .map_err(|_| invalid("intervals", intervals.span))?,
+            )),
+            [("tv_sec", secs), ("tv_nsec", nanos)] => Ok(Self::Timespec {
+                secs: int(secs)?
+                    .try_into()
+                    .map_err(|_| invalid("tv_sec", secs.span))?,
+                nanos: int(nanos)?
+                    .try_into()
+                    .ok()
+                    .filter(|&nanos| nanos < 1_000_000_000)
+                    .ok_or_else(|| invalid("tv_nsec", nanos.span))?,
+            }),
+            _ => Err(invalid("time", node.span)),
+        }
+    }
+
+    /// Interpret this as a `SystemTime`.
+    pub fn to_system_time(self) -> Option<SystemTime> {
+        let nanos = match self {
+            Self::Timespec { secs, nanos } => i128::from(secs) * 1_000_000_000 + i128::from(nanos),
+            Self::Intervals(intervals) => (i128::from(intervals) - WINDOWS_EPOCH_OFFSET) * 100,
+            Self::Duration(_) => return None,
+        };
+
+        let offset = |nanos: i128| {
+            let nanos = u128::try_from(nanos).ok()?;
+            let secs = u64::try_from(nanos / 1_000_000_000).ok()?;
+            Some(Duration::new(secs, (nanos % 1_000_000_000) as u32))
+        };
+
+        match nanos < 0 {
+            true => UNIX_EPOCH.checked_sub(offset(-nanos)?),
+            false => UNIX_EPOCH.checked_add(offset(nanos)?),
+        }
+    }
+
+    /// Interpret this as the time elapsed since the unspecified point that an
+    /// `Instant` is measured from.
+    pub fn to_instant_offset(self) -> Option<Duration> {
+        match self {
+            Self::Timespec { secs, nanos } => Some(Duration::new(secs.try_into().ok()?, nanos)),
+            Self::Duration(duration) => Some(duration),
+            Self::Intervals(_) => None,
+        }
+    }
+}
+
+#[cold]
+fn invalid(what: &str, text: &str) -> Error {
+    serde::de::Error::custom(format_args!("invalid {what} `{text}`"))
+}
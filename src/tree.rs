@@ -0,0 +1,477 @@
+//! A generic syntax tree for debug-formatted text.
+//!
+//! This is used by the helpers that need to look at the structure of the
+//! input without knowing the types it was printed from. Each node borrows its
+//! span of the source text so the original formatting can be recovered.
+
+use alloc::borrow::Cow;
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::lex::{is_operator, is_terminator, Lexer, Token, TokenKind};
+use crate::util::unescape;
+use crate::{Error, Options};
+
+/// The nesting depth used when the options do not set one.
+///
+/// The helpers built on [`Node`] walk it recursively, so unlike when
+/// deserializing there has to be some limit to keep deeply nested input from
+/// overflowing the stack.
+const DEFAULT_MAX_DEPTH: usize = 128;
+
+#[derive(Clone, Debug)]
+pub(crate) struct Node<'a> {
+    /// The text making up this node, without any surrounding whitespace.
+    pub span: &'a str,
+    pub kind: NodeKind<'a>,
+}
+
+#[derive(Clone, Debug)]
+pub(crate) enum NodeKind<'a> {
+    /// `Name { field: value, .. }`
+    Struct {
+        name: &'a str,
+        fields: Vec<(&'a str, Node<'a>)>,
+        non_exhaustive: bool,
+    },
+    /// `Name(a, b)`
+    TupleStruct {
+        name: &'a str,
+        elements: Vec<Node<'a>>,
+    },
+    /// A bare identifier: unit structs, unit variants, and booleans.
+    Ident(&'a str),
+    /// `(a, b)`
+    Tuple(Vec<Node<'a>>),
+    /// `[a, b]`
+    List(Vec<Node<'a>>),
+    /// `{a, b}`
+    Set(Vec<Node<'a>>),
+    /// `{k: v}`. Empty braces are always parsed as a map.
+    Map(Vec<(Node<'a>, Node<'a>)>),
+    Integer,
+    Float,
+    String,
+    Char,
+    /// Values built from operators, such as ranges (`1..5`) or flags
+    /// (`A | B`), and durations (`1.5s`). Only the span is available for
+    /// these.
+    Other,
+}
+
+impl<'a> Node<'a> {
+    /// Parse `input` as a single value.
+    pub fn parse(input: &'a str) -> Result<Self, Error> {
+        Self::parse_lexer(Lexer::new(input), DEFAULT_MAX_DEPTH)
+    }
+
+    /// Parse `input` as a single value, using the hooks and checks from
+    /// `options` while lexing it and the nesting limit from
+    /// [`Options::max_depth`].
+    pub fn parse_with(input: &'a str, options: &Options) -> Result<Self, Error> {
+        let mut lexer = Lexer::new(input);
+        lexer.set_options(input, options);
+
+        let limit = options.config().max_depth.unwrap_or(DEFAULT_MAX_DEPTH);
+        Self::parse_lexer(lexer, limit)
+    }
+
+    fn parse_lexer(lexer: Lexer<'a>, limit: usize) -> Result<Self, Error> {
+        let mut parser = Parser {
+            lexer,
+            stack: Vec::new(),
+            depth: 0,
+            limit,
+        };
+
+        let node = parser.parse()?;
+        let token = parser.lexer.parse_token()?;
+        if token.kind != TokenKind::Eof {
+            return Err(Error::unexpected_token(token, TokenKind::Eof));
+        }
+
+        Ok(node)
+    }
+
+    /// The name of the struct or enum variant that this node was printed
+    /// from, if it has one.
+    pub fn name(&self) -> Option<&'a str> {
+        match self.kind {
+            NodeKind::Struct { name, .. } | NodeKind::TupleStruct { name, .. } => Some(name),
+            NodeKind::Ident(name) if !matches!(name, "true" | "false") => Some(name),
+            _ => None,
+        }
+    }
+
+    /// The contents of a string node, with escape sequences resolved.
+    ///
+    /// Strings produced by [`Options::on_unknown_token`] have no quotes and are
+    /// used as-is.
+    pub fn string(&self) -> Result<Cow<'a, str>, Error> {
+        match self
+            .span
+            .strip_prefix('"')
+            .and_then(|s| s.strip_suffix('"'))
+        {
+            Some(inner) => unescape(inner),
+            None => Ok(Cow::Borrowed(self.span)),
+        }
+    }
+
+    /// A short description of the kind of this node.
+    pub fn kind_name(&self) -> &'static str {
+        match self.kind {
+            NodeKind::Struct { .. } => "struct",
+            NodeKind::TupleStruct { .. } => "tuple struct",
+            NodeKind::Ident("true" | "false") => "bool",
+            NodeKind::Ident(_) => "unit",
+            NodeKind::Tuple(_) => "tuple",
+            NodeKind::List(_) => "list",
+            NodeKind::Set(_) => "set",
+            NodeKind::Map(_) => "map",
+            NodeKind::Integer => "integer",
+            NodeKind::Float => "float",
+            NodeKind::String => "string",
+            NodeKind::Char => "char",
+            NodeKind::Other => "other",
+        }
+    }
+
+    /// Call `func` with each of the direct children of this node.
+    ///
+    /// Map entries produce both the key and the value.
+    pub fn for_each_child(&self, mut func: impl FnMut(&Node<'a>)) {
+        match &self.kind {
+            NodeKind::Struct { fields, .. } => fields.iter().for_each(|(_, node)| func(node)),
+            NodeKind::TupleStruct { elements, .. }
+            | NodeKind::Tuple(elements)
+            | NodeKind::List(elements)
+            | NodeKind::Set(elements) => elements.iter().for_each(func),
+            NodeKind::Map(entries) => entries.iter().for_each(|(key, value)| {
+                func(key);
+                func(value);
+            }),
+            _ => (),
+        }
+    }
+}
+
+/// A value whose children are still being parsed.
+enum Frame<'a> {
+    Struct {
+        name: &'a str,
+        fields: Vec<(&'a str, Node<'a>)>,
+        /// The name of the field whose value is being parsed.
+        field: &'a str,
+        non_exhaustive: bool,
+    },
+    TupleStruct {
+        name: &'a str,
+        elements: Vec<Node<'a>>,
+    },
+    Tuple(Vec<Node<'a>>),
+    List(Vec<Node<'a>>),
+    /// A non-empty `{` that could still be either a set or a map.
+    Braced,
+    Set(Vec<Node<'a>>),
+    Map {
+        entries: Vec<(Node<'a>, Node<'a>)>,
+        key: Option<Node<'a>>,
+    },
+    /// A value built from operators. Its operands are parsed to check that
+    /// they are valid but only the span of the whole expression is kept.
+    Operators,
+}
+
+impl<'a> Frame<'a> {
+    fn close(&self) -> &'static str {
+        match self {
+            Self::TupleStruct { .. } | Self::Tuple(_) => ")",
+            Self::List(_) => "]",
+            _ => "}",
+        }
+    }
+
+    fn finish(self) -> NodeKind<'a> {
+        match self {
+            Self::Struct {
+                name,
+                fields,
+                non_exhaustive,
+                ..
+            } => NodeKind::Struct {
+                name,
+                fields,
+                non_exhaustive,
+            },
+            Self::TupleStruct { name, elements } => NodeKind::TupleStruct { name, elements },
+            Self::Tuple(elements) => NodeKind::Tuple(elements),
+            Self::List(elements) => NodeKind::List(elements),
+            Self::Set(elements) => NodeKind::Set(elements),
+            Self::Braced => NodeKind::Map(Vec::new()),
+            Self::Map { entries, .. } => NodeKind::Map(entries),
+            Self::Operators => NodeKind::Other,
+        }
+    }
+}
+
+/// Parses a [`Node`] one token at a time.
+///
+/// Open values are kept on an explicit stack instead of recursing, so the
+/// cost of each token does not depend on how deeply it is nested. The
+/// nesting depth is still limited since the helpers built on [`Node`] walk
+/// it recursively.
+struct Parser<'a> {
+    lexer: Lexer<'a>,
+    /// The open values, along with the input starting at each one.
+    stack: Vec<(&'a str, Frame<'a>)>,
+    /// The number of collections that the parser is currently within.
+    depth: usize,
+    limit: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Result<Token<'a>, Error> {
+        Ok(self.lexer.peek_token()?)
+    }
+
+    fn expect(&mut self, punct: &'static str) -> Result<(), Error> {
+        let token = self.lexer.parse_token()?;
+        match token.is_punct(punct) {
+            true => Ok(()),
+            false => Err(Error::unexpected_token(token, punct)),
+        }
+    }
+
+    /// The input starting at the next token, after any text that was skipped
+    /// by [`Options::on_unknown_token`].
+    fn start(&self) -> Result<&'a str, Error> {
+        let token = self.peek()?;
+        let rest = self.lexer.remaining();
+        Ok(&rest[token.value.as_ptr() as usize - rest.as_ptr() as usize..])
+    }
+
+    /// The text between `start` and the current position of the lexer.
+    fn span_from(&self, start: &'a str) -> &'a str {
+        &start[..start.len() - self.lexer.remaining().len()]
+    }
+
+    fn parse(&mut self) -> Result<Node<'a>, Error> {
+        loop {
+            let start = self.start()?;
+            let operand = matches!(self.stack.last(), Some((_, Frame::Operators)));
+
+            let node = match self.peek()? {
+                // Ranges may have no start bound.
+                token if is_operator(&token) && !operand => {
+                    self.stack.push((start, Frame::Operators));
+                    None
+                }
+                _ => self.parse_primary(start)?,
+            };
+            let (mut start, mut node) = match node {
+                Some(node) => (start, node),
+                None => match self.next_element()? {
+                    Some(complete) => complete,
+                    None => continue,
+                },
+            };
+
+            // Add the node to the value that contains it. This may in turn
+            // complete that value, and so on.
+            loop {
+                let operand = matches!(self.stack.last(), Some((_, Frame::Operators)));
+                if !operand && is_operator(&self.peek()?) {
+                    self.stack.push((start, Frame::Operators));
+                } else if self.stack.is_empty() {
+                    return Ok(node);
+                } else {
+                    self.push(node)?;
+                }
+
+                match self.next_element()? {
+                    Some(complete) => (start, node) = complete,
+                    None => break,
+                }
+            }
+        }
+    }
+
+    /// Parse a value that is not built from operators. If the value has
+    /// children then it is pushed onto the stack and `None` is returned.
+    fn parse_primary(&mut self, start: &'a str) -> Result<Option<Node<'a>>, Error> {
+        let token = self.lexer.parse_token()?;
+
+        let kind = match token.kind {
+            TokenKind::Ident if matches!(token.value, "NaN" | "inf") => NodeKind::Float,
+            TokenKind::Ident => match self.peek()? {
+                next if next.is_punct("{") => {
+                    self.lexer.parse_token()?;
+                    return self.open(
+                        start,
+                        Frame::Struct {
+                            name: token.value,
+                            fields: Vec::new(),
+                            field: "",
+                            non_exhaustive: false,
+                        },
+                    );
+                }
+                next if next.is_punct("(") => {
+                    self.lexer.parse_token()?;
+                    return self.open(
+                        start,
+                        Frame::TupleStruct {
+                            name: token.value,
+                            elements: Vec::new(),
+                        },
+                    );
+                }
+                _ => NodeKind::Ident(token.value),
+            },
+            TokenKind::Integer | TokenKind::Address => NodeKind::Integer,
+            TokenKind::Float => NodeKind::Float,
+            TokenKind::String => NodeKind::String,
+            TokenKind::Char => NodeKind::Char,
+            TokenKind::Duration => NodeKind::Other,
+            TokenKind::Punct => match token.value {
+                "+" | "-" => {
+                    let number = self.lexer.parse_token()?;
+                    match number.kind {
+                        TokenKind::Integer => NodeKind::Integer,
+                        TokenKind::Float => NodeKind::Float,
+                        TokenKind::Ident if number.value == "inf" => NodeKind::Float,
+                        _ => return Err(Error::unexpected_token(number, "a number")),
+                    }
+                }
+                "(" => return self.open(start, Frame::Tuple(Vec::new())),
+                "[" => return self.open(start, Frame::List(Vec::new())),
+                // Empty braces are always a map, and do not count towards the
+                // nesting depth.
+                "{" if self.peek()?.is_punct("}") => {
+                    self.lexer.parse_token()?;
+                    NodeKind::Map(Vec::new())
+                }
+                "{" => return self.open(start, Frame::Braced),
+                _ => return Err(Error::unexpected_token(token, "a value")),
+            },
+            TokenKind::Eof => return Err(Error::unexpected_token(token, "a value")),
+        };
+
+        Ok(Some(Node {
+            span: self.span_from(start),
+            kind,
+        }))
+    }
+
+    /// Push a collection onto the stack, failing if that would nest it too
+    /// deeply.
+    fn open(&mut self, start: &'a str, frame: Frame<'a>) -> Result<Option<Node<'a>>, Error> {
+        if self.depth >= self.limit {
+            return Err(Error::depth_limit_exceeded(self.limit));
+        }
+
+        self.depth += 1;
+        self.stack.push((start, frame));
+        Ok(None)
+    }
+
+    /// Add a complete node to the innermost value, along with the separator
+    /// that follows it.
+    fn push(&mut self, node: Node<'a>) -> Result<(), Error> {
+        let (_, frame) = self.stack.last_mut().expect("no value is open");
+
+        match frame {
+            Frame::Struct { fields, field, .. } => fields.push((*field, node)),
+            Frame::TupleStruct { elements, .. }
+            | Frame::Tuple(elements)
+            | Frame::List(elements)
+            | Frame::Set(elements) => elements.push(node),
+            Frame::Braced if self.lexer.peek_token()?.is_punct(":") => {
+                *frame = Frame::Map {
+                    entries: Vec::new(),
+                    key: Some(node),
+                };
+                return self.expect(":");
+            }
+            Frame::Braced => *frame = Frame::Set(vec![node]),
+            Frame::Map {
+                key: key @ None, ..
+            } => {
+                *key = Some(node);
+                return self.expect(":");
+            }
+            Frame::Map { entries, key } => entries.push((key.take().unwrap(), node)),
+            Frame::Operators => return Ok(()),
+        }
+
+        let close = frame.close();
+        match self.peek()?.is_punct(close) {
+            true => Ok(()),
+            false => self.expect(","),
+        }
+    }
+
+    /// Prepare to parse the next child of the innermost value.
+    ///
+    /// If the value has ended then it is removed from the stack and returned,
+    /// along with the input starting at it.
+    fn next_element(&mut self) -> Result<Option<(&'a str, Node<'a>)>, Error> {
+        let (start, frame) = self.stack.last_mut().expect("no value is open");
+        let start = *start;
+
+        match frame {
+            Frame::Operators => {
+                while is_operator(&self.peek()?) {
+                    self.lexer.parse_token()?;
+
+                    // Ranges may also have no end bound.
+                    if !is_terminator(&self.peek()?) {
+                        return Ok(None);
+                    }
+                }
+
+                self.stack.pop();
+                return Ok(Some((
+                    start,
+                    Node {
+                        span: self.span_from(start),
+                        kind: NodeKind::Other,
+                    },
+                )));
+            }
+            Frame::Struct {
+                field,
+                non_exhaustive,
+                ..
+            } if !self.lexer.peek_token()?.is_punct("}") => {
+                let token = self.lexer.parse_token()?;
+                if token.is_punct("..") {
+                    *non_exhaustive = true;
+                } else if token.kind != TokenKind::Ident {
+                    return Err(Error::unexpected_token(token, TokenKind::Ident));
+                } else {
+                    *field = token.value;
+                    self.expect(":")?;
+                    return Ok(None);
+                }
+            }
+            Frame::Braced | Frame::Map { key: Some(_), .. } => return Ok(None),
+            _ if !self.lexer.peek_token()?.is_punct(frame.close()) => return Ok(None),
+            _ => (),
+        }
+
+        let close = frame.close();
+        self.expect(close)?;
+
+        let (start, frame) = self.stack.pop().expect("no value is open");
+        self.depth -= 1;
+        Ok(Some((
+            start,
+            Node {
+                span: self.span_from(start),
+                kind: frame.finish(),
+            },
+        )))
+    }
+}
@@ -0,0 +1,325 @@
+//! Standalone helpers for working with debug-formatted text.
+
+use alloc::borrow::Cow;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use crate::Error;
+
+/// Escape `text` in the same way as the [`Debug`] impl for [`str`] does.
+///
+/// The surrounding quotes are not included in the output. If `text` does not
+/// contain any characters that need escaping then it is returned as-is
+/// without allocating.
+///
+/// This is the inverse of [`unescape`].
+///
+/// # Example
+/// ```
+/// use serde_dbgfmt::util::escape;
+///
+/// assert_eq!(escape("say \"hi\"\n"), r#"say \"hi\"\n"#);
+/// assert_eq!(escape("plain"), "plain");
+/// ```
+///
+/// [`Debug`]: std::fmt::Debug
+pub fn escape(text: &str) -> Cow<'_, str> {
+    // Delegating to the Debug impl means that the output will always match what
+    // the standard library emits, even if the exact escaping rules change.
+    let quoted = format!("{text:?}");
+
+    // Escaping always makes the text longer so if the length is unchanged then
+    // nothing needed to be escaped.
+    match quoted.len() - 2 == text.len() {
+        true => Cow::Borrowed(text),
+        false => Cow::Owned(quoted[1..quoted.len() - 1].into()),
+    }
+}
+
+/// Undo the escaping done when a string or character is printed using its
+/// [`Debug`] impl.
+///
+/// `text` should not include the surrounding quotes. If `text` does not
+/// contain any escape sequences then it is returned as-is without allocating.
+///
+/// # Example
+/// ```
+/// use serde_dbgfmt::util::unescape;
+///
+/// let text = unescape(r"tab\t and \u{1f980}").unwrap();
+/// assert_eq!(text, "tab\t and \u{1f980}");
+/// ```
+///
+/// [`Debug`]: std::fmt::Debug
+pub fn unescape(text: &str) -> Result<Cow<'_, str>, Error> {
+    unescape_with(text, |_| None)
+}
+
+/// The same as [`unescape`], except that `surrogate` is called with any
+/// `\u{..}` escapes of a lone surrogate to get the character to use instead.
+/// If it returns `None` then an error is returned as usual.
+pub(crate) fn unescape_with<F>(text: &str, surrogate: F) -> Result<Cow<'_, str>, Error>
+where
+    F: FnMut(&str) -> Option<char>,
+{
+    if !text.contains('\\') {
+        return Ok(Cow::Borrowed(text));
+    }
+
+    let mut escaped = String::new();
+    unescape_into(text, &mut escaped, surrogate)?;
+    Ok(escaped.into())
+}
+
+/// The same as [`unescape_with`], except that the unescaped text is appended
+/// to `escaped`.
+pub(crate) fn unescape_into<F>(
+    mut text: &str,
+    escaped: &mut String,
+    mut surrogate: F,
+) -> Result<(), Error>
+where
+    F: FnMut(&str) -> Option<char>,
+{
+    let mut next = match text.find('\\') {
+        Some(pos) => pos,
+        None => {
+            escaped.push_str(text);
+            return Ok(());
+        }
+    };
+
+    loop {
+        let (head, rest) = text.split_at(next);
+        escaped.push_str(head);
+        text = rest;
+
+        // The escape sequence is identified by the backslash and the character
+        // following it.
+        let prefix = match text.char_indices().nth(2) {
+            Some((index, _)) => &text[..index],
+            None => text,
+        };
+
+        let (c, len) = match prefix {
+            r"\0" => ('\0', 2),
+            r"\t" => ('\t', 2),
+            r"\r" => ('\r', 2),
+            r"\n" => ('\n', 2),
+            r"\\" => ('\\', 2),
+            r"\'" => ('\'', 2),
+            "\\\"" => ('"', 2),
+            r"\x" => {
+                let escape = text.get(..4).unwrap_or(text);
+                let c = escape
+                    .get(2..)
+                    .filter(|digits| digits.len() == 2)
+                    .filter(|digits| digits.bytes().all(|b| b.is_ascii_hexdigit()))
+                    .and_then(|digits| u8::from_str_radix(digits, 16).ok())
+                    .filter(u8::is_ascii)
+                    .ok_or_else(|| Error::invalid_string_literal(escape, "invalid hex escape"))?;
+
+                (char::from(c), 4)
+            }
+            r"\u" => {
+                let rest = &text[2..]
+                    .strip_prefix('{')
+                    .ok_or_else(|| Error::invalid_string_literal(text, "invalid unicode escape"))?;
+
+                let offset = rest
+                    .chars()
+                    .position(|x| !x.is_ascii_hexdigit())
+                    .unwrap_or(rest.len());
+                let (digits, rest) = rest.split_at(offset);
+
+                rest.strip_prefix('}')
+                    .ok_or_else(|| Error::invalid_string_literal(text, "invalid unicode escape"))?;
+
+                let escape = &text[..digits.len() + 4];
+                let code = u32::from_str_radix(digits, 16)
+                    .map_err(|_| Error::invalid_string_literal(escape, "invalid unicode escape"))?;
+                let c = match char::from_u32(code) {
+                    Some(c) => Some(c),
+                    None if (0xD800..=0xDFFF).contains(&code) => surrogate(escape),
+                    None => None,
+                };
+                let c = c.ok_or_else(|| {
+                    Error::invalid_string_literal(
+                        text,
+                        "unicode escape was not a valid unicode codepoint",
+                    )
+                })?;
+
+                (c, digits.len() + 4)
+            }
+            escape => {
+                return Err(Error::invalid_string_literal(
+                    text,
+                    format!("invalid escape sequence '{escape}'"),
+                ))
+            }
+        };
+
+        escaped.push(c);
+        text = text.split_at(len).1;
+
+        next = match text.find('\\') {
+            Some(pos) => pos,
+            None => break,
+        };
+    }
+
+    escaped.push_str(text);
+    Ok(())
+}
+
+/// Undo the escaping done when a byte string literal such as `b"abc\xff"` is
+/// printed.
+///
+/// This accepts the escapes that [`unescape`] does along with `\xNN` escapes
+/// for arbitrary bytes. Unicode escapes are written as UTF-8, as they appear
+/// in an `OsString` that is mostly valid UTF-8. `text` should not include the
+/// surrounding quotes.
+pub(crate) fn unescape_bytes(text: &str) -> Result<Cow<'_, [u8]>, Error> {
+    if !text.contains('\\') {
+        return Ok(Cow::Borrowed(text.as_bytes()));
+    }
+
+    let mut bytes = Vec::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(pos) = rest.find('\\') {
+        bytes.extend_from_slice(&rest.as_bytes()[..pos]);
+        rest = &rest[pos..];
+
+        let (byte, len) = match rest.as_bytes().get(1) {
+            Some(b'x') => {
+                let byte = rest
+                    .get(2..4)
+                    .filter(|digits| digits.bytes().all(|b| b.is_ascii_hexdigit()))
+                    .and_then(|digits| u8::from_str_radix(digits, 16).ok())
+                    .ok_or_else(|| Error::invalid_string_literal(rest, "invalid hex escape"))?;
+                (byte, 4)
+            }
+            Some(b'0') => (b'\0', 2),
+            Some(b't') => (b'\t', 2),
+            Some(b'r') => (b'\r', 2),
+            Some(b'n') => (b'\n', 2),
+            Some(&byte @ (b'\\' | b'\'' | b'"')) => (byte, 2),
+            Some(b'u') => {
+                let len = rest.find('}').map_or(rest.len(), |end| end + 1);
+                bytes.extend_from_slice(unescape(&rest[..len])?.as_bytes());
+                rest = &rest[len..];
+                continue;
+            }
+            _ => {
+                let escape: String = rest.chars().take(2).collect();
+                return Err(Error::invalid_string_literal(
+                    rest,
+                    format!("invalid escape sequence '{escape}'"),
+                ));
+            }
+        };
+
+        bytes.push(byte);
+        rest = &rest[len..];
+    }
+
+    bytes.extend_from_slice(rest.as_bytes());
+    Ok(Cow::Owned(bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    macro_rules! unescape_test {
+        {
+            $(
+                $( #[$attr:meta] )*
+                $test:ident: $input:expr $( => $output:expr )? ;
+            )*
+        } => {$(
+            #[test]
+            $( #[$attr] )*
+            #[allow(unused_variables)]
+            fn $test() {
+                let escaped = unescape($input)
+                    .expect("escape sequence was invalid");
+
+                $(
+                    let expected: &str = $output;
+
+                    assert_eq!(&*escaped, expected);
+                )?
+            }
+        )*}
+    }
+
+    unescape_test! {
+        basic: "test" => "test";
+        null: r"\0" => "\0";
+        tab: r"\t" => "\t";
+        crlf: r"\r\n" => "\r\n";
+        backslash: r"\\" => "\\";
+
+        single_quote: r"\'" => "\'";
+        double_quote: r#"\""# => "\"";
+
+        unicode_null: r"\u{0}" => "\u{0}";
+        unicode_max: r"\u{109999}" => "\u{109999}";
+        unicode_hex_uppercase: r"\u{ABCDE}" => "\u{ABCDE}";
+        unicode_hex_lowercase: r"\u{abcde}" => "\u{abcde}";
+
+        #[should_panic]
+        unicode_incomplete: r"\u{123";
+        #[should_panic]
+        invalid_escape: r"\a";
+
+        hex_escape: r"\x1b[0m" => "\x1b[0m";
+        hex_escape_uppercase: r"\x7F\x0A" => "\x7f\n";
+        #[should_panic]
+        hex_escape_non_ascii: r"\x80";
+        #[should_panic]
+        hex_escape_truncated: r"\x4";
+        #[should_panic]
+        hex_escape_sign: r"\x+1";
+
+        mixed: r"One, two, three, four!\nI declare a \tab war!\n\\\u{9123}"
+            => "One, two, three, four!\nI declare a \tab war!\n\\\u{9123}";
+        empty: "" => "";
+    }
+
+    #[test]
+    fn unescape_truncated_escape() {
+        assert!(unescape("trailing \\").is_err());
+        assert!(unescape("\\\u{e9}").is_err());
+        assert!(unescape("\\u{").is_err());
+    }
+
+    #[test]
+    fn unescape_bytes_mixed() {
+        let bytes = unescape_bytes(r"caf\xe9 \u{e9}\u{1b}\xFF\n").unwrap();
+        assert_eq!(&*bytes, b"caf\xe9 \xc3\xa9\x1b\xff\n");
+        assert!(unescape_bytes(r"\u{d800}").is_err());
+        assert!(unescape_bytes(r"\u{41").is_err());
+    }
+
+    #[test]
+    fn escape_roundtrip() {
+        let inputs = [
+            "",
+            "plain text",
+            "quotes: \" and '",
+            "\0\t\r\n\\",
+            "\u{1b}[0m \u{7f} \u{200b}",
+            "unicode: \u{1f980} caf\u{e9}",
+        ];
+
+        for input in inputs {
+            let escaped = escape(input);
+            assert_eq!(format!("\"{escaped}\""), format!("{input:?}"));
+            assert_eq!(unescape(&escaped).unwrap(), input);
+        }
+    }
+}
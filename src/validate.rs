@@ -0,0 +1,241 @@
+use core::fmt;
+
+use crate::error::LexerError;
+use crate::lex::{is_operator, is_terminator, Lexer, Token, TokenKind};
+#[cfg(feature = "alloc")]
+use crate::Error;
+
+/// The maximum nesting depth accepted by [`validate`].
+///
+/// Validation recurses once per level of nesting so this bounds the amount of
+/// stack that it uses.
+const MAX_DEPTH: usize = 256;
+
+/// Check that `input` is a single well-formed debug-formatted value.
+///
+/// This checks the same structure that the other text-only helpers, such as
+/// [`canonicalize`](crate::canonicalize), accept. Unlike those, it never
+/// allocates: the lexer works purely on slices of the input and the error only
+/// borrows from it. This makes it cheap to check frames before forwarding
+/// them elsewhere, and it is available even without the `alloc` feature.
+///
+/// Values nested more than 256 levels deep are rejected.
+///
+/// # Example
+/// ```
+/// assert!(serde_dbgfmt::validate("Point { x: 1, y: [2, 3] }").is_ok());
+///
+/// let error = serde_dbgfmt::validate("Point { x: 1, y: [2, 3 }").unwrap_err();
+/// assert_eq!(error.found(), Some("}"));
+/// assert_eq!(error.offset(), 23);
+/// ```
+pub fn validate(input: &str) -> Result<(), ValidationError<'_>> {
+    let mut validator = Validator {
+        lexer: Lexer::new(input),
+        depth: 0,
+    };
+
+    let result = validator.value().and_then(|()| {
+        let token = validator.lexer.parse_token()?;
+        match token.kind {
+            TokenKind::Eof => Ok(()),
+            _ => Err(LexerError::unexpected_token(token.value, TokenKind::Eof)),
+        }
+    });
+
+    result.map_err(|error| ValidationError {
+        offset: match error.found.is_empty() {
+            true => input.len(),
+            false => error.found.as_ptr() as usize - input.as_ptr() as usize,
+        },
+        error,
+    })
+}
+
+/// The error returned by [`validate`].
+///
+/// This borrows the unexpected token from the input instead of copying it.
+/// It can be converted into an [`Error`] when an owned error is needed.
+#[derive(Clone, Debug)]
+pub struct ValidationError<'a> {
+    error: LexerError<'a>,
+    offset: usize,
+}
+
+impl<'a> ValidationError<'a> {
+    /// The unexpected token, or `None` if the input ended early.
+    pub fn found(&self) -> Option<&'a str> {
+        // The validator only ever creates borrowed errors.
+        self.error
+            .borrowed_found()
+            .filter(|found| !found.is_empty())
+    }
+
+    /// The byte offset of the unexpected token within the input.
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+}
+
+impl fmt::Display for ValidationError<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.error.fmt(f)
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ValidationError<'_> {}
+
+#[cfg(feature = "alloc")]
+impl From<ValidationError<'_>> for Error {
+    fn from(error: ValidationError<'_>) -> Self {
+        error.error.into()
+    }
+}
+
+struct Validator<'a> {
+    lexer: Lexer<'a>,
+    depth: usize,
+}
+
+impl<'a> Validator<'a> {
+    fn peek(&self) -> Result<Token<'a>, LexerError<'a>> {
+        self.lexer.peek_token()
+    }
+
+    fn expect(&mut self, punct: &'static str) -> Result<(), LexerError<'a>> {
+        self.lexer.expect_punct(punct)
+    }
+
+    fn value(&mut self) -> Result<(), LexerError<'a>> {
+        if !is_operator(&self.peek()?) {
+            self.primary()?;
+        }
+
+        while is_operator(&self.peek()?) {
+            self.lexer.parse_token()?;
+
+            if !is_terminator(&self.peek()?) {
+                self.primary()?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn primary(&mut self) -> Result<(), LexerError<'a>> {
+        let token = self.lexer.parse_token()?;
+
+        match token.kind {
+            TokenKind::Ident => match self.peek()? {
+                next if next.is_punct("{") => self.nested(next, Self::struct_body),
+                next if next.is_punct("(") => self.nested(next, |this| this.elements(")")),
+                _ => Ok(()),
+            },
+            TokenKind::Integer
+            | TokenKind::Address
+            | TokenKind::Float
+            | TokenKind::String
+            | TokenKind::Char
+            | TokenKind::Duration => Ok(()),
+            TokenKind::Punct => match token.value {
+                "+" | "-" => {
+                    let number = self.lexer.parse_token()?;
+                    match number.kind {
+                        TokenKind::Integer | TokenKind::Float => Ok(()),
+                        TokenKind::Ident if number.value == "inf" => Ok(()),
+                        _ => Err(LexerError::unexpected_token(number.value, "a number")),
+                    }
+                }
+                "(" => self.enter(token, |this| this.elements(")")),
+                "[" => self.enter(token, |this| this.elements("]")),
+                "{" => self.enter(token, Self::braced),
+                _ => Err(LexerError::unexpected_token(token.value, "a value")),
+            },
+            _ => Err(LexerError::unexpected_token(token.value, "a value")),
+        }
+    }
+
+    /// Consume the opening bracket `open` and then validate the contents.
+    fn nested<F>(&mut self, open: Token<'a>, func: F) -> Result<(), LexerError<'a>>
+    where
+        F: FnOnce(&mut Self) -> Result<(), LexerError<'a>>,
+    {
+        self.lexer.parse_token()?;
+        self.enter(open, func)
+    }
+
+    /// Validate the contents of a bracket, `open`, that was just consumed.
+    fn enter<F>(&mut self, open: Token<'a>, func: F) -> Result<(), LexerError<'a>>
+    where
+        F: FnOnce(&mut Self) -> Result<(), LexerError<'a>>,
+    {
+        if self.depth == MAX_DEPTH {
+            return Err(LexerError::unexpected_token(
+                open.value,
+                "a value nested less deeply",
+            ));
+        }
+
+        self.depth += 1;
+        let result = func(self);
+        self.depth -= 1;
+        result
+    }
+
+    fn elements(&mut self, close: &'static str) -> Result<(), LexerError<'a>> {
+        while !self.peek()?.is_punct(close) {
+            self.value()?;
+            self.separator(close)?;
+        }
+        self.expect(close)
+    }
+
+    fn separator(&mut self, close: &'static str) -> Result<(), LexerError<'a>> {
+        match self.peek()?.is_punct(close) {
+            true => Ok(()),
+            false => self.expect(","),
+        }
+    }
+
+    fn struct_body(&mut self) -> Result<(), LexerError<'a>> {
+        while !self.peek()?.is_punct("}") {
+            let token = self.lexer.parse_token()?;
+            if token.is_punct("..") {
+                break;
+            }
+            if token.kind != TokenKind::Ident {
+                return Err(LexerError::unexpected_token(token.value, TokenKind::Ident));
+            }
+
+            self.expect(":")?;
+            self.value()?;
+            self.separator("}")?;
+        }
+        self.expect("}")
+    }
+
+    fn braced(&mut self) -> Result<(), LexerError<'a>> {
+        if self.peek()?.is_punct("}") {
+            return self.expect("}");
+        }
+
+        self.value()?;
+        if !self.peek()?.is_punct(":") {
+            self.separator("}")?;
+            return self.elements("}");
+        }
+
+        loop {
+            self.expect(":")?;
+            self.value()?;
+            self.separator("}")?;
+
+            if self.peek()?.is_punct("}") {
+                break;
+            }
+            self.value()?;
+        }
+        self.expect("}")
+    }
+}
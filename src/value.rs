@@ -0,0 +1,1946 @@
+use alloc::boxed::Box;
+use alloc::collections::BTreeMap;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use alloc::{format, vec};
+use core::fmt::{self, Write as _};
+use core::str::FromStr;
+use core::time::Duration;
+
+use serde::de::value::{MapDeserializer, SeqDeserializer, StrDeserializer};
+use serde::de::{
+    Deserialize, DeserializeSeed, Deserializer, EnumAccess, Error as _, IntoDeserializer,
+    MapAccess, SeqAccess, Unexpected, VariantAccess, Visitor,
+};
+use serde::forward_to_deserialize_any;
+
+use crate::de::{visit_duration, VALUE_TOKEN};
+use crate::dialect::{unescape_python, Dialect};
+use crate::flatten::{scalar, Scalar};
+use crate::lex::{is_operator, is_terminator, Lexer, Token, TokenKind};
+use crate::tree::{Node, NodeKind};
+use crate::util::unescape;
+use crate::{literal, Error, FormatVersion, Options};
+
+/// Any value that can appear in debug output.
+///
+/// This is useful when the shape of the input is not known ahead of time. It
+/// can be parsed using [`from_str`](crate::from_str) or [`str::parse`] and
+/// then walked programmatically.
+///
+/// The [`Display`](fmt::Display) impl prints the value in the same format it
+/// was parsed from. The alternate flag (`{:#}`) prints it the same as `{:#?}`
+/// would.
+///
+/// Parsing, dropping, cloning, comparing and displaying a `Value` do not
+/// recurse, so arbitrarily deeply nested input can be collected and used
+/// without overflowing the stack. Because of this `Value` implements [`Drop`]
+/// and cannot be destructured by move.
+///
+/// # Example
+/// ```
+/// use serde_dbgfmt::Value;
+///
+/// let text = r#"Config { name: "prod", ports: [80, 443], tls: Some(Tls { verify: true }) }"#;
+/// let value: Value = serde_dbgfmt::from_str(text).unwrap();
+///
+/// assert_eq!(value.name(), Some("Config"));
+/// assert_eq!(value.field("name").and_then(Value::as_str), Some("prod"));
+/// assert_eq!(value.field("ports").and_then(|p| p.element(1)), Some(&Value::Int(443)));
+///
+/// let tls = value.field("tls").and_then(Value::as_option).flatten().unwrap();
+/// assert_eq!(tls.field("verify").and_then(Value::as_bool), Some(true));
+///
+/// assert_eq!(value.to_string(), text);
+/// ```
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum Value {
+    Bool(bool),
+    /// An integer that fits within an `i128`.
+    Int(i128),
+    /// A positive integer that is too large for an `i128`.
+    UInt(u128),
+    Float(f64),
+    Str(ValueString),
+    Char(char),
+    /// The unit value `()`.
+    Unit,
+    /// `Some(..)` or `None`.
+    Option(Option<Box<Value>>),
+    /// A bare identifier, such as a unit struct or unit variant.
+    Ident(String),
+    /// `Name { field: value }`
+    Struct {
+        name: String,
+        fields: Vec<(ValueString, Value)>,
+        /// Whether the struct ended with `..`, meaning that some of its fields
+        /// were not printed.
+        non_exhaustive: bool,
+    },
+    /// `Name(a, b)`
+    TupleStruct {
+        name: String,
+        elements: Vec<Value>,
+    },
+    /// `(a, b)`
+    Tuple(Vec<Value>),
+    /// `[a, b]`
+    Seq(Vec<Value>),
+    /// `{a, b}`
+    Set(Vec<Value>),
+    /// `{k: v}`. Empty braces are always parsed as a map.
+    Map(Vec<(Value, Value)>),
+    /// Any other value, such as a range, as it appears in canonical form.
+    Other(String),
+    /// A sequence, set, or map that was cut short by
+    /// [`Options::truncate_collections`].
+    ///
+    /// The accessors on `Value` look through this to the collection itself.
+    Truncated {
+        /// The collection, holding only the elements that were kept.
+        value: Box<Value>,
+        /// The number of elements or entries in the input.
+        len: usize,
+    },
+}
+
+impl Value {
+    /// The name of the struct or enum variant that this value was printed
+    /// from, if it has one.
+    pub fn name(&self) -> Option<&str> {
+        match self.untruncated() {
+            Self::Struct { name, .. } | Self::TupleStruct { name, .. } | Self::Ident(name) => {
+                Some(name)
+            }
+            _ => None,
+        }
+    }
+
+    /// Get the value of a struct field, or of a map entry with a string key.
+    pub fn field(&self, name: &str) -> Option<&Value> {
+        match self.untruncated() {
+            Self::Struct { fields, .. } => fields
+                .iter()
+                .find(|(field, _)| field == name)
+                .map(|(_, value)| value),
+            Self::Map(entries) => entries
+                .iter()
+                .find(|(key, _)| key.as_str() == Some(name))
+                .map(|(_, value)| value),
+            _ => None,
+        }
+    }
+
+    /// Get an element of a sequence, set, tuple, or tuple struct.
+    pub fn element(&self, index: usize) -> Option<&Value> {
+        self.elements()?.get(index)
+    }
+
+    /// The elements of a sequence, set, tuple, or tuple struct.
+    pub fn elements(&self) -> Option<&[Value]> {
+        match self.untruncated() {
+            Self::TupleStruct { elements, .. }
+            | Self::Tuple(elements)
+            | Self::Seq(elements)
+            | Self::Set(elements) => Some(elements),
+            _ => None,
+        }
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match *self {
+            Self::Bool(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// Get the value of an integer, if it fits within an `i128`.
+    pub fn as_i128(&self) -> Option<i128> {
+        match *self {
+            Self::Int(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// Get the value of a non-negative integer.
+    pub fn as_u128(&self) -> Option<u128> {
+        match *self {
+            Self::Int(value) => value.try_into().ok(),
+            Self::UInt(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// Get the value of a float or integer as an `f64`.
+    pub fn as_f64(&self) -> Option<f64> {
+        match *self {
+            Self::Float(value) => Some(value),
+            Self::Int(value) => Some(value as f64),
+            Self::UInt(value) => Some(value as f64),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Self::Str(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn as_char(&self) -> Option<char> {
+        match *self {
+            Self::Char(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// The value itself, or the collection within it if it was truncated.
+    pub(crate) fn untruncated(&self) -> &Value {
+        match self {
+            Self::Truncated { value, .. } => value,
+            _ => self,
+        }
+    }
+
+    /// Get the contents of an option. Returns `Some(None)` for `None`.
+    pub fn as_option(&self) -> Option<Option<&Value>> {
+        match self {
+            Self::Option(value) => Some(value.as_deref()),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl Value {
+    /// Hash the structure and content of this value.
+    ///
+    /// This is the same as [`structural_hash`](crate::structural_hash) of the
+    /// value's [`Display`](fmt::Display) output. Use
+    /// [`StructuralHasher::hash_value`](crate::StructuralHasher::hash_value) to
+    /// configure how the value is hashed.
+    ///
+    /// # Example
+    /// ```
+    /// use serde_dbgfmt::Value;
+    ///
+    /// let text = "Event {\n    kind: Login,\n    user: \"alice\",\n}";
+    /// let value: Value = text.parse().unwrap();
+    ///
+    /// assert_eq!(
+    ///     value.structural_hash().unwrap(),
+    ///     serde_dbgfmt::structural_hash(text).unwrap()
+    /// );
+    /// ```
+    pub fn structural_hash(&self) -> Result<u64, Error> {
+        crate::StructuralHasher::new().hash_value(self)
+    }
+}
+
+impl FromStr for Value {
+    type Err = Error;
+
+    fn from_str(input: &str) -> Result<Self, Error> {
+        let mut lexer = Lexer::new(input);
+        let value = Parser::new(&mut lexer).parse()?;
+
+        let token = lexer.parse_token()?;
+        if token.kind != TokenKind::Eof {
+            return Err(Error::unexpected_token(token, TokenKind::Eof));
+        }
+
+        Ok(value)
+    }
+}
+
+/// Parse the fields of the top-level struct in `input` into a map from field
+/// name to value.
+///
+/// A top-level map is accepted as well. Its string keys are used as-is while
+/// any other key is converted to a string using its [`Display`](fmt::Display)
+/// impl. Any other kind of value is an error.
+///
+/// # Example
+/// ```
+/// use serde_dbgfmt::Value;
+///
+/// let text = r#"Config { name: "prod", ports: [80, 443] }"#;
+/// let fields = serde_dbgfmt::to_map(text).unwrap();
+///
+/// assert_eq!(fields["name"], Value::Str("prod".into()));
+/// assert_eq!(fields["ports"].element(1), Some(&Value::Int(443)));
+///
+/// assert!(serde_dbgfmt::to_map("[1, 2]").is_err());
+/// ```
+pub fn to_map(input: &str) -> Result<BTreeMap<String, Value>, Error> {
+    to_map_with(input, &Options::default())
+}
+
+/// Parse the fields of the top-level struct in `input` into a map from field
+/// name to value using the provided options.
+///
+/// See [`to_map`] for details.
+pub fn to_map_with(input: &str, options: &Options) -> Result<BTreeMap<String, Value>, Error> {
+    let mut value: Value = crate::from_str_with(input, options)?;
+    if let Value::Truncated { value: inner, .. } = &mut value {
+        value = core::mem::replace(&mut **inner, Value::Unit);
+    }
+
+    match &mut value {
+        Value::Struct { fields, .. } => Ok(core::mem::take(fields)
+            .into_iter()
+            .map(|(name, value)| (name.into(), value))
+            .collect()),
+        Value::Map(entries) => Ok(core::mem::take(entries)
+            .into_iter()
+            .map(|(key, value)| match key.as_str() {
+                Some(key) => (key.into(), value),
+                None => (key.to_string(), value),
+            })
+            .collect()),
+        _ => Err(Error::invalid_type(value.unexpected(), &"a struct or map")),
+    }
+}
+
+/// Parse the next value from `lexer` according to `options`.
+///
+/// `depth` is the number of collections that the value is nested within, which
+/// counts towards [`Options::max_depth`].
+pub(crate) fn parse_value(
+    lexer: &mut Lexer<'_>,
+    options: &Options,
+    depth: usize,
+) -> Result<Value, Error> {
+    let config = options.config();
+    let mut parser = Parser::new(lexer);
+    parser.depth = depth;
+    parser.max_depth = config.max_depth;
+    parser.max_len = config.truncate_collections;
+    parser.python = config.dialect == Dialect::PythonRepr;
+    parser.struct_durations = config.format_version == FormatVersion::Rust1_26;
+    parser.parse()
+}
+
+// Dropping a value recursively would overflow the stack for deeply nested
+// values, so their children are moved onto a heap allocated stack instead.
+impl Drop for Value {
+    fn drop(&mut self) {
+        let mut stack = Vec::new();
+        self.take_children(&mut stack);
+
+        while let Some(mut value) = stack.pop() {
+            value.take_children(&mut stack);
+        }
+    }
+}
+
+impl Clone for Value {
+    fn clone(&self) -> Self {
+        enum Step<'v> {
+            Clone(&'v Value),
+            /// Build a copy of a collection from the copies of its children,
+            /// which are at the end of `done`.
+            Build(&'v Value),
+        }
+
+        let mut stack = vec![Step::Clone(self)];
+        let mut done = Vec::new();
+
+        while let Some(step) = stack.pop() {
+            let value = match step {
+                Step::Clone(value) => value,
+                Step::Build(value) => {
+                    let children = done.split_off(done.len() - value.child_count());
+                    done.push(value.with_children(children));
+                    continue;
+                }
+            };
+
+            let copy = match value {
+                Self::Bool(value) => Self::Bool(*value),
+                Self::Int(value) => Self::Int(*value),
+                Self::UInt(value) => Self::UInt(*value),
+                Self::Float(value) => Self::Float(*value),
+                Self::Str(value) => Self::Str(value.clone()),
+                Self::Char(value) => Self::Char(*value),
+                Self::Unit => Self::Unit,
+                Self::Option(None) => Self::Option(None),
+                Self::Ident(value) => Self::Ident(value.clone()),
+                Self::Other(value) => Self::Other(value.clone()),
+                _ => {
+                    stack.push(Step::Build(value));
+                    let start = stack.len();
+                    value.for_each_child(|child| stack.push(Step::Clone(child)));
+                    stack[start..].reverse();
+                    continue;
+                }
+            };
+            done.push(copy);
+        }
+
+        done.pop().expect("clone produced no value")
+    }
+}
+
+impl PartialEq for Value {
+    fn eq(&self, other: &Self) -> bool {
+        let mut stack = vec![(self, other)];
+
+        while let Some(pair) = stack.pop() {
+            let equal = match pair {
+                (Self::Bool(a), Self::Bool(b)) => a == b,
+                (Self::Int(a), Self::Int(b)) => a == b,
+                (Self::UInt(a), Self::UInt(b)) => a == b,
+                (Self::Float(a), Self::Float(b)) => a == b,
+                (Self::Str(a), Self::Str(b)) => a == b,
+                (Self::Char(a), Self::Char(b)) => a == b,
+                (Self::Unit, Self::Unit) => true,
+                (Self::Option(None), Self::Option(None)) => true,
+                (Self::Ident(a), Self::Ident(b)) | (Self::Other(a), Self::Other(b)) => a == b,
+                (Self::Option(Some(a)), Self::Option(Some(b))) => {
+                    stack.push((a, b));
+                    true
+                }
+                (
+                    Self::Truncated { value, len },
+                    Self::Truncated {
+                        value: other,
+                        len: other_len,
+                    },
+                ) if len == other_len => {
+                    stack.push((value, other));
+                    true
+                }
+                (
+                    Self::Struct {
+                        name,
+                        fields,
+                        non_exhaustive,
+                    },
+                    Self::Struct {
+                        name: other_name,
+                        fields: other_fields,
+                        non_exhaustive: other_non_exhaustive,
+                    },
+                ) if name == other_name
+                    && non_exhaustive == other_non_exhaustive
+                    && fields.len() == other_fields.len() =>
+                {
+                    let pairs = fields.iter().zip(other_fields);
+                    for ((name, value), (other_name, other)) in pairs {
+                        if name != other_name {
+                            return false;
+                        }
+                        stack.push((value, other));
+                    }
+                    true
+                }
+                (
+                    Self::TupleStruct { name, elements },
+                    Self::TupleStruct {
+                        name: other_name,
+                        elements: others,
+                    },
+                ) if name == other_name && elements.len() == others.len() => {
+                    stack.extend(elements.iter().zip(others));
+                    true
+                }
+                (Self::Tuple(elements), Self::Tuple(others))
+                | (Self::Seq(elements), Self::Seq(others))
+                | (Self::Set(elements), Self::Set(others))
+                    if elements.len() == others.len() =>
+                {
+                    stack.extend(elements.iter().zip(others));
+                    true
+                }
+                (Self::Map(entries), Self::Map(others)) if entries.len() == others.len() => {
+                    for ((key, value), (other_key, other)) in entries.iter().zip(others) {
+                        stack.push((key, other_key));
+                        stack.push((value, other));
+                    }
+                    true
+                }
+                _ => false,
+            };
+
+            if !equal {
+                return false;
+            }
+        }
+
+        true
+    }
+}
+
+impl Value {
+    /// Call `func` with each of the values directly within this one, in
+    /// order. Map entries produce the key and then the value.
+    fn for_each_child<'v>(&'v self, mut func: impl FnMut(&'v Value)) {
+        match self {
+            Self::Option(Some(value)) | Self::Truncated { value, .. } => func(value),
+            Self::Struct { fields, .. } => fields.iter().for_each(|(_, value)| func(value)),
+            Self::TupleStruct { elements, .. }
+            | Self::Tuple(elements)
+            | Self::Seq(elements)
+            | Self::Set(elements) => elements.iter().for_each(func),
+            Self::Map(entries) => entries.iter().for_each(|(key, value)| {
+                func(key);
+                func(value);
+            }),
+            _ => (),
+        }
+    }
+
+    /// The number of values that [`for_each_child`](Self::for_each_child)
+    /// produces.
+    fn child_count(&self) -> usize {
+        match self {
+            Self::Option(Some(_)) | Self::Truncated { .. } => 1,
+            Self::Struct { fields, .. } => fields.len(),
+            Self::TupleStruct { elements, .. }
+            | Self::Tuple(elements)
+            | Self::Seq(elements)
+            | Self::Set(elements) => elements.len(),
+            Self::Map(entries) => entries.len() * 2,
+            _ => 0,
+        }
+    }
+
+    /// Copy this collection, using `children` in place of the values within
+    /// it.
+    fn with_children(&self, children: Vec<Value>) -> Value {
+        let mut children = children.into_iter();
+        match self {
+            Self::Option(_) => Self::Option(children.next().map(Box::new)),
+            Self::Truncated { len, .. } => Self::Truncated {
+                value: Box::new(children.next().unwrap_or(Self::Unit)),
+                len: *len,
+            },
+            Self::Struct {
+                name,
+                fields,
+                non_exhaustive,
+            } => Self::Struct {
+                name: name.clone(),
+                fields: fields
+                    .iter()
+                    .map(|(name, _)| name.clone())
+                    .zip(children)
+                    .collect(),
+                non_exhaustive: *non_exhaustive,
+            },
+            Self::TupleStruct { name, .. } => Self::TupleStruct {
+                name: name.clone(),
+                elements: children.collect(),
+            },
+            Self::Tuple(_) => Self::Tuple(children.collect()),
+            Self::Seq(_) => Self::Seq(children.collect()),
+            Self::Set(_) => Self::Set(children.collect()),
+            Self::Map(_) => {
+                let mut entries = Vec::with_capacity(children.len() / 2);
+                while let (Some(key), Some(value)) = (children.next(), children.next()) {
+                    entries.push((key, value));
+                }
+                Self::Map(entries)
+            }
+            _ => unreachable!("value has no children"),
+        }
+    }
+
+    fn take_children(&mut self, stack: &mut Vec<Value>) {
+        match self {
+            Self::Option(value) => stack.extend(value.take().map(|value| *value)),
+            Self::Truncated { value, .. } => stack.push(core::mem::replace(value, Value::Unit)),
+            Self::Struct { fields, .. } => stack.extend(fields.drain(..).map(|(_, value)| value)),
+            Self::TupleStruct { elements, .. }
+            | Self::Tuple(elements)
+            | Self::Seq(elements)
+            | Self::Set(elements) => stack.append(elements),
+            Self::Map(entries) => {
+                for (key, value) in entries.drain(..) {
+                    stack.push(key);
+                    stack.push(value);
+                }
+            }
+            _ => (),
+        }
+    }
+}
+
+/// A collection whose elements are still being parsed.
+enum Frame<'a> {
+    Struct {
+        name: &'a str,
+        fields: Vec<(ValueString, Value)>,
+        /// The name of the field whose value is being parsed.
+        field: &'a str,
+        non_exhaustive: bool,
+    },
+    TupleStruct {
+        name: &'a str,
+        elements: Vec<Value>,
+    },
+    Tuple(Vec<Value>),
+    Seq(Vec<Value>),
+    /// An empty `{` that could still be either a set or a map.
+    Braced,
+    Set(Vec<Value>),
+    Map {
+        entries: Vec<(Value, Value)>,
+        key: Option<Value>,
+    },
+    /// A truncated collection written by `Value::write_canonical`.
+    Truncated {
+        len: usize,
+        value: Option<Value>,
+    },
+}
+
+impl Frame<'_> {
+    fn close(&self) -> &'static str {
+        match self {
+            Self::TupleStruct { .. } | Self::Tuple(_) => ")",
+            Self::Seq(_) => "]",
+            _ => "}",
+        }
+    }
+
+    fn finish(self) -> Value {
+        match self {
+            Self::Struct {
+                name,
+                fields,
+                non_exhaustive,
+                ..
+            } => Value::Struct {
+                name: name.into(),
+                fields,
+                non_exhaustive,
+            },
+            Self::TupleStruct {
+                name: "Some",
+                mut elements,
+            } if elements.len() == 1 => Value::Option(elements.pop().map(Box::new)),
+            Self::TupleStruct { name, elements } => Value::TupleStruct {
+                name: name.into(),
+                elements,
+            },
+            Self::Tuple(elements) if elements.is_empty() => Value::Unit,
+            Self::Tuple(elements) => Value::Tuple(elements),
+            Self::Seq(elements) => Value::Seq(elements),
+            Self::Braced => Value::Map(Vec::new()),
+            Self::Set(elements) => Value::Set(elements),
+            Self::Map { entries, .. } => Value::Map(entries),
+            Self::Truncated { len, value } => Value::Truncated {
+                value: Box::new(value.expect("truncated collection has no value")),
+                len,
+            },
+        }
+    }
+
+    /// The number of elements or entries that have been kept, for the
+    /// collections that may be truncated.
+    fn kept(&self) -> Option<usize> {
+        match self {
+            Self::Seq(elements) | Self::Set(elements) => Some(elements.len()),
+            Self::Map { entries, .. } => Some(entries.len()),
+            _ => None,
+        }
+    }
+}
+
+/// Parses a [`Value`] using the same grammar as [`Node::parse`].
+///
+/// Open collections are kept on an explicit stack instead of recursing, so
+/// that arbitrarily deeply nested input can be parsed without overflowing the
+/// stack.
+///
+/// [`Node::parse`]: crate::tree::Node::parse
+struct Parser<'l, 'a> {
+    lexer: &'l mut Lexer<'a>,
+    /// The open collections, along with the input starting at each one and
+    /// the number of elements that have been parsed within it.
+    stack: Vec<(&'a str, Frame<'a>, usize)>,
+    /// The number of collections that the value being parsed is nested within.
+    depth: usize,
+    max_depth: Option<usize>,
+    /// The number of elements to keep in each sequence, set, or map.
+    max_len: Option<usize>,
+    /// Whether to accept python's spelling of strings and booleans.
+    python: bool,
+    /// Whether `Duration { secs, nanos }` structs are read as durations.
+    struct_durations: bool,
+    /// Whether the input was written by `Value::write_canonical`.
+    transfer: bool,
+}
+
+impl<'l, 'a> Parser<'l, 'a> {
+    fn new(lexer: &'l mut Lexer<'a>) -> Self {
+        Self {
+            lexer,
+            stack: Vec::new(),
+            depth: 0,
+            max_depth: None,
+            max_len: None,
+            python: false,
+            struct_durations: false,
+            transfer: false,
+        }
+    }
+
+    fn peek(&self) -> Result<Token<'a>, Error> {
+        Ok(self.lexer.peek_token()?)
+    }
+
+    fn expect(&mut self, punct: &'static str) -> Result<(), Error> {
+        let token = self.lexer.parse_token()?;
+        match token.is_punct(punct) {
+            true => Ok(()),
+            false => Err(Error::unexpected_token(token, punct)),
+        }
+    }
+
+    /// The input starting at the next token, after any text that was skipped
+    /// by [`Options::on_unknown_token`].
+    fn start(&self) -> Result<&'a str, Error> {
+        let token = self.peek()?;
+        let rest = self.lexer.remaining();
+        Ok(&rest[token.value.as_ptr() as usize - rest.as_ptr() as usize..])
+    }
+
+    /// The text between `start` and the current position of the lexer.
+    fn span_from(&self, start: &'a str) -> &'a str {
+        &start[..start.len() - self.lexer.remaining().len()]
+    }
+
+    fn parse(&mut self) -> Result<Value, Error> {
+        loop {
+            let mut start = self.start()?;
+            let mut value = match is_operator(&self.peek()?) {
+                // Ranges may have no start bound.
+                true => self.parse_operators(start)?,
+                false => match self.parse_primary()? {
+                    Some(value) => value,
+                    None => match self.next_element()? {
+                        Some((begin, value)) => {
+                            start = begin;
+                            value
+                        }
+                        None => continue,
+                    },
+                },
+            };
+
+            // Add the value to the collection that contains it. This may in
+            // turn complete that collection, and so on.
+            loop {
+                if is_operator(&self.peek()?) {
+                    value = self.parse_operators(start)?;
+                }
+
+                if self.stack.is_empty() {
+                    return Ok(value);
+                }
+
+                if self.push(value)? {
+                    break;
+                }
+
+                match self.next_element()? {
+                    Some((begin, collection)) => {
+                        start = begin;
+                        value = collection;
+                    }
+                    None => break,
+                }
+            }
+        }
+    }
+
+    /// Parse a value that is not built from operators. If the value is a
+    /// collection then it is pushed onto the stack and `None` is returned.
+    fn parse_primary(&mut self) -> Result<Option<Value>, Error> {
+        let start = self.start()?;
+        let token = self.lexer.parse_token()?;
+
+        let kind = match token.kind {
+            // Strings produced by `Options::on_unknown_token` have no quotes
+            // and are used as-is.
+            TokenKind::String if !token.value.starts_with(['"', '\'']) => {
+                return Ok(Some(Value::Str(token.value.into())))
+            }
+            // A struct or variant can have the same name as these, but would
+            // never be followed directly by a literal.
+            TokenKind::Ident
+                if self.transfer
+                    && token.value == "Truncated"
+                    && self.peek()?.kind == TokenKind::Integer =>
+            {
+                let len = self.lexer.parse_token()?;
+                let len = match len.kind {
+                    TokenKind::Integer => len.value.parse().ok(),
+                    _ => None,
+                }
+                .ok_or_else(|| Error::unexpected_token(len, TokenKind::Integer))?;
+                return self.open(start, Frame::Truncated { len, value: None });
+            }
+            TokenKind::Ident
+                if self.transfer
+                    && token.value == "Other"
+                    && self.peek()?.kind == TokenKind::String =>
+            {
+                let text = self.lexer.parse_token()?;
+                let text = unescape(&text.value[1..text.value.len() - 1])?;
+                return Ok(Some(Value::Other(text.into_owned())));
+            }
+            TokenKind::Ident if self.python && matches!(token.value, "True" | "False") => {
+                return Ok(Some(Value::Bool(token.value == "True")))
+            }
+            TokenKind::String | TokenKind::Char if self.python => {
+                let inner = &token.value[1..token.value.len() - 1];
+                return Ok(Some(Value::Str(
+                    unescape_python(inner)?.into_owned().into(),
+                )));
+            }
+            TokenKind::Ident if matches!(token.value, "NaN" | "inf") => NodeKind::Float,
+            TokenKind::Ident => match self.peek()? {
+                next if next.is_punct("{") => {
+                    self.lexer.parse_token()?;
+                    return self.open(
+                        start,
+                        Frame::Struct {
+                            name: token.value,
+                            fields: Vec::new(),
+                            field: "",
+                            non_exhaustive: false,
+                        },
+                    );
+                }
+                next if next.is_punct("(") => {
+                    self.lexer.parse_token()?;
+                    return self.open(
+                        start,
+                        Frame::TupleStruct {
+                            name: token.value,
+                            elements: Vec::new(),
+                        },
+                    );
+                }
+                _ if token.value == "None" => return Ok(Some(Value::Option(None))),
+                _ => NodeKind::Ident(token.value),
+            },
+            TokenKind::Integer | TokenKind::Address => NodeKind::Integer,
+            TokenKind::Float => NodeKind::Float,
+            TokenKind::String => NodeKind::String,
+            TokenKind::Char => NodeKind::Char,
+            TokenKind::Duration => NodeKind::Other,
+            TokenKind::Punct => match token.value {
+                "+" | "-" => {
+                    let number = self.lexer.parse_token()?;
+                    match number.kind {
+                        TokenKind::Integer => NodeKind::Integer,
+                        TokenKind::Float => NodeKind::Float,
+                        TokenKind::Ident if number.value == "inf" => NodeKind::Float,
+                        _ => return Err(Error::unexpected_token(number, "a number")),
+                    }
+                }
+                "(" => return self.open(start, Frame::Tuple(Vec::new())),
+                "[" => return self.open(start, Frame::Seq(Vec::new())),
+                "{" => return self.open(start, Frame::Braced),
+                _ => return Err(Error::unexpected_token(token, "a value")),
+            },
+            TokenKind::Eof => return Err(Error::unexpected_token(token, "a value")),
+        };
+
+        let node = Node {
+            span: self.span_from(start),
+            kind,
+        };
+
+        Ok(Some(match scalar(&node)? {
+            Scalar::Bool(value) => Value::Bool(value),
+            Scalar::Int(value) => Value::Int(value),
+            Scalar::UInt(value) => Value::UInt(value),
+            Scalar::Float(value) => Value::Float(value),
+            Scalar::Str(value) => Value::Str(value.into()),
+            Scalar::Char(value) => Value::Char(value),
+            Scalar::Ident(value) => Value::Ident(value),
+            Scalar::Other(value) => Value::Other(value),
+        }))
+    }
+
+    fn open(&mut self, start: &'a str, frame: Frame<'a>) -> Result<Option<Value>, Error> {
+        match self.max_depth {
+            Some(limit) if self.depth + self.stack.len() >= limit => {
+                Err(Error::depth_limit_exceeded(limit))
+            }
+            _ => {
+                self.stack.push((start, frame, 0));
+                Ok(None)
+            }
+        }
+    }
+
+    /// Parse the operators following the value that starts at `start`, which
+    /// turns the whole expression into [`Value::Other`].
+    fn parse_operators(&mut self, start: &'a str) -> Result<Value, Error> {
+        while is_operator(&self.peek()?) {
+            self.lexer.parse_token()?;
+
+            // Ranges may also have no end bound.
+            if !is_terminator(&self.peek()?) {
+                self.skip_operand()?;
+            }
+        }
+
+        Ok(Value::Other(self.span_from(start).into()))
+    }
+
+    /// Skip over the operand of an operator. Only the text of the whole
+    /// expression is kept so the operand itself does not need to be parsed.
+    fn skip_operand(&mut self) -> Result<(), Error> {
+        let token = self.lexer.parse_token()?;
+        let mut open = Vec::new();
+
+        match (token.kind, token.value) {
+            (TokenKind::Punct, "+" | "-") => {
+                self.lexer.parse_token()?;
+            }
+            (TokenKind::Punct, "(") => open.push(")"),
+            (TokenKind::Punct, "[") => open.push("]"),
+            (TokenKind::Punct, "{") => open.push("}"),
+            (TokenKind::Punct | TokenKind::Eof, _) => {
+                return Err(Error::unexpected_token(token, "a value"))
+            }
+            _ => (),
+        }
+
+        if token.kind == TokenKind::Ident {
+            match self.peek()? {
+                next if next.is_punct("(") => open.push(")"),
+                next if next.is_punct("{") => open.push("}"),
+                _ => return Ok(()),
+            }
+            self.lexer.parse_token()?;
+        }
+
+        while let Some(close) = open.last().copied() {
+            let token = self.lexer.parse_token()?;
+            match (token.kind, token.value) {
+                (TokenKind::Punct, "(") => open.push(")"),
+                (TokenKind::Punct, "[") => open.push("]"),
+                (TokenKind::Punct, "{") => open.push("}"),
+                (TokenKind::Punct, value) if value == close => {
+                    open.pop();
+                }
+                (TokenKind::Punct, ")" | "]" | "}") | (TokenKind::Eof, _) => {
+                    return Err(Error::unexpected_token(token, close))
+                }
+                _ => (),
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Add a complete value to the innermost collection, along with the
+    /// separator that follows it.
+    ///
+    /// Returns true if the value was a map key, in which case the map value
+    /// should be parsed next.
+    fn push(&mut self, value: Value) -> Result<bool, Error> {
+        let (start, mut frame, mut len) = self.stack.pop().expect("no collection is open");
+        let keep = self.max_len.map_or(true, |limit| len < limit);
+
+        match &mut frame {
+            Frame::Struct { fields, field, .. } => fields.push(((*field).into(), value)),
+            Frame::TupleStruct { elements, .. } | Frame::Tuple(elements) => elements.push(value),
+            Frame::Seq(elements) | Frame::Set(elements) => {
+                if keep {
+                    elements.push(value);
+                }
+                len += 1;
+            }
+            Frame::Braced if self.peek()?.is_punct(":") => {
+                frame = Frame::Map {
+                    entries: Vec::new(),
+                    key: Some(value),
+                }
+            }
+            Frame::Braced => {
+                frame = Frame::Set(match keep {
+                    true => vec![value],
+                    false => Vec::new(),
+                });
+                len += 1;
+            }
+            Frame::Map { entries, key } => match key.take() {
+                Some(key) => {
+                    if keep {
+                        entries.push((key, value));
+                    }
+                    len += 1;
+                }
+                None => *key = Some(value),
+            },
+            Frame::Truncated { value: inner, .. } => {
+                *inner = Some(value);
+                self.stack.push((start, frame, len));
+                return Ok(false);
+            }
+        }
+
+        let close = frame.close();
+        let is_key = matches!(frame, Frame::Map { key: Some(_), .. });
+        self.stack.push((start, frame, len));
+
+        match is_key {
+            true => self.expect(":")?,
+            false if self.peek()?.is_punct(close) => (),
+            false => self.expect(",")?,
+        }
+
+        Ok(is_key)
+    }
+
+    /// Prepare to parse the next element of the innermost collection.
+    ///
+    /// If the collection has ended then it is removed from the stack and
+    /// returned, along with the input starting at it.
+    fn next_element(&mut self) -> Result<Option<(&'a str, Value)>, Error> {
+        let (start, mut frame, len) = self.stack.pop().expect("no collection is open");
+
+        if let Frame::Truncated { value, .. } = &frame {
+            if value.is_some() {
+                return Ok(Some((start, frame.finish())));
+            }
+        } else if let Frame::Struct {
+            field,
+            non_exhaustive,
+            ..
+        } = &mut frame
+        {
+            let token = self.lexer.parse_token()?;
+            match token.kind {
+                _ if token.is_punct("}") => return Ok(Some((start, self.finish_struct(frame)))),
+                _ if token.is_punct("..") => {
+                    *non_exhaustive = true;
+                    self.expect("}")?;
+                    return Ok(Some((start, frame.finish())));
+                }
+                TokenKind::Ident => *field = token.value,
+                _ => return Err(Error::unexpected_token(token, TokenKind::Ident)),
+            }
+
+            self.expect(":")?;
+        } else if self.peek()?.is_punct(frame.close()) {
+            self.lexer.parse_token()?;
+
+            let kept = frame.kept();
+            let value = frame.finish();
+            return Ok(Some(match kept {
+                Some(kept) if kept < len => (
+                    start,
+                    Value::Truncated {
+                        value: Box::new(value),
+                        len,
+                    },
+                ),
+                _ => (start, value),
+            }));
+        }
+
+        self.stack.push((start, frame, len));
+        Ok(None)
+    }
+
+    /// Finish a struct, reading it as a duration if it is one that was printed
+    /// by [`FormatVersion::Rust1_26`].
+    fn finish_struct(&self, frame: Frame<'a>) -> Value {
+        let value = frame.finish();
+        if !self.struct_durations {
+            return value;
+        }
+
+        match struct_duration(&value) {
+            Some(duration) => Value::Other(format!("{duration:?}")),
+            None => value,
+        }
+    }
+}
+
+/// Read the derived debug output of a `Duration`, such as
+/// `Duration { secs: 1, nanos: 500000000 }`.
+fn struct_duration(value: &Value) -> Option<Duration> {
+    let Value::Struct {
+        name,
+        fields,
+        non_exhaustive: false,
+    } = value
+    else {
+        return None;
+    };
+
+    match (name.as_str(), fields.as_slice()) {
+        ("Duration", [(secs_name, secs), (nanos_name, nanos)])
+            if secs_name.as_str() == "secs" && nanos_name.as_str() == "nanos" =>
+        {
+            let secs = u64::try_from(secs.as_u128()?).ok()?;
+            let nanos = u32::try_from(nanos.as_u128()?).ok()?;
+            (nanos < 1_000_000_000).then(|| Duration::new(secs, nanos))
+        }
+        _ => None,
+    }
+}
+
+/// Values are built through a visitor, so they can be deserialized from any
+/// self-describing format. This crate's own deserializer parses the value
+/// directly instead, using the same [`Options`] as the rest of the input.
+impl<'de> Deserialize<'de> for Value {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_newtype_struct(VALUE_TOKEN, ValueVisitor)
+    }
+}
+
+struct ValueVisitor;
+
+impl<'de> Visitor<'de> for ValueVisitor {
+    type Value = Value;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("any value")
+    }
+
+    fn visit_bool<E>(self, v: bool) -> Result<Value, E> {
+        Ok(Value::Bool(v))
+    }
+
+    fn visit_i64<E>(self, v: i64) -> Result<Value, E> {
+        Ok(Value::Int(v.into()))
+    }
+
+    fn visit_i128<E>(self, v: i128) -> Result<Value, E> {
+        Ok(Value::Int(v))
+    }
+
+    fn visit_u64<E>(self, v: u64) -> Result<Value, E> {
+        Ok(Value::Int(v.into()))
+    }
+
+    fn visit_u128<E>(self, v: u128) -> Result<Value, E> {
+        Ok(match i128::try_from(v) {
+            Ok(v) => Value::Int(v),
+            Err(_) => Value::UInt(v),
+        })
+    }
+
+    fn visit_f64<E>(self, v: f64) -> Result<Value, E> {
+        Ok(Value::Float(v))
+    }
+
+    fn visit_char<E>(self, v: char) -> Result<Value, E> {
+        Ok(Value::Char(v))
+    }
+
+    fn visit_str<E>(self, v: &str) -> Result<Value, E> {
+        Ok(Value::Str(v.into()))
+    }
+
+    fn visit_string<E>(self, v: String) -> Result<Value, E> {
+        Ok(Value::Str(v.into()))
+    }
+
+    fn visit_bytes<E>(self, v: &[u8]) -> Result<Value, E> {
+        Ok(Value::Seq(
+            v.iter().map(|&b| Value::Int(b.into())).collect(),
+        ))
+    }
+
+    fn visit_unit<E>(self) -> Result<Value, E> {
+        Ok(Value::Unit)
+    }
+
+    fn visit_none<E>(self) -> Result<Value, E> {
+        Ok(Value::Option(None))
+    }
+
+    fn visit_some<D>(self, deserializer: D) -> Result<Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let value = Value::deserialize(deserializer)?;
+        Ok(Value::Option(Some(Box::new(value))))
+    }
+
+    fn visit_newtype_struct<D>(self, deserializer: D) -> Result<Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_any(self)
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<Value, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        let mut elements = Vec::with_capacity(seq.size_hint().unwrap_or(0).min(4096));
+        while let Some(element) = seq.next_element()? {
+            elements.push(element);
+        }
+
+        Ok(Value::Seq(elements))
+    }
+
+    fn visit_map<A>(self, mut map: A) -> Result<Value, A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        let mut entries = Vec::new();
+        while let Some(key) = map.next_key::<Value>()? {
+            // This crate's deserializer hands over values that it has already
+            // parsed as text under a private key. See `visit_parsed`.
+            if entries.is_empty() && key.as_str() == Some(VALUE_TOKEN) {
+                let text: String = map.next_value()?;
+                let mut lexer = Lexer::new(&text);
+                let mut parser = Parser::new(&mut lexer);
+                parser.transfer = true;
+                return parser.parse().map_err(A::Error::custom);
+            }
+
+            entries.push((key, map.next_value()?));
+        }
+
+        Ok(Value::Map(entries))
+    }
+}
+
+/// Pass a value that has already been parsed to a visitor for [`Value`].
+///
+/// The visitor cannot name the concrete type of the deserializer, so the value
+/// has to be passed through the serde data model. Passing it as a nested map
+/// would make the visitor recurse, so it is written out as canonical text
+/// instead, which can be parsed again without recursing or needing any
+/// options.
+pub(crate) fn visit_parsed<'de, V>(value: &Value, visitor: V) -> Result<V::Value, Error>
+where
+    V: Visitor<'de>,
+{
+    let mut text = String::new();
+    value.write_canonical(&mut text);
+
+    let entry = (VALUE_TOKEN, text);
+    visitor.visit_map(MapDeserializer::new(core::iter::once(entry)))
+}
+
+impl Value {
+    /// Write this value in a form that parses back to the same value.
+    ///
+    /// Unlike the `Display` impl this does not recurse. [`Value::Other`] is
+    /// written as `Other` followed by a string literal since its text may not
+    /// be valid on its own, for example if it contains text that was skipped
+    /// by [`Options::on_unknown_token`].
+    fn write_canonical(&self, out: &mut String) {
+        enum Step<'v> {
+            Value(&'v Value),
+            Field(&'v str, &'v Value),
+            Entry(&'v Value, &'v Value),
+            Text(&'static str),
+        }
+
+        /// Push the steps for `items` separated by commas, in reverse order.
+        fn push_list<'v>(
+            stack: &mut Vec<Step<'v>>,
+            items: impl DoubleEndedIterator<Item = Step<'v>>,
+        ) {
+            let mut first = true;
+            for item in items.rev() {
+                if !core::mem::take(&mut first) {
+                    stack.push(Step::Text(", "));
+                }
+                stack.push(item);
+            }
+        }
+
+        let mut stack = vec![Step::Value(self)];
+        while let Some(step) = stack.pop() {
+            let value = match step {
+                Step::Value(value) => value,
+                Step::Field(name, value) => {
+                    out.push_str(name);
+                    out.push_str(": ");
+                    stack.push(Step::Value(value));
+                    continue;
+                }
+                Step::Entry(key, value) => {
+                    stack.push(Step::Value(value));
+                    stack.push(Step::Text(": "));
+                    stack.push(Step::Value(key));
+                    continue;
+                }
+                Step::Text(text) => {
+                    out.push_str(text);
+                    continue;
+                }
+            };
+
+            match value {
+                Value::Bool(_)
+                | Value::Int(_)
+                | Value::UInt(_)
+                | Value::Float(_)
+                | Value::Str(_)
+                | Value::Char(_)
+                | Value::Unit
+                | Value::Option(None)
+                | Value::Ident(_) => {
+                    let _ = write!(out, "{value}");
+                }
+                Value::Other(text) => {
+                    let _ = write!(out, "Other{text:?}");
+                }
+                Value::Truncated { value, len } => {
+                    let _ = write!(out, "Truncated {len} ");
+                    stack.push(Step::Value(value));
+                }
+                Value::Option(Some(value)) => {
+                    out.push_str("Some(");
+                    stack.push(Step::Text(")"));
+                    stack.push(Step::Value(value));
+                }
+                Value::Struct {
+                    name,
+                    fields,
+                    non_exhaustive,
+                } => {
+                    out.push_str(name);
+                    out.push_str(match fields.is_empty() {
+                        true => " {",
+                        false => " { ",
+                    });
+                    stack.push(Step::Text(match (non_exhaustive, fields.is_empty()) {
+                        (true, true) => " .. }",
+                        (true, false) => ", .. }",
+                        (false, true) => "}",
+                        (false, false) => " }",
+                    }));
+                    push_list(
+                        &mut stack,
+                        fields.iter().map(|(name, value)| Step::Field(name, value)),
+                    );
+                }
+                Value::TupleStruct { name, elements } => {
+                    out.push_str(name);
+                    out.push('(');
+                    stack.push(Step::Text(")"));
+                    push_list(&mut stack, elements.iter().map(Step::Value));
+                }
+                Value::Tuple(elements) => {
+                    out.push('(');
+                    stack.push(Step::Text(match elements.len() {
+                        1 => ",)",
+                        _ => ")",
+                    }));
+                    push_list(&mut stack, elements.iter().map(Step::Value));
+                }
+                Value::Seq(elements) => {
+                    out.push('[');
+                    stack.push(Step::Text("]"));
+                    push_list(&mut stack, elements.iter().map(Step::Value));
+                }
+                Value::Set(elements) => {
+                    out.push('{');
+                    stack.push(Step::Text("}"));
+                    push_list(&mut stack, elements.iter().map(Step::Value));
+                }
+                Value::Map(entries) => {
+                    out.push('{');
+                    stack.push(Step::Text("}"));
+                    push_list(
+                        &mut stack,
+                        entries.iter().map(|(key, value)| Step::Entry(key, value)),
+                    );
+                }
+            }
+        }
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        /// Part of the output that is still to be written.
+        enum Step<'v> {
+            Value(&'v Value),
+            Field(&'v str, &'v Value),
+            Entry(&'v Value, &'v Value),
+            Text(&'static str),
+            /// Indent the following lines by one more level, in the same way as
+            /// the pretty debug builders.
+            Indent,
+            Dedent,
+        }
+
+        /// Writes to a formatter, indenting each line by `depth` levels.
+        struct Indented<'a, 'b> {
+            f: &'a mut fmt::Formatter<'b>,
+            depth: usize,
+            on_newline: bool,
+        }
+
+        impl fmt::Write for Indented<'_, '_> {
+            fn write_str(&mut self, s: &str) -> fmt::Result {
+                for line in s.split_inclusive('\n') {
+                    if self.on_newline {
+                        for _ in 0..self.depth {
+                            self.f.write_str("    ")?;
+                        }
+                    }
+
+                    self.on_newline = line.ends_with('\n');
+                    self.f.write_str(line)?;
+                }
+
+                Ok(())
+            }
+        }
+
+        /// Push the steps for a collection holding `items`, in reverse order.
+        /// `open` and `close` are the delimiters in the compact form.
+        fn push_collection<'v, I>(
+            stack: &mut Vec<Step<'v>>,
+            pretty: bool,
+            (open, close): (&'static str, &'static str),
+            items: I,
+            non_exhaustive: bool,
+        ) where
+            I: DoubleEndedIterator<Item = Step<'v>> + ExactSizeIterator,
+        {
+            if pretty {
+                stack.push(Step::Text(close.trim_start()));
+                stack.push(Step::Dedent);
+                if non_exhaustive {
+                    stack.push(Step::Text("..\n"));
+                }
+                for item in items.rev() {
+                    stack.push(Step::Text(",\n"));
+                    stack.push(item);
+                }
+                stack.push(Step::Indent);
+                stack.push(Step::Text("\n"));
+                stack.push(Step::Text(open.trim_end()));
+                return;
+            }
+
+            stack.push(Step::Text(close));
+            if non_exhaustive {
+                stack.push(Step::Text(", .."));
+            }
+            for (index, item) in items.enumerate().rev() {
+                stack.push(item);
+                if index != 0 {
+                    stack.push(Step::Text(", "));
+                }
+            }
+            stack.push(Step::Text(open));
+        }
+
+        let pretty = f.alternate();
+        let mut out = Indented {
+            f,
+            depth: 0,
+            on_newline: false,
+        };
+        let mut stack = vec![Step::Value(self)];
+
+        // This uses an explicit stack instead of the debug builders so that it
+        // does not recurse.
+        while let Some(step) = stack.pop() {
+            let value = match step {
+                Step::Value(value) => value,
+                Step::Field(name, value) => {
+                    write!(out, "{name}: ")?;
+                    stack.push(Step::Value(value));
+                    continue;
+                }
+                Step::Entry(key, value) => {
+                    stack.push(Step::Value(value));
+                    stack.push(Step::Text(": "));
+                    stack.push(Step::Value(key));
+                    continue;
+                }
+                Step::Text(text) => {
+                    out.write_str(text)?;
+                    continue;
+                }
+                Step::Indent => {
+                    out.depth += 1;
+                    continue;
+                }
+                Step::Dedent => {
+                    out.depth -= 1;
+                    continue;
+                }
+            };
+
+            match value {
+                Self::Bool(value) => write!(out, "{value:?}")?,
+                Self::Int(value) => write!(out, "{value:?}")?,
+                Self::UInt(value) => write!(out, "{value:?}")?,
+                Self::Float(value) => write!(out, "{value:?}")?,
+                Self::Str(value) => write!(out, "{value:?}")?,
+                Self::Char(value) => write!(out, "{value:?}")?,
+                Self::Unit => out.write_str("()")?,
+                Self::Option(None) => out.write_str("None")?,
+                Self::Option(Some(value)) => {
+                    out.write_str("Some")?;
+                    let items = core::iter::once(Step::Value(value));
+                    push_collection(&mut stack, pretty, ("(", ")"), items, false);
+                }
+                Self::Ident(value) | Self::Other(value) => out.write_str(value)?,
+                Self::Truncated { value, .. } => stack.push(Step::Value(value)),
+                Self::Struct {
+                    name,
+                    fields,
+                    non_exhaustive,
+                } => {
+                    out.write_str(name)?;
+                    match (fields.is_empty(), non_exhaustive) {
+                        (true, false) => (),
+                        (true, true) => out.write_str(" { .. }")?,
+                        (false, _) => {
+                            let items = fields.iter().map(|(name, value)| Step::Field(name, value));
+                            let delims = (" { ", " }");
+                            push_collection(&mut stack, pretty, delims, items, *non_exhaustive);
+                        }
+                    }
+                }
+                Self::TupleStruct { name, elements } => {
+                    out.write_str(name)?;
+                    if !elements.is_empty() {
+                        let items = elements.iter().map(Step::Value);
+                        push_collection(&mut stack, pretty, ("(", ")"), items, false);
+                    }
+                }
+                // This matches `debug_tuple("")`, which writes nothing when
+                // there are no fields.
+                Self::Tuple(elements) if elements.is_empty() => (),
+                // A tuple with a single element is written as `(a,)`.
+                Self::Tuple(elements) if elements.len() == 1 && !pretty => {
+                    let value = Step::Value(&elements[0]);
+                    stack.extend([Step::Text(",)"), value, Step::Text("(")]);
+                }
+                Self::Tuple(elements) => {
+                    let items = elements.iter().map(Step::Value);
+                    push_collection(&mut stack, pretty, ("(", ")"), items, false);
+                }
+                Self::Seq(elements) if elements.is_empty() => out.write_str("[]")?,
+                Self::Seq(elements) => {
+                    let items = elements.iter().map(Step::Value);
+                    push_collection(&mut stack, pretty, ("[", "]"), items, false);
+                }
+                Self::Set(elements) if elements.is_empty() => out.write_str("{}")?,
+                Self::Set(elements) => {
+                    let items = elements.iter().map(Step::Value);
+                    push_collection(&mut stack, pretty, ("{", "}"), items, false);
+                }
+                Self::Map(entries) if entries.is_empty() => out.write_str("{}")?,
+                Self::Map(entries) => {
+                    let items = entries.iter().map(|(key, value)| Step::Entry(key, value));
+                    push_collection(&mut stack, pretty, ("{", "}"), items, false);
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Deserialize another type from a `Value`.
+///
+/// Strings are passed to the visitor as transient borrows, so this works for
+/// types that borrow from their input only if they fall back to owned data.
+///
+/// # Example
+/// ```
+/// use serde::Deserialize;
+/// use serde_dbgfmt::Value;
+///
+/// #[derive(Debug, Deserialize, PartialEq)]
+/// struct Point {
+///     x: i32,
+///     y: i32,
+/// }
+///
+/// let value: Value = "Point { x: 1, y: -2 }".parse().unwrap();
+/// let point = Point::deserialize(&value).unwrap();
+///
+/// assert_eq!(point, Point { x: 1, y: -2 });
+/// ```
+impl<'de> Deserializer<'de> for &Value {
+    type Error = Error;
+
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        match self {
+            Value::Truncated { value, .. } => value.deserialize_any(visitor),
+            Value::Bool(value) => visitor.visit_bool(*value),
+            Value::Int(value) => match i64::try_from(*value) {
+                Ok(value) => visitor.visit_i64(value),
+                Err(_) => visitor.visit_i128(*value),
+            },
+            Value::UInt(value) => match u64::try_from(*value) {
+                Ok(value) => visitor.visit_u64(value),
+                Err(_) => visitor.visit_u128(*value),
+            },
+            Value::Float(value) => visitor.visit_f64(*value),
+            Value::Str(value) => visitor.visit_str(value),
+            Value::Other(value) => visitor.visit_str(value),
+            Value::Char(value) => visitor.visit_char(*value),
+            Value::Unit | Value::Ident(_) => visitor.visit_unit(),
+            Value::Option(None) => visitor.visit_none(),
+            Value::Option(Some(value)) => visitor.visit_some(&**value),
+            Value::Struct { fields, .. } => visit_fields(fields, visitor),
+            Value::TupleStruct { elements, .. }
+            | Value::Tuple(elements)
+            | Value::Seq(elements)
+            | Value::Set(elements) => {
+                SeqDeserializer::new(elements.iter()).deserialize_any(visitor)
+            }
+            Value::Map(entries) => {
+                let entries = entries.iter().map(|(key, value)| (key, value));
+                MapDeserializer::new(entries).deserialize_any(visitor)
+            }
+        }
+    }
+
+    fn deserialize_option<V>(self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        match self {
+            Value::Option(None) => visitor.visit_none(),
+            Value::Option(Some(value)) => visitor.visit_some(&**value),
+            _ => visitor.visit_some(self),
+        }
+    }
+
+    fn deserialize_newtype_struct<V>(
+        self,
+        name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        if name == VALUE_TOKEN {
+            return visit_parsed(self, visitor);
+        }
+
+        match self {
+            Value::TupleStruct { elements, .. } if elements.len() == 1 => {
+                visitor.visit_newtype_struct(&elements[0])
+            }
+            _ => visitor.visit_newtype_struct(self),
+        }
+    }
+
+    fn deserialize_bytes<V>(self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        match self {
+            Value::Str(value) => visitor.visit_bytes(value.as_bytes()),
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_byte_buf<V>(self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_bytes(visitor)
+    }
+
+    fn deserialize_enum<V>(
+        self,
+        _: &'static str,
+        _: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        let (name, content) = match self {
+            Value::Ident(name) => (name.as_str(), Content::Unit),
+            Value::Str(name) => (name.as_str(), Content::Unit),
+            Value::TupleStruct { name, elements } => (name.as_str(), Content::Tuple(elements)),
+            Value::Struct { name, fields, .. } => (name.as_str(), Content::Struct(fields)),
+            _ => return Err(Error::invalid_type(self.unexpected(), &visitor)),
+        };
+
+        visitor.visit_enum(VariantRef { name, content })
+    }
+
+    fn deserialize_struct<V>(
+        self,
+        name: &'static str,
+        _: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        // `Duration` prints as a number with a unit (e.g. `1.5s`), which
+        // parses as `Value::Other`.
+        if let (Value::Other(text), "Duration") = (self, name) {
+            if let Some(duration) = literal::parse_duration(text) {
+                return visit_duration(duration, visitor);
+            }
+        }
+
+        self.deserialize_any(visitor)
+    }
+
+    fn deserialize_ignored_any<V>(self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_unit()
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        unit unit_struct seq tuple tuple_struct map identifier
+    }
+}
+
+impl<'de> IntoDeserializer<'de, Error> for &Value {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self {
+        self
+    }
+}
+
+impl Value {
+    fn unexpected(&self) -> Unexpected<'_> {
+        match self {
+            Self::Bool(value) => Unexpected::Bool(*value),
+            Self::Int(value) => match i64::try_from(*value) {
+                Ok(value) => Unexpected::Signed(value),
+                Err(_) => Unexpected::Other("a 128-bit integer"),
+            },
+            Self::UInt(value) => match u64::try_from(*value) {
+                Ok(value) => Unexpected::Unsigned(value),
+                Err(_) => Unexpected::Other("a 128-bit integer"),
+            },
+            Self::Float(value) => Unexpected::Float(*value),
+            Self::Str(value) => Unexpected::Str(value),
+            Self::Char(value) => Unexpected::Char(*value),
+            Self::Unit => Unexpected::Unit,
+            Self::Option(_) => Unexpected::Option,
+            Self::Ident(_) => Unexpected::UnitVariant,
+            Self::Struct { .. } => Unexpected::Other("a struct"),
+            Self::TupleStruct { .. } => Unexpected::Other("a tuple struct"),
+            Self::Tuple(_) | Self::Seq(_) | Self::Set(_) => Unexpected::Seq,
+            Self::Map(_) => Unexpected::Map,
+            Self::Other(value) => Unexpected::Str(value),
+            Self::Truncated { value, .. } => value.unexpected(),
+        }
+    }
+}
+
+fn visit_fields<'de, V>(fields: &[(ValueString, Value)], visitor: V) -> Result<V::Value, Error>
+where
+    V: Visitor<'de>,
+{
+    let fields = fields.iter().map(|(name, value)| (name.as_str(), value));
+    MapDeserializer::new(fields).deserialize_any(visitor)
+}
+
+/// The contents of an enum variant within a [`Value`].
+enum Content<'v> {
+    Unit,
+    Tuple(&'v [Value]),
+    Struct(&'v [(ValueString, Value)]),
+}
+
+struct VariantRef<'v> {
+    name: &'v str,
+    content: Content<'v>,
+}
+
+impl<'de, 'v> EnumAccess<'de> for VariantRef<'v> {
+    type Error = Error;
+    type Variant = Content<'v>;
+
+    fn variant_seed<V>(self, seed: V) -> Result<(V::Value, Content<'v>), Error>
+    where
+        V: DeserializeSeed<'de>,
+    {
+        let variant = seed.deserialize(StrDeserializer::<Error>::new(self.name))?;
+        Ok((variant, self.content))
+    }
+}
+
+impl<'de> VariantAccess<'de> for Content<'_> {
+    type Error = Error;
+
+    fn unit_variant(self) -> Result<(), Error> {
+        match self {
+            Self::Unit => Ok(()),
+            _ => Err(Error::invalid_type(self.unexpected(), &"a unit variant")),
+        }
+    }
+
+    fn newtype_variant_seed<T>(self, seed: T) -> Result<T::Value, Error>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        match self {
+            Self::Tuple([value]) => seed.deserialize(value),
+            _ => Err(Error::invalid_type(self.unexpected(), &"a newtype variant")),
+        }
+    }
+
+    fn tuple_variant<V>(self, _: usize, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        match self {
+            Self::Tuple(elements) => SeqDeserializer::new(elements.iter()).deserialize_any(visitor),
+            _ => Err(Error::invalid_type(self.unexpected(), &visitor)),
+        }
+    }
+
+    fn struct_variant<V>(self, _: &'static [&'static str], visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        match self {
+            Self::Struct(fields) => visit_fields(fields, visitor),
+            _ => Err(Error::invalid_type(self.unexpected(), &visitor)),
+        }
+    }
+}
+
+impl Content<'_> {
+    fn unexpected(&self) -> Unexpected<'static> {
+        match self {
+            Self::Unit => Unexpected::UnitVariant,
+            Self::Tuple([_]) => Unexpected::NewtypeVariant,
+            Self::Tuple(_) => Unexpected::TupleVariant,
+            Self::Struct(_) => Unexpected::StructVariant,
+        }
+    }
+}
+
+#[cfg(not(feature = "compact_str"))]
+type Repr = String;
+#[cfg(feature = "compact_str")]
+type Repr = compact_str::CompactString;
+
+/// The string stored in [`Value::Str`] and used for the names of struct fields.
+///
+/// This is a [`String`] unless the `compact_str` feature is enabled, in which
+/// case strings of up to 24 bytes are stored inline without allocating. The
+/// API is the same either way. It dereferences to a [`str`] and can be built
+/// from a `&str` or `String` using [`From`].
+///
+/// # Example
+/// ```
+/// use serde_dbgfmt::{Value, ValueString};
+///
+/// let value = Value::Str("prod".into());
+/// assert_eq!(value.as_str(), Some("prod"));
+///
+/// let name = ValueString::from("ports");
+/// assert_eq!(name, "ports");
+/// assert_eq!(name.len(), 5);
+/// ```
+#[derive(Clone, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ValueString(Repr);
+
+impl ValueString {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl core::ops::Deref for ValueString {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl AsRef<str> for ValueString {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl core::borrow::Borrow<str> for ValueString {
+    fn borrow(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Debug for ValueString {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self.as_str(), f)
+    }
+}
+
+impl fmt::Display for ValueString {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl From<&str> for ValueString {
+    fn from(value: &str) -> Self {
+        Self(value.into())
+    }
+}
+
+// This is only a conversion when the `compact_str` feature is enabled.
+#[allow(clippy::useless_conversion)]
+impl From<String> for ValueString {
+    fn from(value: String) -> Self {
+        Self(value.into())
+    }
+}
+
+// This is only a conversion when the `compact_str` feature is enabled.
+#[allow(clippy::useless_conversion)]
+impl From<ValueString> for String {
+    fn from(value: ValueString) -> Self {
+        value.0.into()
+    }
+}
+
+impl PartialEq<str> for ValueString {
+    fn eq(&self, other: &str) -> bool {
+        self.as_str() == other
+    }
+}
+
+impl PartialEq<&str> for ValueString {
+    fn eq(&self, other: &&str) -> bool {
+        self.as_str() == *other
+    }
+}
+
+impl PartialEq<String> for ValueString {
+    fn eq(&self, other: &String) -> bool {
+        self.as_str() == other
+    }
+}
@@ -0,0 +1,334 @@
+use alloc::rc::Rc;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt;
+
+use crate::flatten::is_ident;
+use crate::Value;
+
+/// The location of a node within a [`Value`], as yielded by [`Value::walk`].
+///
+/// The [`Display`](fmt::Display) impl prints the path using the same syntax as
+/// [`flatten`](crate::flatten), e.g. `server.ports[0]`. The path to the top
+/// level value is empty.
+///
+/// Paths share their parents, so cloning one is cheap no matter how deeply
+/// nested it is.
+#[derive(Clone, Default)]
+pub struct Path<'v>(Option<Rc<Link<'v>>>);
+
+struct Link<'v> {
+    parent: Path<'v>,
+    segment: PathSegment<'v>,
+    len: usize,
+}
+
+/// A single step within a [`Path`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[non_exhaustive]
+pub enum PathSegment<'v> {
+    /// A field of a struct.
+    Field(&'v str),
+    /// An element of a sequence, set, tuple, or tuple struct, or the contents
+    /// of `Some`.
+    Index(usize),
+    /// The value for a key within a map.
+    Key(&'v Value),
+}
+
+impl<'v> Path<'v> {
+    /// The number of segments in the path.
+    pub fn len(&self) -> usize {
+        self.0.as_ref().map_or(0, |link| link.len)
+    }
+
+    /// Whether this is the path to the top level value.
+    pub fn is_empty(&self) -> bool {
+        self.0.is_none()
+    }
+
+    /// The last segment of the path.
+    pub fn last(&self) -> Option<PathSegment<'v>> {
+        self.0.as_ref().map(|link| link.segment)
+    }
+
+    /// The path to the parent of this node.
+    pub fn parent(&self) -> Option<&Path<'v>> {
+        self.0.as_ref().map(|link| &link.parent)
+    }
+
+    /// The segments of the path, starting from the top level value.
+    pub fn segments(&self) -> Vec<PathSegment<'v>> {
+        let mut segments = Vec::with_capacity(self.len());
+        let mut path = self;
+        while let Some(link) = &path.0 {
+            segments.push(link.segment);
+            path = &link.parent;
+        }
+
+        segments.reverse();
+        segments
+    }
+
+    pub(crate) fn child(&self, segment: PathSegment<'v>) -> Self {
+        Self(Some(Rc::new(Link {
+            parent: self.clone(),
+            segment,
+            len: self.len() + 1,
+        })))
+    }
+}
+
+// Dropping a long chain of links recursively would overflow the stack.
+impl Drop for Path<'_> {
+    fn drop(&mut self) {
+        let mut next = self.0.take();
+        while let Some(link) = next {
+            next = match Rc::try_unwrap(link) {
+                Ok(mut link) => link.parent.0.take(),
+                Err(_) => None,
+            };
+        }
+    }
+}
+
+impl fmt::Display for Path<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (index, segment) in self.segments().into_iter().enumerate() {
+            let separator = match index {
+                0 => "",
+                _ => ".",
+            };
+
+            match segment {
+                PathSegment::Field(name) => write!(f, "{separator}{name}")?,
+                PathSegment::Index(index) => write!(f, "[{index}]")?,
+                PathSegment::Key(Value::Str(key)) if is_ident(key) => {
+                    write!(f, "{separator}{key}")?
+                }
+                PathSegment::Key(key) => write!(f, "[{key}]")?,
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl fmt::Debug for Path<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.segments()).finish()
+    }
+}
+
+/// An iterator over every node within a [`Value`], created by
+/// [`Value::walk`].
+pub struct Walk<'v> {
+    stack: Vec<(Path<'v>, &'v Value)>,
+}
+
+impl<'v> Iterator for Walk<'v> {
+    type Item = (Path<'v>, &'v Value);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (path, value) = self.stack.pop()?;
+
+        let start = self.stack.len();
+        let inner = match value {
+            Value::Truncated { value, .. } => value,
+            value => value,
+        };
+        match inner {
+            Value::Struct { fields, .. } => {
+                self.stack.extend(
+                    fields
+                        .iter()
+                        .map(|(name, value)| (path.child(PathSegment::Field(name)), value)),
+                );
+            }
+            Value::TupleStruct { elements, .. }
+            | Value::Tuple(elements)
+            | Value::Seq(elements)
+            | Value::Set(elements) => {
+                self.stack.extend(
+                    elements
+                        .iter()
+                        .enumerate()
+                        .map(|(index, value)| (path.child(PathSegment::Index(index)), value)),
+                );
+            }
+            Value::Map(entries) => {
+                self.stack.extend(
+                    entries
+                        .iter()
+                        .map(|(key, value)| (path.child(PathSegment::Key(key)), value)),
+                );
+            }
+            Value::Option(Some(inner)) => {
+                self.stack.push((path.child(PathSegment::Index(0)), inner));
+            }
+            _ => (),
+        }
+        self.stack[start..].reverse();
+
+        Some((path, value))
+    }
+}
+
+impl Value {
+    /// Iterate over this value and every value nested within it, depth first
+    /// and in the order they appear in the input, along with the path to each.
+    ///
+    /// Map keys are not visited themselves but are part of the path to the
+    /// entry's value. A [`Truncated`](Value::Truncated) collection is yielded
+    /// once, followed by the elements that were kept.
+    ///
+    /// This does not recurse, so it works for arbitrarily deeply nested
+    /// values.
+    ///
+    /// # Example
+    /// ```
+    /// use serde_dbgfmt::Value;
+    ///
+    /// let value: Value = r#"Config { name: "prod", ports: [80, 443] }"#.parse().unwrap();
+    /// let nodes: Vec<_> = value
+    ///     .walk()
+    ///     .map(|(path, value)| format!("{path} = {value}"))
+    ///     .collect();
+    ///
+    /// assert_eq!(
+    ///     nodes,
+    ///     [
+    ///         r#" = Config { name: "prod", ports: [80, 443] }"#,
+    ///         r#"name = "prod""#,
+    ///         "ports = [80, 443]",
+    ///         "ports[0] = 80",
+    ///         "ports[1] = 443",
+    ///     ]
+    /// );
+    /// ```
+    pub fn walk(&self) -> Walk<'_> {
+        Walk {
+            stack: vec![(Path::default(), self)],
+        }
+    }
+
+    /// Find every node within this value that matches `predicate`, along with
+    /// the path to each, in the same order as [`walk`](Self::walk).
+    ///
+    /// # Example
+    /// ```
+    /// use serde_dbgfmt::Value;
+    ///
+    /// let value: Value = "Stats { hits: [3, 40, 7], misses: 52 }".parse().unwrap();
+    /// let large: Vec<_> = value
+    ///     .find_all(|_, value| value.as_i128().map_or(false, |n| n > 10))
+    ///     .into_iter()
+    ///     .map(|(path, _)| path.to_string())
+    ///     .collect();
+    ///
+    /// assert_eq!(large, ["hits[1]", "misses"]);
+    /// ```
+    pub fn find_all<F>(&self, mut predicate: F) -> Vec<(Path<'_>, &Value)>
+    where
+        F: FnMut(&Path<'_>, &Value) -> bool,
+    {
+        self.walk()
+            .filter(|(path, value)| predicate(path, value))
+            .collect()
+    }
+
+    /// Find the value of every struct field, and of every map entry with a
+    /// string key, named `name` no matter how deeply it is nested.
+    ///
+    /// # Example
+    /// ```
+    /// use serde_dbgfmt::Value;
+    ///
+    /// let text = r#"Config { db: Db { password: "hunter2" }, users: {"password": "*"} }"#;
+    /// let value: Value = text.parse().unwrap();
+    /// let found: Vec<_> = value
+    ///     .find_key("password")
+    ///     .into_iter()
+    ///     .map(|(path, value)| format!("{path} = {value}"))
+    ///     .collect();
+    ///
+    /// assert_eq!(found, [r#"db.password = "hunter2""#, r#"users.password = "*""#]);
+    /// ```
+    pub fn find_key(&self, name: &str) -> Vec<(Path<'_>, &Value)> {
+        self.find_all(|path, _| match path.last() {
+            Some(PathSegment::Field(field)) => field == name,
+            Some(PathSegment::Key(key)) => key.as_str() == Some(name),
+            _ => false,
+        })
+    }
+
+    /// Call `f` with this value and every value nested within it, in the same
+    /// order as [`walk`](Self::walk).
+    ///
+    /// `f` is called with each value before the values nested within it, so
+    /// any children that `f` replaces or adds are walked as well. This takes a
+    /// callback instead of returning an iterator since a value cannot be
+    /// borrowed mutably at the same time as the values nested within it.
+    ///
+    /// # Example
+    /// ```
+    /// use serde_dbgfmt::{PathSegment, Value};
+    ///
+    /// let mut value: Value = r#"User { name: "ferris", token: "abc" }"#.parse().unwrap();
+    /// value.walk_mut(|path, value| {
+    ///     if path.last() == Some(PathSegment::Field("token")) {
+    ///         *value = Value::Str("<redacted>".into());
+    ///     }
+    /// });
+    ///
+    /// assert_eq!(value.to_string(), r#"User { name: "ferris", token: "<redacted>" }"#);
+    /// ```
+    pub fn walk_mut<F>(&mut self, mut f: F)
+    where
+        F: FnMut(&Path<'_>, &mut Value),
+    {
+        let mut stack = vec![(Path::default(), self)];
+
+        while let Some((path, value)) = stack.pop() {
+            f(&path, value);
+
+            let start = stack.len();
+            let value = match value {
+                Value::Truncated { value, .. } => value,
+                value => value,
+            };
+            match value {
+                Value::Struct { fields, .. } => {
+                    stack.extend(
+                        fields
+                            .iter_mut()
+                            .map(|(name, value)| (path.child(PathSegment::Field(name)), value)),
+                    );
+                }
+                Value::TupleStruct { elements, .. }
+                | Value::Tuple(elements)
+                | Value::Seq(elements)
+                | Value::Set(elements) => {
+                    stack.extend(
+                        elements
+                            .iter_mut()
+                            .enumerate()
+                            .map(|(index, value)| (path.child(PathSegment::Index(index)), value)),
+                    );
+                }
+                Value::Map(entries) => {
+                    stack.extend(
+                        entries
+                            .iter_mut()
+                            .map(|(key, value)| (path.child(PathSegment::Key(key)), value)),
+                    );
+                }
+                Value::Option(Some(inner)) => {
+                    stack.push((path.child(PathSegment::Index(0)), inner));
+                }
+                _ => (),
+            }
+            stack[start..].reverse();
+        }
+    }
+}
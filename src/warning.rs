@@ -0,0 +1,102 @@
+use core::fmt;
+
+/// A non-fatal event that occurred while deserializing.
+///
+/// Warnings are emitted when the deserializer accepts input but either has to
+/// change or discard part of it in order to do so. They can be observed by
+/// installing a callback via [`Options::on_warning`].
+///
+/// [`Options::on_warning`]: crate::Options::on_warning
+#[derive(Clone, Debug, PartialEq)]
+#[non_exhaustive]
+pub enum Warning<'a> {
+    /// A struct field was present in the input but was ignored by the type
+    /// being deserialized.
+    UnknownField {
+        /// The name of the field.
+        name: &'a str,
+    },
+
+    /// A struct ended with `..`, meaning that some of its fields were left out
+    /// of the debug output.
+    NonExhaustive {
+        /// The `..` marker in the input.
+        span: &'a str,
+    },
+
+    /// A float literal was too large for the float type and has been
+    /// saturated to infinity.
+    FloatOverflow {
+        /// The float literal.
+        literal: &'a str,
+    },
+
+    /// A non-zero float literal was too small for the float type and has been
+    /// rounded to zero.
+    FloatUnderflow {
+        /// The float literal.
+        literal: &'a str,
+    },
+
+    /// A number was converted between integer and float types because
+    /// [`Options::coerce_numbers`] is enabled.
+    ///
+    /// [`Options::coerce_numbers`]: crate::Options::coerce_numbers
+    NumberCoerced {
+        /// The number literal.
+        literal: &'a str,
+    },
+
+    /// A word such as `yes` or `off` was converted to a boolean because
+    /// [`Options::coerce_bools`] is enabled.
+    ///
+    /// [`Options::coerce_bools`]: crate::Options::coerce_bools
+    BoolCoerced {
+        /// The word that was converted.
+        word: &'a str,
+    },
+
+    /// A `\u{..}` escape of a lone surrogate was replaced with U+FFFD because
+    /// [`Options::replace_lone_surrogates`] is enabled.
+    ///
+    /// [`Options::replace_lone_surrogates`]: crate::Options::replace_lone_surrogates
+    SurrogateReplaced {
+        /// The escape sequence.
+        escape: &'a str,
+    },
+
+    /// A map entry was skipped because its key had already been seen, as
+    /// configured by [`Options::duplicate_keys`].
+    ///
+    /// [`Options::duplicate_keys`]: crate::Options::duplicate_keys
+    DuplicateKey {
+        /// The key of the skipped entry.
+        key: &'a str,
+    },
+}
+
+impl fmt::Display for Warning<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnknownField { name } => write!(f, "ignored unknown field `{name}`"),
+            Self::NonExhaustive { .. } => f.write_str("struct has fields omitted with `..`"),
+            Self::FloatOverflow { literal } => {
+                write!(f, "float literal `{literal}` overflowed to infinity")
+            }
+            Self::FloatUnderflow { literal } => {
+                write!(f, "float literal `{literal}` underflowed to zero")
+            }
+            Self::NumberCoerced { literal } => {
+                write!(
+                    f,
+                    "number `{literal}` was coerced to a different numeric type"
+                )
+            }
+            Self::BoolCoerced { word } => write!(f, "`{word}` was coerced to a boolean"),
+            Self::SurrogateReplaced { escape } => {
+                write!(f, "lone surrogate `{escape}` was replaced with U+FFFD")
+            }
+            Self::DuplicateKey { key } => write!(f, "skipped entry with duplicate key `{key}`"),
+        }
+    }
+}
@@ -0,0 +1,917 @@
+//! Helpers for use with `#[serde(with = "...")]`.
+//!
+//! Some types have debug representations which look nothing like their serde
+//! representation. The modules here can be used on individual fields of such
+//! types so that the rest of the struct can still be derived as usual.
+
+use alloc::borrow::ToOwned;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+/// Deserialize a [`std::io::Error`] from its debug representation.
+///
+/// See [`IoError`](crate::IoError) for the accepted forms.
+///
+/// # Example
+/// ```
+/// use serde::Deserialize;
+///
+/// #[derive(Deserialize)]
+/// struct Failure {
+///     #[serde(with = "serde_dbgfmt::with::io_error")]
+///     error: std::io::Error,
+/// }
+///
+/// let text = r#"Failure { error: Custom { kind: Other, error: "oh no" } }"#;
+/// let failure: Failure = serde_dbgfmt::from_str(text).unwrap();
+///
+/// assert_eq!(failure.error.kind(), std::io::ErrorKind::Other);
+/// assert_eq!(failure.error.to_string(), "oh no");
+/// ```
+#[cfg(feature = "std")]
+pub mod io_error {
+    use serde::{Deserialize, Deserializer};
+
+    use crate::IoError;
+
+    /// Deserialize an [`io::Error`](std::io::Error).
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<std::io::Error, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        IoError::deserialize(deserializer).map(Into::into)
+    }
+}
+
+/// Deserialize a set of flags from the debug representation of a `bitflags`
+/// type.
+///
+/// `bitflags` types print as `Flags(READ | WRITE)`. This extracts the flags
+/// expression and then deserializes `T` from it as a string, which matches
+/// what the serde impl for `bitflags` types expects. Unnamed bits (e.g.
+/// `Flags(READ | 0x8)`) are passed through as-is.
+///
+/// The public flags type should derive its serde impls with
+/// `#[serde(transparent)]` as recommended by the `bitflags` docs.
+///
+/// To get the individual flag names instead use
+/// [`bitflags_names`](crate::with::bitflags_names). If you need the raw bits
+/// as an integer then use `bitflags_bits`, which requires the `bitflags`
+/// feature.
+///
+/// # Example
+/// ```
+/// use serde::Deserialize;
+///
+/// #[derive(Deserialize)]
+/// struct File {
+///     #[serde(with = "serde_dbgfmt::with::bitflags")]
+///     mode: String,
+/// }
+///
+/// let file: File = serde_dbgfmt::from_str("File { mode: Mode(READ | WRITE) }").unwrap();
+/// assert_eq!(file.mode, "READ | WRITE");
+/// ```
+pub mod bitflags {
+    use serde::de::value::StrDeserializer;
+    use serde::de::DeserializeOwned;
+    use serde::Deserializer;
+
+    /// Deserialize a `T` from a flags expression.
+    pub fn deserialize<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+    where
+        D: Deserializer<'de>,
+        T: DeserializeOwned,
+    {
+        let flags = super::parse_flags(deserializer)?;
+        T::deserialize(StrDeserializer::<D::Error>::new(&flags.join(" | ")))
+    }
+}
+
+/// Deserialize the bits of a `bitflags` type from its debug representation
+/// into an integer.
+///
+/// The integer type alone does not say which flags exist, so the flags type
+/// is passed as a type parameter using `deserialize_with`. Unnamed bits (e.g.
+/// `Flags(READ | 0x8)`) are kept as they are.
+///
+/// # Example
+/// ```
+/// use serde::Deserialize;
+///
+/// bitflags::bitflags! {
+///     pub struct Mode: u32 {
+///         const READ = 0b01;
+///         const WRITE = 0b10;
+///     }
+/// }
+///
+/// #[derive(Deserialize)]
+/// struct File {
+///     #[serde(deserialize_with = "serde_dbgfmt::with::bitflags_bits::deserialize::<_, Mode>")]
+///     mode: u32,
+/// }
+///
+/// let file: File = serde_dbgfmt::from_str("File { mode: Mode(WRITE | 0x8) }").unwrap();
+/// assert_eq!(file.mode, 0b1010);
+/// ```
+#[cfg(feature = "bitflags")]
+pub mod bitflags_bits {
+    use bitflags::parser::ParseHex;
+    use bitflags::Flags;
+    use serde::de::Error;
+    use serde::Deserializer;
+
+    /// Deserialize the bits of the flags type `F`.
+    pub fn deserialize<'de, D, F>(deserializer: D) -> Result<F::Bits, D::Error>
+    where
+        D: Deserializer<'de>,
+        F: Flags,
+        F::Bits: ParseHex,
+    {
+        let flags = super::parse_flags(deserializer)?;
+        let flags: F = bitflags::parser::from_str(&flags.join(" | ")).map_err(D::Error::custom)?;
+        Ok(flags.bits())
+    }
+}
+
+/// Deserialize the names of the flags that are set in the debug
+/// representation of a `bitflags` type.
+///
+/// This works with any collection of strings, such as `Vec<String>` or
+/// `BTreeSet<String>`. Flags with no bits set print as `Flags(0x0)` and will
+/// result in an empty collection.
+///
+/// # Example
+/// ```
+/// use std::collections::BTreeSet;
+///
+/// use serde::Deserialize;
+///
+/// #[derive(Deserialize)]
+/// struct File {
+///     #[serde(with = "serde_dbgfmt::with::bitflags_names")]
+///     mode: BTreeSet<String>,
+/// }
+///
+/// let file: File = serde_dbgfmt::from_str("File { mode: Mode(WRITE | READ) }").unwrap();
+/// assert_eq!(file.mode, BTreeSet::from(["READ".into(), "WRITE".into()]));
+/// ```
+pub mod bitflags_names {
+    use alloc::string::String;
+
+    use serde::Deserializer;
+
+    /// Deserialize the set flag names into a collection.
+    pub fn deserialize<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+    where
+        D: Deserializer<'de>,
+        T: FromIterator<String>,
+    {
+        let flags = super::parse_flags(deserializer)?;
+        Ok(flags
+            .into_iter()
+            .filter(|flag| !matches!(flag.as_str(), "0x0" | "0"))
+            .collect())
+    }
+}
+
+/// Parse a `Flags(A | B | C)` expression into its individual flags.
+fn parse_flags<'de, D>(deserializer: D) -> Result<Vec<String>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    use core::fmt;
+
+    use serde::de::{Error, Visitor};
+
+    use crate::de::RAW_VALUE_TOKEN;
+    use crate::lex::{Lexer, TokenKind};
+
+    struct FlagsVisitor;
+
+    impl<'de> Visitor<'de> for FlagsVisitor {
+        type Value = Vec<String>;
+
+        fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            f.write_str("a set of flags like `Flags(A | B)`")
+        }
+
+        fn visit_str<E: Error>(self, v: &str) -> Result<Self::Value, E> {
+            let mut lexer = Lexer::new(v);
+            let mut tokens = Vec::new();
+            loop {
+                let token = lexer.parse_token().map_err(E::custom)?;
+                if token.kind == TokenKind::Eof {
+                    break;
+                }
+
+                tokens.push(token);
+            }
+
+            // Strip the `Flags( ... )` wrapper, if present.
+            let inner = match tokens.as_slice() {
+                [name, open, inner @ .., close]
+                    if name.kind == TokenKind::Ident
+                        && open.is_punct("(")
+                        && close.is_punct(")") =>
+                {
+                    inner
+                }
+                tokens => tokens,
+            };
+
+            // The flags alternate between a flag and a `|` separator, and must both
+            // start and end with a flag.
+            let invalid = || E::invalid_value(serde::de::Unexpected::Str(v), &self);
+            if inner.len() % 2 == 0 {
+                return Err(invalid());
+            }
+
+            let mut flags = Vec::new();
+            for (index, token) in inner.iter().enumerate() {
+                match index % 2 {
+                    0 if matches!(token.kind, TokenKind::Ident | TokenKind::Integer) => {
+                        flags.push(token.value.to_owned())
+                    }
+                    1 if token.is_punct("|") => (),
+                    _ => return Err(invalid()),
+                }
+            }
+
+            Ok(flags)
+        }
+    }
+
+    deserializer.deserialize_newtype_struct(RAW_VALUE_TOKEN, FlagsVisitor)
+}
+
+/// Deserialize an [`OsString`](std::ffi::OsString) from its debug
+/// representation.
+///
+/// `OsString` prints as a regular string but serde represents it as an enum
+/// of platform-specific code units, so the two are not compatible.
+///
+/// # Example
+/// ```
+/// use std::ffi::OsString;
+///
+/// use serde::Deserialize;
+///
+/// #[derive(Deserialize)]
+/// struct Command {
+///     #[serde(with = "serde_dbgfmt::with::os_string")]
+///     program: OsString,
+/// }
+///
+/// let text = format!("Command {{ program: {:?} }}", OsString::from("ls"));
+/// let command: Command = serde_dbgfmt::from_str(&text).unwrap();
+///
+/// assert_eq!(command.program, "ls");
+/// ```
+#[cfg(feature = "std_types")]
+pub mod os_string {
+    use std::ffi::OsString;
+
+    use serde::Deserializer;
+
+    /// Deserialize an [`OsString`].
+    ///
+    /// Bytes that are not valid UTF-8 are kept on Unix. Elsewhere they result
+    /// in an error.
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<OsString, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let bytes = super::parse_raw_bytes(deserializer, "an OS string")?;
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::ffi::OsStringExt;
+
+            Ok(OsString::from_vec(bytes))
+        }
+
+        #[cfg(not(unix))]
+        {
+            use serde::de::Error;
+
+            String::from_utf8(bytes)
+                .map(Into::into)
+                .map_err(D::Error::custom)
+        }
+    }
+}
+
+/// Deserialize a [`CString`](std::ffi::CString) from its debug
+/// representation.
+///
+/// `CString` prints as a string literal but serde expects a byte buffer.
+///
+/// # Example
+/// ```
+/// use std::ffi::CString;
+///
+/// use serde::Deserialize;
+///
+/// #[derive(Deserialize)]
+/// struct Symbol {
+///     #[serde(with = "serde_dbgfmt::with::c_string")]
+///     name: CString,
+/// }
+///
+/// let symbol: Symbol = serde_dbgfmt::from_str(r#"Symbol { name: "main" }"#).unwrap();
+///
+/// assert_eq!(symbol.name.as_bytes(), b"main");
+/// ```
+#[cfg(feature = "std_types")]
+pub mod c_string {
+    use std::ffi::CString;
+
+    use serde::de::Error;
+    use serde::Deserializer;
+
+    /// Deserialize a [`CString`].
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<CString, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let bytes = super::parse_raw_bytes(deserializer, "a C string")?;
+        CString::new(bytes).map_err(D::Error::custom)
+    }
+}
+
+/// Deserialize a [`Range`](std::ops::Range) from its debug representation.
+///
+/// Ranges print as `start..end` while serde represents them as a struct with
+/// `start` and `end` fields.
+///
+/// # Example
+/// ```
+/// use std::ops::Range;
+///
+/// use serde::Deserialize;
+///
+/// #[derive(Deserialize)]
+/// struct Slice {
+///     #[serde(with = "serde_dbgfmt::with::range")]
+///     bytes: Range<usize>,
+/// }
+///
+/// let slice: Slice = serde_dbgfmt::from_str("Slice { bytes: 4..16 }").unwrap();
+///
+/// assert_eq!(slice.bytes, 4..16);
+/// ```
+#[cfg(feature = "std_types")]
+pub mod range {
+    use core::ops::Range;
+
+    use serde::de::DeserializeOwned;
+    use serde::Deserializer;
+
+    /// Deserialize a [`Range`].
+    pub fn deserialize<'de, D, T>(deserializer: D) -> Result<Range<T>, D::Error>
+    where
+        D: Deserializer<'de>,
+        T: DeserializeOwned,
+    {
+        let (start, end) = super::parse_range(deserializer, "..")?;
+        Ok(start..end)
+    }
+}
+
+/// Deserialize a [`RangeInclusive`](std::ops::RangeInclusive) from its debug
+/// representation.
+///
+/// Inclusive ranges print as `start..=end`.
+///
+/// # Example
+/// ```
+/// use std::ops::RangeInclusive;
+///
+/// use serde::Deserialize;
+///
+/// #[derive(Deserialize)]
+/// struct Bounds {
+///     #[serde(with = "serde_dbgfmt::with::range_inclusive")]
+///     x: RangeInclusive<i32>,
+/// }
+///
+/// let bounds: Bounds = serde_dbgfmt::from_str("Bounds { x: -5..=5 }").unwrap();
+///
+/// assert_eq!(bounds.x, -5..=5);
+/// ```
+#[cfg(feature = "std_types")]
+pub mod range_inclusive {
+    use core::ops::RangeInclusive;
+
+    use serde::de::DeserializeOwned;
+    use serde::Deserializer;
+
+    /// Deserialize a [`RangeInclusive`].
+    pub fn deserialize<'de, D, T>(deserializer: D) -> Result<RangeInclusive<T>, D::Error>
+    where
+        D: Deserializer<'de>,
+        T: DeserializeOwned,
+    {
+        let (start, end) = super::parse_range(deserializer, "..=")?;
+        Ok(start..=end)
+    }
+}
+
+/// Parse a `start..end` style range with the given separator.
+///
+/// The range is captured as raw text so that each of the bounds can then be
+/// deserialized on their own.
+#[cfg(feature = "std_types")]
+fn parse_range<'de, D, T>(deserializer: D, separator: &'static str) -> Result<(T, T), D::Error>
+where
+    D: serde::Deserializer<'de>,
+    T: serde::de::DeserializeOwned,
+{
+    use core::fmt;
+
+    use serde::de::{Error, Visitor};
+
+    use crate::de::RAW_VALUE_TOKEN;
+    use crate::lex::{Lexer, TokenKind};
+
+    struct RangeVisitor<T> {
+        separator: &'static str,
+        _marker: core::marker::PhantomData<T>,
+    }
+
+    impl<'de, T: serde::de::DeserializeOwned> Visitor<'de> for RangeVisitor<T> {
+        type Value = (T, T);
+
+        fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            write!(f, "a range of the form `start{}end`", self.separator)
+        }
+
+        fn visit_str<E: Error>(self, v: &str) -> Result<Self::Value, E> {
+            let mut lexer = Lexer::new(v);
+            let mut depth = 0usize;
+
+            // Find the separator, skipping over any nested values.
+            let split = loop {
+                let token = lexer.parse_token().map_err(E::custom)?;
+                match (token.kind, token.value) {
+                    (TokenKind::Eof, _) => {
+                        return Err(E::invalid_value(serde::de::Unexpected::Str(v), &self))
+                    }
+                    (TokenKind::Punct, "(" | "[" | "{") => depth += 1,
+                    (TokenKind::Punct, ")" | "]" | "}") => depth = depth.saturating_sub(1),
+                    (TokenKind::Punct, sep) if depth == 0 && sep == self.separator => {
+                        break token.value;
+                    }
+                    _ => (),
+                }
+            };
+
+            let offset = split.as_ptr() as usize - v.as_ptr() as usize;
+            let start = &v[..offset];
+            let end = &v[offset + split.len()..];
+
+            let start = crate::from_str(start).map_err(E::custom)?;
+            let end = crate::from_str(end).map_err(E::custom)?;
+            Ok((start, end))
+        }
+    }
+
+    deserializer.deserialize_newtype_struct(
+        RAW_VALUE_TOKEN,
+        RangeVisitor {
+            separator,
+            _marker: core::marker::PhantomData,
+        },
+    )
+}
+
+/// Deserialize a [`serde_json::Value`] from its debug representation.
+///
+/// See [`parse_json_value`](crate::json::parse_json_value) for details on the
+/// format.
+///
+/// # Example
+/// ```
+/// use serde::Deserialize;
+/// use serde_json::json;
+///
+/// #[derive(Deserialize)]
+/// struct Request {
+///     #[serde(with = "serde_dbgfmt::with::json_value")]
+///     body: serde_json::Value,
+/// }
+///
+/// let text = r#"Request { body: Object {"ok": Bool(true)} }"#;
+/// let request: Request = serde_dbgfmt::from_str(text).unwrap();
+///
+/// assert_eq!(request.body, json!({ "ok": true }));
+/// ```
+#[cfg(feature = "json")]
+pub mod json_value {
+    use core::fmt;
+
+    use serde::de::{Error, Visitor};
+    use serde::Deserializer;
+
+    use crate::de::RAW_VALUE_TOKEN;
+
+    /// Deserialize a [`serde_json::Value`].
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<serde_json::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct ValueVisitor;
+
+        impl<'de> Visitor<'de> for ValueVisitor {
+            type Value = serde_json::Value;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("the debug representation of a JSON value")
+            }
+
+            fn visit_str<E: Error>(self, v: &str) -> Result<Self::Value, E> {
+                crate::json::parse_json_value(v).map_err(E::custom)
+            }
+        }
+
+        deserializer.deserialize_newtype_struct(RAW_VALUE_TOKEN, ValueVisitor)
+    }
+}
+
+/// Deserialize a `u64` written in hexadecimal.
+///
+/// This accepts the value with or without a `0x` prefix, either bare (e.g.
+/// `0xdeadbeef` or `ff`) or within a string (e.g. `"deadbeef"`). Digits
+/// without a prefix are always read as hexadecimal.
+///
+/// # Example
+/// ```
+/// use serde::Deserialize;
+///
+/// #[derive(Deserialize)]
+/// struct Commit {
+///     #[serde(with = "serde_dbgfmt::with::hex_u64")]
+///     hash: u64,
+/// }
+///
+/// let commit: Commit = serde_dbgfmt::from_str("Commit { hash: 1f }").unwrap();
+/// assert_eq!(commit.hash, 0x1f);
+///
+/// let commit: Commit = serde_dbgfmt::from_str(r#"Commit { hash: "0xDEAD_BEEF" }"#).unwrap();
+/// assert_eq!(commit.hash, 0xdead_beef);
+/// ```
+pub mod hex_u64 {
+    use serde::de::{Error, Unexpected};
+    use serde::Deserializer;
+
+    /// Deserialize a hexadecimal `u64`.
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<u64, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        const EXPECTING: &str = "a hexadecimal integer";
+
+        let text = super::parse_raw_text(deserializer, EXPECTING)?;
+        let digits = text
+            .strip_prefix("0x")
+            .or_else(|| text.strip_prefix("0X"))
+            .unwrap_or(&text)
+            .replace('_', "");
+
+        u64::from_str_radix(&digits, 16)
+            .map_err(|_| D::Error::invalid_value(Unexpected::Str(&text), &EXPECTING))
+    }
+}
+
+/// Deserialize a [`Duration`](std::time::Duration) from its debug
+/// representation.
+///
+/// Durations print as a decimal number followed by a unit, such as `1.5s`,
+/// `250ms`, `10µs`, or `7ns`. `us` is accepted in place of `µs`. The value
+/// may also be within a string. The conversion is exact, so no precision is
+/// lost to floating point rounding.
+///
+/// `Duration` fields can be deserialized from the unquoted form without this
+/// module. It is only needed when the duration may be within a string.
+///
+/// # Example
+/// ```
+/// use std::time::Duration;
+///
+/// use serde::Deserialize;
+///
+/// #[derive(Deserialize)]
+/// struct Timing {
+///     #[serde(with = "serde_dbgfmt::with::duration_str")]
+///     elapsed: Duration,
+/// }
+///
+/// let text = format!("{:?}", Duration::from_nanos(1_500_000_001));
+/// let timing: Timing = serde_dbgfmt::from_str(&format!("Timing {{ elapsed: {text} }}")).unwrap();
+///
+/// assert_eq!(timing.elapsed, Duration::from_nanos(1_500_000_001));
+/// ```
+pub mod duration_str {
+    use core::time::Duration;
+
+    use serde::de::{Error, Unexpected};
+    use serde::Deserializer;
+
+    use crate::literal::parse_duration;
+
+    /// Deserialize a [`Duration`].
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Duration, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        const EXPECTING: &str = "a duration such as `1.5s` or `250ms`";
+
+        let text = super::parse_raw_text(deserializer, EXPECTING)?;
+        parse_duration(&text)
+            .ok_or_else(|| D::Error::invalid_value(Unexpected::Str(&text), &EXPECTING))
+    }
+}
+
+/// Deserialize an [`Instant`](std::time::Instant) from its debug
+/// representation, as the [`Duration`](std::time::Duration) since the
+/// unspecified point that it is measured from.
+///
+/// An `Instant` cannot be created from a raw value, and its debug output is
+/// specific to the platform. This accepts the forms used by Unix
+/// (`Instant { tv_sec: 12, tv_nsec: 5 }`, possibly nested within a `t` field)
+/// and by Windows (`Instant { t: 12.000000005s }`). Only differences between
+/// instants printed by the same process are meaningful.
+///
+/// `SystemTime` fields do not need an adapter since they are converted to the
+/// form expected by their serde impl automatically.
+///
+/// # Example
+/// ```
+/// use std::time::{Duration, SystemTime};
+///
+/// use serde::Deserialize;
+///
+/// #[derive(Deserialize)]
+/// struct Request {
+///     #[serde(with = "serde_dbgfmt::with::instant")]
+///     started: Duration,
+///     #[serde(with = "serde_dbgfmt::with::instant")]
+///     finished: Duration,
+///     received: SystemTime,
+/// }
+///
+/// let text = "Request { \
+///     started: Instant { tv_sec: 12, tv_nsec: 0 }, \
+///     finished: Instant { tv_sec: 12, tv_nsec: 250000000 }, \
+///     received: SystemTime { tv_sec: 1700000000, tv_nsec: 0 } \
+/// }";
+/// let request: Request = serde_dbgfmt::from_str(text).unwrap();
+///
+/// assert_eq!(request.finished - request.started, Duration::from_millis(250));
+/// assert_eq!(
+///     request.received,
+///     SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000)
+/// );
+/// ```
+#[cfg(feature = "std")]
+pub mod instant {
+    use core::time::Duration;
+
+    use serde::de::{Error, Unexpected};
+    use serde::Deserializer;
+
+    use crate::time::RawTime;
+
+    /// Deserialize the time since the reference point of an `Instant`.
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Duration, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        const EXPECTING: &str = "the debug representation of an `Instant`";
+
+        let text = super::parse_raw_text(deserializer, EXPECTING)?;
+        RawTime::parse(&text, "Instant")
+            .map_err(D::Error::custom)?
+            .to_instant_offset()
+            .ok_or_else(|| D::Error::invalid_value(Unexpected::Str(&text), &EXPECTING))
+    }
+}
+
+/// Deserialize an IP address from its debug representation.
+///
+/// The types in [`std::net`] print as their bare address (e.g. `127.0.0.1` or
+/// `::1`), which is not made up of valid tokens. This captures the text of the
+/// value and parses it using [`FromStr`](std::str::FromStr), so it works for
+/// [`IpAddr`](std::net::IpAddr), [`Ipv4Addr`](std::net::Ipv4Addr),
+/// [`Ipv6Addr`](std::net::Ipv6Addr), and [`SocketAddr`](std::net::SocketAddr).
+/// Addresses within a string are accepted as well.
+///
+/// # Example
+/// ```
+/// use std::net::{IpAddr, SocketAddr};
+///
+/// use serde::Deserialize;
+///
+/// #[derive(Deserialize)]
+/// struct Peer {
+///     #[serde(with = "serde_dbgfmt::with::ip_addr")]
+///     ip: IpAddr,
+///     #[serde(with = "serde_dbgfmt::with::ip_addr")]
+///     addr: SocketAddr,
+/// }
+///
+/// let peer: Peer = serde_dbgfmt::from_str("Peer { ip: ::1, addr: 10.0.0.1:443 }").unwrap();
+///
+/// assert_eq!(peer.ip.to_string(), "::1");
+/// assert_eq!(peer.addr.port(), 443);
+/// ```
+#[cfg(feature = "std")]
+pub mod ip_addr {
+    use core::fmt::Display;
+    use core::str::FromStr;
+
+    use serde::de::Error;
+    use serde::Deserializer;
+
+    /// Deserialize an address.
+    pub fn deserialize<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+    where
+        D: Deserializer<'de>,
+        T: FromStr,
+        T::Err: Display,
+    {
+        let text = super::parse_raw_text(deserializer, "an IP address")?;
+        text.parse().map_err(D::Error::custom)
+    }
+}
+
+/// Deserialize any value as the raw text that it was printed as.
+///
+/// This is an escape hatch for fields whose debug representation can't be
+/// parsed at all, such as `<closure>` placeholders or the output of a custom
+/// `Debug` impl. The text is kept exactly as it appears in the input,
+/// including the quotes around strings. The value ends at the next `,` or
+/// closing bracket that is not nested within the value.
+///
+/// # Example
+/// ```
+/// use serde::Deserialize;
+///
+/// #[derive(Deserialize)]
+/// struct Task {
+///     id: u32,
+///     #[serde(with = "serde_dbgfmt::with::opaque_string")]
+///     callback: String,
+/// }
+///
+/// let task: Task = serde_dbgfmt::from_str("Task { id: 1, callback: <fn(&str) -> u8> }").unwrap();
+///
+/// assert_eq!(task.callback, "<fn(&str) -> u8>");
+/// ```
+pub mod opaque_string {
+    use alloc::borrow::ToOwned;
+    use alloc::string::String;
+
+    use serde::de::{Deserializer, Visitor};
+
+    use crate::de::RAW_TEXT_TOKEN;
+
+    /// Deserialize the raw text of a value.
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<String, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct TextVisitor;
+
+        impl<'de> Visitor<'de> for TextVisitor {
+            type Value = String;
+
+            fn expecting(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+                f.write_str("any value")
+            }
+
+            fn visit_str<E: serde::de::Error>(self, v: &str) -> Result<Self::Value, E> {
+                Ok(v.to_owned())
+            }
+        }
+
+        deserializer.deserialize_newtype_struct(RAW_TEXT_TOKEN, TextVisitor)
+    }
+}
+
+/// Deserialize a [`Decimal`](rust_decimal::Decimal) from the digits it was
+/// printed with.
+///
+/// `Decimal` deserializes using `deserialize_any`, which reads a float literal
+/// as an `f64` and so can lose precision. This reads the literal as text
+/// instead so the value, including its scale, is kept exactly. Literals in
+/// scientific notation and literals within a string are accepted as well.
+/// Values that need more precision than a `Decimal` has result in an error
+/// instead of being rounded.
+///
+/// # Example
+/// ```
+/// use rust_decimal::Decimal;
+/// use serde::Deserialize;
+///
+/// #[derive(Deserialize)]
+/// struct Invoice {
+///     #[serde(with = "serde_dbgfmt::with::decimal")]
+///     total: Decimal,
+/// }
+///
+/// let invoice: Invoice = serde_dbgfmt::from_str("Invoice { total: 1234.5600 }").unwrap();
+///
+/// assert_eq!(invoice.total.to_string(), "1234.5600");
+/// ```
+#[cfg(feature = "rust_decimal")]
+pub mod decimal {
+    use rust_decimal::Decimal;
+    use serde::de::Error;
+    use serde::Deserializer;
+
+    /// Deserialize a [`Decimal`].
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Decimal, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let text = super::parse_raw_text(deserializer, "a decimal number")?;
+        let text = text.strip_prefix('+').unwrap_or(&text).replace('_', "");
+
+        match text.contains(['e', 'E']) {
+            true => Decimal::from_scientific(&text),
+            false => Decimal::from_str_exact(&text),
+        }
+        .map_err(D::Error::custom)
+    }
+}
+
+/// Capture the raw text of a value, removing the quotes if it is a string.
+fn parse_raw_text<'de, D>(deserializer: D, expecting: &'static str) -> Result<String, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    use core::fmt;
+
+    use serde::de::{Error, Visitor};
+
+    use crate::de::RAW_TEXT_TOKEN;
+    use crate::util::unescape;
+
+    struct TextVisitor(&'static str);
+
+    impl<'de> Visitor<'de> for TextVisitor {
+        type Value = String;
+
+        fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            f.write_str(self.0)
+        }
+
+        fn visit_str<E: Error>(self, v: &str) -> Result<Self::Value, E> {
+            match v.strip_prefix('"').and_then(|v| v.strip_suffix('"')) {
+                Some(inner) => Ok(unescape(inner).map_err(E::custom)?.into_owned()),
+                None => Ok(v.to_owned()),
+            }
+        }
+    }
+
+    deserializer.deserialize_newtype_struct(RAW_TEXT_TOKEN, TextVisitor(expecting))
+}
+
+/// Capture the raw text of a string literal and decode it as bytes.
+///
+/// `CString` and `OsString` print bytes that are not valid UTF-8 as `\x`
+/// escapes, which a regular string literal cannot contain.
+#[cfg(feature = "std_types")]
+fn parse_raw_bytes<'de, D>(deserializer: D, expecting: &'static str) -> Result<Vec<u8>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    use core::fmt;
+
+    use serde::de::{Error, Unexpected, Visitor};
+
+    use crate::de::RAW_TEXT_TOKEN;
+    use crate::util::unescape_bytes;
+
+    struct BytesVisitor(&'static str);
+
+    impl<'de> Visitor<'de> for BytesVisitor {
+        type Value = Vec<u8>;
+
+        fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            f.write_str(self.0)
+        }
+
+        fn visit_str<E: Error>(self, v: &str) -> Result<Self::Value, E> {
+            match v.strip_prefix('"').and_then(|v| v.strip_suffix('"')) {
+                Some(inner) => Ok(unescape_bytes(inner).map_err(E::custom)?.into_owned()),
+                None => Err(E::invalid_value(Unexpected::Str(v), &self)),
+            }
+        }
+    }
+
+    deserializer.deserialize_newtype_struct(RAW_TEXT_TOKEN, BytesVisitor(expecting))
+}
@@ -0,0 +1,84 @@
+use pretty_assertions::assert_eq;
+use serde::Deserialize;
+use serde_dbgfmt::{Options, TokenBuffer};
+
+#[derive(Debug, Deserialize, PartialEq)]
+enum Shape {
+    Circle { radius: f64 },
+    Square(f64),
+}
+
+#[test]
+fn deserialize_many() {
+    let buffer = TokenBuffer::new("[Circle { radius: 1.5 }, Square(2.0)]");
+
+    for _ in 0..2 {
+        let shapes: Vec<Shape> = buffer.deserialize().unwrap();
+        assert_eq!(shapes, [Shape::Circle { radius: 1.5 }, Shape::Square(2.0)]);
+    }
+
+    assert!(buffer.deserialize::<Vec<u32>>().is_err());
+    assert!(buffer.deserialize::<Shape>().is_err());
+}
+
+#[test]
+fn borrowed() {
+    let buffer = TokenBuffer::new(r#"("a", "b")"#);
+    let value: (&str, &str) = buffer.deserialize().unwrap();
+    assert_eq!(value, ("a", "b"));
+    assert_eq!(buffer.input(), r#"("a", "b")"#);
+}
+
+#[test]
+fn same_as_unbuffered() {
+    let text = r#"Test { a: [1, 2, 3], b: {"x": Some(-1)}, c: () }"#;
+    let options = Options::new().collect_stats(true);
+
+    let mut de = serde_dbgfmt::Deserializer::with_options(text, options.clone());
+    serde::de::IgnoredAny::deserialize(&mut de).unwrap();
+    let expected = de.stats();
+
+    let buffer = TokenBuffer::new(text);
+    let mut de = buffer.deserializer_with(options);
+    serde::de::IgnoredAny::deserialize(&mut de).unwrap();
+    de.end().unwrap();
+    assert_eq!(de.stats(), expected);
+}
+
+#[test]
+#[cfg(feature = "std")]
+fn invalid_tokens() {
+    use std::net::IpAddr;
+
+    #[derive(Debug, Deserialize)]
+    struct Peer {
+        #[serde(with = "serde_dbgfmt::with::ip_addr")]
+        ip: IpAddr,
+        port: u16,
+    }
+
+    let buffer = TokenBuffer::new("Peer { ip: 10.0.0.1, port: 80 }");
+    let peer: Peer = buffer.deserialize().unwrap();
+    assert_eq!(peer.ip.to_string(), "10.0.0.1");
+    assert_eq!(peer.port, 80);
+
+    let buffer = TokenBuffer::new("[1, 2, @]");
+    let error = buffer.deserialize::<Vec<u32>>().unwrap_err();
+    let expected = serde_dbgfmt::from_str::<Vec<u32>>("[1, 2, @]").unwrap_err();
+    assert_eq!(error.to_string(), expected.to_string());
+}
+
+#[test]
+fn relex() {
+    let old = "[Circle { radius: 1.5 }, Square(2.0)]";
+    let buffer = TokenBuffer::new(old);
+
+    let new = old.replace("1.5", "3.25");
+    let buffer = buffer.relex(&new, 18..21);
+    let shapes: Vec<Shape> = buffer.deserialize().unwrap();
+    assert_eq!(shapes, [Shape::Circle { radius: 3.25 }, Shape::Square(2.0)]);
+
+    let new = new.replace("Square", "Triangle");
+    let buffer = buffer.relex(&new, 26..32);
+    assert!(buffer.deserialize::<Vec<Shape>>().is_err());
+}
@@ -0,0 +1,75 @@
+use std::collections::{HashMap, HashSet};
+
+use pretty_assertions::assert_eq;
+use serde_dbgfmt::canonicalize;
+
+#[test]
+fn canonicalize_sorts_hash_collections() {
+    #[derive(Debug)]
+    #[allow(dead_code)]
+    struct Dump {
+        map: HashMap<String, Vec<u32>>,
+        set: HashSet<(u8, char)>,
+    }
+
+    let dump = Dump {
+        map: (0..20).map(|i| (format!("key{i:02}"), vec![i])).collect(),
+        set: (0..20).map(|i| (i, 'x')).collect(),
+    };
+
+    let compact = canonicalize(&format!("{dump:?}")).unwrap();
+    let pretty = canonicalize(&format!("{dump:#?}")).unwrap();
+    assert_eq!(compact, pretty);
+
+    let mut map: Vec<_> = dump.map.iter().collect();
+    map.sort();
+    let mut set: Vec<_> = dump.set.iter().map(|e| format!("{e:?}")).collect();
+    set.sort();
+
+    assert_eq!(
+        compact,
+        format!(
+            "Dump {{ map: {{{}}}, set: {{{}}} }}",
+            map.iter()
+                .map(|(k, v)| format!("{k:?}: {v:?}"))
+                .collect::<Vec<_>>()
+                .join(", "),
+            set.join(", ")
+        )
+    );
+}
+
+#[test]
+fn canonicalize_layout() {
+    let cases = [
+        ("Unit", "Unit"),
+        ("  Some(\n    5,\n)  ", "Some(5)"),
+        ("(\n    1,\n)", "(1,)"),
+        ("( 1 , 2 )", "(1, 2)"),
+        ("[ ]", "[]"),
+        ("{}", "{}"),
+        ("A {\n    x: -1.5,\n    ..\n}", "A { x: -1.5, .. }"),
+        ("A { .. }", "A { .. }"),
+        ("Range { r: 1..5 }", "Range { r: 1..5 }"),
+        (r#"{'b', 'a'}"#, r#"{'a', 'b'}"#),
+    ];
+
+    for (input, expected) in cases {
+        assert_eq!(canonicalize(input).unwrap(), expected, "{input}");
+    }
+}
+
+#[test]
+fn canonicalize_invalid() {
+    assert!(canonicalize("{1: 2").is_err());
+    assert!(canonicalize("").is_err());
+}
+
+#[test]
+fn canonicalize_deep() {
+    let deep = "[".repeat(200_000);
+    assert!(canonicalize(&deep).unwrap_err().is_depth_limit_exceeded());
+
+    let nested = format!("{}{}", "[".repeat(100), "]".repeat(100));
+    assert_eq!(canonicalize(&nested).unwrap(), nested);
+}
@@ -0,0 +1,83 @@
+use pretty_assertions::assert_eq;
+use serde_dbgfmt::{diff, structurally_equals, Comparator, Value};
+
+fn differences(comparator: &Comparator, a: &str, b: &str) -> Vec<String> {
+    comparator
+        .diff(a, b)
+        .unwrap()
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+}
+
+#[test]
+fn formatting_and_order_are_ignored() {
+    let a = r#"State { map: {"a": 1, "b": [2.5]}, set: {1, 2, 3}, opt: Some(Unit) }"#;
+    let b = "State {\n    map: {\n        \"b\": [\n            2.5,\n        ],\n        \"a\": 1,\n    },\n    set: {3, 1, 2},\n    opt: Some(\n        Unit,\n    ),\n}";
+
+    assert!(structurally_equals(a, b).unwrap());
+    assert_eq!(diff(a, b).unwrap(), []);
+}
+
+#[test]
+fn differences_in_order() {
+    let a = r#"Config { name: "a", ports: [80, 443], inner: Inner { x: 1 }, map: {1: 2}, set: {1.0, 2} }"#;
+    let b = r#"Config { name: "b", ports: [80], inner: Other { x: 1 }, map: {1: 3, 2: 4}, set: {2, 1.5}, extra: () }"#;
+
+    assert_eq!(
+        differences(&Comparator::new(), a, b),
+        [
+            r#"name: "a" != "b""#,
+            "ports[1]: 443 != <missing>",
+            "inner: Inner { x: 1 } != Other { x: 1 }",
+            "map[1]: 2 != 3",
+            "map[2]: <missing> != 4",
+            "set[0]: 1.0 != 1.5",
+            "extra: <missing> != ()",
+        ]
+    );
+    assert!(!structurally_equals(a, b).unwrap());
+}
+
+#[test]
+fn float_tolerance() {
+    let a = "Sample { x: 0.1, y: 1000000.0, z: [NaN, inf] }";
+    let b = "Sample { x: 0.10000000000000002, y: 1000000.1, z: [NaN, inf] }";
+
+    assert_eq!(
+        differences(&Comparator::new(), a, b),
+        ["x: 0.1 != 0.10000000000000002", "y: 1000000.0 != 1000000.1"]
+    );
+    assert_eq!(
+        differences(&Comparator::new().absolute_epsilon(1e-9), a, b),
+        ["y: 1000000.0 != 1000000.1"]
+    );
+    assert_eq!(
+        differences(&Comparator::new().relative_epsilon(1e-6), a, b),
+        Vec::<String>::new()
+    );
+
+    // Tolerances only apply between floats.
+    let comparator = Comparator::new().absolute_epsilon(1.0);
+    assert!(comparator.equals("1.0", "1.5").unwrap());
+    assert!(!comparator.equals("1", "2").unwrap());
+    assert!(!comparator.equals("1.0", "1").unwrap());
+}
+
+#[test]
+fn deeply_nested() {
+    let depth = 100_000;
+    let a: Value = format!("{}1.0{}", "[Some(".repeat(depth), ")]".repeat(depth))
+        .parse()
+        .unwrap();
+    let b: Value = format!("{}1.5{}", "[Some(".repeat(depth), ")]".repeat(depth))
+        .parse()
+        .unwrap();
+
+    let differences = Comparator::new().diff_values(&a, &b);
+    assert_eq!(differences.len(), 1);
+    assert_eq!(differences[0].path, "[0][0]".repeat(depth));
+    assert!(Comparator::new()
+        .absolute_epsilon(0.5)
+        .equals_values(&a, &b));
+}
@@ -145,9 +145,1182 @@ roundtrip_struct! {
     }
 }
 
+#[test]
+fn test_integer_trailing_zeros() {
+    assert_eq!(serde_dbgfmt::from_str::<i32>("100").unwrap(), 100);
+    assert_eq!(serde_dbgfmt::from_str::<i64>("-1000").unwrap(), -1000);
+    assert_eq!(serde_dbgfmt::from_str::<i16>("0x100").unwrap(), 256);
+    assert_eq!(
+        serde_dbgfmt::from_str::<i8>("-000000000000000000000128").unwrap(),
+        -128
+    );
+    assert!(serde_dbgfmt::from_str::<i8>("1280").is_err());
+}
+
 #[test]
 fn test_nan() {
     let nan: f32 = serde_dbgfmt::from_dbg(&f32::NAN).unwrap_or_else(|e| panic!("{}", e));
 
     assert!(nan.is_nan());
 }
+
+#[test]
+fn test_infinity() {
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Limits {
+        lower: f64,
+        upper: f32,
+        all: Vec<f64>,
+    }
+
+    let value = Limits {
+        lower: f64::NEG_INFINITY,
+        upper: f32::INFINITY,
+        all: vec![f64::INFINITY, -f64::INFINITY, 1.0],
+    };
+    let parsed: Limits = serde_dbgfmt::from_dbg(&value).unwrap();
+    assert_eq!(parsed, value);
+
+    assert_eq!(
+        serde_dbgfmt::from_str::<f64>("Infinity").unwrap(),
+        f64::INFINITY
+    );
+    assert_eq!(
+        serde_dbgfmt::from_str::<f64>("-INF").unwrap(),
+        f64::NEG_INFINITY
+    );
+    assert!(serde_dbgfmt::from_str::<f64>("nan").unwrap().is_nan());
+    assert!(serde_dbgfmt::from_str::<f64>("infinite").is_err());
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    #[serde(untagged)]
+    enum Number {
+        Float(f64),
+        Text(String),
+    }
+
+    let parsed: Vec<Number> = serde_dbgfmt::from_str("[inf, -inf, +inf, 1.5]").unwrap();
+    assert_eq!(
+        parsed,
+        [
+            Number::Float(f64::INFINITY),
+            Number::Float(f64::NEG_INFINITY),
+            Number::Float(f64::INFINITY),
+            Number::Float(1.5),
+        ]
+    );
+
+    let parsed: Vec<Number> = serde_dbgfmt::from_str("[NaN, -NaN]").unwrap();
+    assert!(matches!(parsed[..], [Number::Float(a), Number::Float(b)] if a.is_nan() && b.is_nan()));
+}
+
+#[test]
+fn test_float_like_unit_variants() {
+    #[derive(Debug, Deserialize, PartialEq)]
+    enum Mode {
+        Infinity,
+        Inf,
+        Nan,
+    }
+
+    let parsed: Vec<Mode> = serde_dbgfmt::from_str("[Infinity, Inf, Nan]").unwrap();
+    assert_eq!(parsed, [Mode::Infinity, Mode::Inf, Mode::Nan]);
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    #[serde(untagged)]
+    enum Any {
+        Float(f64),
+        Unit,
+    }
+
+    // Only the exact spellings used by Debug are floats when the type is
+    // unknown.
+    let parsed: Vec<Any> =
+        serde_dbgfmt::from_str("[inf, -inf, Infinity, Inf, INF, Nan, nan]").unwrap();
+    assert_eq!(
+        parsed,
+        [
+            Any::Float(f64::INFINITY),
+            Any::Float(f64::NEG_INFINITY),
+            Any::Unit,
+            Any::Unit,
+            Any::Unit,
+            Any::Unit,
+            Any::Unit,
+        ]
+    );
+}
+
+#[test]
+fn test_bytes() {
+    use std::fmt;
+
+    use serde::de::{Deserializer, Visitor};
+
+    #[derive(Debug, PartialEq)]
+    enum Bytes<'a> {
+        Borrowed(&'a [u8]),
+        Owned(Vec<u8>),
+    }
+
+    impl<'de> Deserialize<'de> for Bytes<'de> {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            struct BytesVisitor;
+
+            impl<'de> Visitor<'de> for BytesVisitor {
+                type Value = Bytes<'de>;
+
+                fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                    f.write_str("bytes")
+                }
+
+                fn visit_borrowed_bytes<E>(self, v: &'de [u8]) -> Result<Self::Value, E> {
+                    Ok(Bytes::Borrowed(v))
+                }
+
+                fn visit_byte_buf<E>(self, v: Vec<u8>) -> Result<Self::Value, E> {
+                    Ok(Bytes::Owned(v))
+                }
+            }
+
+            deserializer.deserialize_bytes(BytesVisitor)
+        }
+    }
+
+    let parse = |text| serde_dbgfmt::from_str::<Bytes>(text).unwrap();
+
+    assert_eq!(parse(r#"b"abc""#), Bytes::Borrowed(b"abc"));
+    assert_eq!(parse(r#"b"""#), Bytes::Borrowed(b""));
+    assert_eq!(
+        parse(r#"b"a\x00\xff\n\"\\""#),
+        Bytes::Owned(b"a\x00\xff\n\"\\".to_vec())
+    );
+    assert_eq!(parse("[1, 2, 255]"), Bytes::Owned(vec![1, 2, 255]));
+    assert_eq!(parse(&format!("{:#?}", [1u8, 2])), Bytes::Owned(vec![1, 2]));
+
+    assert!(serde_dbgfmt::from_str::<Bytes>(r#"b"\x4""#).is_err());
+    assert!(serde_dbgfmt::from_str::<Bytes>(r#"b"\q""#).is_err());
+    assert!(serde_dbgfmt::from_str::<Bytes>("[1, 256]").is_err());
+    assert!(serde_dbgfmt::from_str::<Bytes>(r#"b "abc""#).is_err());
+
+    let options = serde_dbgfmt::Options::new().dialect(serde_dbgfmt::Dialect::PythonRepr);
+    let bytes: Bytes = serde_dbgfmt::from_str_with(r"b'it\'s'", &options).unwrap();
+    assert_eq!(bytes, Bytes::Owned(b"it's".to_vec()));
+}
+
+#[test]
+fn test_hex_float() {
+    assert_eq!(serde_dbgfmt::from_str::<f64>("0x1.8p3").unwrap(), 12.0);
+    assert_eq!(serde_dbgfmt::from_str::<f64>("-0x1p-2").unwrap(), -0.25);
+    assert_eq!(
+        serde_dbgfmt::from_str::<f32>("0x1.fffffep127").unwrap(),
+        f32::MAX
+    );
+    assert_eq!(
+        serde_dbgfmt::from_str::<f64>("0x1.fffffffffffffp1023").unwrap(),
+        f64::MAX
+    );
+    assert_eq!(serde_dbgfmt::from_str::<u32>("0x1f").unwrap(), 31);
+    assert!(serde_dbgfmt::from_str::<f64>("0x1.8").is_err());
+}
+
+#[test]
+fn test_float_roundtrip() {
+    let values = [
+        0.1,
+        -2.5e-8,
+        1e300,
+        f64::MAX,
+        f64::MIN_POSITIVE,
+        5e-324,
+        123456789.0,
+    ];
+    let parsed: Vec<f64> = serde_dbgfmt::from_dbg(&values).unwrap();
+    assert_eq!(parsed, values);
+
+    let values = [0.1f32, f32::MAX, f32::MIN_POSITIVE, 1e-45, -3.25];
+    let parsed: Vec<f32> = serde_dbgfmt::from_dbg(&values).unwrap();
+    assert_eq!(parsed, values);
+
+    assert!(serde_dbgfmt::parse_float_literal::<f64>("1.5e").is_err());
+    assert!(serde_dbgfmt::parse_float_literal::<f64>("1.5.0").is_err());
+}
+
+#[test]
+fn test_from_dbg_buf() {
+    let value = vec![("alpha", 1), ("beta", 2)];
+
+    let mut buf = String::from("stale contents");
+    let parsed: Vec<(&str, u32)> = serde_dbgfmt::from_dbg_buf(&value, &mut buf).unwrap();
+    assert_eq!(parsed, value);
+
+    // Strings containing escapes cannot be borrowed from the buffer.
+    assert!(serde_dbgfmt::from_dbg_buf::<Vec<&str>, _>(&["a\\b"], &mut buf).is_err());
+    let parsed: Vec<String> = serde_dbgfmt::from_dbg_buf(&["a\\b"], &mut buf).unwrap();
+    assert_eq!(parsed, ["a\\b"]);
+}
+
+#[test]
+fn test_in_place() {
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Record {
+        name: String,
+        values: Vec<u32>,
+    }
+
+    let mut record = Record {
+        name: String::with_capacity(64),
+        values: Vec::with_capacity(64),
+    };
+    let name_ptr = record.name.as_ptr();
+    let values_ptr = record.values.as_ptr();
+
+    for i in 0..4u32 {
+        let text = format!(r#"Record {{ name: "record {i}", values: [{i}, 5, 6] }}"#);
+        serde_dbgfmt::from_str_in_place(&text, &mut record).unwrap_or_else(|e| panic!("{e}"));
+
+        assert_eq!(
+            record,
+            Record {
+                name: format!("record {i}"),
+                values: vec![i, 5, 6],
+            }
+        );
+    }
+
+    assert_eq!(record.name.as_ptr(), name_ptr);
+    assert_eq!(record.values.as_ptr(), values_ptr);
+}
+
+#[test]
+fn test_escaped_quotes() {
+    assert_eq!(
+        serde_dbgfmt::from_str::<String>(r#""say \"hi\"""#).unwrap(),
+        "say \"hi\""
+    );
+    assert_eq!(
+        serde_dbgfmt::from_str::<String>(r#""trailing \\""#).unwrap(),
+        "trailing \\"
+    );
+    assert_eq!(serde_dbgfmt::from_str::<char>(r"'\''").unwrap(), '\'');
+    assert_eq!(
+        serde_dbgfmt::from_str::<Vec<String>>(r#"["\\", "\"", "\u{e9}\""]"#).unwrap(),
+        ["\\", "\"", "\u{e9}\""]
+    );
+    assert!(serde_dbgfmt::from_str::<String>(r#""unterminated \""#).is_err());
+}
+
+#[test]
+fn test_empty_strings() {
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Names<'a> {
+        first: &'a str,
+        rest: Vec<String>,
+        nick: Option<&'a str>,
+        map: BTreeMap<String, String>,
+    }
+
+    let value = Names {
+        first: "",
+        rest: vec!["".into(), "\\".into(), "".into()],
+        nick: Some(""),
+        map: [("".to_owned(), "".to_owned())].into(),
+    };
+
+    let text = format!("{value:?}");
+    assert_eq!(serde_dbgfmt::from_str::<Names>(&text).unwrap(), value);
+
+    let text = format!("{value:#?}");
+    assert_eq!(serde_dbgfmt::from_str::<Names>(&text).unwrap(), value);
+
+    assert_eq!(serde_dbgfmt::from_str::<&str>(r#""""#).unwrap(), "");
+    assert_eq!(
+        serde_dbgfmt::from_str::<(String, char, String)>(r#"("", '"', "")"#).unwrap(),
+        ("".to_owned(), '"', "".to_owned())
+    );
+    assert!(serde_dbgfmt::from_str::<char>("''").is_err());
+}
+
+#[test]
+fn test_pathological_strings() {
+    // These would take quadratic time to lex if the lexer rescanned the
+    // string after every escaped quote.
+    let quotes = "\"".repeat(200_000);
+    let text = format!("{quotes:?}");
+    assert_eq!(serde_dbgfmt::from_str::<String>(&text).unwrap(), quotes);
+
+    let backslashes = "\\".repeat(200_000);
+    let text = format!("{backslashes:?}");
+    assert_eq!(
+        serde_dbgfmt::from_str::<String>(&text).unwrap(),
+        backslashes
+    );
+
+    let unterminated = format!("\"{}", "\\\"".repeat(200_000));
+    assert!(serde_dbgfmt::from_str::<String>(&unterminated).is_err());
+}
+
+#[test]
+fn test_owned_input() {
+    use std::borrow::Cow;
+
+    let value: Vec<Option<u32>> = serde_dbgfmt::from_string(Cow::from("[Some(1), None]")).unwrap();
+    assert_eq!(value, [Some(1), None]);
+
+    let mut de = serde_dbgfmt::OwnedDeserializer::new(String::from("5 \"six\" [7]"));
+    assert_eq!(de.deserialize::<u32>().unwrap(), 5);
+    assert!(de.deserialize::<u32>().is_err());
+    assert_eq!(de.deserialize::<String>().unwrap(), "six");
+    assert!(de.end().is_err());
+    assert_eq!(de.deserialize::<Vec<u8>>().unwrap(), [7]);
+    de.end().unwrap();
+    assert_eq!(de.remaining(), "");
+}
+
+#[test]
+fn test_extension_traits() {
+    use serde_dbgfmt::{DebugParseExt, DebugReparse};
+
+    let text = String::from("(1, \"two\")");
+    let value: (u8, &str) = text.parse_debug().unwrap();
+    assert_eq!(value, (1, "two"));
+
+    let reparsed: Vec<u64> = [1u8, 2, 3].reparse().unwrap();
+    assert_eq!(reparsed, [1, 2, 3]);
+
+    let slice: &[i32] = &[-1, 2];
+    let reparsed: Vec<i64> = slice.reparse().unwrap();
+    assert_eq!(reparsed, [-1, 2]);
+
+    let reparsed: String = "quoted".reparse().unwrap();
+    assert_eq!(reparsed, "quoted");
+    assert!("quoted".parse_debug::<String>().is_err());
+}
+
+#[test]
+fn test_for_each_element() {
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Item {
+        id: u32,
+    }
+
+    let mut items = Vec::new();
+    serde_dbgfmt::for_each_element("[Item { id: 1 }, Item { id: 2 }]", |item: Item| {
+        items.push(item)
+    })
+    .unwrap();
+    assert_eq!(items, [Item { id: 1 }, Item { id: 2 }]);
+
+    let mut set = BTreeSet::new();
+    serde_dbgfmt::for_each_element("{3, 1, 2}", |x: u32| {
+        set.insert(x);
+    })
+    .unwrap();
+    assert_eq!(set, BTreeSet::from([1, 2, 3]));
+
+    let mut map = BTreeMap::new();
+    serde_dbgfmt::for_each_element(r#"{"a": [1], "b": []}"#, |(k, v): (String, Vec<u32>)| {
+        map.insert(k, v);
+    })
+    .unwrap();
+    assert_eq!(
+        map,
+        BTreeMap::from([("a".to_owned(), vec![1]), ("b".to_owned(), vec![])])
+    );
+
+    let mut count = 0;
+    serde_dbgfmt::for_each_element("[]", |()| count += 1).unwrap();
+    serde_dbgfmt::for_each_element("{}", |()| count += 1).unwrap();
+    assert_eq!(count, 0);
+}
+
+#[test]
+fn test_for_each_element_errors() {
+    let mut seen = Vec::new();
+    let result = serde_dbgfmt::for_each_element("[1, 2, x, 4]", |x: u32| seen.push(x));
+    assert!(result.is_err());
+    assert_eq!(seen, [1, 2]);
+
+    assert!(serde_dbgfmt::for_each_element("[1, 2] 3", |_: u32| ()).is_err());
+    assert!(serde_dbgfmt::for_each_element("(1, 2)", |_: u32| ()).is_err());
+    assert!(serde_dbgfmt::for_each_element("[1 2]", |_: u32| ()).is_err());
+
+    #[derive(Debug)]
+    enum StopError {
+        Parse(#[allow(dead_code)] serde_dbgfmt::Error),
+        Stop(u32),
+    }
+
+    impl From<serde_dbgfmt::Error> for StopError {
+        fn from(error: serde_dbgfmt::Error) -> Self {
+            Self::Parse(error)
+        }
+    }
+
+    let result = serde_dbgfmt::try_for_each_element("[1, 2, 3]", |x: u32| match x {
+        2 => Err(StopError::Stop(x)),
+        _ => Ok(()),
+    });
+    assert!(matches!(result, Err(StopError::Stop(2))));
+
+    let result = serde_dbgfmt::try_for_each_element("[1, x]", |_: u32| Ok(()));
+    assert!(matches!(result, Err(StopError::Parse(_))));
+}
+
+#[test]
+fn test_enum_dispatch_by_struct_name() {
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Connect {
+        addr: String,
+    }
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Disconnect;
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    enum Event {
+        Connect(Connect),
+        Disconnect(Disconnect),
+        Other(u32),
+    }
+
+    let events: Vec<Event> = serde_dbgfmt::from_str(
+        r#"[Connect { addr: "a" }, Disconnect, Connect(Connect { addr: "b" }), Other(3)]"#,
+    )
+    .unwrap();
+    assert_eq!(
+        events,
+        [
+            Event::Connect(Connect { addr: "a".into() }),
+            Event::Disconnect(Disconnect),
+            Event::Connect(Connect { addr: "b".into() }),
+            Event::Other(3),
+        ]
+    );
+
+    assert!(serde_dbgfmt::from_str::<Event>("Other").is_err());
+    assert!(serde_dbgfmt::from_str::<Event>("Connect { addr: 5 }").is_err());
+}
+
+#[test]
+fn test_error_expected() {
+    use serde_dbgfmt::ExpectedKind;
+
+    let error = serde_dbgfmt::from_str::<Vec<u32>>("[1, 2").unwrap_err();
+    assert_eq!(error.found_token(), None);
+    assert!(error.expected().is_some());
+
+    let error = serde_dbgfmt::from_str::<u32>("5 6").unwrap_err();
+    assert_eq!(error.expected(), Some(ExpectedKind::Eof));
+    assert_eq!(error.found_token(), Some("6"));
+
+    let error = serde_dbgfmt::from_str::<u8>("300").unwrap_err();
+    assert_eq!(error.expected(), None);
+    assert_eq!(error.found_token(), None);
+}
+
+#[test]
+fn test_error_kind() {
+    use serde_dbgfmt::ErrorKind;
+
+    #[derive(Debug, Deserialize)]
+    #[serde(deny_unknown_fields)]
+    #[allow(dead_code)]
+    struct Point {
+        x: u8,
+        y: u8,
+    }
+
+    let kind = |text: &str| serde_dbgfmt::from_str::<Point>(text).unwrap_err().kind();
+
+    assert_eq!(kind("Point { x: 1, y: 2 } 3"), ErrorKind::TrailingData);
+    assert_eq!(kind("Point { x: 1,"), ErrorKind::Eof);
+    assert_eq!(kind("Point [ x: 1 ]"), ErrorKind::UnexpectedToken);
+    assert_eq!(kind("Point { x: 300, y: 2 }"), ErrorKind::ParseInt);
+    assert_eq!(kind("Point { x: 1 }"), ErrorKind::MissingField);
+    assert_eq!(kind("Point { x: 1, y: 2, z: 3 }"), ErrorKind::UnknownField);
+    assert_eq!(kind("Point { x: 1, x: 2 }"), ErrorKind::DuplicateField);
+
+    let error = serde_dbgfmt::from_str::<std::num::NonZeroU8>("0").unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::InvalidValue);
+
+    let error = serde_dbgfmt::from_str::<String>(r#""\q""#).unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::InvalidStringLiteral);
+
+    let error = serde_dbgfmt::from_str::<Point>("Point { x: 1, y: 2, z: 3 }").unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "unknown field `z`, expected `x` or `y` at line 1 column 22"
+    );
+}
+
+#[test]
+fn test_struct_as_map() {
+    use std::collections::HashMap;
+
+    let map: BTreeMap<String, u32> = serde_dbgfmt::from_str("Config { a: 1, b: 2 }").unwrap();
+    assert_eq!(map, BTreeMap::from([("a".into(), 1), ("b".into(), 2)]));
+
+    let map: HashMap<&str, Vec<u32>> =
+        serde_dbgfmt::from_str("Config { a: [1], b: [], .. }").unwrap();
+    assert_eq!(map, HashMap::from([("a", vec![1]), ("b", vec![])]));
+
+    let map: BTreeMap<String, u32> = serde_dbgfmt::from_str("Empty {}").unwrap();
+    assert!(map.is_empty());
+
+    assert!(serde_dbgfmt::from_str::<BTreeMap<String, u32>>("Config(1)").is_err());
+}
+
+#[test]
+fn test_map_as_struct() {
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Person {
+        name: String,
+        age: u32,
+        #[serde(default)]
+        email: Option<String>,
+    }
+
+    let person: Person = serde_dbgfmt::from_str(r#"{"name": "x", "age": 3}"#).unwrap();
+    assert_eq!(
+        person,
+        Person {
+            name: "x".into(),
+            age: 3,
+            email: None
+        }
+    );
+
+    let person: Person =
+        serde_dbgfmt::from_str(r#"{"age": 3, "email": Some("a@b"), "name": "x"}"#).unwrap();
+    assert_eq!(person.email.as_deref(), Some("a@b"));
+
+    assert!(serde_dbgfmt::from_str::<Person>(r#"{"name": "x"}"#).is_err());
+}
+
+/// Text that std does not currently emit, but which is still a valid way to
+/// write the same values, should be accepted.
+#[test]
+fn test_alternate_formats() {
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Record {
+        name: String,
+        c: char,
+        ratio: f64,
+        items: Vec<u32>,
+    }
+
+    let expected = Record {
+        name: "it's café\0".into(),
+        c: '\'',
+        ratio: 1e-7,
+        items: vec![1, 2],
+    };
+
+    // Escaped non-ASCII characters and single quotes within strings, NUL
+    // written as `\u{0}`, floats without exponents, and pretty output without
+    // trailing commas.
+    let alternate = [
+        r#"Record { name: "it\'s caf\u{e9}\u{0}", c: '\'', ratio: 0.0000001, items: [1, 2] }"#,
+        "Record {\n    name: \"it's café\\0\",\n    c: '\\'',\n    ratio: 1e-7,\n    items: [\n        1,\n        2\n    ]\n}",
+    ];
+
+    for text in alternate {
+        let record: Record = serde_dbgfmt::from_str(text).unwrap_or_else(|e| panic!("{e}"));
+        assert_eq!(record, expected);
+    }
+}
+
+#[test]
+fn test_scratch_buffer() {
+    use std::fmt;
+
+    use serde::de::Visitor;
+    use serde_dbgfmt::Deserializer;
+
+    /// Records how the string was passed to the visitor.
+    #[derive(Debug, PartialEq)]
+    enum Text {
+        Borrowed(String),
+        Transient(String),
+        Owned(String),
+    }
+
+    impl<'de> Deserialize<'de> for Text {
+        fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            struct TextVisitor;
+
+            impl<'de> Visitor<'de> for TextVisitor {
+                type Value = Text;
+
+                fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                    f.write_str("a string")
+                }
+
+                fn visit_borrowed_str<E>(self, v: &'de str) -> Result<Text, E> {
+                    Ok(Text::Borrowed(v.into()))
+                }
+
+                fn visit_str<E>(self, v: &str) -> Result<Text, E> {
+                    Ok(Text::Transient(v.into()))
+                }
+
+                fn visit_string<E>(self, v: String) -> Result<Text, E> {
+                    Ok(Text::Owned(v))
+                }
+            }
+
+            deserializer.deserialize_str(TextVisitor)
+        }
+    }
+
+    let text = r#"["plain", "tab\t", "\u{1f980}"]"#;
+
+    let mut de = Deserializer::new(text);
+    assert_eq!(
+        Vec::<Text>::deserialize(&mut de).unwrap(),
+        [
+            Text::Borrowed("plain".into()),
+            Text::Owned("tab\t".into()),
+            Text::Owned("\u{1f980}".into()),
+        ]
+    );
+
+    let mut de = Deserializer::new(text).with_scratch(String::with_capacity(64));
+    assert_eq!(
+        Vec::<Text>::deserialize(&mut de).unwrap(),
+        [
+            Text::Borrowed("plain".into()),
+            Text::Transient("tab\t".into()),
+            Text::Transient("\u{1f980}".into()),
+        ]
+    );
+    de.end().unwrap();
+
+    let scratch = de.into_scratch();
+    assert_eq!(scratch, "\u{1f980}");
+    assert!(scratch.capacity() >= 64);
+
+    assert_eq!(Deserializer::new("1").into_scratch(), "");
+}
+
+#[test]
+#[cfg(feature = "std")]
+fn test_from_reader() {
+    use std::io::{self, Read};
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Record {
+        name: String,
+        values: Vec<u32>,
+    }
+
+    let record = Record {
+        name: "dump".into(),
+        values: (0..1000).collect(),
+    };
+    let text = format!("{record:#?}\n");
+
+    let parsed: Record = serde_dbgfmt::from_reader(text.as_bytes()).unwrap();
+    assert_eq!(parsed, record);
+
+    let options = serde_dbgfmt::Options::new().allow_trailing_data(true);
+    let parsed: Vec<u8> =
+        serde_dbgfmt::from_reader_with(&b"[1, 2] and more"[..], &options).unwrap();
+    assert_eq!(parsed, [1, 2]);
+
+    let error = serde_dbgfmt::from_reader::<_, u8>(&b"\xff"[..]).unwrap_err();
+    assert!(error.is_io());
+
+    struct Failing;
+
+    impl Read for Failing {
+        fn read(&mut self, _: &mut [u8]) -> io::Result<usize> {
+            Err(io::Error::new(io::ErrorKind::BrokenPipe, "pipe closed"))
+        }
+    }
+
+    let error = serde_dbgfmt::from_reader::<_, u8>(Failing).unwrap_err();
+    assert!(error.is_io());
+    assert_eq!(error.to_string(), "failed to read the input: pipe closed");
+    assert_eq!(error.location(), None);
+    assert!(std::error::Error::source(&error).is_some());
+
+    let error = serde_dbgfmt::from_reader::<_, u8>(&b"x"[..]).unwrap_err();
+    assert!(!error.is_io());
+}
+
+#[test]
+#[cfg(feature = "std")]
+fn test_from_reader_incremental() {
+    use std::fmt;
+    use std::io::{self, Read};
+
+    use serde::de::{DeserializeOwned, Deserializer, SeqAccess, Visitor};
+
+    /// Returns at most `len` bytes from each read.
+    struct Trickle<'a>(&'a [u8], usize);
+
+    impl Read for Trickle<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let len = self.1.min(buf.len()).min(self.0.len());
+            buf[..len].copy_from_slice(&self.0[..len]);
+            self.0 = &self.0[len..];
+            Ok(len)
+        }
+    }
+
+    fn check<T>(input: &str)
+    where
+        T: DeserializeOwned + PartialEq + std::fmt::Debug,
+    {
+        let expected =
+            serde_dbgfmt::from_str::<T>(input).map_err(|e| (e.to_string(), e.location()));
+        for len in [1, 2, 3, 64] {
+            let reader = Trickle(input.as_bytes(), len);
+            let parsed = serde_dbgfmt::from_reader::<_, T>(reader)
+                .map_err(|e| (e.to_string(), e.location()));
+            assert_eq!(parsed, expected, "{input:?} read {len} bytes at a time");
+        }
+    }
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Record {
+        name: String,
+        tags: Vec<String>,
+        value: Option<f64>,
+    }
+
+    let records: Vec<_> = (0..20)
+        .map(|i| Record {
+            name: format!("récord \"{i}\" 🦀"),
+            tags: vec!["a".repeat(i), "ü".into()],
+            value: Some(i as f64 / 3.0).filter(|_| i % 2 == 0),
+        })
+        .collect();
+    check::<Vec<Record>>(&format!("{records:?}"));
+    check::<Vec<Record>>(&format!("{records:#?}"));
+    check::<Vec<Record>>(&format!("{records:#?}").replace("tags: [\n", "tags: [\n 5,\n"));
+
+    check::<Vec<u8>>("[1, 2, 3]");
+    check::<Vec<u8>>("  [1, 2, 3,]\n");
+    check::<Vec<u8>>("[]");
+    check::<Vec<u8>>("[1 2]");
+    check::<Vec<u8>>("[1, 2)");
+    check::<Vec<u8>>("[1, 2}");
+    check::<Vec<u8>>("[1, 2");
+    check::<Vec<u8>>("[1, 2,");
+    check::<Vec<u8>>("[1, , 2]");
+    check::<Vec<u8>>("[1, 300]");
+    check::<Vec<u8>>("[1, 2] 3");
+    check::<Vec<u8>>("[1, 2, @]");
+    check::<Vec<u8>>("[");
+    check::<Vec<u8>>("");
+    check::<Vec<Vec<u8>>>("[[1], [2, 3], []]");
+    check::<Vec<(u8, char)>>("[(1, 'a'), (2, 'b')]");
+    check::<Vec<String>>(r#"["a", "b\"c", "\u{1f980}"]"#);
+    check::<Vec<String>>(r#"["a", "b"#);
+    check::<Option<Vec<u8>>>("[1, 2]");
+    check::<Record>(r#"Record { name: "x", tags: [], value: None }"#);
+    check::<Vec<u8>>("Record { name: \"x\" }");
+
+    let options = serde_dbgfmt::Options::new().max_depth(1);
+    let error = serde_dbgfmt::from_reader_with::<_, Vec<Vec<u8>>>(&b"[[1]]"[..], &options);
+    assert!(error.unwrap_err().is_depth_limit_exceeded());
+    let options = serde_dbgfmt::Options::new().max_depth(0);
+    let error = serde_dbgfmt::from_reader_with::<_, Vec<u8>>(&b"[]"[..], &options);
+    assert!(error.unwrap_err().is_depth_limit_exceeded());
+
+    /// Only looks at the first three elements of a list.
+    #[derive(Debug)]
+    struct FirstThree;
+
+    impl<'de> Deserialize<'de> for FirstThree {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            struct FirstThreeVisitor;
+
+            impl<'de> Visitor<'de> for FirstThreeVisitor {
+                type Value = FirstThree;
+
+                fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                    f.write_str("a list")
+                }
+
+                fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<FirstThree, A::Error> {
+                    for _ in 0..3 {
+                        seq.next_element::<u32>()?;
+                    }
+                    Ok(FirstThree)
+                }
+            }
+
+            deserializer.deserialize_seq(FirstThreeVisitor)
+        }
+    }
+
+    // The elements are deserialized as they are read, so an endless list
+    // fails once the visitor stops taking elements instead of being read
+    // forever.
+    let elements = "123, ".repeat(1000);
+    let endless = b"[".chain(elements.as_bytes()).chain(io::repeat(b' '));
+    let error = serde_dbgfmt::from_reader::<_, FirstThree>(endless).unwrap_err();
+    assert!(
+        error
+            .to_string()
+            .contains("unexpected token `123`, expected `]`"),
+        "{error}"
+    );
+    assert_eq!(error.location().unwrap().byte_offset, 16);
+}
+
+#[test]
+fn test_error_location() {
+    #[derive(Debug, Deserialize)]
+    #[allow(dead_code)]
+    struct Point {
+        x: i32,
+        y: i32,
+    }
+
+    let text = "Point {\n    x: 1,\n    y: \"ü\",\n}";
+    let error = serde_dbgfmt::from_str::<Point>(text).unwrap_err();
+    let location = error.location().unwrap();
+    assert_eq!((location.line, location.column), (3, 8));
+    assert_eq!(&text[location.byte_offset..][..1], "\"");
+
+    let error = serde_dbgfmt::from_str::<Vec<u8>>("[1, 2] ü").unwrap_err();
+    let location = error.location().unwrap();
+    assert_eq!((location.line, location.column), (1, 8));
+    assert!(error.to_string().ends_with(" at line 1 column 8"));
+
+    let mut de = serde_dbgfmt::OwnedDeserializer::new("1\n2\nnope");
+    assert_eq!(de.deserialize::<u8>().unwrap(), 1);
+    assert_eq!(de.deserialize::<u8>().unwrap(), 2);
+    let location = de.deserialize::<u8>().unwrap_err().location().unwrap();
+    assert_eq!((location.line, location.column), (3, 1));
+
+    let mut values = serde_dbgfmt::Deserializer::new("1 2\n  x").into_iter::<u8>();
+    values.by_ref().take(2).for_each(|value| {
+        value.unwrap();
+    });
+    let location = values.next().unwrap().unwrap_err().location().unwrap();
+    assert_eq!((location.line, location.column), (2, 3));
+}
+
+#[test]
+fn test_skip_deeply_nested() {
+    use serde::de::IgnoredAny;
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Record {
+        id: u32,
+    }
+
+    let depth = 100_000;
+    let nested = format!("{}{}", "[A { b: (".repeat(depth), ") }]".repeat(depth));
+
+    serde_dbgfmt::from_str::<IgnoredAny>(&nested).unwrap();
+
+    let text = format!("Record {{ extra: {nested}, id: 7 }}");
+    let record: Record = serde_dbgfmt::from_str(&text).unwrap();
+    assert_eq!(record, Record { id: 7 });
+
+    assert!(serde_dbgfmt::from_str::<IgnoredAny>("[(1, 2])").is_err());
+    assert!(serde_dbgfmt::from_str::<IgnoredAny>("[1, [2]").is_err());
+    assert!(serde_dbgfmt::from_str::<Record>("Record { extra: , id: 7 }").is_err());
+}
+
+#[test]
+fn test_skip_unknown_values() {
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Record {
+        id: u32,
+    }
+
+    let values = [
+        "..",
+        "1..=5",
+        "..10",
+        "3u8",
+        "-7i64",
+        "1.5ms",
+        "0x7f3a2c001230",
+        "a | b",
+        "Handle(..)",
+        "Foo { inner: [1, ..], .. }",
+        "Wrapper<u8>(Some(2))",
+        "std::sync::Arc { ptr: 0x10 }",
+        "{1, 2}",
+        "-inf",
+    ];
+
+    for value in values {
+        let text = format!("Record {{ extra: {value}, id: 7 }}");
+        let record: Record = serde_dbgfmt::from_str(&text).unwrap();
+        assert_eq!(record, Record { id: 7 }, "{value}");
+    }
+
+    #[derive(Debug, Deserialize)]
+    #[serde(untagged)]
+    #[allow(dead_code)]
+    enum Any {
+        Int(u64),
+        Text(String),
+    }
+
+    assert!(serde_dbgfmt::from_str::<Any>("..").is_err());
+    assert!(serde_dbgfmt::from_str::<Any>("|").is_err());
+}
+
+#[test]
+fn test_duration() {
+    use std::time::Duration;
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Timings {
+        total: Duration,
+        stages: Vec<Duration>,
+        timeout: Option<Duration>,
+    }
+
+    let timings = Timings {
+        total: Duration::new(5, 300_000_000),
+        stages: vec![
+            Duration::ZERO,
+            Duration::from_nanos(15),
+            Duration::from_nanos(3_200),
+            Duration::from_millis(120),
+            Duration::MAX,
+        ],
+        timeout: Some(Duration::from_secs(30)),
+    };
+
+    let text = format!("{timings:?}");
+    assert_eq!(serde_dbgfmt::from_str::<Timings>(&text).unwrap(), timings);
+    let pretty = format!("{timings:#?}");
+    assert_eq!(serde_dbgfmt::from_str::<Timings>(&pretty).unwrap(), timings);
+
+    // The struct form is still accepted.
+    let value: Duration = serde_dbgfmt::from_str("Duration { secs: 1, nanos: 5 }").unwrap();
+    assert_eq!(value, Duration::new(1, 5));
+
+    let value: serde_dbgfmt::Value = serde_dbgfmt::from_str(&text).unwrap();
+    let total = value.field("total").unwrap();
+    assert_eq!(total, &serde_dbgfmt::Value::Other("5.3s".into()));
+
+    assert!(serde_dbgfmt::from_str::<Duration>("99999999999999999999s").is_err());
+    assert!(serde_dbgfmt::from_str::<Duration>("5").is_err());
+}
+
+#[test]
+fn test_phantom_data_fields() {
+    use std::marker::PhantomData;
+
+    #[derive(Debug, Default)]
+    #[allow(dead_code)]
+    struct Original<T> {
+        id: u32,
+        marker: PhantomData<T>,
+        unit: PhantomData<fn() -> T>,
+    }
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Typed {
+        id: u32,
+        marker: PhantomData<String>,
+        unit: (),
+    }
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Ignored {
+        id: u32,
+    }
+
+    let text = format!(
+        "{:?}",
+        Original::<Vec<u8>> {
+            id: 3,
+            ..Default::default()
+        }
+    )
+    .replace("Original", "Typed");
+    let typed: Typed = serde_dbgfmt::from_str(&text).unwrap_or_else(|e| panic!("{e}: {text}"));
+    assert_eq!(typed.id, 3);
+
+    let text = text.replace("Typed", "Ignored");
+    assert_eq!(
+        serde_dbgfmt::from_str::<Ignored>(&text).unwrap(),
+        Ignored { id: 3 }
+    );
+}
+
+#[test]
+fn test_generic_names() {
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Wrapper<T> {
+        value: T,
+    }
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Pair<A, B>(A, B);
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Marker;
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    enum Event<T> {
+        Data(T),
+        Done,
+    }
+
+    let value: Wrapper<u32> = serde_dbgfmt::from_str("Wrapper<u32> { value: 5 }").unwrap();
+    assert_eq!(value, Wrapper { value: 5 });
+
+    let value: Pair<u8, Option<Vec<u8>>> =
+        serde_dbgfmt::from_str("Pair<u8, Option<Vec<u8>>>(1, Some<Vec<u8>>([2]))").unwrap();
+    assert_eq!(value, Pair(1, Some(vec![2])));
+
+    let value: Marker = serde_dbgfmt::from_str("Marker<fn() -> Marker>").unwrap();
+    assert_eq!(value, Marker);
+
+    let value: Vec<Event<u8>> = serde_dbgfmt::from_str("[Data<u8>(1), Done<u8>]").unwrap();
+    assert_eq!(value, [Event::Data(1), Event::Done]);
+
+    assert!(serde_dbgfmt::from_str::<Wrapper<u32>>("Wrapped<u32> { value: 5 }").is_err());
+}
+
+#[test]
+fn test_from_tokens() {
+    use serde_dbgfmt::{Lexer, Options, Token, TokenBuffer, TokenKind};
+
+    let mut lexer = Lexer::new("[1, @]");
+    assert_eq!(lexer.next().unwrap().unwrap().value, "[");
+    assert_eq!(lexer.next().unwrap().unwrap().kind, TokenKind::Integer);
+    assert!(lexer.next().unwrap().unwrap().is_punct(","));
+    assert!(lexer.next().unwrap().is_err());
+    assert!(lexer.next().is_none());
+
+    // Drop every `None` element of a list, along with the comma after it.
+    let mut after_none = false;
+    let filtered = Lexer::new("[Some(1), None, Some(2)]")
+        .map(Result::unwrap)
+        .filter(|token| {
+            let skip = token.value == "None" || (after_none && token.is_punct(","));
+            after_none = token.value == "None";
+            !skip
+        });
+
+    let mut buf = String::new();
+    let buffer = TokenBuffer::from_tokens(filtered, &mut buf);
+    let mut de = serde_dbgfmt::Deserializer::from_tokens(&buffer);
+    let values = Vec::<Option<u32>>::deserialize(&mut de).unwrap();
+    de.end().unwrap();
+    assert_eq!(values, [Some(1), Some(2)]);
+
+    // Tokens keep their kind instead of being split out of their text again.
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Node {
+        name: String,
+    }
+
+    let tokens = Lexer::new(r#"Node { name: "root" }"#).map(|token| {
+        let token = token.unwrap();
+        match token.kind {
+            TokenKind::String => Token::new(TokenKind::String, r#""a" "b""#),
+            _ => token,
+        }
+    });
+    let mut buf = String::new();
+    let buffer = TokenBuffer::from_tokens(tokens, &mut buf);
+    assert!(serde_dbgfmt::from_str::<Node>(buffer.input()).is_err());
+    assert_eq!(
+        buffer.deserialize::<Node>().unwrap(),
+        Node {
+            name: r#"a" "b"#.into()
+        }
+    );
+
+    let options = Options::new().max_depth(1);
+    let tokens = Lexer::new("[[1]]").map(Result::unwrap);
+    let mut buf = String::new();
+    let buffer = TokenBuffer::from_tokens(tokens, &mut buf);
+    let mut de = serde_dbgfmt::Deserializer::from_tokens_with(&buffer, options);
+    assert!(Vec::<Vec<u32>>::deserialize(&mut de).is_err());
+}
+
+#[test]
+fn test_any_set_or_map() {
+    #[derive(Debug, Deserialize, PartialEq)]
+    #[serde(untagged)]
+    enum Braced {
+        Map(BTreeMap<String, Vec<u32>>),
+        Set(BTreeSet<(u32, u32)>),
+    }
+
+    let set: BTreeSet<(u32, u32)> = [(1, 2), (3, 4)].into();
+    let parsed: Braced = serde_dbgfmt::from_dbg(&set).unwrap();
+    assert_eq!(parsed, Braced::Set(set));
+
+    let parsed: Braced = serde_dbgfmt::from_str("{(5, 6)}").unwrap();
+    assert_eq!(parsed, Braced::Set([(5, 6)].into()));
+
+    let map: BTreeMap<String, Vec<u32>> = [("a".into(), vec![1, 2]), ("b".into(), vec![])].into();
+    let parsed: Braced = serde_dbgfmt::from_dbg(&map).unwrap();
+    assert_eq!(parsed, Braced::Map(map));
+
+    // Empty braces are a map.
+    let parsed: Braced = serde_dbgfmt::from_str("{}").unwrap();
+    assert_eq!(parsed, Braced::Map(BTreeMap::new()));
+}
+
+#[test]
+fn test_skip_rejects_malformed() {
+    use serde::de::IgnoredAny;
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct S {
+        a: u32,
+    }
+
+    for valid in [
+        "[1, 2, 3, foo]",
+        "A { b: 1, c: [2, 3], .. }",
+        "A {\n    b: 1,\n}",
+        "{1: 2, 3: (4, 5)}",
+        "{1, 2}",
+        "{}",
+        "Some(1..5)",
+        "Mode(READ | WRITE)",
+        "(..=-1.5, .., 2..)",
+        "b\"bytes\"",
+    ] {
+        serde_dbgfmt::from_str::<IgnoredAny>(valid).unwrap_or_else(|e| panic!("{valid}: {e}"));
+    }
+
+    for invalid in [
+        "[1 2 3 foo]",
+        "(1 2)",
+        "A { b: 1 c: 2 }",
+        "A { b 1 }",
+        "A { 1: 2 }",
+        "A { .., b: 1 }",
+        "{1: 2, 3}",
+        "{1, 2: 3}",
+        "{1: 2: 3}",
+        "[,]",
+        "[1,,]",
+        "1..,",
+    ] {
+        assert!(
+            serde_dbgfmt::from_str::<IgnoredAny>(invalid).is_err(),
+            "{invalid} was accepted"
+        );
+    }
+
+    assert!(serde_dbgfmt::from_str::<S>("S { a: 1, b: 1 2 : 3 }").is_err());
+    assert!(serde_dbgfmt::from_str::<S>("S { a: 1, b: [1 2] }").is_err());
+    assert_eq!(
+        serde_dbgfmt::from_str::<S>("S { a: 1, b: {1: [2, 3]} }").unwrap(),
+        S { a: 1 }
+    );
+}
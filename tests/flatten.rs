@@ -0,0 +1,76 @@
+use pretty_assertions::assert_eq;
+use serde_dbgfmt::{flatten, Scalar};
+
+fn lines(text: &str) -> Vec<String> {
+    flatten(text)
+        .unwrap_or_else(|e| panic!("{e}"))
+        .into_iter()
+        .map(|(path, value)| format!("{path} = {value}"))
+        .collect()
+}
+
+#[test]
+fn flatten_paths() {
+    let text = r#"State {
+        hosts: {"db": ("10.0.0.1", 5432), "not an ident": 'x', 7: -inf},
+        flags: {A, B},
+        pending: Some(Job { id: 340282366920938463463374607431768211455, ratio: 0.5 }),
+        empty: [],
+        unit: Unit,
+        range: 1..5,
+        none: None,
+    }"#;
+
+    assert_eq!(
+        lines(text),
+        [
+            r#"hosts.db[0] = "10.0.0.1""#,
+            "hosts.db[1] = 5432",
+            r#"hosts["not an ident"] = 'x'"#,
+            "hosts[7] = -inf",
+            "flags[0] = A",
+            "flags[1] = B",
+            "pending[0].id = 340282366920938463463374607431768211455",
+            "pending[0].ratio = 0.5",
+            "empty = []",
+            "unit = Unit",
+            "range = 1..5",
+            "none = None",
+        ]
+    );
+}
+
+#[test]
+fn flatten_scalars() {
+    assert_eq!(flatten("5").unwrap(), [(String::new(), Scalar::Int(5))]);
+    assert_eq!(
+        flatten("(-0x10, true, '\\n', \"a\\tb\", Foo {})").unwrap(),
+        [
+            ("[0]".to_owned(), Scalar::Int(-16)),
+            ("[1]".to_owned(), Scalar::Bool(true)),
+            ("[2]".to_owned(), Scalar::Char('\n')),
+            ("[3]".to_owned(), Scalar::Str("a\tb".into())),
+            ("[4]".to_owned(), Scalar::Other("Foo {}".into())),
+        ]
+    );
+    assert_eq!(
+        flatten("[340282366920938463463374607431768211455]").unwrap(),
+        [("[0]".to_owned(), Scalar::UInt(u128::MAX))]
+    );
+}
+
+#[test]
+fn flatten_invalid() {
+    assert!(flatten("[1, 2").is_err());
+    assert!(flatten("['ab']").is_err());
+    assert!(flatten("[999999999999999999999999999999999999999999]").is_err());
+}
+
+#[test]
+fn flatten_deep() {
+    let deep = "[".repeat(200_000);
+    assert!(flatten(&deep).unwrap_err().is_depth_limit_exceeded());
+
+    let nested = format!("{}1{}", "[".repeat(100), "]".repeat(100));
+    assert_eq!(flatten(&nested).unwrap().len(), 1);
+}
@@ -0,0 +1,52 @@
+#![cfg(feature = "half")]
+
+use half::{bf16, f16};
+use pretty_assertions::assert_eq;
+use serde::Deserialize;
+
+#[derive(Debug, Deserialize, PartialEq)]
+struct Weights {
+    a: f16,
+    b: bf16,
+}
+
+#[test]
+fn roundtrip() {
+    let weights = Weights {
+        a: f16::from_f32(0.1),
+        b: bf16::from_f32(-3.5e10),
+    };
+
+    let text = format!("{weights:?}");
+    let parsed: Weights = serde_dbgfmt::from_str(&text).unwrap();
+    assert_eq!(parsed, weights);
+}
+
+#[test]
+fn special_values() {
+    let weights: Weights = serde_dbgfmt::from_str("Weights { a: NaN, b: 1e300 }").unwrap();
+    assert!(weights.a.is_nan());
+    assert_eq!(weights.b, bf16::INFINITY);
+
+    let weights: Weights = serde_dbgfmt::from_str("Weights { a: 0x1.8p1, b: -0.0 }").unwrap();
+    assert_eq!(weights.a, f16::from_f32(3.0));
+    assert!(weights.b.is_sign_negative());
+}
+
+#[test]
+fn double_rounding() {
+    let parse = |text: &str| serde_dbgfmt::from_str::<f16>(text).unwrap().to_f64();
+
+    // 1.00048828125 is exactly halfway between 1.0 and the next f16. The
+    // literals just above and below it both round to it as an f64.
+    assert_eq!(parse("1.00048828125"), 1.0);
+    assert_eq!(parse("1.00048828125000000000001"), 1.0009765625);
+    assert_eq!(parse("1.00048828124999999999999"), 1.0);
+    assert_eq!(parse("1.00146484375"), 1.001953125);
+}
+
+#[test]
+fn named_newtype() {
+    let value: f16 = serde_dbgfmt::from_str("f16(15360)").unwrap();
+    assert_eq!(value, f16::ONE);
+}
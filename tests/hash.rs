@@ -0,0 +1,222 @@
+#![cfg(feature = "std")]
+
+use std::collections::{BTreeMap, HashMap};
+
+use serde_dbgfmt::{structural_hash, StructuralHasher, Value};
+
+#[derive(Debug)]
+#[allow(dead_code)]
+struct Record {
+    id: u32,
+    tags: Vec<&'static str>,
+    range: std::ops::Range<u8>,
+    extra: Option<(char, f32)>,
+}
+
+fn record() -> Record {
+    Record {
+        id: 7,
+        tags: vec!["a", "b"],
+        range: 1..5,
+        extra: Some(('x', 0.5)),
+    }
+}
+
+#[test]
+fn formatting_is_ignored() {
+    let record = record();
+    assert_eq!(
+        structural_hash(&format!("{record:?}")).unwrap(),
+        structural_hash(&format!("{record:#?}")).unwrap()
+    );
+    assert_eq!(
+        structural_hash("Foo { r: 1..5 }").unwrap(),
+        structural_hash("Foo { r: 1 .. 5 }").unwrap()
+    );
+}
+
+#[test]
+fn content_is_significant() {
+    let base = structural_hash("Foo { a: 1, b: [2, 3] }").unwrap();
+    for other in [
+        "Foo { a: 1, b: [2, 4] }",
+        "Foo { a: 1, c: [2, 3] }",
+        "Bar { a: 1, b: [2, 3] }",
+        "Foo { a: 1, b: (2, 3) }",
+        "Foo { a: 1, b: [2, 3], .. }",
+        "Foo { a: 1, b: [[2], 3] }",
+        r#"Foo { a: "1", b: [2, 3] }"#,
+    ] {
+        assert_ne!(base, structural_hash(other).unwrap(), "{other}");
+    }
+}
+
+#[test]
+fn map_order() {
+    let map: HashMap<_, _> = (0..20).map(|i| (format!("key{i}"), i)).collect();
+    let sorted: BTreeMap<_, _> = map.iter().collect();
+    let (a, b) = (format!("{map:?}"), format!("{sorted:?}"));
+
+    let hasher = StructuralHasher::new().ignore_map_order(true);
+    assert_eq!(hasher.hash(&a).unwrap(), hasher.hash(&b).unwrap());
+    assert_ne!(structural_hash(&a).unwrap(), structural_hash(&b).unwrap());
+
+    assert_eq!(
+        hasher.hash("{1, 2, 3}").unwrap(),
+        hasher.hash("{3, 1, 2}").unwrap()
+    );
+    assert_ne!(
+        hasher.hash(r#"{"a": 1, "b": 2}"#).unwrap(),
+        hasher.hash(r#"{"a": 2, "b": 1}"#).unwrap()
+    );
+    assert_ne!(
+        hasher.hash("[1, 2, 3]").unwrap(),
+        hasher.hash("[3, 1, 2]").unwrap()
+    );
+}
+
+#[test]
+fn invalid_input() {
+    assert!(structural_hash("Foo { a: }").is_err());
+}
+
+#[test]
+fn ignored_paths() {
+    let hasher = StructuralHasher::new()
+        .ignore_path("id")
+        .ignore_path("items[*].at")
+        .ignore_path("meta.*");
+
+    let a = r#"Log { id: 1, items: [Item { at: 5, v: 1 }], meta: {"host": "a", "pid": 1} }"#;
+    let b = r#"Log { id: 2, items: [Item { at: 6, v: 1 }], meta: {"host": "b", "pid": 2} }"#;
+    let c = r#"Log { id: 3, items: [Item { at: 7, v: 2 }], meta: {"host": "c", "pid": 3} }"#;
+    assert_eq!(hasher.hash(a).unwrap(), hasher.hash(b).unwrap());
+    assert_ne!(hasher.hash(a).unwrap(), hasher.hash(c).unwrap());
+
+    // Only the value is ignored, not the field itself.
+    assert_ne!(
+        hasher.hash("Log { id: 1 }").unwrap(),
+        hasher.hash("Log { key: 1 }").unwrap()
+    );
+    assert_ne!(
+        structural_hash("Log { id: 1 }").unwrap(),
+        structural_hash("Log { id: 2 }").unwrap()
+    );
+}
+
+#[test]
+fn group() {
+    let logs = [
+        "Timeout { after: 30, request: 1 }",
+        "Refused { port: 80 }",
+        "Timeout {\n    after: 30,\n    request: 2,\n}",
+        "Refused { port: 443 }",
+        "Timeout { after: 30, request: 3 }",
+        "Refused { port: 80 }",
+    ];
+
+    let groups = StructuralHasher::new()
+        .ignore_path("request")
+        .group(logs)
+        .unwrap();
+    let counts: Vec<_> = groups
+        .iter()
+        .map(|g| (g.example.as_str(), g.count))
+        .collect();
+    assert_eq!(
+        counts,
+        [
+            ("Timeout { after: 30, request: 1 }", 3),
+            ("Refused { port: 80 }", 2),
+            ("Refused { port: 443 }", 1),
+        ]
+    );
+
+    let owned: Vec<String> = logs.iter().map(|s| s.to_string()).collect();
+    assert_eq!(StructuralHasher::new().group(&owned).unwrap().len(), 5);
+    assert!(StructuralHasher::new().group(["Foo {"]).is_err());
+}
+
+#[test]
+fn normalize_pointers() {
+    let a = "Conn { handle: 0x7f8e4c003a20, fd: 3 }";
+    let b = "Conn { handle: 0x7f8e4c009990, fd: 3 }";
+    assert_ne!(structural_hash(a).unwrap(), structural_hash(b).unwrap());
+
+    let hasher = StructuralHasher::new().normalize_pointers(true);
+    assert_eq!(hasher.hash(a).unwrap(), hasher.hash(b).unwrap());
+    assert_ne!(
+        hasher.hash("Conn { handle: 0x10, fd: 3 }").unwrap(),
+        hasher.hash("Conn { handle: 0x20, fd: 3 }").unwrap()
+    );
+}
+
+#[test]
+fn hash_value() {
+    let text = format!("{:#?}", record());
+    let value: Value = text.parse().unwrap();
+    assert_eq!(
+        value.structural_hash().unwrap(),
+        structural_hash(&text).unwrap()
+    );
+
+    let hasher = StructuralHasher::new().ignore_map_order(true);
+    let a: Value = r#"{"a": 1, "b": 0x2}"#.parse().unwrap();
+    let b: Value = r#"{"b": 2, "a": 1}"#.parse().unwrap();
+    assert_ne!(a.structural_hash().unwrap(), b.structural_hash().unwrap());
+    assert_eq!(
+        hasher.hash_value(&a).unwrap(),
+        hasher.hash_value(&b).unwrap()
+    );
+
+    assert!(Value::Ident("not an ident".into())
+        .structural_hash()
+        .is_err());
+
+    // Values are hashed directly, but hash the same as their display output.
+    let hashers = [
+        StructuralHasher::new(),
+        StructuralHasher::new().ignore_map_order(true),
+        StructuralHasher::new().ignore_path("*b*"),
+        StructuralHasher::new().normalize_pointers(true),
+    ];
+    let texts = [
+        r#"A { a: 1, b: -2.5, c: 'x', d: "s\"t", e: Some(None), f: () }"#,
+        r#"B(true, NaN, -inf, 1e-7, 1..5, 1.5s, Unit, Empty { .. }, X { a: 1, .. })"#,
+        r#"{"b": {1, 2}, "a b": [{}, (1,)], 3: {"c": [0x10]}}"#,
+        "[Some(B { b: [1, 2] }), None, {}]",
+    ];
+    for hasher in &hashers {
+        for text in texts {
+            let value: Value = text.parse().unwrap();
+            assert_eq!(
+                hasher.hash_value(&value).unwrap(),
+                hasher.hash(&value.to_string()).unwrap(),
+                "{text}"
+            );
+        }
+    }
+}
+
+#[test]
+fn deep_input() {
+    let deep = "[".repeat(200_000);
+    assert!(structural_hash(&deep)
+        .unwrap_err()
+        .is_depth_limit_exceeded());
+    let error = StructuralHasher::new().hash(&deep).unwrap_err();
+    assert!(error.is_depth_limit_exceeded());
+
+    let nested = format!("{}{}", "[".repeat(100), "]".repeat(100));
+    assert!(structural_hash(&nested).is_ok());
+
+    // Values are hashed without going through text, so they can be nested
+    // more deeply.
+    let deep = format!("{}{}", "[".repeat(200_000), "]".repeat(200_000));
+    let value: Value = deep.parse().unwrap();
+    let other: Value = deep.replacen("[]", "[1]", 1).parse().unwrap();
+    assert_ne!(
+        value.structural_hash().unwrap(),
+        other.structural_hash().unwrap()
+    );
+}
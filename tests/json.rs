@@ -0,0 +1,178 @@
+#![cfg(feature = "json")]
+
+use pretty_assertions::assert_eq;
+use serde::Deserialize;
+use serde_dbgfmt::json::{parse_json_value, parse_json_value_with};
+use serde_json::{json, Value};
+
+#[track_caller]
+fn roundtrip(value: Value) {
+    let text = format!("{value:?}");
+    let parsed = parse_json_value(&text).unwrap_or_else(|e| panic!("{e}: {text}"));
+    assert_eq!(parsed, value, "{text}");
+
+    let text = format!("{value:#?}");
+    let parsed = parse_json_value(&text).unwrap_or_else(|e| panic!("{e}: {text}"));
+    assert_eq!(parsed, value, "{text}");
+}
+
+#[test]
+fn scalars() {
+    roundtrip(json!(null));
+    roundtrip(json!(true));
+    roundtrip(json!(false));
+    roundtrip(json!(0));
+    roundtrip(json!(-17));
+    roundtrip(json!(u64::MAX));
+    roundtrip(json!(i64::MIN));
+    roundtrip(json!(1.5));
+    roundtrip(json!(-2.0e-300));
+    roundtrip(json!(1e100));
+    roundtrip(json!("tab\t \"quoted\" \u{1f980}"));
+}
+
+#[test]
+fn nested() {
+    roundtrip(json!([]));
+    roundtrip(json!({}));
+    roundtrip(json!({
+        "id": 7,
+        "tags": ["a", null, [true]],
+        "nested": { "key with: punct": { "x": -0.25 } },
+    }));
+}
+
+#[test]
+fn field() {
+    #[derive(Debug, Deserialize)]
+    struct Request {
+        #[serde(with = "serde_dbgfmt::with::json_value")]
+        body: Value,
+        id: u32,
+    }
+
+    #[derive(Debug)]
+    #[allow(dead_code)]
+    struct Source {
+        body: Value,
+        id: u32,
+    }
+
+    let text = format!(
+        "{:?}",
+        Source {
+            body: json!({ "items": [1, 2, 3] }),
+            id: 4,
+        }
+    )
+    .replace("Source", "Request");
+    let request: Request = serde_dbgfmt::from_str(&text).unwrap();
+
+    assert_eq!(request.body, json!({ "items": [1, 2, 3] }));
+    assert_eq!(request.id, 4);
+}
+
+#[test]
+fn invalid() {
+    assert!(parse_json_value("Number(1").is_err());
+    assert!(parse_json_value("Bool(yes)").is_err());
+    assert!(parse_json_value("Array [Null Null]").is_err());
+    assert!(parse_json_value("Object {a: Null}").is_err());
+    assert!(parse_json_value("Integer(1)").is_err());
+
+    // Deeply nested input is rejected instead of overflowing the stack.
+    let text = format!("{}{}", "Array [".repeat(100_000), "]".repeat(100_000));
+    let error = parse_json_value(&text).unwrap_err();
+    assert!(error.is_depth_limit_exceeded());
+    assert_eq!(
+        error.to_string(),
+        "input exceeded the maximum nesting depth of 128"
+    );
+
+    let options = serde_dbgfmt::Options::new().max_depth(200_000);
+    let text = format!("{}{}", "Array [".repeat(200), "]".repeat(200));
+    assert!(parse_json_value_with(&text, &options).is_ok());
+    assert!(parse_json_value("Null Null").is_err());
+}
+
+#[test]
+fn infer_schema_merges_samples() {
+    let schema = serde_dbgfmt::schema::infer_schema([
+        "Job { id: 1, tags: {\"a\"}, args: [1, 2], env: {\"K\": \"V\"}, pos: (1, 'x'), state: Running, owner: None }",
+        "Job { id: 2, tags: {}, args: [1.5], env: {}, pos: (2, 'y'), state: Done(0), owner: Some(User { name: \"b\" }) }",
+    ])
+    .unwrap();
+
+    assert_eq!(
+        schema,
+        json!({
+            "$schema": "https://json-schema.org/draft/2020-12/schema",
+            "type": "object",
+            "title": "Job",
+            "properties": {
+                "args": { "type": "array", "items": { "type": "number" } },
+                "env": { "type": "object", "additionalProperties": { "type": "string" } },
+                "id": { "type": "integer" },
+                "owner": {
+                    "anyOf": [
+                        { "type": "null" },
+                        {
+                            "type": "object",
+                            "title": "User",
+                            "properties": { "name": { "type": "string" } },
+                            "required": ["name"],
+                        },
+                    ],
+                },
+                "pos": {
+                    "type": "array",
+                    "prefixItems": [{ "type": "integer" }, { "type": "string" }],
+                    "items": false,
+                },
+                "state": {
+                    "anyOf": [
+                        { "type": "string", "enum": ["Running"] },
+                        {
+                            "type": "array",
+                            "title": "Done",
+                            "prefixItems": [{ "type": "integer" }],
+                            "items": false,
+                        },
+                    ],
+                },
+                "tags": {
+                    "type": "array",
+                    "items": { "type": "string" },
+                    "uniqueItems": true,
+                },
+            },
+            "required": ["args", "env", "id", "owner", "pos", "state", "tags"],
+        })
+    );
+}
+
+#[test]
+fn infer_schema_edge_cases() {
+    assert_eq!(
+        serde_dbgfmt::schema::infer_schema([]).unwrap(),
+        json!({ "$schema": "https://json-schema.org/draft/2020-12/schema" })
+    );
+    assert_eq!(
+        serde_dbgfmt::schema::infer_schema(["1", "\"a\"", "2", "None"]).unwrap(),
+        json!({
+            "$schema": "https://json-schema.org/draft/2020-12/schema",
+            "anyOf": [{ "type": "integer" }, { "type": "string" }, { "type": "null" }],
+        })
+    );
+    assert!(serde_dbgfmt::schema::infer_schema(["[1, 2"]).is_err());
+}
+
+#[test]
+fn infer_schema_deep() {
+    let deep = "[".repeat(200_000);
+    let error = serde_dbgfmt::schema::infer_schema([deep.as_str()]).unwrap_err();
+    assert!(error.is_depth_limit_exceeded());
+
+    let nested = format!("{}{}", "[".repeat(100), "]".repeat(100));
+    assert!(serde_dbgfmt::schema::infer_schema([nested.as_str()]).is_ok());
+}
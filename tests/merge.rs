@@ -0,0 +1,229 @@
+use std::time::Duration;
+
+use pretty_assertions::assert_eq;
+use serde::de::DeserializeSeed;
+use serde::{Deserialize, Serialize};
+use serde_dbgfmt::{Deserializer, MergeSeed, Options, UnknownToken};
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+enum Mode {
+    Fast,
+    Safe { retries: u32 },
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+struct Limits {
+    min: i32,
+    max: i32,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+struct Config {
+    name: String,
+    limits: Limits,
+    tags: Vec<String>,
+    mode: Mode,
+}
+
+fn config() -> Config {
+    Config {
+        name: "default".into(),
+        limits: Limits { min: -5, max: 5 },
+        tags: vec!["a".into(), "b".into()],
+        mode: Mode::Fast,
+    }
+}
+
+fn merge(target: &mut Config, text: &str) -> Result<(), serde_dbgfmt::Error> {
+    let mut de = Deserializer::new(text);
+    MergeSeed::new(target).deserialize(&mut de)?;
+    de.end()
+}
+
+#[test]
+fn merge_partial() {
+    let mut target = config();
+    merge(
+        &mut target,
+        r#"Config { limits: Limits { max: 10, .. }, tags: ["c"], .. }"#,
+    )
+    .unwrap_or_else(|e| panic!("{e}"));
+
+    assert_eq!(
+        target,
+        Config {
+            limits: Limits { min: -5, max: 10 },
+            tags: vec!["c".into()],
+            ..config()
+        }
+    );
+}
+
+#[test]
+fn merge_replaces_non_struct_values() {
+    let mut target = config();
+    merge(&mut target, "Config { mode: Safe { retries: 3 }, .. }")
+        .unwrap_or_else(|e| panic!("{e}"));
+
+    assert_eq!(
+        target,
+        Config {
+            mode: Mode::Safe { retries: 3 },
+            ..config()
+        }
+    );
+}
+
+#[test]
+fn merge_full() {
+    let mut target = config();
+    let text =
+        r#"Config { name: "other", limits: Limits { min: 0, max: 1 }, tags: [], mode: Fast }"#;
+    merge(&mut target, text).unwrap_or_else(|e| panic!("{e}"));
+
+    assert_eq!(
+        target,
+        Config {
+            name: "other".into(),
+            limits: Limits { min: 0, max: 1 },
+            tags: vec![],
+            mode: Mode::Fast,
+        }
+    );
+}
+
+#[test]
+fn merge_errors() {
+    let mut target = config();
+
+    assert!(merge(&mut target, "Config { unknown: 5, .. }").is_err());
+    assert!(merge(
+        &mut target,
+        "Config { limits: Limits { max: \"x\", .. }, .. }"
+    )
+    .is_err());
+    assert!(merge(&mut target, "Other { name: \"x\" }").is_err());
+    assert_eq!(target, config());
+}
+
+#[test]
+fn merge_nested_in_option_and_newtype() {
+    #[derive(Debug, Deserialize, Serialize, PartialEq)]
+    struct Wrapper(Limits);
+
+    #[derive(Debug, Deserialize, Serialize, PartialEq)]
+    struct Outer {
+        limits: Option<Limits>,
+        wrapped: Wrapper,
+    }
+
+    let mut target = Outer {
+        limits: Some(Limits { min: 1, max: 2 }),
+        wrapped: Wrapper(Limits { min: 3, max: 4 }),
+    };
+
+    let text =
+        "Outer { limits: Some(Limits { max: 20, .. }), wrapped: Wrapper(Limits { min: 30, .. }) }";
+    let mut de = Deserializer::new(text);
+    MergeSeed::new(&mut target).deserialize(&mut de).unwrap();
+    de.end().unwrap();
+
+    assert_eq!(
+        target,
+        Outer {
+            limits: Some(Limits { min: 1, max: 20 }),
+            wrapped: Wrapper(Limits { min: 30, max: 4 }),
+        }
+    );
+}
+
+#[test]
+fn merge_uses_deserializer_options() {
+    let mut target = config();
+    let options = Options::new().on_unknown_token(|text| match text.find('>') {
+        Some(end) if text.starts_with('<') => UnknownToken::String(end + 1),
+        _ => UnknownToken::Error,
+    });
+    let mut de = Deserializer::with_options("Config { name: <unnamed>, .. }", options);
+    MergeSeed::new(&mut target).deserialize(&mut de).unwrap();
+    assert_eq!(
+        target,
+        Config {
+            name: "<unnamed>".into(),
+            ..config()
+        }
+    );
+
+    let mut target = config();
+    let options = Options::new().max_depth(1);
+    let mut de =
+        Deserializer::with_options("Config { limits: Limits { max: 7, .. }, .. }", options);
+    assert!(MergeSeed::new(&mut target).deserialize(&mut de).is_err());
+    assert_eq!(target, config());
+}
+
+#[test]
+fn merge_ignores_debug_impl() {
+    // The current value is captured using its `Serialize` impl, so a custom
+    // `Debug` impl does not get in the way.
+    #[derive(Deserialize, Serialize, PartialEq)]
+    struct Secret {
+        user: String,
+        token: String,
+    }
+
+    impl std::fmt::Debug for Secret {
+        fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+            f.write_str("<redacted>")
+        }
+    }
+
+    let mut target = Secret {
+        user: "root".into(),
+        token: "abc".into(),
+    };
+
+    let mut de = Deserializer::new(r#"Secret { token: "xyz", .. }"#);
+    MergeSeed::new(&mut target).deserialize(&mut de).unwrap();
+
+    assert_eq!(target.user, "root");
+    assert_eq!(target.token, "xyz");
+}
+
+#[test]
+fn merge_duration_and_empty_array() {
+    // These are written differently from their serde representation, so the
+    // current value has to be read back from their debug output.
+    #[derive(Debug, Deserialize, Serialize, PartialEq)]
+    struct Job {
+        name: String,
+        timeout: Duration,
+        retries: Vec<Duration>,
+        reserved: [u8; 0],
+    }
+
+    let job = || Job {
+        name: "build".into(),
+        timeout: Duration::from_millis(1500),
+        retries: vec![Duration::from_micros(12), Duration::from_secs(3)],
+        reserved: [],
+    };
+
+    let mut target = job();
+    let mut de = Deserializer::new(r#"Job { name: "test", .. }"#);
+    MergeSeed::new(&mut target).deserialize(&mut de).unwrap();
+    de.end().unwrap();
+    assert_eq!(
+        target,
+        Job {
+            name: "test".into(),
+            ..job()
+        }
+    );
+
+    let mut de = Deserializer::new("Job { timeout: 2s, .. }");
+    MergeSeed::new(&mut target).deserialize(&mut de).unwrap();
+    de.end().unwrap();
+    assert_eq!(target.timeout, Duration::from_secs(2));
+    assert_eq!(target.retries, job().retries);
+}
@@ -0,0 +1,1422 @@
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+use pretty_assertions::assert_eq;
+use serde::de::IgnoredAny;
+use serde::Deserialize;
+use serde_dbgfmt::{
+    Addresses, DuplicateKeys, FormatVersion, Options, OwnedDeserializer, UnknownToken, Value,
+};
+
+#[test]
+fn alias_variant() {
+    #[derive(Debug, Deserialize, PartialEq)]
+    enum Event {
+        Connected { id: u32 },
+        Disconnected(u32),
+    }
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Log {
+        events: Vec<Event>,
+    }
+
+    let options = Options::new()
+        .alias_variant("Connect", "Connected")
+        .alias_variant("Disconnect", "Disconnected");
+
+    let text = "Log { events: [Connect { id: 5 }, Disconnected(5), Disconnect(6)] }";
+    let log: Log = serde_dbgfmt::from_str_with(text, &options).unwrap_or_else(|e| panic!("{e}"));
+
+    assert_eq!(
+        log.events,
+        [
+            Event::Connected { id: 5 },
+            Event::Disconnected(5),
+            Event::Disconnected(6)
+        ]
+    );
+}
+
+#[test]
+fn alias_variant_not_applied_by_default() {
+    #[derive(Debug, Deserialize, PartialEq)]
+    enum Event {
+        Connected,
+    }
+
+    assert!(serde_dbgfmt::from_str::<Event>("Connect").is_err());
+}
+
+#[test]
+fn coerce_float_to_integer() {
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Counts {
+        a: u32,
+        b: i64,
+        c: u128,
+        d: u8,
+    }
+
+    let options = Options::new().coerce_numbers(true);
+    let text = "Counts { a: 1e6, b: -2.5e1, c: 1.5e30, d: 100.0 }";
+    let counts: Counts =
+        serde_dbgfmt::from_str_with(text, &options).unwrap_or_else(|e| panic!("{e}"));
+
+    assert_eq!(
+        counts,
+        Counts {
+            a: 1_000_000,
+            b: -25,
+            c: 1_500_000_000_000_000_000_000_000_000_000,
+            d: 100,
+        }
+    );
+}
+
+#[test]
+fn coerce_float_to_integer_errors() {
+    let options = Options::new().coerce_numbers(true);
+
+    assert!(serde_dbgfmt::from_str_with::<u32>("1.5", &options).is_err());
+    assert!(serde_dbgfmt::from_str_with::<u8>("1e3", &options).is_err());
+    assert!(serde_dbgfmt::from_str_with::<u64>("1e400", &options).is_err());
+    assert!(serde_dbgfmt::from_str::<u32>("1e6").is_err());
+}
+
+#[test]
+fn coerce_integer_to_float() {
+    let options = Options::new().coerce_numbers(true);
+
+    assert_eq!(
+        serde_dbgfmt::from_str_with::<f64>("-5", &options).unwrap(),
+        -5.0
+    );
+    assert_eq!(
+        serde_dbgfmt::from_str_with::<f32>("0x10", &options).unwrap(),
+        16.0
+    );
+    assert!(serde_dbgfmt::from_str::<f64>("5").is_err());
+}
+
+#[test]
+fn digit_grouping() {
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Stats {
+        count: u64,
+        delta: i32,
+        total: f64,
+        list: Vec<u32>,
+    }
+
+    let options = Options::new().digit_grouping(true);
+    let text =
+        "Stats { count: 1,000,000, delta: -12,345, total: 1,234.5, list: [1,000, 20, 3, 400] }";
+    let stats: Stats =
+        serde_dbgfmt::from_str_with(text, &options).unwrap_or_else(|e| panic!("{e}"));
+
+    assert_eq!(
+        stats,
+        Stats {
+            count: 1_000_000,
+            delta: -12_345,
+            total: 1_234.5,
+            list: vec![1000, 20, 3, 400],
+        }
+    );
+}
+
+#[test]
+fn digit_grouping_requires_exact_groups() {
+    let options = Options::new().digit_grouping(true);
+
+    assert_eq!(
+        serde_dbgfmt::from_str_with::<Vec<u32>>("[1,00, 1, 0123,456]", &options).unwrap(),
+        [1, 0, 1, 123, 456]
+    );
+    assert_eq!(
+        serde_dbgfmt::from_str_with::<(u32, u32)>("(1, 000)", &options).unwrap(),
+        (1, 0)
+    );
+    assert!(serde_dbgfmt::from_str::<u32>("1,000").is_err());
+}
+
+#[test]
+fn stringify_nested() {
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Event {
+        kind: String,
+        payload: String,
+        tags: Vec<String>,
+        note: Option<String>,
+    }
+
+    let options = Options::new().stringify_nested(true);
+    let text = r#"Event { kind: "a\tb", payload: User { id: 5, roles: [Admin] }, tags: [Some(1), "x", 2.5], note: Some(Thing(1, 2)) }"#;
+
+    assert_eq!(
+        serde_dbgfmt::from_str_with::<Event>(text, &options).unwrap(),
+        Event {
+            kind: "a\tb".into(),
+            payload: "User { id: 5, roles: [Admin] }".into(),
+            tags: vec!["Some(1)".into(), "x".into(), "2.5".into()],
+            note: Some("Thing(1, 2)".into()),
+        }
+    );
+    assert!(serde_dbgfmt::from_str::<Event>(text).is_err());
+    assert!(serde_dbgfmt::from_str_with::<String>("", &options).is_err());
+}
+
+fn collect_warnings(options: Options) -> (Options, Arc<Mutex<Vec<String>>>) {
+    let warnings = Arc::new(Mutex::new(Vec::new()));
+    let options = options.on_warning({
+        let warnings = warnings.clone();
+        move |warning| warnings.lock().unwrap().push(warning.to_string())
+    });
+
+    (options, warnings)
+}
+
+#[test]
+fn warn_unknown_field() {
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Inner {
+        a: u32,
+    }
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Outer {
+        inner: Inner,
+        b: Vec<u32>,
+    }
+
+    let (options, warnings) = collect_warnings(Options::new());
+    let text = "Outer { inner: Inner { a: 1, c: [1, 2] }, b: [], d: Inner { a: 2 }, .. }";
+    let value: Outer =
+        serde_dbgfmt::from_str_with(text, &options).unwrap_or_else(|e| panic!("{e}"));
+
+    assert_eq!(
+        value,
+        Outer {
+            inner: Inner { a: 1 },
+            b: vec![]
+        }
+    );
+    assert_eq!(
+        *warnings.lock().unwrap(),
+        [
+            "ignored unknown field `c`",
+            "ignored unknown field `d`",
+            "struct has fields omitted with `..`",
+        ]
+    );
+}
+
+#[test]
+fn warn_float_saturation() {
+    let (options, warnings) = collect_warnings(Options::new());
+
+    assert_eq!(
+        serde_dbgfmt::from_str_with::<f32>("1e39", &options).unwrap(),
+        f32::INFINITY
+    );
+    assert_eq!(
+        serde_dbgfmt::from_str_with::<f64>("-1e-400", &options).unwrap(),
+        -0.0
+    );
+    assert_eq!(
+        serde_dbgfmt::from_str_with::<f64>("0.0e-400", &options).unwrap(),
+        0.0
+    );
+
+    assert_eq!(
+        *warnings.lock().unwrap(),
+        [
+            "float literal `1e39` overflowed to infinity",
+            "float literal `-1e-400` underflowed to zero",
+        ]
+    );
+}
+
+#[test]
+fn warn_number_coerced() {
+    let (options, warnings) = collect_warnings(Options::new().coerce_numbers(true));
+
+    assert_eq!(
+        serde_dbgfmt::from_str_with::<u32>("1e3", &options).unwrap(),
+        1000
+    );
+    assert_eq!(
+        serde_dbgfmt::from_str_with::<f64>("-5", &options).unwrap(),
+        -5.0
+    );
+    assert_eq!(
+        serde_dbgfmt::from_str_with::<u32>("5", &options).unwrap(),
+        5
+    );
+
+    assert_eq!(
+        *warnings.lock().unwrap(),
+        [
+            "number `1e3` was coerced to a different numeric type",
+            "number `-5` was coerced to a different numeric type",
+        ]
+    );
+}
+
+#[test]
+fn collect_stats() {
+    #[derive(Debug, Deserialize)]
+    #[allow(dead_code)]
+    struct Record {
+        name: String,
+        tags: Vec<String>,
+        nested: Option<Box<Record>>,
+    }
+
+    let text = r#"Record { name: "a\tb", tags: [], nested: Some(Record { name: "c", tags: ["d", "e"], nested: None }) }"#;
+    let mut de = serde_dbgfmt::Deserializer::with_options(text, Options::new().collect_stats(true));
+    Record::deserialize(&mut de).unwrap_or_else(|e| panic!("{e}"));
+    de.end().unwrap();
+
+    let stats = de.stats().unwrap();
+    assert_eq!(stats.max_depth, 3);
+    assert_eq!(stats.collection_sizes, [0, 2, 3, 3]);
+    assert_eq!(stats.unescaped_bytes, 3);
+    assert_eq!(stats.tokens, 35);
+
+    let de = serde_dbgfmt::Deserializer::new(text);
+    assert!(de.stats().is_none());
+}
+
+#[test]
+fn from_dbg_with_options() {
+    #[derive(Debug)]
+    #[allow(dead_code)]
+    enum Source {
+        Connect { id: u32 },
+    }
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    enum Target {
+        Connected { id: u64 },
+    }
+
+    let options = Options::new().alias_variant("Connect", "Connected");
+    let value: Target = serde_dbgfmt::from_dbg_with(&Source::Connect { id: 3 }, &options).unwrap();
+
+    assert_eq!(value, Target::Connected { id: 3 });
+    assert!(serde_dbgfmt::from_dbg::<Target, _>(&Source::Connect { id: 3 }).is_err());
+}
+
+#[test]
+fn on_progress() {
+    let reports = Arc::new(Mutex::new(Vec::new()));
+    let options = Options::new().on_progress({
+        let reports = reports.clone();
+        move |consumed, total| reports.lock().unwrap().push((consumed, total))
+    });
+
+    let values: Vec<u32> = (0..100_000).collect();
+    let text = format!("{values:?}");
+    let parsed: Vec<u32> = serde_dbgfmt::from_str_with(&text, &options).unwrap();
+    assert_eq!(parsed, values);
+
+    let reports = reports.lock().unwrap();
+    assert!(reports.len() > 2);
+    assert!(reports.windows(2).all(|w| w[0].0 < w[1].0));
+    assert!(reports.iter().all(|&(_, total)| total == text.len()));
+    assert_eq!(reports.last(), Some(&(text.len(), text.len())));
+
+    drop(reports);
+
+    // Progress is also reported for values that are not split into elements
+    // by the deserializer.
+    let seen = Arc::new(AtomicUsize::new(0));
+    let options = Options::new().on_progress({
+        let seen = seen.clone();
+        move |_, _| {
+            seen.fetch_add(1, Ordering::Relaxed);
+        }
+    });
+
+    let _: IgnoredAny = serde_dbgfmt::from_str_with(&text, &options).unwrap();
+    assert!(seen.swap(0, Ordering::Relaxed) > 2);
+
+    let _: Value = serde_dbgfmt::from_str_with(&text, &options).unwrap();
+    assert!(seen.swap(0, Ordering::Relaxed) > 2);
+}
+
+#[test]
+fn cancellation_token() {
+    #[derive(Debug, Deserialize)]
+    #[allow(dead_code)]
+    struct Item {
+        id: u32,
+    }
+
+    let token = Arc::new(AtomicBool::new(false));
+    let options = Options::new().cancellation_token(token.clone());
+
+    let items: Vec<Item> = serde_dbgfmt::from_str_with("[Item { id: 1 }]", &options).unwrap();
+    assert_eq!(items.len(), 1);
+
+    let seen = Arc::new(AtomicUsize::new(0));
+    let options = options.on_progress({
+        let token = token.clone();
+        let seen = seen.clone();
+        move |_, _| {
+            seen.fetch_add(1, Ordering::Relaxed);
+            token.store(true, Ordering::Relaxed);
+        }
+    });
+
+    let text = format!("{:?}", (0..100_000).collect::<Vec<u32>>());
+    let error = serde_dbgfmt::from_str_with::<Vec<u32>>(&text, &options).unwrap_err();
+    assert!(error.is_cancelled());
+    assert_eq!(error.to_string(), "deserialization was cancelled");
+    assert_eq!(seen.load(Ordering::Relaxed), 1);
+
+    // Values that are skipped or parsed without going through the elements
+    // of a collection can be cancelled as well.
+    token.store(false, Ordering::Relaxed);
+    seen.store(0, Ordering::Relaxed);
+    let error = serde_dbgfmt::from_str_with::<IgnoredAny>(&text, &options).unwrap_err();
+    assert!(error.is_cancelled());
+    assert_eq!(seen.load(Ordering::Relaxed), 1);
+
+    token.store(false, Ordering::Relaxed);
+    let text = format!("Wrapper {{ skipped: {text}, value: 5 }}");
+    let error = serde_dbgfmt::get_with::<u32>(&text, "value", &options).unwrap_err();
+    assert!(error.is_cancelled());
+
+    token.store(true, Ordering::Relaxed);
+    let text = format!("{:?}", "x".repeat(1_000_000));
+    let error = serde_dbgfmt::from_str_with::<String>(&text, &options).unwrap_err();
+    assert!(error.is_cancelled());
+}
+
+#[test]
+#[cfg(feature = "std")]
+fn time_budget() {
+    use std::time::Duration;
+
+    let options = Options::new().time_budget(Duration::ZERO);
+
+    let error = serde_dbgfmt::from_str_with::<u32>("5", &options).unwrap_err();
+    assert!(error.is_timed_out());
+
+    let error = serde_dbgfmt::from_str_with::<Vec<u32>>("[1, 2]", &options).unwrap_err();
+    assert!(error.is_timed_out());
+    assert!(!error.is_cancelled());
+
+    let error = serde_dbgfmt::from_str_with::<IgnoredAny>("[1, 2]", &options).unwrap_err();
+    assert!(error.is_timed_out());
+    let error = serde_dbgfmt::get_with::<u32>("[1, 2]", "[1]", &options).unwrap_err();
+    assert!(error.is_timed_out());
+
+    // The budget covers the whole input, so it runs out part way through a
+    // large value.
+    let text = format!("{:?}", (0..1_000_000).collect::<Vec<u32>>());
+    let options = Options::new().time_budget(Duration::from_micros(100));
+    let error = serde_dbgfmt::from_str_with::<IgnoredAny>(&text, &options).unwrap_err();
+    assert!(error.is_timed_out());
+
+    let options = Options::new().time_budget(Duration::MAX);
+    let values: Vec<u32> = serde_dbgfmt::from_str_with("[1, 2]", &options).unwrap();
+    assert_eq!(values, [1, 2]);
+}
+
+#[test]
+fn coerce_bools() {
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Config {
+        verbose: bool,
+        color: bool,
+    }
+
+    let text = "Config { verbose: yes, color: OFF }";
+    assert!(serde_dbgfmt::from_str::<Config>(text).is_err());
+
+    let (options, warnings) = collect_warnings(Options::new().coerce_bools(true));
+    let config: Config = serde_dbgfmt::from_str_with(text, &options).unwrap();
+    assert_eq!(
+        config,
+        Config {
+            verbose: true,
+            color: false
+        }
+    );
+    assert!(serde_dbgfmt::from_str_with::<bool>("maybe", &options).is_err());
+
+    assert_eq!(
+        *warnings.lock().unwrap(),
+        [
+            "`yes` was coerced to a boolean",
+            "`OFF` was coerced to a boolean"
+        ]
+    );
+}
+
+#[test]
+fn replace_lone_surrogates() {
+    let text = r#"("a\u{d800}b\u{DFFF}", '\u{dc00}')"#;
+    assert!(serde_dbgfmt::from_str::<(String, char)>(text).is_err());
+
+    let (options, warnings) = collect_warnings(Options::new().replace_lone_surrogates(true));
+    let value: (String, char) = serde_dbgfmt::from_str_with(text, &options).unwrap();
+    assert_eq!(value, ("a\u{fffd}b\u{fffd}".to_owned(), '\u{fffd}'));
+
+    assert!(serde_dbgfmt::from_str_with::<String>(r#""\u{110000}""#, &options).is_err());
+    assert_eq!(
+        serde_dbgfmt::from_str_with::<String>(r#""\u{1f980}""#, &options).unwrap(),
+        "\u{1f980}"
+    );
+
+    assert_eq!(
+        *warnings.lock().unwrap(),
+        [
+            "lone surrogate `\\u{d800}` was replaced with U+FFFD",
+            "lone surrogate `\\u{DFFF}` was replaced with U+FFFD",
+            "lone surrogate `\\u{dc00}` was replaced with U+FFFD",
+        ]
+    );
+}
+
+#[test]
+fn duplicate_keys() {
+    /// Keeps every entry of a map, in order.
+    #[derive(Debug, PartialEq)]
+    struct Entries(Vec<(String, u32)>);
+
+    impl<'de> Deserialize<'de> for Entries {
+        fn deserialize<D: serde::Deserializer<'de>>(de: D) -> Result<Self, D::Error> {
+            struct Visitor;
+
+            impl<'de> serde::de::Visitor<'de> for Visitor {
+                type Value = Entries;
+
+                fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                    f.write_str("a map")
+                }
+
+                fn visit_map<A: serde::de::MapAccess<'de>>(
+                    self,
+                    mut map: A,
+                ) -> Result<Entries, A::Error> {
+                    let mut entries = Vec::new();
+                    while let Some(entry) = map.next_entry()? {
+                        entries.push(entry);
+                    }
+                    Ok(Entries(entries))
+                }
+            }
+
+            de.deserialize_map(Visitor)
+        }
+    }
+
+    let entries =
+        |items: &[(&str, u32)]| Entries(items.iter().map(|&(k, v)| (k.to_owned(), v)).collect());
+
+    let text = r#"{"a": 1, "b": 2, "a": 3, "c": 4, "a": 5}"#;
+
+    let parse = |mode| {
+        let (options, warnings) = collect_warnings(Options::new().duplicate_keys(mode));
+        let result = serde_dbgfmt::from_str_with::<Entries>(text, &options);
+        let warnings = warnings.lock().unwrap().clone();
+        (result, warnings)
+    };
+
+    let (result, warnings) = parse(DuplicateKeys::Allow);
+    assert_eq!(
+        result.unwrap(),
+        entries(&[("a", 1), ("b", 2), ("a", 3), ("c", 4), ("a", 5)])
+    );
+    assert!(warnings.is_empty());
+
+    let (result, warnings) = parse(DuplicateKeys::KeepFirst);
+    assert_eq!(result.unwrap(), entries(&[("a", 1), ("b", 2), ("c", 4)]));
+    assert_eq!(warnings, [r#"skipped entry with duplicate key `"a"`"#; 2]);
+
+    let (result, warnings) = parse(DuplicateKeys::KeepLast);
+    assert_eq!(result.unwrap(), entries(&[("b", 2), ("c", 4), ("a", 5)]));
+    assert_eq!(warnings, [r#"skipped entry with duplicate key `"a"`"#; 2]);
+
+    let (result, _) = parse(DuplicateKeys::Error);
+    assert_eq!(
+        result.unwrap_err().to_string(),
+        r#"duplicate map key `"a"` at offset 17, first seen at offset 1 at line 1 column 18"#
+    );
+}
+
+#[test]
+fn on_unknown_token() {
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Frame {
+        addr: String,
+        names: Vec<String>,
+        line: u32,
+    }
+
+    let text = "Frame { addr: @0x1f, names: [<main>, «inner»], line: 12 # comment\n }";
+    assert!(serde_dbgfmt::from_str::<Frame>(text).is_err());
+
+    let options = Options::new().on_unknown_token(|text| {
+        let word = text.find([',', ']', ' ']).unwrap_or(text.len());
+        match text.chars().next().unwrap() {
+            '#' => UnknownToken::Skip(text.find('\n').unwrap_or(text.len())),
+            '@' | '<' | '«' => UnknownToken::String(word),
+            _ => UnknownToken::Error,
+        }
+    });
+    let frame: Frame = serde_dbgfmt::from_str_with(text, &options).unwrap();
+    assert_eq!(
+        frame,
+        Frame {
+            addr: "@0x1f".into(),
+            names: vec!["<main>".into(), "«inner»".into()],
+            line: 12,
+        }
+    );
+
+    let error = serde_dbgfmt::from_str_with::<Vec<String>>("[$x]", &options).unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "unexpected token `$`, expected a valid token at line 1 column 2"
+    );
+
+    let options = Options::new().on_unknown_token(|_| UnknownToken::Skip(1));
+    let text = format!("[1, {}2]", "@".repeat(2_000_000));
+    let values: Vec<u32> = serde_dbgfmt::from_str_with(&text, &options).unwrap();
+    assert_eq!(values, [1, 2]);
+
+    let error = serde_dbgfmt::from_str_with::<String>("¤", &options).unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "unexpected token `¤`, expected a valid token at line 1 column 1"
+    );
+}
+
+#[test]
+fn on_unknown_token_text_helpers() {
+    use serde_dbgfmt::{Redactor, Scalar, Summary};
+
+    let options = Options::new().on_unknown_token(|text| match text.chars().next().unwrap() {
+        '<' => UnknownToken::String(text.find('>').unwrap() + 1),
+        _ => UnknownToken::Skip(1),
+    });
+    let text = "Frame { func: <main>, line: @12, args: {\"b\": <opaque>, \"a\": 1} }";
+
+    assert!(serde_dbgfmt::canonicalize(text).is_err());
+    assert_eq!(
+        serde_dbgfmt::canonicalize_with(text, &options).unwrap(),
+        r#"Frame { func: <main>, line: 12, args: {"a": 1, "b": <opaque>} }"#
+    );
+
+    let leaves = serde_dbgfmt::flatten_with(text, &options).unwrap();
+    assert_eq!(leaves[0], ("func".into(), Scalar::Str("<main>".into())));
+    assert_eq!(leaves[1], ("line".into(), Scalar::Int(12)));
+
+    let summary: Summary = serde_dbgfmt::summarize_with(text, &options).unwrap();
+    assert_eq!(summary.kinds["string"], 4);
+    assert_eq!(summary.string_bytes, "<main>b<opaque>a".len());
+
+    let redacted = Redactor::new()
+        .field("func")
+        .options(options.clone())
+        .redact(text)
+        .unwrap();
+    assert!(
+        redacted.starts_with(r#"Frame { func: "<redacted>", line: @12"#),
+        "{redacted}"
+    );
+
+    let tree = serde_dbgfmt::render::ascii_tree_with(text, &options).unwrap();
+    assert!(tree.contains("func: <main>"), "{tree}");
+
+    #[cfg(feature = "std")]
+    {
+        let hasher = serde_dbgfmt::StructuralHasher::new().options(options.clone());
+        assert_eq!(
+            hasher.hash(text).unwrap(),
+            hasher.hash(&text.replace("@12", "12")).unwrap()
+        );
+    }
+
+    let value: Value = serde_dbgfmt::from_str_with(text, &options).unwrap();
+    assert_eq!(value.field("func"), Some(&Value::Str("<main>".into())));
+}
+
+#[cfg(feature = "unicode_normalization")]
+#[test]
+fn normalize_identifiers() {
+    #[derive(Debug, Deserialize, PartialEq)]
+    enum Drink {
+        Café,
+    }
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Crème {
+        drink: Drink,
+    }
+
+    let text = "Cre\u{300}me { drink: Cafe\u{301} }";
+    assert!(serde_dbgfmt::from_str::<Crème>(text).is_err());
+
+    let options = Options::new().normalize_identifiers(true);
+    let value: Crème = serde_dbgfmt::from_str_with(text, &options).unwrap();
+    assert_eq!(
+        value,
+        Crème {
+            drink: Drink::Café
+        }
+    );
+}
+
+#[cfg(feature = "unicode_normalization")]
+#[test]
+fn normalize_repeated_fields() {
+    #[derive(Clone, Debug, Deserialize, PartialEq)]
+    struct Entry {
+        café: u32,
+        #[serde(default)]
+        crème: u32,
+    }
+
+    // The same spellings are seen many times, which goes through the cache
+    // of matched field names.
+    let entry = "Entry { cafe\u{301}: 1, cre\u{300}me: 2, the\u{301}: 3 }";
+    let text = format!("[{}]", [entry; 3].join(", "));
+
+    let options = Options::new().normalize_identifiers(true);
+    let (options, warnings) = collect_warnings(options);
+    let entries: Vec<Entry> = serde_dbgfmt::from_str_with(&text, &options).unwrap();
+    assert_eq!(
+        entries,
+        vec![
+            Entry {
+                café: 1, crème: 2
+            };
+            3
+        ]
+    );
+    assert_eq!(warnings.lock().unwrap().len(), 3);
+}
+
+#[test]
+fn exact_floats() {
+    let options = Options::new().exact_floats(true);
+    let parse_f32 = |text: &str| serde_dbgfmt::from_str_with::<f32>(text, &options);
+    let parse_f64 = |text: &str| serde_dbgfmt::from_str_with::<f64>(text, &options);
+
+    assert_eq!(parse_f32("1.5e3").unwrap(), 1500.0);
+    assert_eq!(parse_f32("-0.000").unwrap(), 0.0);
+    assert_eq!(
+        parse_f32(&format!("{:?}", 1.0f32 / 3.0)).unwrap(),
+        1.0 / 3.0
+    );
+    assert_eq!(
+        parse_f64(&format!("{:?}", 1.0f64 / 3.0)).unwrap(),
+        1.0 / 3.0
+    );
+    assert_eq!(parse_f64("0x1.8p3").unwrap(), 12.0);
+
+    assert!(parse_f32(&format!("{:?}", 1.0f64 / 3.0)).is_err());
+    assert!(parse_f64("0.10000000000000000000001").is_err());
+    assert!(parse_f64("1e400").is_err());
+
+    let error = parse_f32("16777217.0").unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "float literal `16777217.0` cannot be represented exactly as f32 at line 1 column 1"
+    );
+
+    let options = options.coerce_numbers(true);
+    assert!(serde_dbgfmt::from_str_with::<f32>("16777216", &options).is_ok());
+    assert!(serde_dbgfmt::from_str_with::<f32>("16777217", &options).is_err());
+}
+
+#[test]
+fn error_span_limit() {
+    let text = format!("[1 \"{}\"]", "é".repeat(500));
+
+    let error = serde_dbgfmt::from_str::<Vec<u32>>(&text).unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        format!(
+            "unexpected token `\"{}…`, expected `,` at line 1 column 4",
+            "é".repeat(99)
+        )
+    );
+
+    let options = Options::new().error_span_limit(None);
+    let error = serde_dbgfmt::from_str_with::<Vec<u32>>(&text, &options).unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        format!(
+            "unexpected token `\"{}\"`, expected `,` at line 1 column 4",
+            "é".repeat(500)
+        )
+    );
+
+    let options = Options::new().error_span_limit(Some(0));
+    let error = serde_dbgfmt::from_dbg_with::<Vec<u32>, _>(&("a", 5), &options).unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "unexpected token `…`, expected `[` or `{` at line 1 column 1"
+    );
+    assert_eq!(error.span(), Some("("));
+}
+
+#[test]
+fn max_depth() {
+    use serde::de::IgnoredAny;
+
+    let options = Options::new().max_depth(3);
+    let parse = |text: &str| serde_dbgfmt::from_str_with::<IgnoredAny>(text, &options);
+
+    assert!(parse("[(1, A { b: {2: 3} })]").is_err());
+    assert!(parse("[(1, A { b: 2 }), (3, B { c: () })]").is_err());
+    assert!(parse("[(1, A { b: 2 }), (3, B(4))]").is_ok());
+
+    // Options are not collections, although `Some(..)` counts as one when the
+    // type is not known.
+    let text = "Some(Some(Some(Some(1))))";
+    assert!(
+        serde_dbgfmt::from_str_with::<Option<Option<Option<Option<u8>>>>>(text, &options).is_ok()
+    );
+    assert!(parse(text).is_err());
+
+    let deep = format!("{}{}", "[".repeat(100_000), "]".repeat(100_000));
+    let error = parse(&deep).unwrap_err();
+    assert!(error.is_depth_limit_exceeded());
+    assert_eq!(
+        error.to_string(),
+        "input exceeded the maximum nesting depth of 3 at line 1 column 5"
+    );
+
+    // The depth goes back down once a collection is closed.
+    let wide = format!("[{}]", ["[[1]]"; 1000].join(", "));
+    assert!(parse(&wide).is_ok());
+}
+
+#[test]
+fn max_depth_text_helpers() {
+    let options = Options::new().max_depth(64);
+    let deep = "[".repeat(200_000);
+
+    let errors = [
+        serde_dbgfmt::canonicalize_with(&deep, &options).unwrap_err(),
+        serde_dbgfmt::summarize_with(&deep, &options).unwrap_err(),
+        serde_dbgfmt::flatten_with(&deep, &options).unwrap_err(),
+        serde_dbgfmt::render::ascii_tree_with(&deep, &options).unwrap_err(),
+        serde_dbgfmt::render::dot_with(&deep, &options).unwrap_err(),
+        serde_dbgfmt::Redactor::new()
+            .options(options.clone())
+            .redact(&deep)
+            .unwrap_err(),
+    ];
+    for error in errors {
+        assert!(error.is_depth_limit_exceeded());
+        assert!(error
+            .to_string()
+            .contains("exceeded the maximum nesting depth of 64"));
+    }
+
+    #[cfg(feature = "std")]
+    {
+        let hasher = serde_dbgfmt::StructuralHasher::new().options(options.clone());
+        let error = hasher.hash(&deep).unwrap_err();
+        assert!(error.is_depth_limit_exceeded(), "{error}");
+    }
+
+    // The limit replaces the default one, rather than only lowering it.
+    let nested = format!("{}{}", "[".repeat(200), "]".repeat(200));
+    let options = Options::new().max_depth(200);
+    assert!(serde_dbgfmt::canonicalize(&nested).is_err());
+    assert_eq!(
+        serde_dbgfmt::canonicalize_with(&nested, &options).unwrap(),
+        nested
+    );
+}
+
+#[test]
+fn allow_trailing_data() {
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Point {
+        x: i32,
+        y: i32,
+    }
+
+    let text = "Point { x: 1, y: 2 } at 12:00:01 }{";
+    assert!(serde_dbgfmt::from_str::<Point>(text).is_err());
+
+    let options = Options::new().allow_trailing_data(true);
+    let point: Point = serde_dbgfmt::from_str_with(text, &options).unwrap();
+    assert_eq!(point, Point { x: 1, y: 2 });
+
+    let mut de = OwnedDeserializer::with_options(text, options);
+    assert_eq!(de.deserialize::<Point>().unwrap(), Point { x: 1, y: 2 });
+    de.end().unwrap();
+}
+
+#[test]
+fn ignore_struct_names() {
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Marker;
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Meters(f64);
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Pair(u8, u8);
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    enum Kind {
+        Road,
+        Trail,
+    }
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Route {
+        marker: Marker,
+        length: Meters,
+        pair: Pair,
+        kind: Kind,
+    }
+
+    let text = "Path { marker: Start, length: Length(1.5), pair: Coords(1, 2), kind: Trail }";
+    assert!(serde_dbgfmt::from_str::<Route>(text).is_err());
+
+    let options = Options::new().ignore_struct_names(true);
+    let route: Route = serde_dbgfmt::from_str_with(text, &options).unwrap();
+    assert_eq!(
+        route,
+        Route {
+            marker: Marker,
+            length: Meters(1.5),
+            pair: Pair(1, 2),
+            kind: Kind::Trail,
+        }
+    );
+
+    // Variant names are still checked.
+    let text = "Path { marker: Start, length: Length(1.5), pair: Coords(1, 2), kind: Highway }";
+    assert!(serde_dbgfmt::from_str_with::<Route>(text, &options).is_err());
+}
+
+#[test]
+fn ignore_type_paths() {
+    #[derive(Debug, Deserialize, PartialEq)]
+    enum Kind {
+        Admin,
+        Guest(u32),
+    }
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct User {
+        id: u32,
+        kind: Kind,
+        manager: Option<Box<User>>,
+    }
+
+    let text = "app::model::User { \
+        id: 1, \
+        kind: app::model::Kind::Admin, \
+        manager: core::option::Option::Some(app::model::User<u8> { \
+            id: 2, \
+            kind: Kind::Guest(3), \
+            manager: None, \
+        }) \
+    }";
+    assert!(serde_dbgfmt::from_str::<User>(text).is_err());
+
+    let options = Options::new().ignore_type_paths(true);
+    let user: User = serde_dbgfmt::from_str_with(text, &options).unwrap();
+    assert_eq!(
+        user,
+        User {
+            id: 1,
+            kind: Kind::Admin,
+            manager: Some(Box::new(User {
+                id: 2,
+                kind: Kind::Guest(3),
+                manager: None,
+            })),
+        }
+    );
+
+    // Paths are single tokens even when they are not matched.
+    let value: serde_dbgfmt::Value = serde_dbgfmt::from_str(text).unwrap();
+    assert_eq!(value.name(), Some("app::model::User"));
+}
+
+#[test]
+fn format_version() {
+    use std::time::Duration;
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Timing {
+        elapsed: Duration,
+        raw: Value,
+    }
+
+    let old = "Timing { elapsed: Duration { secs: 1, nanos: 500000000 }, \
+               raw: [Duration { secs: 0, nanos: 7 }, Duration { secs: 2, nanos: 0, .. }] }";
+    let new = "Timing { elapsed: 1.5s, raw: [7ns, Duration { secs: 2, nanos: 0, .. }] }";
+
+    let options = Options::new().format_version(FormatVersion::Rust1_26);
+    let old: Timing = serde_dbgfmt::from_str_with(old, &options).unwrap();
+    let new: Timing = serde_dbgfmt::from_str(new).unwrap();
+    assert_eq!(old, new);
+    assert_eq!(old.elapsed, Duration::from_millis(1500));
+    assert_eq!(
+        old.raw.to_string(),
+        "[7ns, Duration { secs: 2, nanos: 0, .. }]"
+    );
+
+    // Only structs that the derived impl could have printed are durations.
+    let other = [
+        "Duration { secs: 1 }",
+        "Duration { nanos: 1, secs: 1 }",
+        "Duration { secs: 1, nanos: 1000000000 }",
+        "Duration { secs: -1, nanos: 0 }",
+        "Elapsed { secs: 1, nanos: 0 }",
+    ];
+    for text in other {
+        let value: Value = serde_dbgfmt::from_str_with(text, &options).unwrap();
+        assert_eq!(value, text.parse::<Value>().unwrap());
+    }
+
+    // Without the option these are left as structs.
+    let text = "Duration { secs: 1, nanos: 0 }";
+    let value: Value = serde_dbgfmt::from_str(text).unwrap();
+    assert_eq!(value.to_string(), text);
+}
+
+#[test]
+fn addresses() {
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Node {
+        parent: Option<usize>,
+        vtable: (),
+        data: usize,
+        tag: u8,
+    }
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    #[serde(untagged)]
+    enum Any {
+        Unit(()),
+        Int(u64),
+    }
+
+    let text = "Node { parent: 0x7f3a2c001230, vtable: 0x0000600003e1c000, data: 0x7f3a2c001250, tag: 0xff }";
+
+    let value: Value = serde_dbgfmt::from_str(text).unwrap();
+    assert_eq!(
+        value.field("parent").and_then(Value::as_i128),
+        Some(0x7f3a2c001230)
+    );
+    assert!(serde_dbgfmt::from_str::<Node>(text).is_err());
+
+    let options = Options::new().addresses(Addresses::Skip);
+    let any: Vec<Any> = serde_dbgfmt::from_str_with("[0x7f3a2c001230, 5]", &options).unwrap();
+    assert_eq!(any, [Any::Unit(()), Any::Int(5)]);
+    let node: Node = serde_dbgfmt::from_str_with(text, &options).unwrap();
+    assert_eq!(
+        node,
+        Node {
+            parent: None,
+            vtable: (),
+            data: 0x7f3a2c001250,
+            tag: 0xff,
+        }
+    );
+
+    let options = Options::new().addresses(Addresses::Error);
+    let error = serde_dbgfmt::from_str_with::<Node>(text, &options).unwrap_err();
+    assert!(error.is_address_not_allowed());
+    assert_eq!(error.location().unwrap().column, 16);
+
+    // Small hex integers are not addresses.
+    let tag: u8 = serde_dbgfmt::from_str_with("0xff", &options).unwrap();
+    assert_eq!(tag, 0xff);
+}
+
+#[test]
+fn unwrap_std_wrappers() {
+    use std::cell::RefCell;
+    use std::collections::BTreeMap;
+
+    mod original {
+        use std::cell::{Cell, RefCell};
+        use std::collections::BTreeMap;
+        use std::rc::Rc;
+        use std::sync::{Arc, Mutex, RwLock};
+
+        #[allow(dead_code)]
+        #[derive(Debug)]
+        pub struct State {
+            pub count: Cell<u32>,
+            pub shared: Rc<RefCell<String>>,
+            pub nested: Arc<Mutex<RefCell<Vec<u8>>>>,
+            pub maybe: Option<RwLock<i64>>,
+            pub map: BTreeMap<u8, Mutex<bool>>,
+        }
+    }
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct State {
+        count: u32,
+        shared: String,
+        nested: Vec<u8>,
+        maybe: Option<i64>,
+        map: BTreeMap<u8, bool>,
+    }
+
+    let state = original::State {
+        count: 1.into(),
+        shared: RefCell::new("text".to_owned()).into(),
+        nested: Arc::new(RefCell::new(vec![2, 3]).into()),
+        maybe: Some((-4).into()),
+        map: [(5, true.into())].into(),
+    };
+    let expected = State {
+        count: 1,
+        shared: "text".into(),
+        nested: vec![2, 3],
+        maybe: Some(-4),
+        map: [(5, true)].into(),
+    };
+
+    assert!(serde_dbgfmt::from_dbg::<State, _>(&state).is_err());
+
+    let options = Options::new().unwrap_std_wrappers(true);
+    let parsed: State = serde_dbgfmt::from_dbg_with(&state, &options).unwrap();
+    assert_eq!(parsed, expected);
+
+    // Pretty-printed output has trailing commas after the extra fields.
+    let text = format!("{state:#?}");
+    let parsed: State = serde_dbgfmt::from_str_with(&text, &options).unwrap();
+    assert_eq!(parsed, expected);
+
+    // A borrowed value prints a placeholder that can't be deserialized.
+    let _borrow = state.shared.borrow_mut();
+    assert!(serde_dbgfmt::from_dbg_with::<State, _>(&state, &options).is_err());
+}
+
+#[test]
+fn unwrap_std_wrappers_top_level_and_named_structs() {
+    let options = Options::new().unwrap_std_wrappers(true);
+
+    let value: u32 = serde_dbgfmt::from_str_with("RefCell { value: 5 }", &options).unwrap();
+    assert_eq!(value, 5);
+    let value: Vec<u8> =
+        serde_dbgfmt::from_str_with("Mutex { data: [1, 2], poisoned: false, .. }", &options)
+            .unwrap();
+    assert_eq!(value, [1, 2]);
+
+    // Structs that share a name with a wrapper are not unwrapped.
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Cell {
+        value: u32,
+    }
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Outer {
+        c: Cell,
+        plain: u32,
+    }
+
+    let text = "Outer { c: Cell { value: 1 }, plain: Cell { value: 2 } }";
+    let outer: Outer = serde_dbgfmt::from_str_with(text, &options).unwrap();
+    assert_eq!(
+        outer,
+        Outer {
+            c: Cell { value: 1 },
+            plain: 2
+        }
+    );
+
+    let cell: Cell = serde_dbgfmt::from_str_with("Cell { value: 3 }", &options).unwrap();
+    assert_eq!(cell, Cell { value: 3 });
+    let cell: Cell =
+        serde_dbgfmt::from_str_with("RefCell { value: Cell { value: 4 } }", &options).unwrap();
+    assert_eq!(cell, Cell { value: 4 });
+}
+
+#[test]
+fn unwrap_some() {
+    #[derive(Debug, Deserialize, PartialEq)]
+    enum Maybe {
+        Some(u32),
+        Nothing,
+    }
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Record {
+        id: u32,
+        tags: Vec<String>,
+        nested: Option<u8>,
+        maybe: Maybe,
+        #[serde(default)]
+        count: u64,
+        any: Value,
+    }
+
+    let text = r#"Record {
+        id: Some(1),
+        tags: Some([Some("a"), "b"]),
+        nested: Some(Some(2)),
+        maybe: Some(3),
+        count: None,
+        any: Some(4),
+    }"#;
+    assert!(serde_dbgfmt::from_str::<Record>(text).is_err());
+
+    // A `None` field is only treated as missing when asked to.
+    let options = Options::new().unwrap_some(true);
+    assert!(serde_dbgfmt::from_str_with::<Record>(text, &options).is_err());
+
+    let options = options.none_as_missing(true);
+    let record: Record = serde_dbgfmt::from_str_with(text, &options).unwrap();
+    assert_eq!(
+        record,
+        Record {
+            id: 1,
+            tags: vec!["a".into(), "b".into()],
+            nested: Some(2),
+            maybe: Maybe::Some(3),
+            count: 0,
+            any: serde_dbgfmt::from_str("Some(4)").unwrap(),
+        }
+    );
+
+    // Without a default a `None` field is missing.
+    let error = serde_dbgfmt::from_str_with::<Record>(
+        r#"Record { id: None, tags: [], nested: None, maybe: Nothing, any: () }"#,
+        &options,
+    )
+    .unwrap_err();
+    assert!(error.to_string().contains("missing field `id`"), "{error}");
+}
+
+#[test]
+fn unwrap_some_keeps_none_variants() {
+    #[derive(Debug, Default, Deserialize, PartialEq)]
+    enum Mode {
+        None,
+        #[default]
+        Fast,
+    }
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Job {
+        id: u32,
+        #[serde(default)]
+        mode: Mode,
+    }
+
+    let text = "Job { id: Some(1), mode: None }";
+    let options = Options::new().unwrap_some(true);
+    let job: Job = serde_dbgfmt::from_str_with(text, &options).unwrap();
+    assert_eq!(
+        job,
+        Job {
+            id: 1,
+            mode: Mode::None
+        }
+    );
+
+    // With `none_as_missing` the variant can't be told apart from a missing
+    // value.
+    let job: Job = serde_dbgfmt::from_str_with(text, &options.none_as_missing(true)).unwrap();
+    assert_eq!(
+        job,
+        Job {
+            id: 1,
+            mode: Mode::Fast
+        }
+    );
+}
+
+#[test]
+fn strict_field_order() {
+    #[derive(Debug, Deserialize, PartialEq)]
+    enum Shape {
+        Rect { w: u32, h: u32 },
+    }
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Scene {
+        name: String,
+        #[serde(default)]
+        shapes: Vec<Shape>,
+        scale: f32,
+    }
+
+    let options = Options::new().strict_field_order(true);
+
+    // Missing and unknown fields are fine as long as the rest are in order.
+    let text = r#"Scene { name: "a", extra: 1, scale: 1.0 }"#;
+    assert!(serde_dbgfmt::from_str_with::<Scene>(text, &options).is_ok());
+
+    let text = r#"Scene { scale: 1.0, name: "a" }"#;
+    assert!(serde_dbgfmt::from_str::<Scene>(text).is_ok());
+    let error = serde_dbgfmt::from_str_with::<Scene>(text, &options).unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "field `name` is out of order, it should come before `scale` at line 1 column 21"
+    );
+
+    let text = r#"Scene { name: "a", shapes: [Rect { h: 1, w: 2 }], scale: 1.0 }"#;
+    let error = serde_dbgfmt::from_str_with::<Scene>(text, &options).unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "field `w` is out of order, it should come before `h` at line 1 column 42"
+    );
+}
+
+#[test]
+fn collect_unknown_fields() {
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Inner {
+        a: u32,
+    }
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Outer {
+        inner: Inner,
+        name: String,
+    }
+
+    let text = r#"Outer {
+        inner: Inner { a: 1, b: Extra { c: [1, 2] }, range: 1..=5 },
+        name: "x",
+        tags: {"k": Some(1.5)},
+    }"#;
+
+    let mut de = serde_dbgfmt::Deserializer::new(text);
+    Outer::deserialize(&mut de).unwrap();
+    assert!(de.unknown_fields().is_empty());
+
+    let options = Options::new().collect_unknown_fields(true);
+    let mut de = serde_dbgfmt::Deserializer::with_options(text, options);
+    let outer = Outer::deserialize(&mut de).unwrap();
+    de.end().unwrap();
+    assert_eq!(outer.inner, Inner { a: 1 });
+
+    let unknown = de.take_unknown_fields();
+    assert!(de.unknown_fields().is_empty());
+
+    let unknown: Vec<_> = unknown
+        .iter()
+        .map(|(name, value)| (name.as_str(), value.to_string()))
+        .collect();
+    assert_eq!(
+        unknown,
+        [
+            ("inner.b", "Extra { c: [1, 2] }".to_owned()),
+            ("inner.range", "1..=5".to_owned()),
+            ("tags", r#"{"k": Some(1.5)}"#.to_owned()),
+        ]
+    );
+}
+
+#[test]
+fn collect_unknown_fields_by_path() {
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Item {
+        id: u32,
+    }
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    enum Event {
+        Moved { items: Vec<Item> },
+    }
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Log {
+        items: Vec<Item>,
+        pairs: (Item, Item),
+        by_name: BTreeMap<String, Item>,
+        by_id: BTreeMap<u32, Item>,
+        events: Vec<Event>,
+    }
+
+    let text = r#"Log {
+        items: [Item { id: 1, b: 1 }, Item { id: 2, b: 2 }],
+        pairs: (Item { id: 3 }, Item { id: 4, b: 4 }),
+        by_name: {"x": Item { id: 5, b: 5 }, "a b": Item { id: 6, b: 6 }},
+        by_id: {7: Item { id: 7, b: 7 }},
+        events: [Moved { items: [Item { id: 8, b: 8 }], b: 9 }],
+    }"#;
+
+    let options = Options::new().collect_unknown_fields(true);
+    let mut de = serde_dbgfmt::Deserializer::with_options(text, options);
+    Log::deserialize(&mut de).unwrap();
+    de.end().unwrap();
+
+    let unknown: Vec<_> = de
+        .unknown_fields()
+        .iter()
+        .map(|(name, value)| (name.as_str(), value.as_i128().unwrap()))
+        .collect();
+    assert_eq!(
+        unknown,
+        [
+            (r#"by_id[7].b"#, 7),
+            (r#"by_name.x.b"#, 5),
+            (r#"by_name["a b"].b"#, 6),
+            ("events[0].b", 9),
+            ("events[0].items[0].b", 8),
+            ("items[0].b", 1),
+            ("items[1].b", 2),
+            ("pairs[1].b", 4),
+        ]
+    );
+}
+
+#[test]
+fn require_borrowed() {
+    use std::borrow::Cow;
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Line<'a> {
+        #[serde(borrow)]
+        text: Cow<'a, str>,
+        tags: BTreeMap<&'a str, char>,
+    }
+
+    let text = r#"Line { text: "plain", tags: {"a": '\n'} }"#;
+    let options = Options::new().require_borrowed(true).collect_stats(true);
+    let mut de = serde_dbgfmt::Deserializer::with_options(text, options.clone());
+    let line = Line::deserialize(&mut de).unwrap();
+    de.end().unwrap();
+    assert!(matches!(line.text, Cow::Borrowed("plain")));
+
+    let stats = de.stats().unwrap();
+    assert_eq!((stats.borrowed_strings, stats.owned_strings), (2, 0));
+
+    let text = r#"Line { text: "two\nlines", tags: {} }"#;
+    let error = serde_dbgfmt::from_str_with::<Line>(text, &options).unwrap_err();
+    assert!(error.is_not_borrowed());
+    assert_eq!(error.kind(), serde_dbgfmt::ErrorKind::NotBorrowed);
+    assert_eq!(error.span(), Some(r#""two\nlines""#));
+    assert_eq!(
+        error.to_string(),
+        r#"string "two\nlines" contains escape sequences and cannot be borrowed at line 1 column 14"#
+    );
+
+    let mut de = serde_dbgfmt::Deserializer::with_options(text, Options::new().collect_stats(true));
+    let line = Line::deserialize(&mut de).unwrap();
+    assert_eq!(line.text, "two\nlines");
+    assert_eq!(de.stats().unwrap().owned_strings, 1);
+
+    let mut de =
+        serde_dbgfmt::Deserializer::with_options(text, options).with_scratch(String::new());
+    assert!(Line::deserialize(&mut de).unwrap_err().is_not_borrowed());
+}
@@ -0,0 +1,146 @@
+use pretty_assertions::assert_eq;
+use serde::Deserialize;
+
+const RESPONSE: &str = r#"Response { status: 200, headers: {"content-type": "text/plain", "x-id": Some(7)}, items: [Item { id: 1, pos: Point(3, 4) }, Item { id: 2, pos: Point(5, 6) }, Item { id: 3, pos: Point(7, 8) }], extra: ("a", [[1, 2], [3]]) }"#;
+
+#[test]
+fn get_field() {
+    assert_eq!(serde_dbgfmt::get::<u32>(RESPONSE, "status").unwrap(), 200);
+    assert_eq!(
+        serde_dbgfmt::get::<&str>(RESPONSE, "headers.content-type").unwrap(),
+        "text/plain"
+    );
+    assert_eq!(
+        serde_dbgfmt::get::<Option<u32>>(RESPONSE, "headers.x-id").unwrap(),
+        Some(7)
+    );
+}
+
+#[test]
+fn get_index() {
+    assert_eq!(
+        serde_dbgfmt::get::<u32>(RESPONSE, "items[2].id").unwrap(),
+        3
+    );
+    assert_eq!(
+        serde_dbgfmt::get::<u32>(RESPONSE, "items[1].pos.1").unwrap(),
+        6
+    );
+    assert_eq!(
+        serde_dbgfmt::get::<u32>(RESPONSE, "items[0].pos[0]").unwrap(),
+        3
+    );
+    assert_eq!(
+        serde_dbgfmt::get::<u32>(RESPONSE, "extra.1[0][1]").unwrap(),
+        2
+    );
+    assert_eq!(serde_dbgfmt::get::<u32>("[[5]]", "[0][0]").unwrap(), 5);
+}
+
+#[test]
+fn get_whole() {
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Point(u32, u32);
+
+    assert_eq!(
+        serde_dbgfmt::get::<Point>(RESPONSE, "items[1].pos").unwrap(),
+        Point(5, 6)
+    );
+    assert_eq!(serde_dbgfmt::get::<Vec<u32>>("[1, 2]", "").unwrap(), [1, 2]);
+}
+
+#[test]
+fn get_missing() {
+    assert!(serde_dbgfmt::get::<u32>(RESPONSE, "missing").is_err());
+    assert!(serde_dbgfmt::get::<u32>(RESPONSE, "items[3].id").is_err());
+    assert!(serde_dbgfmt::get::<u32>(RESPONSE, "status.x").is_err());
+    assert!(serde_dbgfmt::get::<u32>(RESPONSE, "status[0]").is_err());
+    assert!(serde_dbgfmt::get::<u32>("[]", "[0]").is_err());
+}
+
+#[test]
+fn get_with_options() {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    use serde_dbgfmt::{Options, UnknownToken};
+
+    let text = RESPONSE.replace("status: 200", "status: 200, handle: <opaque>");
+    let options = Options::new().on_unknown_token(|text| match text.find('>') {
+        Some(end) if text.starts_with('<') => UnknownToken::String(end + 1),
+        _ => UnknownToken::Error,
+    });
+
+    assert!(serde_dbgfmt::get::<u32>(&text, "items[2].id").is_err());
+    assert_eq!(
+        serde_dbgfmt::get_with::<u32>(&text, "items[2].id", &options).unwrap(),
+        3
+    );
+
+    // Progress is measured against the whole input, even while searching
+    // through it.
+    let items = vec![RESPONSE; 2_000].join(", ");
+    let text = format!("Log {{ items: [{items}], last: 5 }}");
+    let seen = Arc::new(AtomicUsize::new(0));
+    let options = options.on_progress({
+        let seen = seen.clone();
+        move |consumed, total| {
+            assert!(consumed < total);
+            seen.fetch_add(1, Ordering::Relaxed);
+        }
+    });
+
+    assert_eq!(
+        serde_dbgfmt::get_with::<u32>(&text, "last", &options).unwrap(),
+        5
+    );
+    assert!(seen.load(Ordering::Relaxed) > 2);
+}
+
+#[test]
+fn get_invalid_path() {
+    for path in ["items[", "items[x]", "items..id", ".items", "items[0]id"] {
+        assert!(serde_dbgfmt::get::<u32>(RESPONSE, path).is_err(), "{path}");
+    }
+}
+
+#[test]
+#[cfg(feature = "path_to_error")]
+fn from_str_with_path() {
+    use std::collections::BTreeMap;
+
+    #[derive(Debug, Deserialize)]
+    #[allow(dead_code)]
+    struct Response {
+        status: u16,
+        headers: BTreeMap<String, Option<u32>>,
+        items: Vec<Item>,
+    }
+
+    #[derive(Debug, Deserialize)]
+    #[allow(dead_code)]
+    struct Item {
+        id: u32,
+        pos: (u8, u8),
+    }
+
+    let text = r#"Response { status: 200, headers: {"x-id": Some(7)}, items: [Item { id: 1, pos: (3, 4) }, Item { id: 2, pos: (5, 6) }] }"#;
+    serde_dbgfmt::from_str_with_path::<Response>(text).unwrap();
+
+    let text = text.replace("(5, 6)", "(5, -6)");
+    let error = serde_dbgfmt::from_str_with_path::<Response>(&text).unwrap_err();
+    let location = error.inner().location().unwrap();
+    assert_eq!(error.path().to_string(), "items[1].pos[1]");
+    assert_eq!(&text[location.byte_offset..][..2], "-6");
+
+    let text = text.replace("Some(7)", "Some(\"7\")");
+    let error = serde_dbgfmt::from_str_with_path::<Response>(&text).unwrap_err();
+    let location = error.inner().location().unwrap();
+    assert_eq!(error.path().to_string(), "headers.x-id");
+    assert_eq!(error.inner().span(), Some("\"7\""));
+    assert_eq!(&text[location.byte_offset..][..3], "\"7\"");
+
+    let error = serde_dbgfmt::from_str_with_path::<u32>("1 2").unwrap_err();
+    assert_eq!(error.path().to_string(), ".");
+    assert_eq!(error.inner().location().unwrap().byte_offset, 2);
+}
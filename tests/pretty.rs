@@ -0,0 +1,184 @@
+//! Tests for parsing the pretty-printed output of `{:#?}`.
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fmt;
+
+use pretty_assertions::assert_eq;
+use serde::Deserialize;
+
+/// Check that `value` survives a round trip through its pretty-printed debug
+/// representation.
+#[track_caller]
+fn roundtrip<T>(value: T)
+where
+    T: fmt::Debug + PartialEq + for<'de> Deserialize<'de>,
+{
+    let text = format!("{value:#?}");
+    let parsed: T = serde_dbgfmt::from_str(&text)
+        .unwrap_or_else(|e| panic!("failed to parse pretty output: {e}\n{text}"));
+    assert_eq!(parsed, value);
+}
+
+#[derive(Debug, Deserialize, PartialEq)]
+struct Unit;
+
+#[derive(Debug, Deserialize, PartialEq)]
+struct Newtype(u32);
+
+#[derive(Debug, Deserialize, PartialEq)]
+struct Tuple(i8, String, Unit);
+
+#[derive(Debug, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
+struct Key {
+    id: u8,
+    tag: char,
+}
+
+#[derive(Debug, Deserialize, PartialEq)]
+enum Shape {
+    Empty,
+    Circle(f64),
+    Rect(u32, u32),
+    Poly { sides: u8, names: Vec<String> },
+}
+
+#[derive(Debug, Deserialize, PartialEq)]
+struct Nested {
+    name: String,
+    shapes: Vec<Shape>,
+    lookup: BTreeMap<Key, Option<Shape>>,
+    tags: BTreeSet<String>,
+    pair: (u8, (Newtype,)),
+    empty_list: Vec<u8>,
+    empty_map: BTreeMap<u8, u8>,
+}
+
+#[test]
+fn structs() {
+    roundtrip(Unit);
+    roundtrip(Newtype(7));
+    roundtrip(Tuple(-1, "two".into(), Unit));
+    roundtrip(Key { id: 1, tag: 'x' });
+}
+
+#[test]
+fn enums() {
+    roundtrip(Shape::Empty);
+    roundtrip(Shape::Circle(1.5));
+    roundtrip(Shape::Rect(2, 3));
+    roundtrip(Shape::Poly {
+        sides: 3,
+        names: vec!["tri".into(), "angle".into()],
+    });
+    roundtrip(vec![Some(Shape::Empty), None]);
+}
+
+#[test]
+fn collections() {
+    roundtrip(vec![1, 2, 3]);
+    roundtrip(Vec::<u8>::new());
+    roundtrip(BTreeSet::from(["a".to_owned(), "b".to_owned()]));
+    roundtrip(BTreeMap::from([
+        (1, "one".to_owned()),
+        (2, "two".to_owned()),
+    ]));
+    roundtrip(HashMap::from([("k".to_owned(), vec![(1u8, 'c')])]));
+    roundtrip(BTreeMap::from([(
+        Key { id: 1, tag: 'k' },
+        BTreeMap::from([(vec![1u8], ())]),
+    )]));
+}
+
+#[test]
+fn tuples() {
+    roundtrip(());
+    roundtrip((1u8,));
+    roundtrip((1u8, "a".to_owned(), ('b', -2.5f32)));
+    roundtrip(((),));
+}
+
+#[test]
+fn strings() {
+    roundtrip("multiple\nlines\n    with indentation".to_owned());
+    roundtrip(vec!["{".to_owned(), "}".to_owned(), ",\n".to_owned()]);
+    roundtrip('\n');
+}
+
+#[test]
+fn nested() {
+    roundtrip(Nested {
+        name: "nested".into(),
+        shapes: vec![
+            Shape::Empty,
+            Shape::Poly {
+                sides: 4,
+                names: vec![],
+            },
+        ],
+        lookup: BTreeMap::from([
+            (Key { id: 1, tag: 'a' }, Some(Shape::Rect(1, 1))),
+            (Key { id: 2, tag: 'b' }, None),
+        ]),
+        tags: BTreeSet::from(["x".into(), "y".into()]),
+        pair: (5, (Newtype(6),)),
+        empty_list: vec![],
+        empty_map: BTreeMap::new(),
+    });
+}
+
+#[test]
+fn non_exhaustive() {
+    /// Types with hand-written debug impls that hide some of their fields.
+    mod printed {
+        use std::fmt;
+
+        pub struct Partial;
+
+        impl fmt::Debug for Partial {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.debug_struct("Partial")
+                    .field("shown", &vec![1, 2])
+                    .finish_non_exhaustive()
+            }
+        }
+
+        pub struct Opaque;
+
+        impl fmt::Debug for Opaque {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.debug_struct("Opaque").finish_non_exhaustive()
+            }
+        }
+    }
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Partial {
+        shown: Vec<u8>,
+    }
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Opaque {}
+
+    let text = format!("{:#?}", [(printed::Partial, Some(printed::Opaque))]);
+    let parsed: Vec<(Partial, Option<Opaque>)> = serde_dbgfmt::from_str(&text).unwrap();
+    assert_eq!(parsed, [(Partial { shown: vec![1, 2] }, Some(Opaque {}))]);
+}
+
+#[test]
+fn layout() {
+    // Every element in pretty output is followed by a comma, including the
+    // last one.
+    let text = "Key {\n    id: 1,\n    tag: 'a',\n}";
+    assert_eq!(
+        serde_dbgfmt::from_str::<Key>(text).unwrap(),
+        Key { id: 1, tag: 'a' }
+    );
+
+    // Whitespace is not significant, so compact and pretty output can be
+    // mixed and the indentation does not need to be consistent.
+    let text = "[\n    Key { id: 1, tag: 'a' },\n        Key {\n id: 2,\n tag: 'b',\n },\n]";
+    assert_eq!(
+        serde_dbgfmt::from_str::<Vec<Key>>(text).unwrap(),
+        [Key { id: 1, tag: 'a' }, Key { id: 2, tag: 'b' }]
+    );
+}
@@ -0,0 +1,116 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use pretty_assertions::assert_eq;
+use serde::Deserialize;
+use serde_dbgfmt::{Dialect, Options};
+
+fn python() -> Options {
+    Options::new().dialect(Dialect::PythonRepr)
+}
+
+#[test]
+fn python_dict_to_struct() {
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Inner {
+        name: String,
+    }
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Record {
+        key: bool,
+        x: Option<u32>,
+        y: Option<Inner>,
+        items: Vec<i64>,
+        pair: (f64, String),
+    }
+
+    let text = r#"{'key': True, 'x': None, "y": {'name': "it's"}, 'items': [1, -2], 'pair': (1.5, 'a\tb')}"#;
+    let record: Record =
+        serde_dbgfmt::from_str_with(text, &python()).unwrap_or_else(|e| panic!("{e}"));
+
+    assert_eq!(
+        record,
+        Record {
+            key: true,
+            x: None,
+            y: Some(Inner {
+                name: "it's".into()
+            }),
+            items: vec![1, -2],
+            pair: (1.5, "a\tb".into()),
+        }
+    );
+}
+
+#[test]
+fn python_collections() {
+    let options = python();
+
+    assert_eq!(
+        serde_dbgfmt::from_str_with::<Vec<u32>>("(1, 2, 3)", &options).unwrap(),
+        [1, 2, 3]
+    );
+    assert_eq!(
+        serde_dbgfmt::from_str_with::<Vec<u32>>("(1,)", &options).unwrap(),
+        [1]
+    );
+    assert_eq!(
+        serde_dbgfmt::from_str_with::<BTreeMap<String, Option<bool>>>(
+            "{'a': False, 'b': None}",
+            &options
+        )
+        .unwrap(),
+        BTreeMap::from([("a".into(), Some(false)), ("b".into(), None)])
+    );
+}
+
+#[test]
+fn python_sets() {
+    let options = python();
+
+    for (text, expected) in [
+        ("{3, 1, 2}", vec![1, 2, 3]),
+        ("set()", vec![]),
+        ("frozenset({2, 1})", vec![1, 2]),
+        ("frozenset()", vec![]),
+    ] {
+        let set: BTreeSet<u32> = serde_dbgfmt::from_str_with(text, &options).unwrap();
+        assert_eq!(set.into_iter().collect::<Vec<_>>(), expected, "{text}");
+    }
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    #[serde(untagged)]
+    enum Any {
+        Int(u32),
+        List(Vec<Any>),
+    }
+
+    let value: Any = serde_dbgfmt::from_str_with("[set(), frozenset({1})]", &options).unwrap();
+    assert_eq!(
+        value,
+        Any::List(vec![Any::List(vec![]), Any::List(vec![Any::Int(1)])])
+    );
+
+    assert!(serde_dbgfmt::from_str::<Vec<u32>>("set()").is_err());
+}
+
+#[test]
+fn python_strings() {
+    let options = python();
+
+    assert_eq!(
+        serde_dbgfmt::from_str_with::<String>(r"'\x1b[0m é \U0001f980'", &options).unwrap(),
+        "\x1b[0m \u{e9} \u{1f980}"
+    );
+    assert_eq!(
+        serde_dbgfmt::from_str_with::<char>("'x'", &options).unwrap(),
+        'x'
+    );
+}
+
+#[test]
+fn python_syntax_not_accepted_by_default() {
+    assert!(serde_dbgfmt::from_str::<bool>("True").is_err());
+    assert!(serde_dbgfmt::from_str::<String>("'abc'").is_err());
+    assert!(serde_dbgfmt::from_str::<Option<u32>>("5").is_err());
+}
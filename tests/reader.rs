@@ -0,0 +1,67 @@
+use pretty_assertions::assert_eq;
+use serde_dbgfmt::MapReader;
+
+fn entries(text: &str) -> Vec<(&str, &str)> {
+    MapReader::new(text)
+        .unwrap_or_else(|e| panic!("{e}"))
+        .collect::<Result<_, _>>()
+        .unwrap_or_else(|e| panic!("{e}"))
+}
+
+#[test]
+fn read_struct() {
+    assert_eq!(
+        entries("Test { a: 5, b: Inner { c: [1, 2, {3}] }, c: (\"}\", ',') }"),
+        [
+            ("a", "5"),
+            ("b", "Inner { c: [1, 2, {3}] }"),
+            ("c", "(\"}\", ',')"),
+        ]
+    );
+}
+
+#[test]
+fn read_struct_non_exhaustive() {
+    assert_eq!(entries("Test { a: 5, .. }"), [("a", "5")]);
+    assert_eq!(entries("Test { .. }"), []);
+}
+
+#[test]
+fn read_empty() {
+    let reader = MapReader::new("Empty").unwrap();
+    assert_eq!(reader.name(), Some("Empty"));
+    assert_eq!(reader.count(), 0);
+
+    assert_eq!(entries("{}"), []);
+}
+
+#[test]
+fn read_map() {
+    let reader = MapReader::new("{\"a\": 1, 2: [3], Some(4): None}").unwrap();
+    assert_eq!(reader.name(), None);
+    assert_eq!(
+        reader.collect::<Result<Vec<_>, _>>().unwrap(),
+        [("\"a\"", "1"), ("2", "[3]"), ("Some(4)", "None")]
+    );
+}
+
+#[test]
+fn read_lazily() {
+    // Errors after the entries that are read are not reported.
+    let mut reader = MapReader::new("Test { a: 1, b: 2 ] }").unwrap();
+    assert_eq!(reader.next().unwrap().unwrap(), ("a", "1"));
+    assert!(reader.next().unwrap().is_err());
+    assert!(reader.next().is_none());
+}
+
+#[test]
+fn read_errors() {
+    assert!(MapReader::new("[1, 2]").is_err());
+    assert!(MapReader::new("Test(1, 2)").is_err());
+
+    let errors = |text| MapReader::new(text).unwrap().any(|entry| entry.is_err());
+    assert!(errors("Test { a: 1 } trailing"));
+    assert!(errors("Test { a 1 }"));
+    assert!(errors("Test { a: 1"));
+    assert!(errors("Test { a: }"));
+}
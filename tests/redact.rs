@@ -0,0 +1,98 @@
+use pretty_assertions::assert_eq;
+use serde_dbgfmt::Redactor;
+
+const PRETTY: &str = r#"Request {
+    url: "https://example.com",
+    headers: {
+        "Authorization": "Bearer abc",
+        "Accept": "*/*",
+    },
+    auth: Some(
+        Credentials {
+            user: "alice",
+            secret: [
+                "x",
+                "y\"z",
+            ],
+        },
+    ),
+    retries: 3,
+}"#;
+
+#[test]
+fn redact_all() {
+    let redacted = Redactor::new().redact(PRETTY).unwrap();
+
+    assert_eq!(
+        redacted,
+        r#"Request {
+    url: "<redacted>",
+    headers: {
+        "Authorization": "<redacted>",
+        "Accept": "<redacted>",
+    },
+    auth: Some(
+        Credentials {
+            user: "<redacted>",
+            secret: [
+                "<redacted>",
+                "<redacted>",
+            ],
+        },
+    ),
+    retries: 3,
+}"#
+    );
+}
+
+#[test]
+fn redact_fields() {
+    let redacted = Redactor::new()
+        .field("auth*")
+        .field("Authorization")
+        .redact(PRETTY)
+        .unwrap();
+
+    assert_eq!(
+        redacted,
+        r#"Request {
+    url: "https://example.com",
+    headers: {
+        "Authorization": "<redacted>",
+        "Accept": "*/*",
+    },
+    auth: Some(
+        Credentials {
+            user: "<redacted>",
+            secret: [
+                "<redacted>",
+                "<redacted>",
+            ],
+        },
+    ),
+    retries: 3,
+}"#
+    );
+}
+
+#[test]
+fn redact_nothing_matching() {
+    let text = "Point { x: 1, y: 'c' }";
+    assert_eq!(Redactor::new().redact(text).unwrap(), text);
+    assert_eq!(Redactor::new().field("x").redact(text).unwrap(), text);
+}
+
+#[test]
+fn redact_invalid() {
+    assert!(Redactor::new().redact("Point { x: \"a\"").is_err());
+}
+
+#[test]
+fn redact_deep() {
+    let deep = "[".repeat(200_000);
+    let error = Redactor::new().redact(&deep).unwrap_err();
+    assert!(error.is_depth_limit_exceeded());
+
+    let nested = format!("{}{}", "[".repeat(100), "]".repeat(100));
+    assert_eq!(Redactor::new().redact(&nested).unwrap(), nested);
+}
@@ -0,0 +1,69 @@
+use pretty_assertions::assert_eq;
+use serde_dbgfmt::render;
+
+const TEXT: &str = r#"State { ids: {"a\"b": (1, Some(2))}, tags: {'x'}, empty: [] }"#;
+
+#[test]
+fn ascii_tree() {
+    assert_eq!(
+        render::ascii_tree(TEXT).unwrap(),
+        r#"State
+|-- ids: map (1)
+|   `-- "a\"b": tuple (2)
+|       |-- [0]: 1
+|       `-- [1]: Some
+|           `-- [0]: 2
+|-- tags: set (1)
+|   `-- [0]: 'x'
+`-- empty: list (0)
+"#
+    );
+
+    assert_eq!(render::ascii_tree("5").unwrap(), "5\n");
+}
+
+#[test]
+fn dot() {
+    assert_eq!(
+        render::dot(TEXT).unwrap(),
+        r#"digraph {
+    n0 [label="State"];
+    n1 [label="map (1)"];
+    n0 -> n1 [label="ids"];
+    n2 [label="tuple (2)"];
+    n1 -> n2 [label="\"a\\\"b\""];
+    n3 [label="1"];
+    n2 -> n3 [label="[0]"];
+    n4 [label="Some"];
+    n2 -> n4 [label="[1]"];
+    n5 [label="2"];
+    n4 -> n5 [label="[0]"];
+    n6 [label="set (1)"];
+    n0 -> n6 [label="tags"];
+    n7 [label="'x'"];
+    n6 -> n7 [label="[0]"];
+    n8 [label="list (0)"];
+    n0 -> n8 [label="empty"];
+}
+"#
+    );
+}
+
+#[test]
+fn render_invalid() {
+    assert!(render::ascii_tree("State {").is_err());
+    assert!(render::dot("[1,, 2]").is_err());
+}
+
+#[test]
+fn render_deep() {
+    let deep = "[".repeat(200_000);
+    assert!(render::ascii_tree(&deep)
+        .unwrap_err()
+        .is_depth_limit_exceeded());
+    assert!(render::dot(&deep).unwrap_err().is_depth_limit_exceeded());
+
+    let nested = format!("{}{}", "[".repeat(100), "]".repeat(100));
+    assert!(render::ascii_tree(&nested).is_ok());
+    assert!(render::dot(&nested).is_ok());
+}
@@ -0,0 +1,66 @@
+use pretty_assertions::assert_eq;
+use serde_dbgfmt::rewrite::{rewrite, Action, TokenKind};
+
+#[test]
+fn keep_everything() {
+    let text = "Config {\n    name: \"a\",\n    ports: [\n        80,\n    ],\n}  ";
+    assert_eq!(rewrite(text, |_| Action::Keep).unwrap(), text);
+}
+
+#[test]
+fn drop_tokens() {
+    let text = "Test { a: -1, b: +2 }";
+    let output = rewrite(text, |token| match token.text() {
+        "+" | "-" => Action::Drop,
+        _ => Action::Keep,
+    })
+    .unwrap();
+
+    assert_eq!(output, "Test { a: 1, b: 2 }");
+}
+
+#[test]
+fn token_spans() {
+    let text = " Some( 'x' ) ";
+    let mut tokens = Vec::new();
+    rewrite(text, |token| {
+        tokens.push((token.kind(), token.text(), token.span()));
+        Action::Keep
+    })
+    .unwrap();
+
+    assert_eq!(
+        tokens,
+        [
+            (TokenKind::Ident, "Some", 1..5),
+            (TokenKind::Punct, "(", 5..6),
+            (TokenKind::Char, "'x'", 7..10),
+            (TokenKind::Punct, ")", 11..12),
+        ]
+    );
+}
+
+#[test]
+fn invalid_token() {
+    assert!(rewrite("Test { a: @ }", |_| Action::Keep).is_err());
+}
+
+#[test]
+fn normalize_pointers() {
+    use serde_dbgfmt::rewrite::normalize_pointers;
+
+    let a = 0x5555_1234_abcdusize as *const u8;
+    let b = 0x5555_1234_ff00usize as *const u8;
+    let text =
+        format!("Handles {{ a: {a:?}, b: {b:?}, again: {a:?}, small: 0x10, neg: -0xdeadbeef }}");
+
+    assert_eq!(
+        normalize_pointers(&text).unwrap(),
+        "Handles { a: 0x1, b: 0x2, again: 0x1, small: 0x10, neg: -0x3 }"
+    );
+    assert_eq!(
+        normalize_pointers("[0x1, 0x12345678]").unwrap(),
+        "[0x1, 0x1]"
+    );
+    assert!(normalize_pointers("'unterminated").is_err());
+}
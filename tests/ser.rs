@@ -0,0 +1,244 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::time::Duration;
+
+use pretty_assertions::assert_eq;
+use serde::{Deserialize, Serialize};
+use serde_dbgfmt::ser::{display, to_slice, to_writer};
+use serde_dbgfmt::{to_string, to_string_pretty};
+
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+struct Unit;
+
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+struct Newtype(u8);
+
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+struct Pair(i64, String);
+
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+enum Shape {
+    Empty,
+    Circle(f64),
+    Rect(u32, u32),
+    Poly { sides: u8, name: Option<char> },
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+struct Everything {
+    flag: bool,
+    int: i128,
+    uint: u128,
+    float: f32,
+    text: String,
+    escaped: String,
+    unit: (),
+    unit_struct: Unit,
+    newtype: Newtype,
+    pair: Pair,
+    tuple: (u8, (i8,)),
+    list: Vec<Shape>,
+    map: BTreeMap<String, Option<u16>>,
+    empty: Vec<u8>,
+}
+
+fn everything() -> Everything {
+    Everything {
+        flag: true,
+        int: -170141183460469231731687303715884105728,
+        uint: u128::MAX,
+        float: 0.1,
+        text: "hello".into(),
+        escaped: "tab\t \"quote\" \u{0}".into(),
+        unit: (),
+        unit_struct: Unit,
+        newtype: Newtype(7),
+        pair: Pair(-1, "b".into()),
+        tuple: (1, (-2,)),
+        list: vec![
+            Shape::Empty,
+            Shape::Circle(1.5),
+            Shape::Rect(2, 3),
+            Shape::Poly {
+                sides: 5,
+                name: Some('p'),
+            },
+        ],
+        map: [("a".to_owned(), Some(1)), ("b".to_owned(), None)].into(),
+        empty: vec![],
+    }
+}
+
+#[test]
+fn matches_derived_debug() {
+    let value = everything();
+
+    assert_eq!(display(&value).to_string(), format!("{value:?}"));
+    assert_eq!(format!("{:#}", display(&value)), format!("{value:#?}"));
+}
+
+#[test]
+fn round_trip() {
+    let value = everything();
+    let text = display(&value).to_string();
+    assert_eq!(serde_dbgfmt::from_str::<Everything>(&text).unwrap(), value);
+}
+
+#[test]
+fn debug_impl() {
+    struct Wrapper(Shape);
+
+    impl fmt::Debug for Wrapper {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            fmt::Display::fmt(&display(&self.0), f)
+        }
+    }
+
+    let wrapper = Wrapper(Shape::Poly {
+        sides: 3,
+        name: None,
+    });
+    assert_eq!(format!("{wrapper:?}"), "Poly { sides: 3, name: None }");
+    assert_eq!(
+        format!("{:?}", [&wrapper]),
+        "[Poly { sides: 3, name: None }]"
+    );
+}
+
+#[test]
+fn serialize_error() {
+    struct Failing;
+
+    impl Serialize for Failing {
+        fn serialize<S: serde::Serializer>(&self, _: S) -> Result<S::Ok, S::Error> {
+            Err(serde::ser::Error::custom("failed"))
+        }
+    }
+
+    let mut text = String::new();
+    assert!(fmt::write(&mut text, format_args!("{}", display(&[Failing]))).is_err());
+}
+
+#[test]
+fn writer_matches_display() {
+    let value = everything();
+
+    let mut text = String::new();
+    to_writer(&mut text, &value).unwrap();
+    assert_eq!(text, format!("{value:?}"));
+}
+
+#[test]
+fn slice() {
+    let value = Shape::Rect(2, 3);
+    let expected = format!("{value:?}");
+
+    let mut buffer = [0u8; 64];
+    let len = to_slice(&mut buffer, &value).unwrap();
+    assert_eq!(&buffer[..len], expected.as_bytes());
+
+    let len = to_slice(&mut buffer[..expected.len()], &value).unwrap();
+    assert_eq!(len, expected.len());
+
+    let err = to_slice(&mut buffer[..expected.len() - 1], &value).unwrap_err();
+    assert_eq!(err.to_string(), "the buffer is too small");
+}
+
+#[test]
+fn writer_errors() {
+    struct Failing;
+
+    impl Serialize for Failing {
+        fn serialize<S: serde::Serializer>(&self, _: S) -> Result<S::Ok, S::Error> {
+            Err(serde::ser::Error::custom("failed"))
+        }
+    }
+
+    struct Closed;
+
+    impl fmt::Write for Closed {
+        fn write_str(&mut self, _: &str) -> fmt::Result {
+            Err(fmt::Error)
+        }
+    }
+
+    let mut text = String::new();
+    let err = to_writer(&mut text, &vec![Some(Failing)]).unwrap_err();
+    assert_eq!(err.to_string(), "failed");
+
+    let mut buffer = [0u8; 64];
+    let err = to_slice(&mut buffer, &(1, Failing)).unwrap_err();
+    assert_eq!(err.to_string(), "failed");
+
+    let err = to_writer(&mut Closed, &Unit).unwrap_err();
+    assert_eq!(err.to_string(), "the writer returned an error");
+}
+
+#[test]
+fn string() {
+    let value = everything();
+
+    assert_eq!(to_string(&value).unwrap(), format!("{value:?}"));
+    assert_eq!(to_string_pretty(&value).unwrap(), format!("{value:#?}"));
+
+    let text = to_string_pretty(&value).unwrap();
+    assert_eq!(serde_dbgfmt::from_str::<Everything>(&text).unwrap(), value);
+}
+
+#[test]
+fn duration() {
+    #[derive(Debug, Serialize, Deserialize, PartialEq)]
+    struct Timings {
+        total: Duration,
+        steps: Vec<Duration>,
+    }
+
+    let value = Timings {
+        total: Duration::new(1, 500_000_000),
+        steps: vec![Duration::from_nanos(7), Duration::from_micros(12)],
+    };
+
+    let text = to_string(&value).unwrap();
+    assert_eq!(text, format!("{value:?}"));
+    assert_eq!(to_string_pretty(&value).unwrap(), format!("{value:#?}"));
+    assert_eq!(serde_dbgfmt::from_str::<Timings>(&text).unwrap(), value);
+}
+
+#[test]
+fn empty_array() {
+    #[derive(Debug, Serialize, Deserialize, PartialEq)]
+    struct Buffers {
+        empty: [u8; 0],
+        nested: Vec<[u8; 0]>,
+    }
+
+    let value = Buffers {
+        empty: [],
+        nested: vec![[], []],
+    };
+
+    let text = to_string(&value).unwrap();
+    assert_eq!(text, format!("{value:?}"));
+    assert_eq!(to_string_pretty(&value).unwrap(), format!("{value:#?}"));
+    assert_eq!(serde_dbgfmt::from_str::<Buffers>(&text).unwrap(), value);
+    assert_eq!(serde_dbgfmt::from_str::<[u8; 2]>("[1, 2]").unwrap(), [1, 2]);
+}
+
+#[test]
+fn sets_and_arrays() {
+    let set: BTreeSet<u8> = [1, 2].into();
+    assert_eq!(format!("{set:?}"), "{1, 2}");
+    assert_eq!(to_string(&set).unwrap(), "[1, 2]");
+    assert_eq!(to_string_pretty(&set).unwrap(), "[\n    1,\n    2,\n]");
+    assert_eq!(to_string(&BTreeSet::<u8>::new()).unwrap(), "[]");
+    let text = to_string(&set).unwrap();
+    assert_eq!(serde_dbgfmt::from_str::<BTreeSet<u8>>(&text).unwrap(), set);
+
+    let array = [1u8, 2];
+    assert_eq!(format!("{array:?}"), "[1, 2]");
+    assert_eq!(to_string(&array).unwrap(), "(1, 2)");
+    assert_eq!(to_string(&[0u8; 0]).unwrap(), "[]");
+    assert_eq!(to_string_pretty(&[0u8; 0]).unwrap(), "[]");
+    let text = to_string(&array).unwrap();
+    assert_eq!(serde_dbgfmt::from_str::<[u8; 2]>(&text).unwrap(), array);
+}
@@ -0,0 +1,164 @@
+//! Round-trip tests for the debug representations of std types.
+//!
+//! Each test here formats a std type using its `Debug` impl and then checks
+//! that it can be deserialized back.
+
+#![cfg(feature = "std_types")]
+
+use std::cell::RefCell;
+use std::collections::{BTreeMap, BTreeSet, HashMap, VecDeque};
+use std::ffi::{CString, OsString};
+use std::fmt::Debug;
+use std::marker::PhantomData;
+use std::num::{NonZeroI64, NonZeroU32, NonZeroU8};
+use std::ops::{Range, RangeInclusive};
+use std::path::PathBuf;
+use std::rc::Rc;
+use std::sync::Arc;
+use std::time::Duration;
+
+use pretty_assertions::assert_eq;
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+
+#[track_caller]
+fn roundtrip<T>(value: T)
+where
+    T: Debug + DeserializeOwned + PartialEq,
+{
+    let text = format!("{value:?}");
+    let parsed: T = serde_dbgfmt::from_str(&text).unwrap_or_else(|e| panic!("{e}: {text}"));
+
+    assert_eq!(parsed, value);
+}
+
+#[test]
+fn path_buf() {
+    roundtrip(PathBuf::from("/usr/lib/libc.so"));
+    roundtrip(PathBuf::from("relative/path with spaces"));
+}
+
+#[test]
+fn option() {
+    roundtrip(Some(5u32));
+    roundtrip(None::<u32>);
+    roundtrip(Some(Some("nested".to_string())));
+    roundtrip(vec![Some(1u8), None, Some(3)]);
+}
+
+#[test]
+fn result() {
+    roundtrip(Ok::<u32, String>(5));
+    roundtrip(Err::<u32, String>("failed".into()));
+}
+
+#[test]
+fn duration() {
+    roundtrip(Duration::from_millis(1500));
+    roundtrip(Duration::from_micros(3));
+    roundtrip(Duration::new(7, 1));
+    roundtrip(Duration::MAX);
+}
+
+#[test]
+fn phantom_data() {
+    roundtrip(PhantomData::<u32>);
+    roundtrip(PhantomData::<Vec<(u8, &str)>>);
+    roundtrip(PhantomData::<fn(u8) -> BTreeMap<u8, u8>>);
+    roundtrip((PhantomData::<[u8; 4]>, 5u8));
+}
+
+#[test]
+fn non_zero() {
+    roundtrip(NonZeroU8::new(1).unwrap());
+    roundtrip(NonZeroU32::new(42).unwrap());
+    roundtrip(NonZeroI64::new(-7).unwrap());
+}
+
+#[test]
+fn smart_pointers() {
+    roundtrip(Box::new(5u32));
+    roundtrip(Rc::new("shared".to_string()));
+    roundtrip(Arc::new(vec![1u32, 2, 3]));
+}
+
+#[test]
+fn collections() {
+    roundtrip(VecDeque::from(vec![1u32, 2, 3]));
+    roundtrip(BTreeSet::from([1u32, 2, 3]));
+    roundtrip(BTreeMap::from([(1u32, "a".to_string()), (2, "b".into())]));
+    roundtrip(HashMap::from([("key".to_string(), 5u32)]));
+}
+
+#[derive(Debug, Deserialize, PartialEq)]
+struct Adapters {
+    #[serde(with = "serde_dbgfmt::with::os_string")]
+    os_string: OsString,
+    #[serde(with = "serde_dbgfmt::with::c_string")]
+    c_string: CString,
+    #[serde(with = "serde_dbgfmt::with::range")]
+    range: Range<u64>,
+    #[serde(with = "serde_dbgfmt::with::range_inclusive")]
+    range_inclusive: RangeInclusive<i8>,
+    #[serde(with = "serde_dbgfmt::with::range")]
+    float_range: Range<f64>,
+}
+
+#[test]
+fn adapters() {
+    roundtrip(Adapters {
+        os_string: "program\targ".into(),
+        c_string: CString::new("symbol").unwrap(),
+        range: 0..100,
+        range_inclusive: -128..=127,
+        float_range: -0.5..1.5,
+    });
+}
+
+#[test]
+fn adapters_non_utf8() {
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Symbol {
+        #[serde(with = "serde_dbgfmt::with::c_string")]
+        name: CString,
+    }
+
+    roundtrip(Symbol {
+        name: CString::new(b"caf\xe9 \x1b[0m\xff".to_vec()).unwrap(),
+    });
+
+    let text = r#"Symbol { name: "a\x00b" }"#;
+    assert!(serde_dbgfmt::from_str::<Symbol>(text).is_err());
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::ffi::OsStringExt;
+
+        #[derive(Debug, Deserialize, PartialEq)]
+        struct Command {
+            #[serde(with = "serde_dbgfmt::with::os_string")]
+            program: OsString,
+        }
+
+        roundtrip(Command {
+            program: OsString::from_vec(b"caf\xe9 \x1b[0m\xff \xc3\xa9".to_vec()),
+        });
+    }
+}
+
+#[test]
+fn ref_cell() {
+    let options = serde_dbgfmt::Options::new().unwrap_std_wrappers(true);
+    let text = format!("{:?}", RefCell::new(5u32));
+    let value: RefCell<u32> = serde_dbgfmt::from_str_with(&text, &options).unwrap();
+    assert_eq!(value, RefCell::new(5));
+}
+
+#[test]
+fn range_lexing() {
+    assert_eq!(
+        serde_dbgfmt::from_str::<Vec<u32>>("[1, 2]").unwrap(),
+        [1, 2]
+    );
+    assert!(serde_dbgfmt::from_str::<f64>("1.").is_err());
+}
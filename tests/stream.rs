@@ -0,0 +1,74 @@
+use pretty_assertions::assert_eq;
+use serde::Deserialize;
+use serde_dbgfmt::{Deserializer, Options};
+
+#[derive(Debug, Deserialize, PartialEq)]
+struct Event<'a> {
+    id: u32,
+    name: &'a str,
+}
+
+#[test]
+fn one_per_line() {
+    let events = [
+        Event { id: 1, name: "a" },
+        Event { id: 2, name: "b" },
+        Event { id: 3, name: "c" },
+    ];
+    let log: String = events.iter().map(|e| format!("{e:?}\n")).collect();
+
+    let parsed: Vec<Event> = Deserializer::new(&log)
+        .into_iter()
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(parsed, events);
+}
+
+#[test]
+fn pretty_and_mixed_whitespace() {
+    let text = format!(
+        "  {:#?}\n\n\t{:?} {:?}\n",
+        vec![1, 2],
+        vec![3],
+        Vec::<u8>::new()
+    );
+
+    let values: Vec<Vec<u8>> = Deserializer::new(&text)
+        .into_iter()
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(values, [vec![1, 2], vec![3], vec![]]);
+}
+
+#[test]
+fn empty() {
+    let mut stream = Deserializer::new(" \n ").into_iter::<u32>();
+    assert!(stream.next().is_none());
+    assert_eq!(stream.byte_offset(), 0);
+}
+
+#[test]
+fn stops_at_error() {
+    let text = "1 2 x 4";
+    let mut stream = Deserializer::new(text).into_iter::<u32>();
+
+    assert_eq!(stream.next().unwrap().unwrap(), 1);
+    assert_eq!(stream.byte_offset(), 1);
+    assert_eq!(stream.next().unwrap().unwrap(), 2);
+    assert_eq!(stream.byte_offset(), 3);
+    assert!(stream.next().unwrap().is_err());
+    assert_eq!(stream.byte_offset(), 3);
+    assert!(stream.next().is_none());
+}
+
+#[test]
+fn options() {
+    let text = "Some(1)\nNone\nSome(1e3)";
+    let options = Options::new().coerce_numbers(true);
+
+    let values: Vec<Option<u32>> = Deserializer::with_options(text, options)
+        .into_iter()
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(values, [Some(1), None, Some(1000)]);
+}
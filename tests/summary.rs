@@ -0,0 +1,73 @@
+use std::collections::BTreeMap;
+
+use pretty_assertions::assert_eq;
+use serde_dbgfmt::summarize;
+
+#[test]
+fn summarize_kinds() {
+    let text = r#"Dump { a: (1, -2.5, 'c', "s\n"), b: {"k": [true, None]}, c: {1, 2}, d: Some(Unit), e: 1..5, f: Opts { .. } }"#;
+    let summary = summarize(text).unwrap();
+
+    assert_eq!(summary.nodes, 18);
+    assert_eq!(summary.max_depth, 4);
+    assert_eq!(summary.string_bytes, 3);
+    assert_eq!(
+        summary.kinds,
+        BTreeMap::from([
+            ("bool", 1),
+            ("char", 1),
+            ("float", 1),
+            ("integer", 3),
+            ("list", 1),
+            ("map", 1),
+            ("other", 1),
+            ("set", 1),
+            ("string", 2),
+            ("struct", 2),
+            ("tuple", 1),
+            ("tuple struct", 1),
+            ("unit", 2),
+        ])
+    );
+    assert_eq!(
+        summary.names,
+        BTreeMap::from([
+            ("Dump".to_owned(), 1),
+            ("None".to_owned(), 1),
+            ("Opts".to_owned(), 1),
+            ("Some".to_owned(), 1),
+            ("Unit".to_owned(), 1),
+        ])
+    );
+}
+
+#[test]
+fn summarize_display() {
+    let summary = summarize("[A, A, B(1)]").unwrap();
+
+    assert_eq!(
+        summary.to_string(),
+        "nodes: 5\n\
+         max depth: 3\n\
+         string bytes: 0\n\
+         kinds:\n  integer: 1\n  list: 1\n  tuple struct: 1\n  unit: 2\n\
+         names:\n  A: 2\n  B: 1\n"
+    );
+}
+
+#[test]
+fn summarize_invalid() {
+    assert!(summarize("[1, 2").is_err());
+    assert!(summarize("Foo { a: }").is_err());
+    assert!(summarize("{1: 2, 3}").is_err());
+    assert!(summarize("1 2").is_err());
+}
+
+#[test]
+fn summarize_deep() {
+    let deep = "[".repeat(200_000);
+    assert!(summarize(&deep).unwrap_err().is_depth_limit_exceeded());
+
+    let nested = format!("{}{}", "[".repeat(100), "]".repeat(100));
+    assert!(summarize(&nested).is_ok());
+}
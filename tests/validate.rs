@@ -0,0 +1,66 @@
+use pretty_assertions::assert_eq;
+
+#[test]
+fn valid() {
+    let inputs = [
+        "5",
+        "-inf",
+        "Unit",
+        "Point { x: 1.5, y: -2 }",
+        "Test { a: 1, .. }",
+        "Wrapper(Some(\"a\"), [])",
+        "{}",
+        "{1, 2, 3}",
+        "{\"a\": [1], \"b\": []}",
+        "(1,)",
+        "0..=5",
+        "..",
+        "Config {\n    name: 'x',\n}",
+    ];
+
+    for input in inputs {
+        assert!(
+            serde_dbgfmt::validate(input).is_ok(),
+            "{input:?} should be valid"
+        );
+    }
+}
+
+#[test]
+fn invalid() {
+    let cases = [
+        ("", None, 0),
+        ("[1, 2", None, 5),
+        ("Point { x: 1 ]", Some("]"), 13),
+        ("Point { 5: 1 }", Some("5"), 8),
+        ("{1: 2, 3}", Some("}"), 8),
+        ("1 2", Some("2"), 2),
+        ("- x", Some("x"), 2),
+        ("\"unterminated", None, 13),
+    ];
+
+    for (input, found, offset) in cases {
+        let error = serde_dbgfmt::validate(input).unwrap_err();
+        assert_eq!(
+            (error.found(), error.offset()),
+            (found, offset),
+            "{input:?}"
+        );
+    }
+}
+
+#[test]
+fn depth_limit() {
+    let input = format!("{}{}", "[".repeat(256), "]".repeat(256));
+    assert!(serde_dbgfmt::validate(&input).is_ok());
+
+    let input = format!("{}{}", "[".repeat(257), "]".repeat(257));
+    let error = serde_dbgfmt::validate(&input).unwrap_err();
+    assert_eq!(error.offset(), 256);
+}
+
+#[test]
+fn into_error() {
+    let error: serde_dbgfmt::Error = serde_dbgfmt::validate("[1 2]").unwrap_err().into();
+    assert_eq!(error.to_string(), "unexpected token `2`, expected `,`");
+}
@@ -0,0 +1,452 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use pretty_assertions::assert_eq;
+use serde::Deserialize;
+use serde_dbgfmt::Value;
+
+#[derive(Debug)]
+#[allow(dead_code)]
+enum Shape {
+    Empty,
+    Circle(f64),
+    Poly { sides: u8, name: Option<char> },
+}
+
+#[derive(Debug)]
+#[allow(dead_code)]
+struct Everything {
+    flag: bool,
+    int: i128,
+    uint: u128,
+    float: f32,
+    text: &'static str,
+    unit: (),
+    tuple: (u8, (i8,)),
+    shapes: Vec<Shape>,
+    map: BTreeMap<&'static str, Option<u16>>,
+    set: BTreeSet<u8>,
+    range: std::ops::Range<u8>,
+}
+
+fn everything() -> Everything {
+    Everything {
+        flag: true,
+        int: -5,
+        uint: u128::MAX,
+        float: 0.1,
+        text: "tab\t",
+        unit: (),
+        tuple: (1, (-2,)),
+        shapes: vec![
+            Shape::Empty,
+            Shape::Circle(1.5),
+            Shape::Poly {
+                sides: 5,
+                name: Some('p'),
+            },
+        ],
+        map: [("a", Some(1)), ("b", None)].into(),
+        set: [3, 4].into(),
+        range: 1..5,
+    }
+}
+
+#[test]
+fn parse() {
+    let value: Value = format!("{:?}", everything()).parse().unwrap();
+
+    let Value::Struct {
+        name,
+        fields,
+        non_exhaustive,
+    } = &value
+    else {
+        panic!("expected a struct, got {value:?}");
+    };
+    assert_eq!(name, "Everything");
+    assert!(!non_exhaustive);
+    assert_eq!(
+        fields
+            .iter()
+            .map(|(name, _)| name.as_str())
+            .collect::<Vec<_>>(),
+        [
+            "flag", "int", "uint", "float", "text", "unit", "tuple", "shapes", "map", "set",
+            "range"
+        ]
+    );
+
+    assert_eq!(value.field("flag"), Some(&Value::Bool(true)));
+    assert_eq!(value.field("int"), Some(&Value::Int(-5)));
+    assert_eq!(value.field("uint"), Some(&Value::UInt(u128::MAX)));
+    assert_eq!(value.field("float"), Some(&Value::Float(0.1)));
+    assert_eq!(value.field("text"), Some(&Value::Str("tab\t".into())));
+    assert_eq!(value.field("unit"), Some(&Value::Unit));
+    assert_eq!(
+        value.field("tuple"),
+        Some(&Value::Tuple(vec![
+            Value::Int(1),
+            Value::Tuple(vec![Value::Int(-2)])
+        ]))
+    );
+    assert_eq!(
+        value.field("shapes"),
+        Some(&Value::Seq(vec![
+            Value::Ident("Empty".into()),
+            Value::TupleStruct {
+                name: "Circle".into(),
+                elements: vec![Value::Float(1.5)],
+            },
+            Value::Struct {
+                name: "Poly".into(),
+                fields: vec![
+                    ("sides".into(), Value::Int(5)),
+                    (
+                        "name".into(),
+                        Value::Option(Some(Box::new(Value::Char('p'))))
+                    ),
+                ],
+                non_exhaustive: false,
+            },
+        ]))
+    );
+    assert_eq!(
+        value.field("map"),
+        Some(&Value::Map(vec![
+            (
+                Value::Str("a".into()),
+                Value::Option(Some(Box::new(Value::Int(1))))
+            ),
+            (Value::Str("b".into()), Value::Option(None)),
+        ]))
+    );
+    assert_eq!(
+        value.field("set"),
+        Some(&Value::Set(vec![Value::Int(3), Value::Int(4)]))
+    );
+    assert_eq!(value.field("range"), Some(&Value::Other("1..5".into())));
+    assert_eq!(value.field("missing"), None);
+}
+
+#[test]
+fn accessors() {
+    let value: Value = r#"Point(1, -2.5, "x", 'c', false, {"k": 18446744073709551616})"#
+        .parse()
+        .unwrap();
+
+    assert_eq!(value.name(), Some("Point"));
+    assert_eq!(value.elements().map(<[_]>::len), Some(6));
+    assert_eq!(value.element(0).and_then(Value::as_i128), Some(1));
+    assert_eq!(value.element(0).and_then(Value::as_u128), Some(1));
+    assert_eq!(value.element(0).and_then(Value::as_f64), Some(1.0));
+    assert_eq!(value.element(1).and_then(Value::as_f64), Some(-2.5));
+    assert_eq!(value.element(1).and_then(Value::as_u128), None);
+    assert_eq!(value.element(2).and_then(Value::as_str), Some("x"));
+    assert_eq!(value.element(3).and_then(Value::as_char), Some('c'));
+    assert_eq!(value.element(4).and_then(Value::as_bool), Some(false));
+    assert_eq!(value.element(6), None);
+
+    let map = value.element(5).unwrap();
+    assert_eq!(map.field("k").and_then(Value::as_u128), Some(1 << 64));
+    assert_eq!(map.element(0), None);
+
+    let value: Value = "[Some(None), None]".parse().unwrap();
+    assert_eq!(
+        value.element(0).and_then(Value::as_option),
+        Some(Some(&Value::Option(None)))
+    );
+    assert_eq!(value.element(1).and_then(Value::as_option), Some(None));
+}
+
+#[test]
+fn display() {
+    let text = format!("{:?}", everything());
+    let value: Value = text.parse().unwrap();
+    assert_eq!(value.to_string(), text);
+
+    let pretty = format!("{:#?}", everything());
+    assert_eq!(pretty.parse::<Value>().unwrap(), value);
+    assert_eq!(format!("{value:#}"), pretty);
+
+    let value: Value = "Partial { a: 1, .. }".parse().unwrap();
+    assert_eq!(value.to_string(), "Partial { a: 1, .. }");
+    assert_eq!(format!("{value:#}"), "Partial {\n    a: 1,\n    ..\n}");
+
+    let value: Value = "[Empty { .. }, Unit, (), (1,), T(1), {}]".parse().unwrap();
+    assert_eq!(
+        value.to_string(),
+        "[Empty { .. }, Unit, (), (1,), T(1), {}]"
+    );
+    assert_eq!(
+        format!("{value:#}"),
+        "[\n    Empty { .. },\n    Unit,\n    (),\n    (\n        1,\n    ),\n    T(\n        1,\n    ),\n    {},\n]"
+    );
+}
+
+#[test]
+fn deserialize_field() {
+    #[derive(Debug, Deserialize)]
+    struct Event {
+        kind: String,
+        payload: Value,
+    }
+
+    let event: Event =
+        serde_dbgfmt::from_str(r#"Event { kind: "login", payload: User { id: 3 } }"#).unwrap();
+    assert_eq!(event.kind, "login");
+    assert_eq!(event.payload.name(), Some("User"));
+    assert_eq!(event.payload.field("id").and_then(Value::as_i128), Some(3));
+
+    assert!(serde_dbgfmt::from_str::<Value>("Event {").is_err());
+}
+
+#[test]
+fn edge_cases() {
+    let cases = [
+        ("{}", Value::Map(vec![])),
+        ("{1, 2}", Value::Set(vec![Value::Int(1), Value::Int(2)])),
+        ("(1,)", Value::Tuple(vec![Value::Int(1)])),
+        ("-inf", Value::Float(f64::NEG_INFINITY)),
+        ("..5", Value::Other("..5".into())),
+        (
+            "Foo(1)..=Foo([2, 3])",
+            Value::Other("Foo(1)..=Foo([2, 3])".into()),
+        ),
+        (
+            "[1.., 2]",
+            Value::Seq(vec![Value::Other("1..".into()), Value::Int(2)]),
+        ),
+        (
+            "Flags(A | B)",
+            Value::TupleStruct {
+                name: "Flags".into(),
+                elements: vec![Value::Other("A | B".into())],
+            },
+        ),
+        (
+            "Empty { .. }",
+            Value::Struct {
+                name: "Empty".into(),
+                fields: vec![],
+                non_exhaustive: true,
+            },
+        ),
+    ];
+
+    for (text, expected) in cases {
+        assert_eq!(text.parse::<Value>().unwrap(), expected, "{text}");
+    }
+
+    for text in [
+        "{1: 2, 3}",
+        "{1, 2: 3}",
+        "{1: }",
+        "[1, 2",
+        "(1]",
+        "A { 1: 2 }",
+        "1..(2]",
+    ] {
+        assert!(text.parse::<Value>().is_err(), "{text}");
+    }
+}
+
+#[test]
+fn deeply_nested() {
+    let depth = 100_000;
+    let text = format!("{}1{}", "[Some(".repeat(depth), ")]".repeat(depth));
+
+    // This would overflow the stack if parsing or dropping the value recursed.
+    let value: Value = serde_dbgfmt::from_str(&text).unwrap();
+
+    let mut inner = &value;
+    for _ in 0..depth {
+        inner = inner.element(0).unwrap().as_option().unwrap().unwrap();
+    }
+    assert_eq!(inner, &Value::Int(1));
+
+    // Nor should anything else that walks the whole value.
+    let copy = value.clone();
+    assert_eq!(copy, value);
+    assert_eq!(value.to_string(), text);
+
+    let other: Value = text.replacen('1', "2", 1).parse().unwrap();
+    assert_ne!(other, value);
+}
+
+#[test]
+fn deserialize_uses_options() {
+    use serde_dbgfmt::{Dialect, Options, UnknownToken};
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Event {
+        kind: String,
+        payload: Value,
+    }
+
+    let options = Options::new().max_depth(3);
+    let text = r#"Event { kind: "a", payload: [[1]] }"#;
+    serde_dbgfmt::from_str_with::<Event>(text, &options).unwrap();
+    let text = r#"Event { kind: "a", payload: [[[1]]] }"#;
+    let error = serde_dbgfmt::from_str_with::<Event>(text, &options).unwrap_err();
+    assert!(error.is_depth_limit_exceeded(), "{error}");
+
+    let options = Options::new().dialect(Dialect::PythonRepr);
+    let value: Value =
+        serde_dbgfmt::from_str_with("{'a': True, 'b': (None, 'it\\'s')}", &options).unwrap();
+    assert_eq!(
+        value,
+        Value::Map(vec![
+            (Value::Str("a".into()), Value::Bool(true)),
+            (
+                Value::Str("b".into()),
+                Value::Tuple(vec![Value::Option(None), Value::Str("it's".into())])
+            ),
+        ])
+    );
+
+    let options = Options::new().on_unknown_token(|text| match text.starts_with('<') {
+        true => UnknownToken::String(text.find('>').unwrap() + 1),
+        false => UnknownToken::Skip(1),
+    });
+    let value: Value =
+        serde_dbgfmt::from_str_with("Frame { fn: <main>, line: @12, range: @1..5 }", &options)
+            .unwrap();
+    assert_eq!(value.field("fn"), Some(&Value::Str("<main>".into())));
+    assert_eq!(value.field("line"), Some(&Value::Int(12)));
+    assert_eq!(value.field("range"), Some(&Value::Other("1..5".into())));
+}
+
+#[test]
+fn deserialize_from_other_deserializers() {
+    use serde::de::value::{
+        Error, I64Deserializer, MapDeserializer, SeqDeserializer, StrDeserializer,
+    };
+
+    let value = Value::deserialize(I64Deserializer::<Error>::new(-5)).unwrap();
+    assert_eq!(value, Value::Int(-5));
+
+    let value = Value::deserialize(StrDeserializer::<Error>::new("text")).unwrap();
+    assert_eq!(value, Value::Str("text".into()));
+
+    let seq = SeqDeserializer::<_, Error>::new([1u32, 2].into_iter());
+    assert_eq!(
+        Value::deserialize(seq).unwrap(),
+        Value::Seq(vec![Value::Int(1), Value::Int(2)])
+    );
+
+    let map = MapDeserializer::<_, Error>::new([("a", true)].into_iter());
+    assert_eq!(
+        Value::deserialize(map).unwrap(),
+        Value::Map(vec![(Value::Str("a".into()), Value::Bool(true))])
+    );
+}
+
+#[test]
+fn deserialize_from_value() {
+    let text = r#"Config { name: "prod", ports: [80, 443], tls: Some(Tls { .. }), extra: {1: (2,), 3: ()}, range: 1..5, flags: Flags(A | B), empty: Empty {}, set: {'x'}, big: 340282366920938463463374607431768211455, nan: NaN, neg: -1.5e-7 }"#;
+    let value: Value = text.parse().unwrap();
+    assert_eq!(
+        Value::deserialize(&value).unwrap().to_string(),
+        value.to_string()
+    );
+
+    let text = r#"[Other { x: 1 }, Other("a"), Other, Truncated(3), Truncated { len: 3 }]"#;
+    let value: Value = text.parse().unwrap();
+    assert_eq!(Value::deserialize(&value).unwrap(), value);
+
+    let depth = 100_000;
+    let text = format!("{}1{}", "[Some(".repeat(depth), ")]".repeat(depth));
+    let value: Value = text.parse().unwrap();
+    let copy = Value::deserialize(&value).unwrap();
+
+    let mut inner = &copy;
+    for _ in 0..depth {
+        inner = inner.element(0).unwrap().as_option().unwrap().unwrap();
+    }
+    assert_eq!(inner, &Value::Int(1));
+}
+
+#[test]
+fn truncate_collections() {
+    let options = serde_dbgfmt::Options::new().truncate_collections(3);
+
+    let len = 200_000;
+    let text = format!(
+        "Log {{ lines: {:?}, pair: (1, 2, 3, 4) }}",
+        (0..len).collect::<Vec<_>>()
+    );
+    let value: Value = serde_dbgfmt::from_str_with(&text, &options).unwrap();
+
+    let lines = value.field("lines").unwrap();
+    assert!(matches!(lines, Value::Truncated { len: 200_000, .. }));
+    assert_eq!(
+        lines.elements(),
+        Some(&[Value::Int(0), Value::Int(1), Value::Int(2)][..])
+    );
+    assert_eq!(value.field("pair").unwrap().elements().unwrap().len(), 4);
+
+    let value: Value =
+        serde_dbgfmt::from_str_with("[{1: 2, 3: 4, 5: 6, 7: 8}, {'a'}]", &options).unwrap();
+    assert!(matches!(
+        value.element(0),
+        Some(Value::Truncated { len: 4, .. })
+    ));
+    assert_eq!(value.element(1), Some(&Value::Set(vec![Value::Char('a')])));
+    assert_eq!(value.to_string(), "[{1: 2, 3: 4, 5: 6}, {'a'}]");
+
+    // Truncated collections survive being deserialized from a Value.
+    assert_eq!(Value::deserialize(&value).unwrap(), value);
+}
+
+#[test]
+fn to_map() {
+    let fields = serde_dbgfmt::to_map(r#"Config { name: "prod", tls: Some(Tls { .. }) }"#).unwrap();
+    assert_eq!(
+        fields.keys().map(String::as_str).collect::<Vec<_>>(),
+        ["name", "tls"]
+    );
+    assert_eq!(fields["name"], Value::Str("prod".into()));
+    assert_eq!(
+        fields["tls"].as_option().unwrap().unwrap().name(),
+        Some("Tls")
+    );
+
+    let entries = serde_dbgfmt::to_map(r#"{"a": 1, 2: [3], Key: 4}"#).unwrap();
+    assert_eq!(
+        entries.into_iter().collect::<Vec<_>>(),
+        [
+            ("2".into(), Value::Seq(vec![Value::Int(3)])),
+            ("Key".into(), Value::Int(4)),
+            ("a".into(), Value::Int(1)),
+        ]
+    );
+
+    assert!(serde_dbgfmt::to_map("Point(1, 2)").is_err());
+    assert!(serde_dbgfmt::to_map("[1, 2]").is_err());
+    assert!(serde_dbgfmt::to_map("Config { a: 1 } trailing").is_err());
+}
+
+#[test]
+fn value_string() {
+    let value: Value =
+        r#"User { name: "ferris", bio: "a considerably longer string than fits inline" }"#
+            .parse()
+            .unwrap();
+
+    let Value::Struct { fields, .. } = &value else {
+        panic!("expected a struct, got {value:?}");
+    };
+    assert_eq!(fields[0].0, "name");
+    assert_eq!(
+        fields[1].1.as_str(),
+        Some("a considerably longer string than fits inline")
+    );
+    assert_eq!(String::from(fields[0].0.clone()), "name");
+    assert_eq!(
+        value.to_string(),
+        r#"User { name: "ferris", bio: "a considerably longer string than fits inline" }"#
+    );
+
+    #[cfg(feature = "compact_str")]
+    assert_eq!(std::mem::size_of::<serde_dbgfmt::ValueString>(), 24);
+}
@@ -0,0 +1,122 @@
+use pretty_assertions::assert_eq;
+use serde_dbgfmt::{Options, PathSegment, Value};
+
+#[test]
+fn walk_order() {
+    let text = r#"Config { tls: Some(Tls { .. }), extra: {"a-b": (1,), "c": [], 2: Unit}, point: Point(1, 2) }"#;
+    let value: Value = text.parse().unwrap();
+    let paths: Vec<_> = value.walk().map(|(path, _)| path.to_string()).collect();
+
+    assert_eq!(
+        paths,
+        [
+            "",
+            "tls",
+            "tls[0]",
+            "extra",
+            r#"extra["a-b"]"#,
+            r#"extra["a-b"][0]"#,
+            "extra.c",
+            "extra[2]",
+            "point",
+            "point[0]",
+            "point[1]",
+        ]
+    );
+
+    let (path, node) = value.walk().nth(7).unwrap();
+    assert_eq!(node, &Value::Ident("Unit".into()));
+    assert_eq!(path.len(), 2);
+    assert_eq!(path.last(), Some(PathSegment::Key(&Value::Int(2))));
+    assert_eq!(path.parent().unwrap().to_string(), "extra");
+    assert_eq!(
+        path.segments(),
+        [
+            PathSegment::Field("extra"),
+            PathSegment::Key(&Value::Int(2))
+        ]
+    );
+}
+
+#[test]
+fn walk_truncated() {
+    let options = Options::new().truncate_collections(1);
+    let value: Value = serde_dbgfmt::from_str_with("[[1, 2], 3]", &options).unwrap();
+    let nodes: Vec<_> = value
+        .walk()
+        .map(|(path, value)| format!("{path} = {value}"))
+        .collect();
+
+    assert_eq!(nodes, [" = [[1]]", "[0] = [1]", "[0][0] = 1"]);
+}
+
+#[test]
+fn walk_mut() {
+    let mut value: Value = "[Some(1), None, Some([2, 3])]".parse().unwrap();
+    let mut paths = Vec::new();
+    value.walk_mut(|path, value| {
+        paths.push(path.to_string());
+        if let Value::Int(n @ 0..=9) = value {
+            *value = Value::Seq(vec![Value::Int(*n * 10)]);
+        }
+    });
+
+    assert_eq!(
+        paths,
+        [
+            "",
+            "[0]",
+            "[0][0]",
+            "[0][0][0]",
+            "[1]",
+            "[2]",
+            "[2][0]",
+            "[2][0][0]",
+            "[2][0][0][0]",
+            "[2][0][1]",
+            "[2][0][1][0]",
+        ]
+    );
+    assert_eq!(value.to_string(), "[Some([10]), None, Some([[20], [30]])]");
+}
+
+#[test]
+fn walk_deeply_nested() {
+    let depth = 100_000;
+    let text = format!("{}1{}", "[Some(".repeat(depth), ")]".repeat(depth));
+    let mut value: Value = text.parse().unwrap();
+
+    let (path, leaf) = value.walk().last().unwrap();
+    assert_eq!(leaf, &Value::Int(1));
+    assert_eq!(path.len(), depth * 2);
+    drop(path);
+
+    let mut count = 0;
+    value.walk_mut(|_, _| count += 1);
+    assert_eq!(count, depth * 2 + 1);
+}
+
+#[test]
+fn find() {
+    let text = r#"Config { password: "a", nested: [Db { password: "b" }, {"password": "c", 1: Password}] }"#;
+    let value: Value = text.parse().unwrap();
+
+    let found: Vec<_> = value
+        .find_key("password")
+        .into_iter()
+        .map(|(path, value)| (path.to_string(), value.as_str().unwrap()))
+        .collect();
+    assert_eq!(
+        found,
+        [
+            ("password".to_owned(), "a"),
+            ("nested[0].password".to_owned(), "b"),
+            ("nested[1].password".to_owned(), "c"),
+        ]
+    );
+
+    let found = value.find_all(|path, value| path.len() == 2 && value.name().is_some());
+    assert_eq!(found.len(), 1);
+    assert_eq!(found[0].0.to_string(), "nested[0]");
+    assert!(value.find_key("user").is_empty());
+}
@@ -0,0 +1,403 @@
+#![cfg(feature = "std")]
+
+use std::io;
+
+use pretty_assertions::assert_eq;
+use serde::Deserialize;
+use serde_dbgfmt::IoError;
+
+#[derive(Debug, Deserialize)]
+struct Failure {
+    #[serde(with = "serde_dbgfmt::with::io_error")]
+    error: io::Error,
+    attempt: u32,
+}
+
+fn roundtrip_io_error(error: io::Error) -> io::Error {
+    #[derive(Debug)]
+    #[allow(dead_code)]
+    struct Failure {
+        error: io::Error,
+        attempt: u32,
+    }
+
+    let text = format!("{:?}", Failure { error, attempt: 3 });
+    let failure: self::Failure =
+        serde_dbgfmt::from_str(&text).unwrap_or_else(|e| panic!("{e}: {text}"));
+
+    assert_eq!(failure.attempt, 3);
+    failure.error
+}
+
+#[test]
+fn io_error_os() {
+    let error = roundtrip_io_error(io::Error::from_raw_os_error(2));
+
+    assert_eq!(error.raw_os_error(), Some(2));
+    assert_eq!(error.kind(), io::Error::from_raw_os_error(2).kind());
+}
+
+#[test]
+fn io_error_kind() {
+    let error = roundtrip_io_error(io::ErrorKind::TimedOut.into());
+
+    assert_eq!(error.kind(), io::ErrorKind::TimedOut);
+}
+
+#[test]
+fn io_error_custom() {
+    let error = roundtrip_io_error(io::Error::new(
+        io::ErrorKind::InvalidData,
+        "bad header\tvalue",
+    ));
+
+    assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+    assert_eq!(error.to_string(), "bad header\tvalue");
+}
+
+#[test]
+fn io_error_custom_nested() {
+    let inner = io::Error::from(io::ErrorKind::NotFound);
+    let error = roundtrip_io_error(io::Error::new(io::ErrorKind::Other, inner));
+
+    assert_eq!(error.kind(), io::ErrorKind::Other);
+    assert_eq!(error.to_string(), "Kind(NotFound)");
+}
+
+#[test]
+fn io_error_mirror() {
+    let text = "Error { kind: UnexpectedEof, message: \"failed to fill whole buffer\" }";
+    let error: IoError = serde_dbgfmt::from_str(text).unwrap();
+
+    assert_eq!(error.kind(), io::ErrorKind::UnexpectedEof);
+    assert_eq!(error.code, None);
+    assert_eq!(
+        error.message.as_deref(),
+        Some("failed to fill whole buffer")
+    );
+
+    let error: IoError = serde_dbgfmt::from_str("Kind(SomeFutureKind)").unwrap();
+    assert_eq!(error.kind_name, "SomeFutureKind");
+    assert_eq!(error.kind(), io::ErrorKind::Other);
+}
+
+bitflags::bitflags! {
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Serialize, Deserialize)]
+    #[serde(transparent)]
+    struct Mode: u32 {
+        const READ = 0b001;
+        const WRITE = 0b010;
+        const EXEC = 0b100;
+    }
+}
+
+#[derive(Debug, Deserialize, PartialEq)]
+struct Permissions {
+    #[serde(with = "serde_dbgfmt::with::bitflags")]
+    mode: Mode,
+    #[serde(with = "serde_dbgfmt::with::bitflags_names")]
+    names: Vec<String>,
+}
+
+#[test]
+fn bitflags_roundtrip() {
+    for mode in [
+        Mode::READ | Mode::WRITE,
+        Mode::empty(),
+        Mode::all(),
+        Mode::from_bits_retain(0b1001),
+    ] {
+        let text = format!("Permissions {{ mode: {mode:?}, names: {mode:?} }}");
+        let permissions: Permissions =
+            serde_dbgfmt::from_str(&text).unwrap_or_else(|e| panic!("{e}: {text}"));
+
+        assert_eq!(permissions.mode, mode, "{text}");
+        assert_eq!(
+            permissions.names,
+            mode.iter_names()
+                .map(|(name, _)| name.to_owned())
+                .chain((mode.bits() & !Mode::all().bits() != 0).then(|| "0x8".into()))
+                .collect::<Vec<_>>(),
+            "{text}"
+        );
+    }
+}
+
+#[test]
+#[cfg(feature = "bitflags")]
+fn bitflags_bits() {
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Bits {
+        #[serde(deserialize_with = "serde_dbgfmt::with::bitflags_bits::deserialize::<_, Mode>")]
+        mode: u32,
+    }
+
+    for mode in [
+        Mode::READ | Mode::EXEC,
+        Mode::empty(),
+        Mode::all(),
+        Mode::from_bits_retain(0b1001),
+    ] {
+        let text = format!("Bits {{ mode: {mode:?} }}");
+        let bits: Bits = serde_dbgfmt::from_str(&text).unwrap_or_else(|e| panic!("{e}: {text}"));
+        assert_eq!(bits.mode, mode.bits(), "{text}");
+    }
+
+    assert!(serde_dbgfmt::from_str::<Bits>("Bits { mode: Mode(READ | OTHER) }").is_err());
+}
+
+#[test]
+fn bitflags_invalid() {
+    #[derive(Debug, Deserialize)]
+    #[allow(dead_code)]
+    struct Names(#[serde(with = "serde_dbgfmt::with::bitflags_names")] Vec<String>);
+
+    assert!(serde_dbgfmt::from_str::<Names>("Names(Flags(A | B))").is_ok());
+    assert!(serde_dbgfmt::from_str::<Names>("Names(Flags(A | ))").is_err());
+    assert!(serde_dbgfmt::from_str::<Names>("Names(Flags(A B))").is_err());
+    assert!(serde_dbgfmt::from_str::<Names>("Names(Flags())").is_err());
+}
+
+#[test]
+fn hex_u64() {
+    #[derive(Debug, Deserialize)]
+    struct Object {
+        #[serde(with = "serde_dbgfmt::with::hex_u64")]
+        id: u64,
+        #[serde(with = "serde_dbgfmt::with::hex_u64")]
+        parent: u64,
+    }
+
+    let object: Object = serde_dbgfmt::from_str("Object { id: 0x7FF0, parent: 10 }").unwrap();
+    assert_eq!((object.id, object.parent), (0x7ff0, 0x10));
+
+    let object: Object = serde_dbgfmt::from_str(r#"Object { id: "ab", parent: cd }"#).unwrap();
+    assert_eq!((object.id, object.parent), (0xab, 0xcd));
+
+    assert!(serde_dbgfmt::from_str::<Object>("Object { id: xyz, parent: 0 }").is_err());
+}
+
+#[test]
+fn duration_str() {
+    use std::time::Duration;
+
+    #[derive(Debug, Deserialize)]
+    struct Timing {
+        #[serde(with = "serde_dbgfmt::with::duration_str")]
+        elapsed: Duration,
+    }
+
+    let durations = [
+        Duration::ZERO,
+        Duration::from_nanos(7),
+        Duration::from_nanos(1_001),
+        Duration::from_micros(250),
+        Duration::from_millis(1_500),
+        Duration::new(3, 141_592_653),
+        Duration::new(u64::MAX, 999_999_999),
+    ];
+    for duration in durations {
+        let text = format!("Timing {{ elapsed: {duration:?} }}");
+        let timing: Timing = serde_dbgfmt::from_str(&text).unwrap();
+        assert_eq!(timing.elapsed, duration, "{text}");
+    }
+
+    let timing: Timing = serde_dbgfmt::from_str(r#"Timing { elapsed: "10us" }"#).unwrap();
+    assert_eq!(timing.elapsed, Duration::from_micros(10));
+
+    for text in ["5", "5h", "1.s", ".5s", "s"] {
+        let text = format!("Timing {{ elapsed: {text} }}");
+        assert!(serde_dbgfmt::from_str::<Timing>(&text).is_err(), "{text}");
+    }
+}
+
+#[test]
+fn instant() {
+    use std::time::{Duration, Instant};
+
+    #[derive(Debug, Deserialize)]
+    struct Span {
+        #[serde(with = "serde_dbgfmt::with::instant")]
+        start: Duration,
+        #[serde(with = "serde_dbgfmt::with::instant")]
+        end: Duration,
+    }
+
+    mod original {
+        #[derive(Debug)]
+        #[allow(dead_code)]
+        pub struct Span {
+            pub start: std::time::Instant,
+            pub end: std::time::Instant,
+        }
+    }
+
+    let start = Instant::now();
+    let end = start + Duration::from_micros(1500);
+    let text = format!("{:?}", original::Span { start, end });
+    let span: Span = serde_dbgfmt::from_str(&text).unwrap_or_else(|e| panic!("{e}: {text}"));
+    assert_eq!(span.end - span.start, Duration::from_micros(1500));
+
+    let forms = [
+        "Instant { tv_sec: 3, tv_nsec: 5 }",
+        "Instant { t: Timespec { tv_sec: 3, tv_nsec: 5 } }",
+        "Instant { t: 3.000000005s }",
+    ];
+    for form in forms {
+        let text = format!("Span {{ start: {form}, end: {form} }}");
+        let span: Span = serde_dbgfmt::from_str(&text).unwrap_or_else(|e| panic!("{e}: {text}"));
+        assert_eq!(span.start, Duration::new(3, 5), "{form}");
+    }
+
+    for form in [
+        "Instant { tv_sec: -3, tv_nsec: 5 }",
+        "Instant { tv_sec: 3, tv_nsec: 1000000000 }",
+        "Instant { intervals: 5 }",
+        "SystemTime { tv_sec: 3, tv_nsec: 5 }",
+    ] {
+        let text = format!("Span {{ start: {form}, end: {form} }}");
+        assert!(serde_dbgfmt::from_str::<Span>(&text).is_err(), "{form}");
+    }
+}
+
+#[test]
+fn system_time() {
+    use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Event {
+        at: SystemTime,
+        seen: Option<SystemTime>,
+    }
+
+    let at = UNIX_EPOCH + Duration::new(1_700_000_000, 123_456_789);
+    let text = format!("Event {{ at: {at:?}, seen: Some({at:?}) }}");
+    let event: Event = serde_dbgfmt::from_str(&text).unwrap_or_else(|e| panic!("{e}: {text}"));
+    assert_eq!(event, Event { at, seen: Some(at) });
+
+    let windows = "SystemTime { intervals: 133444736001234567 }";
+    let time: SystemTime = serde_dbgfmt::from_str(windows).unwrap();
+    assert_eq!(time, UNIX_EPOCH + Duration::new(1_700_000_000, 123_456_700));
+
+    assert!(serde_dbgfmt::from_str::<SystemTime>("SystemTime { tv_sec: -1, tv_nsec: 0 }").is_err());
+    assert!(serde_dbgfmt::from_str::<SystemTime>("SystemTime { t: 1.5s }").is_err());
+
+    // The serde representation is still accepted.
+    let time: SystemTime =
+        serde_dbgfmt::from_str("SystemTime { secs_since_epoch: 5, nanos_since_epoch: 0 }").unwrap();
+    assert_eq!(time, UNIX_EPOCH + Duration::from_secs(5));
+}
+
+#[test]
+fn skip_times() {
+    use std::time::{Instant, SystemTime};
+
+    mod original {
+        #[derive(Debug)]
+        #[allow(dead_code)]
+        pub struct Record {
+            pub id: u32,
+            pub created: std::time::Instant,
+            pub modified: std::time::SystemTime,
+        }
+    }
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Record {
+        id: u32,
+    }
+
+    let original = original::Record {
+        id: 4,
+        created: Instant::now(),
+        modified: SystemTime::now(),
+    };
+    let record: Record = serde_dbgfmt::from_str(&format!("{original:#?}")).unwrap();
+    assert_eq!(record, Record { id: 4 });
+}
+
+#[test]
+fn ip_addr() {
+    use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+
+    #[derive(Debug, Deserialize)]
+    struct Peer {
+        #[serde(with = "serde_dbgfmt::with::ip_addr")]
+        ip: IpAddr,
+        #[serde(with = "serde_dbgfmt::with::ip_addr")]
+        v4: Ipv4Addr,
+        #[serde(with = "serde_dbgfmt::with::ip_addr")]
+        addrs: SocketAddr,
+    }
+
+    #[derive(Debug)]
+    #[allow(dead_code)]
+    struct Original {
+        ip: IpAddr,
+        v4: Ipv4Addr,
+        addrs: SocketAddr,
+    }
+
+    let original = Original {
+        ip: "fe80::1".parse().unwrap(),
+        v4: Ipv4Addr::LOCALHOST,
+        addrs: "[::1]:8080".parse().unwrap(),
+    };
+    let text = format!("{original:?}").replace("Original", "Peer");
+    let peer: Peer = serde_dbgfmt::from_str(&text).unwrap();
+
+    assert_eq!(peer.ip, original.ip);
+    assert_eq!(peer.v4, original.v4);
+    assert_eq!(peer.addrs, original.addrs);
+
+    let text = format!("{original:#?}").replace("Original", "Peer");
+    let peer: Peer = serde_dbgfmt::from_str(&text).unwrap();
+    assert_eq!(peer.addrs, original.addrs);
+}
+
+#[test]
+fn opaque_string() {
+    #[derive(Debug, Deserialize)]
+    struct Task {
+        #[serde(with = "serde_dbgfmt::with::opaque_string")]
+        callback: String,
+        #[serde(with = "serde_dbgfmt::with::opaque_string")]
+        state: String,
+        id: u32,
+    }
+
+    let text = r#"Task { callback: <closure>, state: Weird { a: "x, }" }, id: 3 }"#;
+    let task: Task = serde_dbgfmt::from_str(text).unwrap();
+
+    assert_eq!(task.callback, "<closure>");
+    assert_eq!(task.state, r#"Weird { a: "x, }" }"#);
+    assert_eq!(task.id, 3);
+}
+
+#[cfg(feature = "rust_decimal")]
+#[test]
+fn decimal() {
+    use rust_decimal::Decimal;
+
+    #[derive(Debug, Deserialize)]
+    struct Trade {
+        #[serde(with = "serde_dbgfmt::with::decimal")]
+        price: Decimal,
+        #[serde(with = "serde_dbgfmt::with::decimal")]
+        fee: Decimal,
+    }
+
+    let original = Decimal::from_str_exact("79228162514264.337593543950335").unwrap();
+    let text = format!("Trade {{ price: {original:?}, fee: -0.010 }}");
+    let trade: Trade = serde_dbgfmt::from_str(&text).unwrap();
+    assert_eq!(trade.price, original);
+    assert_eq!(trade.price.to_string(), original.to_string());
+    assert_eq!(trade.fee.to_string(), "-0.010");
+
+    let trade: Trade = serde_dbgfmt::from_str(r#"Trade { price: 1.5e3, fee: "7" }"#).unwrap();
+    assert_eq!(trade.price, Decimal::from(1500));
+    assert_eq!(trade.fee, Decimal::from(7));
+
+    let text = "Trade { price: 0.0000000000000000000000000000001, fee: 0 }";
+    assert!(serde_dbgfmt::from_str::<Trade>(text).is_err());
+}